@@ -26,7 +26,7 @@ else
 fi
 
 # 2. Create Policy File
-POLICY_NAME="com.msi.fancontrol.run-sidecar.policy"
+POLICY_NAME="org.msifancontrol.policy"
 TEMP_POLICY="/tmp/$POLICY_NAME"
 
 cat <<EOF > $TEMP_POLICY
@@ -35,7 +35,7 @@ cat <<EOF > $TEMP_POLICY
  "-//freedesktop//DTD PolicyKit Policy Configuration 1.0//EN"
  "http://www.freedesktop.org/standards/PolicyKit/1/policyconfig.dtd">
 <policyconfig>
-  <action id="com.msi.fancontrol.run-sidecar">
+  <action id="org.msifancontrol.run-sidecar">
     <description>Run MSI Fan Control Sidecar</description>
     <message>Authentication is required to run the MSI Fan Control helper</message>
     <defaults>
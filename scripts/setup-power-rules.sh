@@ -0,0 +1,86 @@
+#!/bin/bash
+
+# This script installs a udev rule that switches the fan mode when the AC
+# adapter is plugged in or removed. The rule calls the sidecar in --oneshot
+# mode, so it works without the GUI running.
+#
+# Usage: ./scripts/setup-power-rules.sh [--ac MODE] [--battery MODE] [--experimental]
+#        ./scripts/setup-power-rules.sh --remove
+#
+# MODE is one of auto, silent, basic, advanced. Defaults: --ac auto --battery silent.
+# --experimental passes the fan_mode opt-in, needed on models that aren't verified yet.
+
+RULE_FILE="/etc/udev/rules.d/99-msi-fan-control-power.rules"
+AC_MODE="auto"
+BATTERY_MODE="silent"
+OPT_IN=""
+
+valid_mode() {
+    case "$1" in
+        auto|silent|basic|advanced) return 0 ;;
+        *) return 1 ;;
+    esac
+}
+
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --ac) AC_MODE="$2"; shift 2 ;;
+        --battery) BATTERY_MODE="$2"; shift 2 ;;
+        --experimental) OPT_IN="--opt-in fan_mode "; shift ;;
+        --remove)
+            echo "Removing $RULE_FILE..."
+            sudo rm -f "$RULE_FILE" && sudo udevadm control --reload
+            exit $?
+            ;;
+        *)
+            echo "Unknown option: $1"
+            exit 1
+            ;;
+    esac
+done
+
+for mode in "$AC_MODE" "$BATTERY_MODE"; do
+    if ! valid_mode "$mode"; then
+        echo "Error: Invalid fan mode '$mode' (expected auto, silent, basic or advanced)."
+        exit 1
+    fi
+done
+
+# 1. Detect Sidecar Path
+SIDECAR_PATH=""
+
+# Check for installed version first
+if [ -f "/usr/bin/msi-sidecar" ]; then
+    SIDECAR_PATH="/usr/bin/msi-sidecar"
+    echo "Detected Installed Sidecar: $SIDECAR_PATH"
+# Check for local release build
+elif [ -f "src-tauri/target/release/msi-sidecar-x86_64-unknown-linux-gnu" ]; then
+    SIDECAR_PATH="$(pwd)/src-tauri/target/release/msi-sidecar-x86_64-unknown-linux-gnu"
+    echo "Detected Local Release Sidecar: $SIDECAR_PATH"
+else
+    echo "Error: Could not find an installed or release sidecar binary."
+    echo "  - udev runs the rule as root at any time, so a debug build is not used."
+    exit 1
+fi
+
+# 2. Create Rule File
+TEMP_RULE="/tmp/99-msi-fan-control-power.rules"
+
+cat <<EOF > $TEMP_RULE
+# Installed by msi-fan-control scripts/setup-power-rules.sh
+SUBSYSTEM=="power_supply", ATTR{type}=="Mains", ATTR{online}=="1", RUN+="$SIDECAR_PATH --oneshot ${OPT_IN}set_fan_mode $AC_MODE"
+SUBSYSTEM=="power_supply", ATTR{type}=="Mains", ATTR{online}=="0", RUN+="$SIDECAR_PATH --oneshot ${OPT_IN}set_fan_mode $BATTERY_MODE"
+EOF
+
+# 3. Install Rule
+echo "Installing udev rule to $RULE_FILE..."
+if sudo cp $TEMP_RULE "$RULE_FILE"; then
+    sudo udevadm control --reload
+    echo "Success: fan mode will be '$AC_MODE' on AC and '$BATTERY_MODE' on battery."
+else
+    echo "Failed to install udev rule."
+    rm $TEMP_RULE
+    exit 1
+fi
+
+rm $TEMP_RULE
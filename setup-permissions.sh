@@ -17,7 +17,7 @@ fi
 
 echo "Detected Sidecar Path: $SIDECAR_PATH"
 
-POLICY_FILE="com.msi.fancontrol.policy"
+POLICY_FILE="org.msifancontrol.policy"
 
 cat <<EOF > $POLICY_FILE
 <?xml version="1.0" encoding="UTF-8"?>
@@ -25,7 +25,7 @@ cat <<EOF > $POLICY_FILE
  "- //freedesktop//DTD PolicyKit Policy Configuration 1.0//EN"
  "http://www.freedesktop.org/standards/PolicyKit/1/policyconfig.dtd">
 <policyconfig>
-  <action id="com.msi.fancontrol.run-sidecar">
+  <action id="org.msifancontrol.run-sidecar">
     <description>Run MSI Fan Control Sidecar</description>
     <message>Authentication is required to run the MSI Fan Control sidecar</message>
     <defaults>
@@ -0,0 +1,134 @@
+//! Sending one command to whatever serves the protocol: the daemon's
+//! socket if it runs, otherwise a `--oneshot` sidecar started through
+//! pkexec. A stdin session would not do for the latter: its failsafe hands
+//! the fans back to Auto as soon as we disconnect.
+
+use msi_protocol::framing::{self, Line};
+use msi_protocol::{Command, CurvePoint, Response};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::process::Command as Process;
+
+/// Same socket and override variable as the daemon and the app use.
+const DAEMON_SOCKET_PATH: &str = "/run/msi-fan-control.sock";
+const DAEMON_SOCKET_ENV: &str = "MSI_FAN_CONTROL_SOCKET";
+const SIDECAR_ENV: &str = "MSI_SIDECAR";
+
+/// `$MSI_SIDECAR`, the sidecar next to this binary (both are built into
+/// the same target directory), or the one on the `PATH`.
+fn sidecar_path() -> String {
+    if let Ok(path) = std::env::var(SIDECAR_ENV) {
+        return path;
+    }
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join("msi-sidecar")))
+        .filter(|path| path.exists())
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "msi-sidecar".to_string())
+}
+
+/// The next reply, skipping status pushes meant for subscribers.
+fn read_response(input: &mut impl BufRead) -> Result<Response, String> {
+    loop {
+        let line = match framing::read_line(input) {
+            Ok(Line::Message(line)) => line,
+            Ok(Line::TooLong { .. }) => return Err("Reply too long".to_string()),
+            Ok(Line::Eof) => return Err("The daemon closed the connection".to_string()),
+            Err(e) => return Err(format!("Read error: {}", e)),
+        };
+        match serde_json::from_str(&line) {
+            Ok(Response::StatusUpdate(_) | Response::StatusLiteUpdate(_)) => {}
+            Ok(Response::FailsafeTriggered { message }) => eprintln!("Failsafe: {}", message),
+            Ok(response) => return Ok(response),
+            Err(e) => return Err(format!("Parse error: {} (line: {})", e, line)),
+        }
+    }
+}
+
+fn send_to_daemon(stream: UnixStream, command: &Command) -> Result<Response, String> {
+    let mut output = stream.try_clone().map_err(|e| e.to_string())?;
+    let mut input = BufReader::new(stream);
+    // Every connection starts with the current status, or why there is none
+    if let Response::Error { message } = read_response(&mut input)? {
+        return Err(message);
+    }
+    let json = serde_json::to_string(command).map_err(|e| e.to_string())?;
+    writeln!(output, "{}", json).map_err(|e| format!("Write error: {}", e))?;
+    read_response(&mut input)
+}
+
+fn curve_json(points: &[CurvePoint]) -> Result<String, String> {
+    serde_json::to_string(points).map_err(|e| e.to_string())
+}
+
+/// `--oneshot` arguments for the commands this CLI sends.
+fn oneshot_args(command: &Command) -> Result<Vec<String>, String> {
+    Ok(match command {
+        Command::GetStatus => vec!["get_status".to_string()],
+        Command::SetFanMode { mode } => vec!["set_fan_mode".to_string(), mode.clone()],
+        Command::SetCoolerBoost { enabled } => vec![
+            "set_cooler_boost".to_string(),
+            if *enabled { "on" } else { "off" }.to_string(),
+        ],
+        Command::SetFanCurve { cpu, gpu, force } => {
+            let mut args = vec![
+                "set_fan_curve".to_string(),
+                curve_json(cpu)?,
+                curve_json(gpu)?,
+            ];
+            if *force {
+                args.push("--force".to_string());
+            }
+            args
+        }
+        Command::GetHistory { .. } => {
+            return Err("History is only kept by the daemon, which isn't running".to_string())
+        }
+        _ => return Err("This command needs the daemon".to_string()),
+    })
+}
+
+fn send_oneshot(command: &Command, simulate: bool) -> Result<Response, String> {
+    let sidecar = sidecar_path();
+    let mut process = if simulate {
+        let mut process = Process::new(&sidecar);
+        process.arg("--simulate");
+        process
+    } else {
+        let mut process = Process::new("pkexec");
+        process.arg(&sidecar);
+        process
+    };
+    let output = process
+        .arg("--oneshot")
+        .args(oneshot_args(command)?)
+        .output()
+        .map_err(|e| format!("Failed to start {}: {}", sidecar, e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // The last line is the error; earlier ones are the sidecar's notes
+        let message = stderr.trim().lines().last().unwrap_or("The sidecar failed");
+        return Err(message.trim_start_matches("Error: ").to_string());
+    }
+    // Data replies are printed as JSON, Ok replies as their message
+    Ok(
+        serde_json::from_str(&stdout).unwrap_or_else(|_| Response::Ok {
+            message: stdout.trim().to_string(),
+        }),
+    )
+}
+
+/// Send `command` and return the reply. `simulate` skips the daemon and
+/// runs an unprivileged sidecar against a simulated EC.
+pub fn send(command: &Command, simulate: bool) -> Result<Response, String> {
+    if !simulate {
+        let socket =
+            std::env::var(DAEMON_SOCKET_ENV).unwrap_or_else(|_| DAEMON_SOCKET_PATH.to_string());
+        if let Ok(stream) = UnixStream::connect(&socket) {
+            return send_to_daemon(stream, command);
+        }
+    }
+    send_oneshot(command, simulate)
+}
@@ -0,0 +1,190 @@
+//! `msi-fanctl`: control the fans from a terminal or a script. It talks to
+//! the daemon's socket when the daemon runs and otherwise runs the sidecar's
+//! `--oneshot` mode through pkexec.
+//!
+//! ```text
+//! msi-fanctl status
+//! msi-fanctl mode silent
+//! msi-fanctl boost on
+//! msi-fanctl curve apply gaming.json
+//! msi-fanctl --simulate status
+//! msi-fanctl --json history 10 | jq '.[-1].cpu_temp'
+//! ```
+//!
+//! `--json` prints `status` and `history` as JSON for jq and status bar
+//! modules: the status object, or an array of samples, oldest first.
+//! History is only recorded by the daemon.
+//!
+//! A curve file holds `{"cpu": [...], "gpu": [...]}` with points like
+//! `{"temp": 60, "speed": 50}`, the shape of a profile's `curve`. Errors go to
+//! stderr with exit code 1, bad arguments exit with 2.
+
+mod client;
+
+use msi_protocol::{Command, CurvePoint, HistorySample, Response, Status};
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "Usage: msi-fanctl [--simulate] [--json] COMMAND
+
+Commands:
+  status                     Temperatures, fan speeds and mode
+  history [MINUTES]          Readings recorded by the daemon (default 10 minutes)
+  mode MODE                  Set the fan mode (auto, silent, basic, advanced)
+  boost on|off               Turn Cooler Boost on or off
+  curve apply FILE [--force] Apply a fan curve from a JSON file
+
+Options:
+  --simulate                 Use a sidecar with a simulated EC instead of the hardware
+  --json                     Print status and history as JSON";
+
+const DEFAULT_HISTORY_MINUTES: u64 = 10;
+
+#[derive(serde::Deserialize)]
+struct CurveFile {
+    cpu: Vec<CurvePoint>,
+    gpu: Vec<CurvePoint>,
+}
+
+fn parse(args: &[&str]) -> Result<Command, String> {
+    match args {
+        ["status"] => Ok(Command::GetStatus),
+        ["history"] => Ok(Command::GetHistory {
+            range_secs: DEFAULT_HISTORY_MINUTES * 60,
+        }),
+        ["history", minutes] => {
+            let minutes: u64 = minutes
+                .parse()
+                .map_err(|_| format!("'{}' is not a number of minutes", minutes))?;
+            Ok(Command::GetHistory {
+                range_secs: minutes * 60,
+            })
+        }
+        ["mode", mode] => Ok(Command::SetFanMode {
+            mode: mode.to_string(),
+        }),
+        ["boost", state] => {
+            let enabled = match *state {
+                "on" => true,
+                "off" => false,
+                _ => return Err(format!("Expected on or off, got '{}'", state)),
+            };
+            Ok(Command::SetCoolerBoost { enabled })
+        }
+        ["curve", "apply", rest @ ..] => {
+            let force = rest.contains(&"--force");
+            let files: Vec<&&str> = rest.iter().filter(|a| **a != "--force").collect();
+            let [file] = files[..] else {
+                return Err(USAGE.to_string());
+            };
+            let json = std::fs::read_to_string(file)
+                .map_err(|e| format!("Failed to read {}: {}", file, e))?;
+            let curve: CurveFile = serde_json::from_str(&json)
+                .map_err(|e| format!("{} is not a fan curve: {}", file, e))?;
+            Ok(Command::SetFanCurve {
+                cpu: curve.cpu,
+                gpu: curve.gpu,
+                force,
+            })
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn print_status(status: &Status) {
+    println!("Model:        {}", status.model);
+    println!("Backend:      {}", status.backend);
+    println!("Support:      {:?}", status.support_level);
+    println!("CPU:          {} °C", status.cpu_temp);
+    println!("GPU:          {} °C", status.gpu_temp);
+    if status.fans.is_empty() {
+        // An older sidecar
+        println!("Fan 1:        {} RPM", status.fan1_rpm);
+        println!("Fan 2:        {} RPM", status.fan2_rpm);
+    }
+    for fan in &status.fans {
+        let label = format!("{} fan:", fan.name);
+        match fan.target_percent {
+            Some(percent) => println!("{:<13} {} RPM ({} %)", label, fan.rpm, percent),
+            None => println!("{:<13} {} RPM", label, fan.rpm),
+        }
+    }
+    println!("Fan mode:     {}", status.fan_mode);
+    println!(
+        "Cooler Boost: {}",
+        if status.cooler_boost { "on" } else { "off" }
+    );
+    if status.simulated {
+        println!("(simulated EC)");
+    }
+}
+
+fn print_history(samples: &[HistorySample]) {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    println!(
+        "{:>8}  {:>4}  {:>4}  {:>5}  {:>5}",
+        "Ago", "CPU", "GPU", "Fan 1", "Fan 2"
+    );
+    for sample in samples {
+        println!(
+            "{:>6} s  {:>4}  {:>4}  {:>5}  {:>5}",
+            now_ms.saturating_sub(sample.timestamp_ms) / 1000,
+            sample.cpu_temp,
+            sample.gpu_temp,
+            sample.fan1_rpm,
+            sample.fan2_rpm
+        );
+    }
+}
+
+fn print_json(value: &impl serde::Serialize) -> Result<(), String> {
+    let json = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn run(command: &Command, simulate: bool, json: bool) -> Result<(), String> {
+    match client::send(command, simulate)? {
+        Response::Status(status) if json => print_json(&status)?,
+        Response::Status(status) => print_status(&status),
+        Response::History { samples } if json => print_json(&samples)?,
+        Response::History { samples } => print_history(&samples),
+        Response::Ok { message } => println!("{}", message),
+        Response::Error { message } => return Err(message),
+        other => return Err(format!("Unexpected response: {:?}", other)),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let simulate = args.iter().any(|a| a == "--simulate");
+    let json = args.iter().any(|a| a == "--json");
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| *a != "--simulate" && *a != "--json")
+        .collect();
+
+    let command = match parse(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&command, simulate, json) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(1)
+        }
+    }
+}
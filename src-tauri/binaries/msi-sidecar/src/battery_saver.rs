@@ -0,0 +1,64 @@
+//! Pausing the daemon's history and metrics on a low battery.
+//!
+//! With `battery_saver_percent` set in `daemon.json`, the power supply is
+//! checked every 30 seconds. While the laptop runs on battery at or below
+//! that charge, no history samples are taken and `/metrics` answers 503,
+//! so a status bar or a scraper doesn't keep the EC busy through the last
+//! few percent. Both resume once the adapter is plugged in or the charge
+//! climbs back above the limit. The failsafe watchdog and the fan
+//! smoothing keep running.
+
+use crate::{lock_session, Session};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Charge of the emptiest battery while on battery; `None` on AC, or
+/// without a battery to read.
+fn charge_on_battery() -> Option<u8> {
+    let mut lowest: Option<u8> = None;
+    for entry in fs::read_dir(POWER_SUPPLY_PATH).ok()?.flatten() {
+        let dir = entry.path();
+        match read(&dir.join("type")).as_deref() {
+            Some("Mains") if read(&dir.join("online")).as_deref() == Some("1") => return None,
+            // `hid-*` batteries belong to mice and keyboards
+            Some("Battery") if entry.file_name().to_string_lossy().starts_with("BAT") => {
+                if let Some(charge) = read(&dir.join("capacity")).and_then(|c| c.parse().ok()) {
+                    lowest = Some(lowest.map_or(charge, |l: u8| l.min(charge)));
+                }
+            }
+            _ => {}
+        }
+    }
+    lowest
+}
+
+/// Check the battery for as long as the process runs.
+pub fn spawn(session: Arc<Mutex<Session>>, percent: u8) {
+    thread::spawn(move || loop {
+        let charge = charge_on_battery();
+        let saving = charge.is_some_and(|charge| charge <= percent);
+        let mut session = lock_session(&session);
+        if session.saving_battery != saving {
+            session.saving_battery = saving;
+            match charge {
+                Some(charge) if saving => eprintln!(
+                    "Battery at {}% (limit {}%): history and metrics paused",
+                    charge, percent
+                ),
+                _ => eprintln!("History and metrics resumed"),
+            }
+        }
+        drop(session);
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
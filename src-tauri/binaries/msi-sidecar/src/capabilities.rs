@@ -0,0 +1,109 @@
+//! `get_capabilities`: which of monitoring, writes, Cooler Boost and fan
+//! curves work in this session, each judged on its own. A read-only EC, an
+//! unverified model or a driver without curves takes away only what it has
+//! to. Also `hello`, which says what this build of the sidecar supports at
+//! all.
+
+use crate::{Session, FEATURE_COOLER_BOOST, FEATURE_FAN_CURVE, FEATURE_FAN_MODE};
+use msi_ec_core::model;
+use msi_protocol::{
+    Capability, CapabilityState, CapabilityStatus, SidecarFeature, SidecarHello, SupportLevel,
+    PROTOCOL_VERSION,
+};
+
+/// Whether the model's gate lets writes of `feature` through, as
+/// `Session::gated_write` decides it.
+fn gate(session: &Session, capability: Capability, feature: &str) -> CapabilityStatus {
+    if session.support_level == SupportLevel::Verified {
+        return CapabilityStatus::available(capability);
+    }
+    if !session.opt_ins.iter().any(|f| f == feature) {
+        return CapabilityStatus::unavailable(
+            capability,
+            format!(
+                "Disabled on {} models; enable the experimental {} opt-in to allow it",
+                session.support_level.as_str(),
+                feature
+            ),
+        );
+    }
+    if !session.trial_passed {
+        return CapabilityStatus::degraded(
+            capability,
+            "Opted in; a trial write has to pass before the first write",
+        );
+    }
+    CapabilityStatus::degraded(
+        capability,
+        format!(
+            "Experimental on this {} model",
+            session.support_level.as_str()
+        ),
+    )
+}
+
+fn monitor(session: &mut Session) -> CapabilityStatus {
+    // Under lockdown the error already says so
+    if let Err(e) = session.backend.read() {
+        return CapabilityStatus::unavailable(Capability::Monitor, e);
+    }
+    let generic = session.register_map.map.name == model::GENERIC.name;
+    if generic && session.backend.name() == "ec_sys" {
+        return CapabilityStatus::degraded(
+            Capability::Monitor,
+            "Unknown model: readings use the generic register map and may be off",
+        );
+    }
+    CapabilityStatus::available(Capability::Monitor)
+}
+
+pub fn report(session: &mut Session) -> Vec<CapabilityStatus> {
+    let monitor = monitor(session);
+    // Writes read the EC first, and nothing is written blind
+    let blocker = match &monitor.reason {
+        Some(reason) if monitor.state == CapabilityState::Unavailable => {
+            Some(format!("The EC can't be read: {}", reason))
+        }
+        _ => session.backend.write_blocker(),
+    };
+    let write = |capability, feature| match &blocker {
+        Some(reason) => CapabilityStatus::unavailable(capability, reason.clone()),
+        None => gate(session, capability, feature),
+    };
+
+    let fan_curves = if session.backend.supports_curves() {
+        write(Capability::FanCurves, FEATURE_FAN_CURVE)
+    } else {
+        CapabilityStatus::unavailable(
+            Capability::FanCurves,
+            format!(
+                "The {} backend only switches modes; unload it to use ec_sys",
+                session.backend.name()
+            ),
+        )
+    };
+    let write_status = write(Capability::Write, FEATURE_FAN_MODE);
+    let cooler_boost = write(Capability::CoolerBoost, FEATURE_COOLER_BOOST);
+    vec![monitor, write_status, cooler_boost, fan_curves]
+}
+
+/// Answer the app's `hello`. A different protocol version is only logged;
+/// the app decides whether it can work with this sidecar.
+pub fn hello(session: &Session, app_protocol_version: u32) -> SidecarHello {
+    if app_protocol_version != PROTOCOL_VERSION {
+        eprintln!(
+            "App speaks protocol {}, this sidecar {}",
+            app_protocol_version, PROTOCOL_VERSION
+        );
+    }
+    let mut features = vec![SidecarFeature::FanList];
+    if session.backend.supports_curves() {
+        features.push(SidecarFeature::PerFanControl);
+    }
+    SidecarHello {
+        protocol_version: PROTOCOL_VERSION,
+        sidecar_version: env!("CARGO_PKG_VERSION").to_string(),
+        model: session.detected_model(),
+        features,
+    }
+}
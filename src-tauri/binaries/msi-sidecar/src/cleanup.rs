@@ -0,0 +1,144 @@
+//! `cleanup_system_files`: remove what the app and sidecar left outside
+//! the package, so uninstalling leaves no root-owned residue. That is the
+//! `ec_sys` boot files, a polkit policy written for an AppImage or source
+//! build, the udev rule from `scripts/setup-power-rules.sh`, a D-Bus policy
+//! copied to `/etc`, the daemon's enablement, and the config and log
+//! directories.
+//!
+//! Files the package installs are left to the package manager, and a boot
+//! file someone edited is kept.
+
+use msi_ec_core::ec;
+use msi_protocol::CleanupReport;
+use std::fs;
+use std::path::Path;
+use std::process::Command as ProcessCommand;
+
+const POLICY_PATH: &str = "/usr/share/polkit-1/actions/org.msifancontrol.policy";
+/// The sidecar the packaged policy names; a policy naming another one was
+/// written by `install_polkit_policy`.
+const PACKAGED_SIDECAR_PATH: &str = "/usr/bin/msi-sidecar";
+/// Written by `scripts/setup-power-rules.sh`.
+const POWER_RULE_PATH: &str = "/etc/udev/rules.d/99-msi-fan-control-power.rules";
+/// The package installs its copy under `/usr/share/dbus-1`; this one was
+/// copied by hand for a source build.
+const DBUS_POLICY_PATH: &str = "/etc/dbus-1/system.d/org.msifancontrol.Daemon.conf";
+const SERVICE: &str = "msi-fan-control.service";
+const SERVICE_LINK: &str = "/etc/systemd/system/multi-user.target.wants/msi-fan-control.service";
+/// Daemon config and register map override.
+const CONFIG_DIR: &str = "/etc/msi-fan-control";
+/// Experimental and raw register write logs.
+const LOG_DIR: &str = "/var/log/msi-fan-control";
+
+fn disable_service(report: &mut CleanupReport) {
+    if fs::symlink_metadata(SERVICE_LINK).is_err() {
+        return;
+    }
+    // Only the boot-time start; a running daemon keeps serving
+    match ProcessCommand::new("systemctl")
+        .args(["disable", SERVICE])
+        .output()
+    {
+        Ok(output) if output.status.success() => report.removed.push(SERVICE_LINK.to_string()),
+        Ok(output) => report.failed.push(format!(
+            "{}: {}",
+            SERVICE_LINK,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => report
+            .failed
+            .push(format!("{}: failed to run systemctl: {}", SERVICE_LINK, e)),
+    }
+}
+
+fn remove_persistence(report: &mut CleanupReport) {
+    match ec::remove_persistence() {
+        Ok(persistence) => {
+            report.removed.extend(persistence.removed);
+            report.kept.extend(
+                persistence
+                    .kept
+                    .into_iter()
+                    .map(|path| format!("{}: edited since it was written", path)),
+            );
+        }
+        Err(e) => report.failed.push(e),
+    }
+}
+
+fn remove_policy(report: &mut CleanupReport) {
+    let Ok(policy) = fs::read_to_string(POLICY_PATH) else {
+        return;
+    };
+    if policy.contains(PACKAGED_SIDECAR_PATH) {
+        report
+            .kept
+            .push(format!("{}: installed by the package", POLICY_PATH));
+        return;
+    }
+    match fs::remove_file(POLICY_PATH) {
+        Ok(()) => report.removed.push(POLICY_PATH.to_string()),
+        Err(e) => report.failed.push(format!("{}: {}", POLICY_PATH, e)),
+    }
+}
+
+/// Whether `path` was there and is now gone.
+fn remove_file(report: &mut CleanupReport, path: &str) -> bool {
+    match fs::remove_file(path) {
+        Ok(()) => {
+            report.removed.push(path.to_string());
+            true
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => {
+            report.failed.push(format!("{}: {}", path, e));
+            false
+        }
+    }
+}
+
+fn remove_power_rule(report: &mut CleanupReport) {
+    if !remove_file(report, POWER_RULE_PATH) {
+        return;
+    }
+    // Otherwise udev keeps running the rule until the next boot
+    match ProcessCommand::new("udevadm")
+        .args(["control", "--reload"])
+        .output()
+    {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => report.failed.push(format!(
+            "udevadm control --reload: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => report
+            .failed
+            .push(format!("udevadm control --reload: failed to run: {}", e)),
+    }
+}
+
+fn remove_dir(report: &mut CleanupReport, dir: &str) {
+    if !Path::new(dir).exists() {
+        return;
+    }
+    match fs::remove_dir_all(dir) {
+        Ok(()) => report.removed.push(dir.to_string()),
+        Err(e) => report.failed.push(format!("{}: {}", dir, e)),
+    }
+}
+
+/// Remove everything that's there; a failure doesn't stop the rest.
+pub fn run() -> CleanupReport {
+    let mut report = CleanupReport::default();
+    disable_service(&mut report);
+    remove_persistence(&mut report);
+    remove_policy(&mut report);
+    remove_power_rule(&mut report);
+    remove_file(&mut report, DBUS_POLICY_PATH);
+    remove_dir(&mut report, CONFIG_DIR);
+    remove_dir(&mut report, LOG_DIR);
+    for path in &report.removed {
+        eprintln!("Removed {}", path);
+    }
+    report
+}
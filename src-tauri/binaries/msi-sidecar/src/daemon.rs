@@ -0,0 +1,221 @@
+//! `--daemon` mode: keep running under systemd without the GUI.
+//!
+//! At startup the daemon waits `startup_delay_secs`, then until the EC can
+//! be read (up to `ec_ready_timeout_secs`, for distros that load `ec_sys`
+//! late), and runs the commands listed in
+//! `/etc/msi-fan-control/daemon.json` (typically opt-ins, a trial write on
+//! non-verified models, a fan behavior and a fan curve). It then serves the
+//! usual JSON-lines protocol on a Unix socket, one thread per client. All
+//! clients share one session, so the failsafe watchdog keeps guarding the
+//! fans whether or not the GUI is connected, and the last half hour of
+//! readings is kept for `get_history`. With the `dbus` feature the same
+//! session is also served on the system bus (see `dbus.rs`), and with
+//! `metrics_port` set as Prometheus metrics (see `metrics.rs`). With
+//! `battery_saver_percent` set, history and metrics pause on a low battery
+//! (see `battery_saver.rs`).
+//!
+//! The socket is only open to root and the `msi-fan-control` group, which
+//! the systemd unit runs the daemon under. Group members may read the
+//! fans and set modes, curves and speeds; register writes, loading
+//! `ec_sys`, opt-ins, the failsafe and system file changes take a root
+//! client.
+//!
+//! ```json
+//! { "startup_delay_secs": 5,
+//!   "startup": [
+//!     { "cmd": "set_fan_curve", "data": { "cpu": [...], "gpu": [...] } }
+//! ] }
+//! ```
+
+use crate::{
+    battery_saver, handle_command, history, lock_session, metrics, serve, smoothing, watchdog,
+    Output, Outputs, Session,
+};
+use msi_protocol::{Command, Response};
+use serde::Deserialize;
+use std::fs;
+use std::io::BufReader;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CONFIG_PATH: &str = "/etc/msi-fan-control/daemon.json";
+
+/// Also read by the app to find the daemon.
+const SOCKET_PATH: &str = "/run/msi-fan-control.sock";
+const SOCKET_ENV: &str = "MSI_FAN_CONTROL_SOCKET";
+
+const EC_READY_RETRY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct DaemonConfig {
+    /// Seconds to wait before anything touches the EC.
+    startup_delay_secs: u64,
+    /// Give up waiting for a readable EC after this many seconds and run
+    /// the startup commands anyway.
+    ec_ready_timeout_secs: u64,
+    /// Run once, in order, before clients are accepted.
+    startup: Vec<Command>,
+    /// Serve Prometheus metrics on this port; off when unset.
+    metrics_port: Option<u16>,
+    /// Address the metrics are served on; `0.0.0.0` to let other machines
+    /// scrape them.
+    metrics_address: String,
+    /// Pause history and metrics on battery at or below this charge; off
+    /// when unset.
+    battery_saver_percent: Option<u8>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        DaemonConfig {
+            startup_delay_secs: 0,
+            ec_ready_timeout_secs: 30,
+            startup: Vec::new(),
+            metrics_port: None,
+            metrics_address: "127.0.0.1".to_string(),
+            battery_saver_percent: None,
+        }
+    }
+}
+
+fn load_config() -> Result<DaemonConfig, String> {
+    match fs::read_to_string(CONFIG_PATH) {
+        Ok(content) => {
+            serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", CONFIG_PATH, e))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DaemonConfig::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", CONFIG_PATH, e)),
+    }
+}
+
+/// Retry reads until the EC answers, loading `ec_sys` between attempts.
+fn wait_for_ec(session: &Mutex<Session>, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let mut session = lock_session(session);
+        let Err(e) = session.backend.read() else {
+            return;
+        };
+        if Instant::now() >= deadline {
+            eprintln!("EC still not readable after {} s: {}", timeout.as_secs(), e);
+            return;
+        }
+        let _ = session.backend.load_module();
+        drop(session);
+        thread::sleep(EC_READY_RETRY);
+    }
+}
+
+fn socket_path() -> String {
+    std::env::var(SOCKET_ENV).unwrap_or_else(|_| SOCKET_PATH.to_string())
+}
+
+/// Uid of the process on the other end, from `SO_PEERCRED`.
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: `cred` and `len` outlive the call and `len` is its size
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (ret == 0).then_some(cred.uid)
+}
+
+fn serve_client(session: &Mutex<Session>, outputs: &Outputs, stream: UnixStream) {
+    // Unknown peers are treated as unprivileged
+    let root = peer_uid(&stream) == Some(0);
+    let reader = match stream.try_clone() {
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!("Failed to set up client connection: {}", e);
+            return;
+        }
+    };
+    let output: Output = Arc::new(Mutex::new(stream));
+    if let Ok(mut outputs) = outputs.lock() {
+        outputs.push(output.clone());
+    }
+    serve(session, BufReader::new(reader), &output, root);
+    if let Ok(mut outputs) = outputs.lock() {
+        outputs.retain(|o| !Arc::ptr_eq(o, &output));
+    }
+}
+
+/// Returns the process exit code; only returns on setup errors.
+pub fn run(simulate: bool) -> i32 {
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    thread::sleep(Duration::from_secs(config.startup_delay_secs));
+    let session = Arc::new(Mutex::new(Session::new(simulate)));
+    wait_for_ec(&session, Duration::from_secs(config.ec_ready_timeout_secs));
+    for cmd in config.startup {
+        match handle_command(&mut lock_session(&session), cmd) {
+            Response::Error { message } => eprintln!("Startup command failed: {}", message),
+            Response::Ok { message } => eprintln!("{}", message),
+            _ => {}
+        }
+    }
+
+    let path = socket_path();
+    // Left behind by a previous run that didn't shut down cleanly
+    let _ = fs::remove_file(&path);
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen on {}: {}", path, e);
+            return 1;
+        }
+    };
+    // The unit's Group= gives the socket the msi-fan-control group; run by
+    // hand it stays root's
+    if let Err(e) = fs::set_permissions(&path, fs::Permissions::from_mode(0o660)) {
+        eprintln!("Failed to set permissions on {}: {}", path, e);
+    }
+
+    let outputs: Outputs = Arc::new(Mutex::new(Vec::new()));
+    watchdog::spawn(session.clone(), outputs.clone());
+    smoothing::spawn(session.clone());
+    history::spawn(session.clone());
+    if let Some(port) = config.metrics_port {
+        metrics::spawn(session.clone(), &config.metrics_address, port);
+    }
+    if let Some(percent) = config.battery_saver_percent {
+        battery_saver::spawn(session.clone(), percent);
+    }
+    #[cfg(feature = "dbus")]
+    crate::dbus::spawn(session.clone());
+    eprintln!("Listening on {}", path);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let session = session.clone();
+                let outputs = outputs.clone();
+                thread::spawn(move || serve_client(&session, &outputs, stream));
+            }
+            Err(e) => eprintln!("Failed to accept client: {}", e),
+        }
+    }
+    0
+}
@@ -0,0 +1,162 @@
+//! D-Bus interface of the daemon, built with `--features dbus`.
+//!
+//! Claims `org.msifancontrol.Daemon` on the system bus and serves
+//! `/org/msifancontrol/Daemon` for GNOME extensions, KDE widgets and
+//! scripts. Calls go through the same session (and model gating) as socket
+//! clients; the bus policy limits callers to root and the `msi-fan-control`
+//! group. The status is re-read every two seconds and changes are
+//! announced with `PropertiesChanged`.
+
+use crate::{get_status, handle_command, lock_session, Session};
+use msi_protocol::{Command, CurvePoint, Response, Status};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use zbus::blocking::connection;
+use zbus::{block_on, fdo, interface};
+
+const BUS_NAME: &str = "org.msifancontrol.Daemon";
+const OBJECT_PATH: &str = "/org/msifancontrol/Daemon";
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+struct Daemon {
+    session: Arc<Mutex<Session>>,
+    /// Last status read by the poll loop, served as properties.
+    status: Option<Status>,
+}
+
+impl Daemon {
+    fn run(&self, cmd: Command) -> fdo::Result<()> {
+        match handle_command(&mut lock_session(&self.session), cmd) {
+            Response::Error { message } => Err(fdo::Error::Failed(message)),
+            _ => Ok(()),
+        }
+    }
+
+    fn status_field<T: Default>(&self, read: impl FnOnce(&Status) -> T) -> T {
+        self.status.as_ref().map(read).unwrap_or_default()
+    }
+}
+
+fn to_curve(points: Vec<(u8, u8)>) -> Vec<CurvePoint> {
+    points
+        .into_iter()
+        .map(|(temp, speed)| CurvePoint { temp, speed })
+        .collect()
+}
+
+#[interface(name = "org.msifancontrol.Daemon")]
+impl Daemon {
+    /// (cpu_temp, gpu_temp, fan1_rpm, fan2_rpm, cooler_boost, fan_mode)
+    fn get_status(&self) -> fdo::Result<(u8, u8, u32, u32, bool, String)> {
+        let status = get_status(&mut lock_session(&self.session)).map_err(fdo::Error::Failed)?;
+        Ok((
+            status.cpu_temp,
+            status.gpu_temp,
+            status.fan1_rpm,
+            status.fan2_rpm,
+            status.cooler_boost,
+            status.fan_mode,
+        ))
+    }
+
+    /// One of auto, silent, basic, advanced.
+    fn set_fan_mode(&self, mode: String) -> fdo::Result<()> {
+        self.run(Command::SetFanMode { mode })
+    }
+
+    fn set_cooler_boost(&self, enabled: bool) -> fdo::Result<()> {
+        self.run(Command::SetCoolerBoost { enabled })
+    }
+
+    /// Seven (temperature °C, speed %) points per fan.
+    fn set_curve(&self, cpu: Vec<(u8, u8)>, gpu: Vec<(u8, u8)>) -> fdo::Result<()> {
+        self.run(Command::SetFanCurve {
+            cpu: to_curve(cpu),
+            gpu: to_curve(gpu),
+            force: false,
+        })
+    }
+
+    #[zbus(property)]
+    fn cpu_temp(&self) -> u8 {
+        self.status_field(|s| s.cpu_temp)
+    }
+
+    #[zbus(property)]
+    fn gpu_temp(&self) -> u8 {
+        self.status_field(|s| s.gpu_temp)
+    }
+
+    #[zbus(property)]
+    fn fan1_rpm(&self) -> u32 {
+        self.status_field(|s| s.fan1_rpm)
+    }
+
+    #[zbus(property)]
+    fn fan2_rpm(&self) -> u32 {
+        self.status_field(|s| s.fan2_rpm)
+    }
+
+    #[zbus(property)]
+    fn cooler_boost(&self) -> bool {
+        self.status_field(|s| s.cooler_boost)
+    }
+
+    #[zbus(property)]
+    fn fan_mode(&self) -> String {
+        self.status_field(|s| s.fan_mode.clone())
+    }
+}
+
+fn serve(session: Arc<Mutex<Session>>) -> zbus::Result<()> {
+    let daemon = Daemon {
+        session: session.clone(),
+        status: None,
+    };
+    let conn = connection::Builder::system()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, daemon)?
+        .build()?;
+    eprintln!("Serving {} on the system bus", BUS_NAME);
+
+    let iface_ref = conn.object_server().interface::<_, Daemon>(OBJECT_PATH)?;
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let Ok(status) = get_status(&mut lock_session(&session)) else {
+            continue;
+        };
+        let previous = iface_ref.get_mut().status.replace(status.clone());
+
+        let emitter = iface_ref.signal_emitter();
+        let iface = iface_ref.get();
+        macro_rules! notify {
+            ($($field:ident => $signal:ident),*) => {$(
+                if previous.as_ref().map(|p| &p.$field) != Some(&status.$field) {
+                    iface.$signal(emitter).await?;
+                }
+            )*};
+        }
+        block_on(async {
+            notify!(
+                cpu_temp => cpu_temp_changed,
+                gpu_temp => gpu_temp_changed,
+                fan1_rpm => fan1_rpm_changed,
+                fan2_rpm => fan2_rpm_changed,
+                cooler_boost => cooler_boost_changed,
+                fan_mode => fan_mode_changed
+            );
+            zbus::Result::Ok(())
+        })?;
+    }
+}
+
+/// Serve the interface on its own thread. Failing to reach the system bus
+/// is logged and leaves the socket interface running.
+pub fn spawn(session: Arc<Mutex<Session>>) {
+    thread::spawn(move || {
+        if let Err(e) = serve(session) {
+            eprintln!("D-Bus interface unavailable: {}", e);
+        }
+    });
+}
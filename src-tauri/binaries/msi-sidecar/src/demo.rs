@@ -0,0 +1,128 @@
+//! `--demo`: the simulated EC with readings that move.
+//!
+//! A made-up workload (idle stretches, a long wave of activity and short
+//! bursts) heats the CPU and GPU, and the fans answer it through whatever
+//! curve, flat speed or Cooler Boost is in the EC, cooling the chips in
+//! turn. Good enough for screenshots and for trying the UI; not a thermal
+//! model of any laptop.
+
+use crate::{lock_session, Session};
+use msi_ec_core::model::RegisterMap;
+use msi_protocol::DEFAULT_RPM_DIVISOR;
+use std::f32::consts::TAU;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TICK: Duration = Duration::from_secs(1);
+/// Share of the gap to the target temperature closed per tick.
+const THERMAL_LAG: f32 = 0.15;
+
+struct Demo {
+    started: Instant,
+    cpu_temp: f32,
+    gpu_temp: f32,
+    /// xorshift state for the jitter; no need for a `rand` dependency.
+    seed: u32,
+}
+
+impl Demo {
+    fn new() -> Self {
+        Demo {
+            started: Instant::now(),
+            cpu_temp: 52.0,
+            gpu_temp: 47.0,
+            seed: 0x2545_F491,
+        }
+    }
+
+    /// Uniform in -1..1.
+    fn jitter(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    fn step(&mut self, map: &RegisterMap, ec: &mut [u8]) {
+        let t = self.started.elapsed().as_secs_f32();
+        let duty1 = fan_duty(
+            map,
+            ec,
+            map.fan1_temp_start,
+            map.fan1_speed_start,
+            self.cpu_temp,
+        );
+        let duty2 = fan_duty(
+            map,
+            ec,
+            map.fan2_temp_start,
+            map.fan2_speed_start,
+            self.gpu_temp,
+        );
+
+        let cpu_target = 38.0 + 55.0 * workload(t) - 18.0 * duty1 / 100.0;
+        let gpu_target = 35.0 + 45.0 * workload(t + 60.0) - 15.0 * duty2 / 100.0;
+        self.cpu_temp += (cpu_target - self.cpu_temp) * THERMAL_LAG + self.jitter() * 0.6;
+        self.gpu_temp += (gpu_target - self.gpu_temp) * THERMAL_LAG + self.jitter() * 0.4;
+        ec[map.cpu_temp as usize] = self.cpu_temp.round().clamp(0.0, 110.0) as u8;
+        ec[map.gpu_temp as usize] = self.gpu_temp.round().clamp(0.0, 110.0) as u8;
+
+        let rpm1 = rpm(duty1, self.jitter());
+        for &(low, high) in map.fan1_rpm {
+            set_tach(ec, low, high, rpm1);
+        }
+        if let Some((low, high)) = map.fan2_rpm {
+            let rpm2 = rpm(duty2, self.jitter());
+            set_tach(ec, low, high, rpm2);
+        }
+    }
+}
+
+/// 0..1: a four-minute wave of activity with short bursts on top.
+fn workload(t: f32) -> f32 {
+    let wave = 0.5 - 0.5 * (t / 240.0 * TAU).cos();
+    let bursts = (t / 23.0 * TAU).sin().max(0.0).powi(3);
+    (0.1 + 0.5 * wave + 0.4 * bursts).min(1.0)
+}
+
+/// Duty in percent the EC would run a fan at: its curve registers looked up
+/// at `temp` (flat after `set_fan_speed`), or full speed under Cooler Boost.
+fn fan_duty(map: &RegisterMap, ec: &[u8], temp_start: u64, speed_start: u64, temp: f32) -> f32 {
+    if ec[map.cooler_boost as usize] & map.cooler_boost_bit != 0 {
+        return 100.0;
+    }
+    let point = (0..6)
+        .take_while(|&i| temp >= ec[(temp_start + i) as usize] as f32)
+        .count() as u64;
+    (ec[(speed_start + point) as usize] as f32).min(100.0)
+}
+
+fn rpm(duty: f32, jitter: f32) -> u32 {
+    if duty < 1.0 {
+        return 0;
+    }
+    (1500.0 + 45.0 * duty + 40.0 * jitter) as u32
+}
+
+/// Tachometers hold `DEFAULT_RPM_DIVISOR / RPM`, as on real models.
+fn set_tach(ec: &mut [u8], low: u64, high: u64, rpm: u32) {
+    let tach = DEFAULT_RPM_DIVISOR.checked_div(rpm).unwrap_or(0) as u16;
+    ec[low as usize] = tach as u8;
+    ec[high as usize] = (tach >> 8) as u8;
+}
+
+/// Animate the simulated EC once a second for as long as the process runs.
+pub fn spawn(session: Arc<Mutex<Session>>) {
+    thread::spawn(move || {
+        let mut demo = Demo::new();
+        loop {
+            thread::sleep(TICK);
+            let mut session = lock_session(&session);
+            let map = session.register_map.map;
+            if let Some(ec) = session.backend.simulated_ec() {
+                demo.step(map, ec);
+            }
+        }
+    });
+}
@@ -0,0 +1,80 @@
+//! Temperature and fan history kept by the daemon, for scripts and status
+//! bars that have no app to ask (`get_history`, `msi-fanctl history`).
+//! Stdin sessions don't record; the app keeps its own history. Sampling
+//! stops while the battery is low (see `battery_saver.rs`), leaving a gap.
+
+use crate::{lock_session, Session};
+use msi_protocol::HistorySample;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+/// How far back history is kept, as in the app.
+const RETENTION_SECS: u64 = 30 * 60;
+
+#[derive(Debug, Default)]
+pub struct History {
+    /// Set once the daemon starts sampling.
+    pub recording: bool,
+    samples: VecDeque<HistorySample>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl History {
+    /// Append a sample and drop those older than `RETENTION_SECS`.
+    fn push(&mut self, sample: HistorySample) {
+        let cutoff = sample.timestamp_ms.saturating_sub(RETENTION_SECS * 1000);
+        while self
+            .samples
+            .front()
+            .is_some_and(|s| s.timestamp_ms < cutoff)
+        {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Samples from the last `range_secs` seconds, oldest first.
+    pub fn last(&self, range_secs: u64) -> Vec<HistorySample> {
+        let since = now_ms().saturating_sub(range_secs.min(RETENTION_SECS) * 1000);
+        self.samples
+            .iter()
+            .filter(|s| s.timestamp_ms >= since)
+            .copied()
+            .collect()
+    }
+}
+
+fn sample(session: &mut Session) {
+    if session.saving_battery {
+        return;
+    }
+    // Failed reads leave a gap rather than a made-up sample
+    let Ok(reading) = session.backend.read() else {
+        return;
+    };
+    session.history.push(HistorySample {
+        timestamp_ms: now_ms(),
+        cpu_temp: reading.cpu_temp,
+        gpu_temp: reading.gpu_temp,
+        fan1_rpm: reading.fan1_rpm,
+        fan2_rpm: reading.fan2_rpm,
+    });
+}
+
+/// Record a sample every two seconds for as long as the process runs.
+pub fn spawn(session: Arc<Mutex<Session>>) {
+    lock_session(&session).history.recording = true;
+    thread::spawn(move || loop {
+        sample(&mut lock_session(&session));
+        thread::sleep(SAMPLE_INTERVAL);
+    });
+}
@@ -1,357 +1,778 @@
 //! MSI EC Sidecar - Privileged binary for EC register access
 //!
 //! This binary runs with root privileges via pkexec and handles
-//! all Embedded Controller I/O operations.
-
-use serde::{Deserialize, Serialize};
-use std::fs::{File, OpenOptions};
-use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
-use std::path::Path;
-use std::process::Command as ProcessCommand;
-
-const EC_IO_PATH: &str = "/sys/kernel/debug/ec/ec0/io";
-
-// Register offsets from MSI EC documentation & MControlCenter
-const REG_CPU_TEMP: u64 = 0x68;
-const REG_GPU_TEMP: u64 = 0x80;
-
-const REG_COOLER_BOOST: u64 = 0x98;
-const COOLER_BOOST_BIT: u8 = 0x80; // Bit 7
-
-// Fan 1 (CPU) candidates
-const REG_FAN1_RPM_L_0XC9: u64 = 0xC9;
-const REG_FAN1_RPM_H_0XC9: u64 = 0xC8;
-const REG_FAN1_RPM_L_0XCD: u64 = 0xCD; // MControlCenter prefers CD if non-zero
-const REG_FAN1_RPM_H_0XCD: u64 = 0xCC;
-
-// Fan 2 (GPU)
-const REG_FAN2_RPM_L: u64 = 0xCB;
-const REG_FAN2_RPM_H: u64 = 0xCA;
-
-// Fan mode control (Advanced fan control)
-const REG_FAN_MODE_0XD4: u64 = 0xD4;
-const REG_FAN_MODE_0XF4: u64 = 0xF4;
-const FAN_MODE_AUTO: u8 = 0x0D;
-const FAN_MODE_SILENT: u8 = 0x1D;
-const FAN_MODE_BASIC: u8 = 0x4D;
-const FAN_MODE_ADVANCED: u8 = 0x8D;
-
-// Fan 1 (CPU) speed curve - 7 speed points (0x72-0x78)
-const REG_FAN1_SPEED_START: u64 = 0x72;
-// Fan 2 (GPU) speed curve - 7 speed points (0x8A-0x90)
-const REG_FAN2_SPEED_START: u64 = 0x8A;
-const FAN_SPEED_POINTS: u64 = 7;
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(tag = "cmd", content = "data")]
-enum Command {
-    #[serde(rename = "get_status")]
-    GetStatus,
-    #[serde(rename = "set_cooler_boost")]
-    SetCoolerBoost { enabled: bool },
-    #[serde(rename = "set_fan_speed")]
-    SetFanSpeed { percent: u8 },
-    #[serde(rename = "set_fan_mode")]
-    SetFanMode { mode: String },
-    #[serde(rename = "exit")]
-    Exit,
+//! all Embedded Controller I/O operations, through `msi-ec-core`.
+
+mod battery_saver;
+mod capabilities;
+mod cleanup;
+mod daemon;
+#[cfg(feature = "dbus")]
+mod dbus;
+mod demo;
+mod history;
+mod metrics;
+mod oneshot;
+mod smoothing;
+mod watchdog;
+
+use backend::{EcBackend, EcReading};
+use curve::{check_duty, from_ec_duty, to_ec_duty, validate_curve};
+use msi_ec_core::{backend, curve, ec, kbd_backlight, model, quirks};
+use msi_protocol::framing::{self, Line};
+use msi_protocol::{
+    ActiveRegisterMap, Command, Crash, CurvePoint, DetectedModel, Fan, FanBehavior,
+    RegisterMapSource, Reply, Response, Status, StatusLite, SupportLevel, CRASH_PREFIX,
+    RAW_WRITE_CONFIRMATION,
+};
+use quirks::ModelQuirks;
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Fastest status push rate a subscriber can ask for.
+const MIN_PUSH_INTERVAL_MS: u64 = 250;
+
+// Write features that need an explicit opt-in on non-verified models
+const FEATURE_COOLER_BOOST: &str = "cooler_boost";
+const FEATURE_FAN_MODE: &str = "fan_mode";
+const FEATURE_FAN_SPEED: &str = "fan_speed";
+const FEATURE_FAN_CURVE: &str = "fan_curve";
+
+/// Start of the error for writes attempted before a trial write, matched by
+/// the app to ask the user for confirmation.
+const TRIAL_REQUIRED: &str = "Trial write required:";
+
+/// Per-connection state kept across commands.
+struct Session {
+    backend: Box<dyn EcBackend>,
+    product_name: String,
+    register_map: model::Selection,
+    quirks: ModelQuirks,
+    support_level: SupportLevel,
+    opt_ins: Vec<String>,
+    /// A trial write passed, so real writes are allowed on a non-verified
+    /// model.
+    trial_passed: bool,
+    boost_since: Option<Instant>,
+    /// `--simulate`: nothing is read from or written to the real EC.
+    simulated: bool,
+    /// `--demo`: simulated, with readings animated by `demo.rs`.
+    demo: bool,
+    failsafe: watchdog::Failsafe,
+    smoothing: smoothing::Smoothing,
+    history: history::History,
+    /// The battery is low, so history and metrics are paused; see
+    /// `battery_saver.rs`.
+    saving_battery: bool,
 }
 
-#[derive(Debug, Serialize)]
-struct Status {
-    cpu_temp: u8,
-    gpu_temp: u8,
-    fan1_rpm: u32,
-    fan2_rpm: u32,
-    cooler_boost: bool,
-    fan_mode: String,
-}
-
-#[derive(Debug, Serialize)]
-#[serde(tag = "type")]
-enum Response {
-    #[serde(rename = "status")]
-    Status(Status),
-    #[serde(rename = "ok")]
-    Ok { message: String },
-    #[serde(rename = "error")]
-    Error { message: String },
-}
-
-fn setup_ec_module() {
-    // 1. Check if module is loaded by checking file existence
-    if !Path::new(EC_IO_PATH).exists() {
-        eprintln!("EC module not loaded. Attempting to load...");
-        let status = ProcessCommand::new("modprobe")
-            .arg("ec_sys")
-            .arg("write_support=1")
-            .status();
-
-        match status {
-            Ok(s) if s.success() => eprintln!("Successfully loaded ec_sys"),
-            _ => eprintln!("Failed to load ec_sys. Cooler Boost might fail."),
+impl Session {
+    fn detected_model(&self) -> DetectedModel {
+        DetectedModel {
+            model: self.product_name.clone(),
+            register_map: self.register_map.map.name.to_string(),
+            backend: self.backend.name().to_string(),
         }
     }
 
-    // 2. Setup Persistence (Best Effort)
-    // /etc/modules-load.d/ec_sys.conf
-    let load_conf = Path::new("/etc/modules-load.d/ec_sys.conf");
-    if !load_conf.exists() {
-        if let Ok(mut f) = OpenOptions::new().create(true).truncate(true).write(true).open(load_conf) {
-            let _ = writeln!(f, "ec_sys");
-            eprintln!("Created persistence: {:?}", load_conf);
+    /// Detect the model and pick a backend and register map for it. When
+    /// simulating, the EC lives in memory and every write is allowed.
+    fn new(simulate: bool) -> Self {
+        let product_name = quirks::read_product_name();
+        let register_map = model::detect(&product_name);
+        let (backend, support_level) = if simulate {
+            (backend::simulated(register_map.map), SupportLevel::Verified)
+        } else {
+            (
+                backend::detect(register_map.map),
+                quirks::support_level(&product_name),
+            )
+        };
+        Session {
+            backend,
+            register_map,
+            quirks: quirks::lookup(&product_name),
+            support_level,
+            product_name,
+            opt_ins: Vec::new(),
+            trial_passed: false,
+            boost_since: None,
+            simulated: simulate,
+            demo: false,
+            failsafe: watchdog::Failsafe::default(),
+            smoothing: smoothing::Smoothing::default(),
+            history: history::History::default(),
+            saving_battery: false,
         }
     }
 
-    // /etc/modprobe.d/ec_sys.conf
-    let modprobe_conf = Path::new("/etc/modprobe.d/ec_sys.conf");
-    if !modprobe_conf.exists() {
-        if let Ok(mut f) = OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(modprobe_conf)
-        {
-            let _ = writeln!(f, "options ec_sys write_support=1");
-            eprintln!("Created persistence: {:?}", modprobe_conf);
+    /// Run a write if the model is verified, or if the feature was opted
+    /// into and a trial write passed. Outcomes on non-verified models are
+    /// logged for support reports.
+    fn gated_write(
+        &mut self,
+        feature: &str,
+        write: impl FnOnce(&mut Session) -> Result<(), String>,
+    ) -> Result<(), String> {
+        if self.support_level == SupportLevel::Verified {
+            return write(self);
+        }
+        if !self.opt_ins.iter().any(|f| f == feature) {
+            let model = if self.product_name.is_empty() {
+                "unknown model"
+            } else {
+                &self.product_name
+            };
+            return Err(format!(
+                "Writing {} is disabled on unverified models ({}); enable the experimental {} opt-in to allow it",
+                feature, model, feature
+            ));
+        }
+        if !self.trial_passed {
+            return Err(format!(
+                "{} Run trial_write first to check this model's EC accepts writes",
+                TRIAL_REQUIRED
+            ));
         }
+        let result = write(self);
+        quirks::log_experimental_write(&self.product_name, feature, &result);
+        result
     }
 }
 
-fn read_ec_snapshot() -> io::Result<Vec<u8>> {
-    let mut file = File::open(EC_IO_PATH)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    Ok(buffer)
+fn set_fan_speed_fixed(session: &mut Session, percent: u8, force: bool) -> Result<(), String> {
+    check_duty(&session.quirks, percent, force)?;
+    let fans = smoothing::fans(session);
+    if !session.smoothing.ramp_to(fans, percent) {
+        let duty = to_ec_duty(&session.quirks, percent);
+        session.backend.set_fan_speed(duty)?;
+        session.smoothing.written(fans, percent);
+    }
+    session.failsafe.armed = true;
+    Ok(())
 }
 
-fn write_ec_byte(offset: u64, value: u8) -> io::Result<()> {
-    let mut file = OpenOptions::new().write(true).open(EC_IO_PATH)?;
-    file.seek(SeekFrom::Start(offset))?;
-    file.write_all(&[value])?;
-    file.flush()?;
+fn set_fan_speed_for(
+    session: &mut Session,
+    fan: Fan,
+    percent: u8,
+    force: bool,
+) -> Result<(), String> {
+    check_duty(&session.quirks, percent, force)?;
+    if !session.smoothing.ramp_to(&[fan], percent) {
+        let duty = to_ec_duty(&session.quirks, percent);
+        session.backend.set_fan_speed_for(fan, duty)?;
+        session.smoothing.written(&[fan], percent);
+    }
+    session.failsafe.armed = true;
     Ok(())
 }
 
-fn get_fan_rpm(buffer: &[u8], low_offset: usize, high_offset: usize) -> u32 {
-    if high_offset >= buffer.len() || low_offset >= buffer.len() {
-        return 0;
+/// Silent mode caps the fans, so the watchdog guards it like a fixed speed.
+fn set_fan_mode(session: &mut Session, mode: &str) -> Result<(), String> {
+    session.backend.set_fan_mode(mode)?;
+    session.smoothing.reset();
+    session.failsafe.armed = mode == "silent";
+    Ok(())
+}
+
+fn set_fan_curve(
+    session: &mut Session,
+    cpu: &[CurvePoint],
+    gpu: &[CurvePoint],
+    force: bool,
+) -> Result<(), String> {
+    validate_curve("CPU", cpu)?;
+    validate_curve("GPU", gpu)?;
+    let max_speed = cpu.iter().chain(gpu).map(|p| p.speed).max().unwrap_or(0);
+    check_duty(&session.quirks, max_speed, force)?;
+    // A curve follows the temperature, no watchdog needed
+    session.failsafe.armed = false;
+    if session.smoothing.follows_curves() {
+        session.smoothing.follow(cpu, gpu);
+        return Ok(());
     }
-    let low = buffer[low_offset] as u32;
-    let high = buffer[high_offset] as u32;
+    write_ec_curve(session, cpu, gpu)
+}
 
-    let value = (high << 8) | low;
+/// Hand a curve to the EC, which then runs the fans on its own.
+fn write_ec_curve(
+    session: &mut Session,
+    cpu: &[CurvePoint],
+    gpu: &[CurvePoint],
+) -> Result<(), String> {
+    let to_ec = |points: &[CurvePoint]| -> Vec<CurvePoint> {
+        points
+            .iter()
+            .map(|p| CurvePoint {
+                temp: p.temp,
+                speed: to_ec_duty(&session.quirks, p.speed),
+            })
+            .collect()
+    };
+    let (cpu, gpu) = (to_ec(cpu), to_ec(gpu));
+    session.backend.set_fan_curve(&cpu, &gpu)?;
+    session.smoothing.reset();
+    Ok(())
+}
 
-    if value > 0 {
-        470000 / value
-    } else {
-        0
+/// Leave a curve the sidecar was following to the EC, e.g. before exiting.
+fn hand_curve_to_ec(session: &mut Session) -> Result<(), String> {
+    match session.smoothing.curve() {
+        Some((cpu, gpu)) => write_ec_curve(session, &cpu, &gpu),
+        None => Ok(()),
     }
 }
 
-fn get_fan1_rpm(buffer: &[u8]) -> u32 {
-    // Check 0xCD first
-    let rpm_cd = get_fan_rpm(
-        buffer,
-        REG_FAN1_RPM_L_0XCD as usize,
-        REG_FAN1_RPM_H_0XCD as usize,
-    );
-    if rpm_cd > 0 && rpm_cd < 10000 {
-        return rpm_cd;
+fn set_fan_behavior(session: &mut Session, behavior: FanBehavior) -> Result<String, String> {
+    if behavior.hysteresis > smoothing::MAX_HYSTERESIS {
+        return Err(format!(
+            "Hysteresis must be at most {} °C",
+            smoothing::MAX_HYSTERESIS
+        ));
     }
-    // Fallback to 0xC9
-    get_fan_rpm(
-        buffer,
-        REG_FAN1_RPM_L_0XC9 as usize,
-        REG_FAN1_RPM_H_0XC9 as usize,
-    )
+    if behavior.ramp_rate > smoothing::MAX_RAMP_RATE {
+        return Err(format!(
+            "Ramp rate must be at most {} %/s",
+            smoothing::MAX_RAMP_RATE
+        ));
+    }
+    session.smoothing.behavior = behavior;
+    if !session.smoothing.follows_curves() {
+        hand_curve_to_ec(session)?;
+        return Ok("Fan changes are no longer smoothed".to_string());
+    }
+    Ok(format!(
+        "Fan changes ramp at {} %/s, curves step down after {} °C",
+        behavior.ramp_rate, behavior.hysteresis
+    ))
 }
 
-fn detect_fan_mode_address(buffer: &[u8]) -> u64 {
-    let val_d4 = buffer.get(REG_FAN_MODE_0XD4 as usize).copied().unwrap_or(0);
-    if val_d4 == FAN_MODE_AUTO
-        || val_d4 == FAN_MODE_SILENT
-        || val_d4 == FAN_MODE_BASIC
-        || val_d4 == FAN_MODE_ADVANCED
-    {
-        return REG_FAN_MODE_0XD4;
+/// Switch to the register map with a corrected tachometer constant and
+/// save it as the override, except when simulating.
+fn set_rpm_divisor(session: &mut Session, divisor: u32) -> Result<ActiveRegisterMap, String> {
+    let mut selection = model::with_rpm_divisor(&session.register_map, divisor)?;
+    // Asked first, so nothing is saved for a backend that ignores the map
+    session.backend.set_register_map(selection.map)?;
+    if !session.simulated {
+        if let Err(e) = model::save_override(selection.map) {
+            let _ = session.backend.set_register_map(session.register_map.map);
+            return Err(e);
+        }
+        selection.source = RegisterMapSource::Override;
     }
-    REG_FAN_MODE_0XF4
+    session.register_map = selection;
+    Ok(session.register_map.describe())
 }
 
-fn get_fan_mode_string(buffer: &[u8]) -> String {
-    let fan_mode_addr = detect_fan_mode_address(buffer);
-    let mode_value = buffer.get(fan_mode_addr as usize).copied().unwrap_or(0);
-    match mode_value {
-        FAN_MODE_AUTO => "auto".to_string(),
-        FAN_MODE_SILENT => "silent".to_string(),
-        FAN_MODE_BASIC => "basic".to_string(),
-        FAN_MODE_ADVANCED => "advanced".to_string(),
-        _ => format!("unknown(0x{:02X})", mode_value),
+/// Write one register for someone mapping the EC, after the confirmation
+/// and the blocklist. Every attempt is logged, and on stderr.
+fn write_ec_register(
+    session: &mut Session,
+    offset: u8,
+    value: u8,
+    confirm: &str,
+) -> Result<String, String> {
+    let mut before = None;
+    let result = if confirm != RAW_WRITE_CONFIRMATION {
+        Err(format!(
+            "Raw register writes need confirm set to \"{}\"",
+            RAW_WRITE_CONFIRMATION
+        ))
+    } else if let Some(reason) = ec::blocked_register(offset) {
+        Err(format!("Refusing to write 0x{:02X}: {}", offset, reason))
+    } else if let Some(blocker) = session.backend.write_blocker() {
+        Err(blocker)
+    } else {
+        session
+            .backend
+            .write_register(offset, value)
+            .map(|previous| before = Some(previous))
+    };
+    if !session.simulated {
+        quirks::log_register_write(&session.product_name, offset, value, before, &result);
     }
+    let change = format!(
+        "0x{:02X}: 0x{:02X} -> 0x{:02X}",
+        offset,
+        before.unwrap_or(0),
+        value
+    );
+    match &result {
+        Ok(()) => eprintln!("Raw write {}", change),
+        Err(e) => eprintln!("Raw write 0x{:02X} = 0x{:02X} failed: {}", offset, value, e),
+    }
+    result.map(|()| change)
 }
 
-fn set_fan_speed_fixed(percent: u8) -> Result<(), String> {
-    let buffer = read_ec_snapshot().map_err(|e| e.to_string())?;
-    let fan_mode_addr = detect_fan_mode_address(&buffer);
-
-    // 1. Enable Advanced mode
-    write_ec_byte(fan_mode_addr, FAN_MODE_ADVANCED).map_err(|e| e.to_string())?;
-
-    // 2. Set all 7 speed points to the same value for Fan 1 (CPU)
-    for i in 0..FAN_SPEED_POINTS {
-        write_ec_byte(REG_FAN1_SPEED_START + i, percent).map_err(|e| e.to_string())?;
+/// Track how long boost has been on, whoever turned it on. Returns whether
+/// a cooldown should be suggested.
+fn track_boost(session: &mut Session, cooler_boost: bool) -> bool {
+    if cooler_boost {
+        session.boost_since.get_or_insert_with(Instant::now);
+    } else {
+        session.boost_since = None;
     }
+    session
+        .boost_since
+        .is_some_and(|since| since.elapsed().as_secs() >= session.quirks.max_boost_secs)
+}
 
-    // 3. Set all 7 speed points for Fan 2 (GPU)
-    for i in 0..FAN_SPEED_POINTS {
-        write_ec_byte(REG_FAN2_SPEED_START + i, percent).map_err(|e| e.to_string())?;
+/// Read the EC, with fan duties converted to user-facing percentages.
+fn read_ec(session: &mut Session) -> Result<EcReading, String> {
+    let mut reading = session.backend.read()?;
+    for fan in &mut reading.fans {
+        fan.target_percent = fan.target_percent.map(|d| from_ec_duty(&session.quirks, d));
     }
-
-    Ok(())
+    Ok(reading)
 }
 
-fn set_fan_mode(mode: &str) -> Result<(), String> {
-    let buffer = read_ec_snapshot().map_err(|e| e.to_string())?;
-    let fan_mode_addr = detect_fan_mode_address(&buffer);
-
-    let mode_value = match mode {
-        "auto" => FAN_MODE_AUTO,
-        "silent" => FAN_MODE_SILENT,
-        "basic" => FAN_MODE_BASIC,
-        "advanced" => FAN_MODE_ADVANCED,
-        _ => return Err(format!("Unknown mode: {}", mode)),
-    };
+fn get_status(session: &mut Session) -> Result<Status, String> {
+    let reading = read_ec(session)?;
+    let boost_cooldown_suggested = track_boost(session, reading.cooler_boost);
 
-    write_ec_byte(fan_mode_addr, mode_value).map_err(|e| e.to_string())
+    Ok(Status {
+        cpu_temp: reading.cpu_temp,
+        gpu_temp: reading.gpu_temp,
+        fan1_rpm: reading.fan1_rpm,
+        fan2_rpm: reading.fan2_rpm,
+        fans: reading.fans,
+        cooler_boost: reading.cooler_boost,
+        fan_mode: reading.fan_mode,
+        boost_cooldown_suggested,
+        backend: session.backend.name().to_string(),
+        model: session.product_name.clone(),
+        support_level: session.support_level,
+        simulated: session.simulated,
+        demo: session.demo,
+        warnings: Vec::new(),
+    })
 }
 
-fn get_status() -> Result<Status, String> {
-    let buffer = read_ec_snapshot().map_err(|e| format!("Failed to read EC: {}", e))?;
+fn get_status_lite(session: &mut Session) -> Result<StatusLite, String> {
+    let reading = session.backend.read_lite()?;
+    let boost_cooldown_suggested = track_boost(session, reading.cooler_boost);
 
-    // Safety check
-    if buffer.len() < 0xFF {
-        // Ensure we have enough data
-        return Err(format!("EC buffer too small: {} bytes", buffer.len()));
-    }
+    Ok(StatusLite {
+        cpu_temp: reading.cpu_temp,
+        gpu_temp: reading.gpu_temp,
+        cooler_boost: reading.cooler_boost,
+        boost_cooldown_suggested,
+    })
+}
 
-    let cpu_temp = buffer.get(REG_CPU_TEMP as usize).copied().unwrap_or(0);
-    let gpu_temp = buffer.get(REG_GPU_TEMP as usize).copied().unwrap_or(0);
+/// Active status push schedule.
+struct Subscription {
+    interval: Duration,
+    lite: bool,
+    next: Instant,
+}
 
-    let cooler_boost_byte = buffer.get(REG_COOLER_BOOST as usize).copied().unwrap_or(0);
-    let cooler_boost = (cooler_boost_byte & COOLER_BOOST_BIT) != 0;
+/// Where one client's responses go: stdout, or a daemon socket connection.
+type Output = Arc<Mutex<dyn Write + Send>>;
 
-    let fan1_rpm = get_fan1_rpm(&buffer);
-    let fan2_rpm = get_fan_rpm(&buffer, REG_FAN2_RPM_L as usize, REG_FAN2_RPM_H as usize);
-    let fan_mode = get_fan_mode_string(&buffer);
+/// Every connected client, for unsolicited messages such as failsafe trips.
+type Outputs = Arc<Mutex<Vec<Output>>>;
 
-    Ok(Status {
-        cpu_temp,
-        gpu_temp,
-        fan1_rpm,
-        fan2_rpm,
-        cooler_boost,
-        fan_mode,
-    })
+/// Push one status line. Failed reads are skipped rather than sent as errors,
+/// which the app would take as the reply to its next command.
+fn push_status(session: &mut Session, lite: bool, output: &Output) {
+    if lite {
+        if let Ok(status) = get_status_lite(session) {
+            send_response(output, &Response::StatusLiteUpdate(status));
+        }
+    } else if let Ok(status) = get_status(session) {
+        send_response(output, &Response::StatusUpdate(status));
+    }
 }
 
-fn set_cooler_boost(enabled: bool) -> Result<(), String> {
-    // Read current state first
-    let buffer = read_ec_snapshot().map_err(|e| e.to_string())?;
-    // Or just open and read single byte?? Snapshot is safer.
-    let current = buffer
-        .get(REG_COOLER_BOOST as usize)
-        .copied()
-        .ok_or("Cannot read cooler boost reg")?;
-
-    let new_value = if enabled {
-        current | COOLER_BOOST_BIT
-    } else {
-        current & !COOLER_BOOST_BIT
+/// Write an unsolicited line, i.e. one answering no request.
+fn send_response(output: &Output, response: &Response) {
+    send_reply(output, None, response);
+}
+
+fn send_reply(output: &Output, id: Option<u64>, response: &Response) {
+    let reply = Reply {
+        id,
+        response: response.clone(),
+    };
+    let Ok(json) = serde_json::to_string(&reply) else {
+        return;
     };
+    if let Ok(mut out) = output.lock() {
+        let _ = writeln!(out, "{}", json);
+        // Flush to ensure the response is sent immediately
+        let _ = out.flush();
+    }
+}
 
-    write_ec_byte(REG_COOLER_BOOST, new_value).map_err(|e| e.to_string())?;
+/// The session stays usable if a thread panicked while holding it.
+fn lock_session(session: &Mutex<Session>) -> MutexGuard<'_, Session> {
+    session.lock().unwrap_or_else(|e| e.into_inner())
+}
 
-    // Check verification? skipping for speed, relying on UI to poll
-    Ok(())
+/// Run one command against the session. Subscriptions and `exit` change the
+/// main loop and are handled there.
+fn handle_command(session: &mut Session, cmd: Command) -> Response {
+    match cmd {
+        Command::GetStatus => get_status(session).map_or_else(error, Response::Status),
+        Command::GetStatusLite => get_status_lite(session).map_or_else(error, Response::StatusLite),
+        Command::DumpEc => session
+            .backend
+            .dump()
+            .map_or_else(error, |data| Response::EcDump { data }),
+        Command::Hello { protocol_version } => {
+            Response::Hello(capabilities::hello(session, protocol_version))
+        }
+        Command::GetDetectedModel => Response::DetectedModel(session.detected_model()),
+        Command::GetRegisterMap => Response::RegisterMap(session.register_map.describe()),
+        Command::SetRpmDivisor { divisor } => {
+            set_rpm_divisor(session, divisor).map_or_else(error, Response::RegisterMap)
+        }
+        Command::GetCapabilities => Response::Capabilities {
+            capabilities: capabilities::report(session),
+        },
+        // The LED class device is real hardware, outside the simulated EC
+        Command::GetKeyboardBacklight | Command::SetKeyboardBacklight { .. }
+            if session.simulated =>
+        {
+            error("The keyboard backlight is not simulated".to_string())
+        }
+        Command::GetKeyboardBacklight => {
+            kbd_backlight::read().map_or_else(error, Response::KeyboardBacklight)
+        }
+        // Goes through the LED class driver, not raw EC writes, so it
+        // isn't gated on the model
+        Command::SetKeyboardBacklight { brightness } => reply(
+            kbd_backlight::set(brightness),
+            format!("Keyboard backlight set to {}", brightness),
+        ),
+        Command::SetCoolerBoost { enabled } => reply(
+            session.gated_write(FEATURE_COOLER_BOOST, |s| {
+                s.backend.set_cooler_boost(enabled)
+            }),
+            format!(
+                "Cooler Boost {}",
+                if enabled { "enabled" } else { "disabled" }
+            ),
+        ),
+        Command::SetFanSpeed { percent, force } => reply(
+            session.gated_write(FEATURE_FAN_SPEED, |s| {
+                set_fan_speed_fixed(s, percent, force)
+            }),
+            format!("Fan speed set to {}%", percent),
+        ),
+        Command::SetFanSpeedPerFan {
+            fan,
+            percent,
+            force,
+        } => reply(
+            session.gated_write(FEATURE_FAN_SPEED, |s| {
+                set_fan_speed_for(s, fan, percent, force)
+            }),
+            format!(
+                "{} speed set to {}%",
+                match fan {
+                    Fan::Fan1 => "CPU fan",
+                    Fan::Fan2 => "GPU fan",
+                },
+                percent
+            ),
+        ),
+        Command::SetFanMode { mode } => reply(
+            session.gated_write(FEATURE_FAN_MODE, |s| set_fan_mode(s, &mode)),
+            format!("Fan mode set to {}", mode),
+        ),
+        Command::SetFanCurve { cpu, gpu, force } => reply(
+            session.gated_write(FEATURE_FAN_CURVE, |s| set_fan_curve(s, &cpu, &gpu, force)),
+            "Fan curve applied".to_string(),
+        ),
+        Command::SetOptIns { features } => {
+            let message = format!("Experimental opt-ins: {}", features.join(", "));
+            session.opt_ins = features;
+            Response::Ok { message }
+        }
+        Command::SetFanBehavior(behavior) => set_fan_behavior(session, behavior)
+            .map_or_else(error, |message| Response::Ok { message }),
+        Command::SetFailsafe(config) => {
+            if let Err(e) = config.validate() {
+                return error(e);
+            }
+            session.failsafe.config = config;
+            Response::Ok {
+                message: format!(
+                    "Failsafe at {} °C, heartbeat timeout {} s",
+                    config.max_temp, config.heartbeat_timeout_secs
+                ),
+            }
+        }
+        Command::WriteEcRegister {
+            offset,
+            value,
+            confirm,
+        } => write_ec_register(session, offset, value, &confirm)
+            .map_or_else(error, |message| Response::Ok { message }),
+        Command::LoadEcModule => Response::EcModule {
+            error: session.backend.load_module().err(),
+        },
+        Command::InstallPersistence | Command::RemovePersistence | Command::CleanupSystemFiles
+            if session.simulated =>
+        {
+            error("Nothing is written to /etc while simulating".to_string())
+        }
+        Command::InstallPersistence => {
+            ec::install_persistence().map_or_else(error, Response::Persistence)
+        }
+        Command::RemovePersistence => {
+            ec::remove_persistence().map_or_else(error, Response::Persistence)
+        }
+        Command::CleanupSystemFiles => Response::Cleanup(cleanup::run()),
+        Command::TrialWrite => {
+            let result = session.backend.trial_write();
+            if session.support_level != SupportLevel::Verified {
+                let logged = result.clone().map(|_| ());
+                quirks::log_experimental_write(&session.product_name, "trial_write", &logged);
+            }
+            session.trial_passed = result.is_ok();
+            result.map_or_else(error, |message| Response::Ok { message })
+        }
+        Command::SetWriteRetries { retries } => {
+            session.backend.set_write_retries(retries);
+            Response::Ok {
+                message: format!("EC writes retried up to {} time(s)", retries),
+            }
+        }
+        Command::GetHistory { range_secs } => {
+            if !session.history.recording {
+                return error("History is only kept by the daemon".to_string());
+            }
+            Response::History {
+                samples: session.history.last(range_secs),
+            }
+        }
+        Command::Subscribe { .. } | Command::Heartbeat | Command::Exit => {
+            error("Only available in a stdin session".to_string())
+        }
+    }
 }
 
-fn send_response(response: &Response) {
-    if let Ok(json) = serde_json::to_string(response) {
-        println!("{}", json);
-        // Flush to ensure the response is sent immediately
-        let _ = std::io::stdout().flush();
+fn error(message: String) -> Response {
+    Response::Error { message }
+}
+
+/// `ok` with `message` if the write succeeded, the write's error otherwise.
+fn reply(result: Result<(), String>, message: String) -> Response {
+    match result {
+        Ok(()) => Response::Ok { message },
+        Err(e) => error(e),
     }
 }
 
-fn main() {
-    setup_ec_module();
+/// Commands that change more than the fans, or undo their safeguards:
+/// only root may send them to the daemon.
+fn root_only(cmd: &Command) -> bool {
+    matches!(
+        cmd,
+        Command::WriteEcRegister { .. }
+            | Command::LoadEcModule
+            | Command::CleanupSystemFiles
+            | Command::InstallPersistence
+            | Command::RemovePersistence
+            | Command::SetRpmDivisor { .. }
+            | Command::SetOptIns { .. }
+            | Command::TrialWrite
+            | Command::SetFailsafe(_)
+    )
+}
+
+/// Talk to one client: send the initial status, then answer the commands
+/// read from `input` until it closes or sends `exit`. Its heartbeats are
+/// dropped from the failsafe when it goes. `root` is false for
+/// daemon clients that aren't root, which are refused [`root_only`]
+/// commands.
+fn serve(
+    session: &Mutex<Session>,
+    mut input: impl BufRead + Send + 'static,
+    output: &Output,
+    root: bool,
+) {
+    static NEXT_CLIENT: AtomicU64 = AtomicU64::new(0);
+    let client = NEXT_CLIENT.fetch_add(1, Ordering::Relaxed);
 
     // Send initial status
-    match get_status() {
-        Ok(status) => send_response(&Response::Status(status)),
-        Err(e) => send_response(&Response::Error { message: e }),
+    match get_status(&mut lock_session(session)) {
+        Ok(status) => send_response(output, &Response::Status(status)),
+        Err(e) => send_response(output, &Response::Error { message: e }),
     }
 
-    let stdin = io::stdin();
-    for line in stdin.lock().lines() {
-        let line: String = match line {
-            Ok(l) => l,
-            Err(_) => break,
+    // Read input on its own thread so the loop can wake up for pushes
+    let (lines_tx, lines_rx) = mpsc::channel::<Line>();
+    thread::spawn(move || loop {
+        match framing::read_line(&mut input) {
+            Ok(Line::Eof) | Err(_) => break,
+            Ok(line) => {
+                if lines_tx.send(line).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut subscription: Option<Subscription> = None;
+    loop {
+        let line = match subscription.as_mut() {
+            Some(sub) => {
+                let wait = sub.next.saturating_duration_since(Instant::now());
+                match lines_rx.recv_timeout(wait) {
+                    Ok(l) => l,
+                    Err(RecvTimeoutError::Timeout) => {
+                        sub.next = Instant::now() + sub.interval;
+                        let lite = sub.lite;
+                        push_status(&mut lock_session(session), lite, output);
+                        continue;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            None => match lines_rx.recv() {
+                Ok(l) => l,
+                Err(_) => break,
+            },
         };
 
-        if line.is_empty() {
-            continue;
-        }
+        let line = match line {
+            Line::Message(line) if line.is_empty() => continue,
+            Line::Message(line) => line,
+            Line::TooLong { id } => {
+                let message = format!(
+                    "Command longer than {} bytes ignored",
+                    framing::MAX_LINE_BYTES
+                );
+                send_reply(output, id, &Response::Error { message });
+                continue;
+            }
+            Line::Eof => break,
+        };
 
-        let cmd: Command = match serde_json::from_str(&line) {
-            Ok(c) => c,
-            Err(e) => {
-                send_response(&Response::Error {
-                    message: format!("Invalid command: {}", e),
-                });
+        let (id, cmd) = match framing::parse_request(&line) {
+            Ok(request) => (request.id, request.command),
+            Err(invalid) => {
+                let message = invalid.message;
+                send_reply(output, invalid.id, &Response::Error { message });
                 continue;
             }
         };
 
         match cmd {
-            Command::GetStatus => match get_status() {
-                Ok(status) => send_response(&Response::Status(status)),
-                Err(e) => send_response(&Response::Error { message: e }),
-            },
-            Command::SetCoolerBoost { enabled } => match set_cooler_boost(enabled) {
-                Ok(()) => {
-                    send_response(&Response::Ok {
-                        message: format!(
-                            "Cooler Boost {}",
-                            if enabled { "enabled" } else { "disabled" }
-                        ),
-                    });
-                }
-                Err(e) => send_response(&Response::Error { message: e }),
-            },
-            Command::SetFanSpeed { percent } => match set_fan_speed_fixed(percent) {
-                Ok(()) => {
-                    send_response(&Response::Ok {
-                        message: format!("Fan speed set to {}%", percent),
-                    });
-                }
-                Err(e) => send_response(&Response::Error { message: e }),
-            },
-            Command::SetFanMode { mode } => match set_fan_mode(&mode) {
-                Ok(()) => {
-                    send_response(&Response::Ok {
-                        message: format!("Fan mode set to {}", mode),
+            Command::Subscribe { interval_ms, lite } => {
+                if interval_ms == 0 {
+                    subscription = None;
+                    send_reply(
+                        output,
+                        id,
+                        &Response::Ok {
+                            message: "Unsubscribed".to_string(),
+                        },
+                    );
+                } else {
+                    let interval = Duration::from_millis(interval_ms.max(MIN_PUSH_INTERVAL_MS));
+                    subscription = Some(Subscription {
+                        interval,
+                        lite,
+                        next: Instant::now() + interval,
                     });
+                    send_reply(
+                        output,
+                        id,
+                        &Response::Ok {
+                            message: format!(
+                                "Pushing {} every {} ms",
+                                if lite { "lite status" } else { "status" },
+                                interval.as_millis()
+                            ),
+                        },
+                    );
                 }
-                Err(e) => send_response(&Response::Error { message: e }),
-            },
+            }
             Command::Exit => {
-                send_response(&Response::Ok {
-                    message: "Goodbye".to_string(),
-                });
+                send_reply(
+                    output,
+                    id,
+                    &Response::Ok {
+                        message: "Goodbye".to_string(),
+                    },
+                );
                 break;
             }
+            Command::Heartbeat => {
+                lock_session(session).failsafe.heartbeat(client);
+                send_reply(
+                    output,
+                    id,
+                    &Response::Ok {
+                        message: "Alive".to_string(),
+                    },
+                );
+            }
+            cmd if !root && root_only(&cmd) => send_reply(
+                output,
+                id,
+                &error("Only root may send this command to the daemon".to_string()),
+            ),
+            cmd => send_reply(output, id, &handle_command(&mut lock_session(session), cmd)),
+        }
+    }
+    lock_session(session).failsafe.forget(client);
+}
+
+/// Report panics on stderr as a [`Crash`] the app can save, before the
+/// usual message. Costs nothing until something panics, so it is always on;
+/// whether reports are kept is up to the app.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let crash = Crash::capture(env!("CARGO_PKG_VERSION"), info);
+        if let Ok(json) = serde_json::to_string(&crash) {
+            eprintln!("{}{}", CRASH_PREFIX, json);
         }
+        default_hook(info);
+    }));
+}
+
+fn main() {
+    install_panic_hook();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let demo = args.first().map(String::as_str) == Some("--demo");
+    let simulate = demo || args.first().map(String::as_str) == Some("--simulate");
+    if simulate {
+        args.remove(0);
+    }
+    match args.first().map(String::as_str) {
+        Some("--oneshot") => std::process::exit(oneshot::run(&args[1..], simulate)),
+        Some("--daemon") => std::process::exit(daemon::run(simulate)),
+        _ => {}
+    }
+
+    let mut session = Session::new(simulate);
+    session.demo = demo;
+    let session = Arc::new(Mutex::new(session));
+    if demo {
+        demo::spawn(session.clone());
+    }
+    let stdout: Output = Arc::new(Mutex::new(io::stdout()));
+    watchdog::spawn(session.clone(), Arc::new(Mutex::new(vec![stdout.clone()])));
+    smoothing::spawn(session.clone());
+    // Started through pkexec, so the app already authenticated as root
+    serve(&session, BufReader::new(io::stdin()), &stdout, true);
+
+    // The app is gone; nobody is left to watch the temperatures
+    let mut session = lock_session(&session);
+    if let Err(e) = hand_curve_to_ec(&mut session) {
+        eprintln!("Failed to hand the fan curve to the EC: {}", e);
     }
+    watchdog::on_disconnect(&mut session);
 }
@@ -0,0 +1,147 @@
+//! Prometheus metrics for the daemon, off unless `metrics_port` is set in
+//! its config. A bare-bones HTTP server on std's `TcpListener` answers
+//! `GET /metrics` with the current reading as gauges; anything else gets
+//! a 404. While the battery is low (see `battery_saver.rs`) scrapes get a
+//! 503 without the EC being read.
+
+use crate::{lock_session, read_ec, Session};
+use msi_protocol::framing::{self, Line};
+use std::fmt::Write as _;
+use std::io::{BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const FAN_MODES: &[&str] = &["auto", "silent", "basic", "advanced"];
+/// A scraper that stalls mustn't hold a thread forever.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn gauge(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+}
+
+/// Escape a label value for the text format: backslash, quote, newline.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The exposition text for one scrape, or the reason the EC can't be read.
+fn render(session: &mut Session) -> Result<String, String> {
+    let reading = read_ec(session)?;
+    let mut out = String::new();
+    gauge(&mut out, "msi_cpu_temp_celsius", "CPU temperature.");
+    let _ = writeln!(out, "msi_cpu_temp_celsius {}", reading.cpu_temp);
+    gauge(&mut out, "msi_gpu_temp_celsius", "GPU temperature.");
+    let _ = writeln!(out, "msi_gpu_temp_celsius {}", reading.gpu_temp);
+    gauge(&mut out, "msi_fan_rpm", "Fan speed.");
+    for (i, fan) in reading.fans.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "msi_fan_rpm{{fan=\"{}\",name=\"{}\"}} {}",
+            i + 1,
+            label(&fan.name),
+            fan.rpm
+        );
+    }
+    gauge(
+        &mut out,
+        "msi_fan_target_percent",
+        "Duty the EC drives a fan at.",
+    );
+    for (i, fan) in reading.fans.iter().enumerate() {
+        if let Some(percent) = fan.target_percent {
+            let _ = writeln!(
+                out,
+                "msi_fan_target_percent{{fan=\"{}\",name=\"{}\"}} {}",
+                i + 1,
+                label(&fan.name),
+                percent
+            );
+        }
+    }
+    gauge(&mut out, "msi_cooler_boost", "1 while Cooler Boost is on.");
+    let _ = writeln!(out, "msi_cooler_boost {}", reading.cooler_boost as u8);
+    gauge(&mut out, "msi_fan_mode", "1 for the active fan mode.");
+    for mode in FAN_MODES {
+        let active = reading.fan_mode == *mode;
+        let _ = writeln!(out, "msi_fan_mode{{mode=\"{}\"}} {}", mode, active as u8);
+    }
+    Ok(out)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+}
+
+fn serve_client(session: &Mutex<Session>, mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    // Only the request line matters; headers and body are ignored
+    let Ok(Line::Message(request_line)) = framing::read_line(&mut BufReader::new(reader)) else {
+        return;
+    };
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next();
+    let path = parts.next().and_then(|p| p.split('?').next());
+    if method != Some("GET") || path != Some("/metrics") {
+        respond(&mut stream, "404 Not Found", "Not found; try /metrics\n");
+        return;
+    }
+    let rendered = {
+        let mut session = lock_session(session);
+        if session.saving_battery {
+            Err("Paused while the battery is low\n".to_string())
+        } else {
+            render(&mut session).map_err(|e| format!("EC read failed: {}\n", e))
+        }
+    };
+    match rendered {
+        Ok(body) => respond(&mut stream, "200 OK", &body),
+        Err(reason) => respond(&mut stream, "503 Service Unavailable", &reason),
+    }
+}
+
+/// Serve metrics on `address:port` until the process exits. Failing to
+/// bind is reported and leaves the rest of the daemon running.
+pub fn spawn(session: Arc<Mutex<Session>>, address: &str, port: u16) {
+    let listener = match TcpListener::bind((address, port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to serve metrics on {}:{}: {}", address, port, e);
+            return;
+        }
+    };
+    eprintln!("Serving metrics on http://{}:{}/metrics", address, port);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let session = session.clone();
+            thread::spawn(move || serve_client(&session, stream));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::label;
+
+    #[test]
+    fn label_escapes_backslash_quote_and_newline() {
+        assert_eq!(label("CPU"), "CPU");
+        assert_eq!(label(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(label("a\nb"), r"a\nb");
+    }
+}
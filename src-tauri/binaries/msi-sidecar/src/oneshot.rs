@@ -0,0 +1,152 @@
+//! `--oneshot` mode: run a single command given on the command line and
+//! exit, for systemd units, udev rules and sudo one-liners.
+//!
+//! ```text
+//! msi-sidecar --oneshot set_fan_mode auto
+//! msi-sidecar --oneshot set_fan_speed 80 --force
+//! msi-sidecar --oneshot --opt-in fan_mode set_fan_mode silent
+//! msi-sidecar --simulate --oneshot get_status
+//! ```
+//!
+//! Positional arguments fill the command's fields in order. Each one is
+//! read as JSON if it parses (numbers, `true`, arrays of curve points) and as
+//! a string otherwise. Ok replies print their message, errors go to stderr
+//! with exit code 1 (2 for bad arguments), and data replies print as JSON.
+//!
+//! `--opt-in` counts as confirming writes on a non-verified model, so the
+//! trial write those need runs first.
+
+use crate::{handle_command, Session};
+use msi_protocol::{Command, Response};
+use serde_json::{Map, Value};
+
+/// Field names filled by positional arguments, per command.
+const COMMAND_ARGS: &[(&str, &[&str])] = &[
+    ("get_status", &[]),
+    ("get_status_lite", &[]),
+    ("get_detected_model", &[]),
+    ("get_register_map", &[]),
+    ("get_capabilities", &[]),
+    ("get_keyboard_backlight", &[]),
+    ("dump_ec", &[]),
+    ("load_ec_module", &[]),
+    ("install_persistence", &[]),
+    ("remove_persistence", &[]),
+    ("cleanup_system_files", &[]),
+    ("trial_write", &[]),
+    ("set_rpm_divisor", &["divisor"]),
+    ("set_keyboard_backlight", &["brightness"]),
+    ("set_cooler_boost", &["enabled"]),
+    ("set_fan_speed", &["percent"]),
+    ("set_fan_speed_per_fan", &["fan", "percent"]),
+    ("set_fan_mode", &["mode"]),
+    ("set_fan_curve", &["cpu", "gpu"]),
+    ("write_ec_register", &["offset", "value", "confirm"]),
+];
+
+const USAGE: &str =
+    "Usage: msi-sidecar --oneshot [--opt-in FEATURE]... COMMAND [ARGS]... [--force]";
+
+struct Invocation {
+    command: Command,
+    opt_ins: Vec<String>,
+}
+
+fn parse_value(arg: &str) -> Value {
+    match arg {
+        "on" => Value::Bool(true),
+        "off" => Value::Bool(false),
+        // Register offsets and values are usually written in hex
+        _ if arg.starts_with("0x") => u64::from_str_radix(&arg[2..], 16)
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(arg.to_string())),
+        _ => serde_json::from_str(arg).unwrap_or_else(|_| Value::String(arg.to_string())),
+    }
+}
+
+fn parse(args: &[String]) -> Result<Invocation, String> {
+    let mut opt_ins = Vec::new();
+    let mut force = false;
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--force" => force = true,
+            "--opt-in" => opt_ins.push(iter.next().ok_or("--opt-in needs a feature")?.clone()),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let (&name, values) = positional.split_first().ok_or(USAGE)?;
+    let (_, fields) = COMMAND_ARGS
+        .iter()
+        .find(|(cmd, _)| *cmd == name)
+        .ok_or_else(|| format!("Unknown command: {}\n{}", name, USAGE))?;
+    if values.len() != fields.len() {
+        return Err(format!(
+            "{} takes {} argument(s): {}",
+            name,
+            fields.len(),
+            fields.join(" ")
+        ));
+    }
+
+    let mut data: Map<String, Value> = fields
+        .iter()
+        .zip(values)
+        .map(|(field, value)| (field.to_string(), parse_value(value)))
+        .collect();
+    if force {
+        data.insert("force".to_string(), Value::Bool(true));
+    }
+    let mut request = Map::new();
+    request.insert("cmd".to_string(), Value::String(name.to_string()));
+    if !data.is_empty() {
+        request.insert("data".to_string(), Value::Object(data));
+    }
+
+    let command = serde_json::from_value(Value::Object(request))
+        .map_err(|e| format!("Invalid arguments for {}: {}", name, e))?;
+    Ok(Invocation { command, opt_ins })
+}
+
+/// Run the command in `args` (everything after `--oneshot`) and return the
+/// process exit code.
+pub fn run(args: &[String], simulate: bool) -> i32 {
+    let invocation = match parse(args) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+
+    let mut session = Session::new(simulate);
+    if !invocation.opt_ins.is_empty() {
+        session.opt_ins = invocation.opt_ins;
+        if let Response::Error { message } = handle_command(&mut session, Command::TrialWrite) {
+            eprintln!("Error: {}", message);
+            return 1;
+        }
+    }
+    match handle_command(&mut session, invocation.command) {
+        Response::Ok { message } => {
+            println!("{}", message);
+            0
+        }
+        Response::Error { message } => {
+            eprintln!("Error: {}", message);
+            1
+        }
+        response => match serde_json::to_string_pretty(&response) {
+            Ok(json) => {
+                println!("{}", json);
+                0
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                1
+            }
+        },
+    }
+}
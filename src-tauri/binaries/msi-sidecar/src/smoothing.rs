@@ -0,0 +1,301 @@
+//! Gradual fan speed changes, configured with `set_fan_behavior`.
+//!
+//! With a ramp rate, a fixed speed is approached in steps from the last
+//! speed the sidecar wrote. With a ramp rate or hysteresis, a fan curve is
+//! not handed to the EC, which jumps straight from one point to the next:
+//! a thread looks both fans up on their curve twice a second and writes
+//! flat duties instead, stepping down only once the temperature fell by the
+//! hysteresis. When the app disconnects the curve goes to the EC after all.
+
+use crate::{lock_session, to_ec_duty, Session};
+use msi_protocol::{CurvePoint, Fan, FanBehavior};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const TICK: Duration = Duration::from_millis(500);
+pub const MAX_HYSTERESIS: u8 = 20;
+pub const MAX_RAMP_RATE: u8 = 100;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct FanState {
+    /// Percent the fan is driven toward.
+    target: Option<f32>,
+    /// Last percent the sidecar wrote; `None` while the EC decides.
+    current: Option<f32>,
+    /// Temperature the curve is looked up at; lags falling temperatures.
+    basis: Option<u8>,
+}
+
+#[derive(Debug, Default)]
+pub struct Smoothing {
+    pub behavior: FanBehavior,
+    /// CPU and GPU curves followed by the sidecar, in user percent.
+    curve: Option<[Vec<CurvePoint>; 2]>,
+    fans: [FanState; 2],
+}
+
+fn index(fan: Fan) -> usize {
+    match fan {
+        Fan::Fan1 => 0,
+        Fan::Fan2 => 1,
+    }
+}
+
+impl Smoothing {
+    /// Whether curves are followed here rather than written to the EC.
+    pub fn follows_curves(&self) -> bool {
+        self.behavior.hysteresis > 0 || self.behavior.ramp_rate > 0
+    }
+
+    /// The curve being followed, to hand to the EC.
+    pub fn curve(&self) -> Option<(Vec<CurvePoint>, Vec<CurvePoint>)> {
+        self.curve.clone().map(|[cpu, gpu]| (cpu, gpu))
+    }
+
+    /// A mode, an EC curve or the failsafe took over.
+    pub fn reset(&mut self) {
+        self.curve = None;
+        self.fans = Default::default();
+    }
+
+    /// Start following `cpu` and `gpu`, ramping from the current speeds.
+    pub fn follow(&mut self, cpu: &[CurvePoint], gpu: &[CurvePoint]) {
+        self.curve = Some([cpu.to_vec(), gpu.to_vec()]);
+        for fan in &mut self.fans {
+            fan.target = None;
+            fan.basis = None;
+        }
+    }
+
+    /// Queue a ramp to `percent` for `fans`. Returns false when it should be
+    /// written at once instead: no ramp rate, or a fan's speed is unknown.
+    pub fn ramp_to(&mut self, fans: &[Fan], percent: u8) -> bool {
+        if self.behavior.ramp_rate == 0
+            || fans.iter().any(|&f| self.fans[index(f)].current.is_none())
+        {
+            return false;
+        }
+        self.curve = None;
+        for &fan in fans {
+            self.fans[index(fan)].target = Some(percent as f32);
+        }
+        true
+    }
+
+    /// `percent` was written to `fans` directly.
+    pub fn written(&mut self, fans: &[Fan], percent: u8) {
+        self.curve = None;
+        for &fan in fans {
+            let state = &mut self.fans[index(fan)];
+            state.target = Some(percent as f32);
+            state.current = Some(percent as f32);
+        }
+    }
+}
+
+/// The fans this model has.
+pub fn fans(session: &Session) -> &'static [Fan] {
+    if session.register_map.map.fan2_rpm.is_some() {
+        &[Fan::Fan1, Fan::Fan2]
+    } else {
+        &[Fan::Fan1]
+    }
+}
+
+/// Speed of the last point at or below `temp`; the first point's
+/// temperature is implicit, as on the EC.
+fn lookup(points: &[CurvePoint], temp: u8) -> u8 {
+    points
+        .iter()
+        .skip(1)
+        .take_while(|p| p.temp <= temp)
+        .last()
+        .or(points.first())
+        .map_or(0, |p| p.speed)
+}
+
+/// Temperature to look the curve up at: rises count at once, falls only
+/// once they reach `hysteresis` below the previous basis.
+fn next_basis(previous: Option<u8>, temp: u8, hysteresis: u8) -> u8 {
+    match previous {
+        Some(basis) if temp < basis && basis - temp < hysteresis => basis,
+        _ => temp,
+    }
+}
+
+/// One step from `current` toward `target`, by at most `max_change`.
+fn step(current: Option<f32>, target: f32, max_change: f32) -> f32 {
+    match current {
+        Some(current) => current + (target - current).clamp(-max_change, max_change),
+        None => target,
+    }
+}
+
+fn update_targets(session: &mut Session) {
+    let Some(curve) = session.smoothing.curve.clone() else {
+        return;
+    };
+    let Ok(reading) = session.backend.read_lite() else {
+        return;
+    };
+    let hysteresis = session.smoothing.behavior.hysteresis;
+    for (i, temp) in [reading.cpu_temp, reading.gpu_temp].into_iter().enumerate() {
+        let state = &mut session.smoothing.fans[i];
+        let basis = next_basis(state.basis, temp, hysteresis);
+        state.basis = Some(basis);
+        state.target = Some(lookup(&curve[i], basis) as f32);
+    }
+}
+
+fn tick(session: &mut Session, elapsed: Duration) {
+    update_targets(session);
+    let max_change = match session.smoothing.behavior.ramp_rate {
+        0 => f32::MAX,
+        rate => rate as f32 * elapsed.as_secs_f32(),
+    };
+    for &fan in fans(session) {
+        let state = session.smoothing.fans[index(fan)];
+        let Some(target) = state.target else {
+            continue;
+        };
+        let next = step(state.current, target, max_change);
+        if state.current.map(f32::round) == Some(next.round()) {
+            session.smoothing.fans[index(fan)].current = Some(next);
+            continue;
+        }
+        let duty = to_ec_duty(&session.quirks, next.round() as u8);
+        match session.backend.set_fan_speed_for(fan, duty) {
+            Ok(()) => session.smoothing.fans[index(fan)].current = Some(next),
+            Err(e) => eprintln!("Fan smoothing: {}", e),
+        }
+    }
+}
+
+/// Step the fans twice a second for as long as the process runs.
+pub fn spawn(session: Arc<Mutex<Session>>) {
+    thread::spawn(move || loop {
+        thread::sleep(TICK);
+        tick(&mut lock_session(&session), TICK);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> Vec<CurvePoint> {
+        [
+            (0, 0),
+            (50, 30),
+            (60, 45),
+            (70, 60),
+            (80, 75),
+            (90, 90),
+            (95, 100),
+        ]
+        .into_iter()
+        .map(|(temp, speed)| CurvePoint { temp, speed })
+        .collect()
+    }
+
+    #[test]
+    fn lookup_uses_last_point_at_or_below() {
+        let points = curve();
+        assert_eq!(lookup(&points, 20), 0);
+        assert_eq!(lookup(&points, 49), 0);
+        assert_eq!(lookup(&points, 50), 30);
+        assert_eq!(lookup(&points, 59), 30);
+        assert_eq!(lookup(&points, 95), 100);
+        assert_eq!(lookup(&points, 255), 100);
+        assert_eq!(lookup(&[], 60), 0);
+    }
+
+    #[test]
+    fn lookup_first_point_temperature_is_implicit() {
+        let mut points = curve();
+        points[0].temp = 40;
+        assert_eq!(lookup(&points, 10), 0);
+    }
+
+    #[test]
+    fn basis_follows_rises_at_once() {
+        assert_eq!(next_basis(None, 62, 5), 62);
+        assert_eq!(next_basis(Some(60), 61, 5), 61);
+    }
+
+    #[test]
+    fn basis_holds_within_hysteresis_band() {
+        assert_eq!(next_basis(Some(60), 59, 5), 60);
+        assert_eq!(next_basis(Some(60), 56, 5), 60);
+        // The band's edge counts as fallen far enough
+        assert_eq!(next_basis(Some(60), 55, 5), 55);
+        assert_eq!(next_basis(Some(60), 40, 5), 40);
+    }
+
+    #[test]
+    fn basis_without_hysteresis_tracks_temperature() {
+        assert_eq!(next_basis(Some(60), 59, 0), 59);
+    }
+
+    #[test]
+    fn hysteresis_keeps_speed_when_crossing_point_downward() {
+        let points = curve();
+        let basis = next_basis(Some(61), 58, 5);
+        assert_eq!(lookup(&points, basis), 45);
+        let basis = next_basis(Some(basis), 56, 5);
+        assert_eq!(lookup(&points, basis), 30);
+    }
+
+    #[test]
+    fn step_is_limited_by_max_change() {
+        assert_eq!(step(Some(30.0), 80.0, 10.0), 40.0);
+        assert_eq!(step(Some(80.0), 30.0, 10.0), 70.0);
+        assert_eq!(step(Some(30.0), 35.0, 10.0), 35.0);
+        assert_eq!(step(Some(30.0), 80.0, f32::MAX), 80.0);
+    }
+
+    #[test]
+    fn step_without_known_speed_jumps() {
+        assert_eq!(step(None, 80.0, 10.0), 80.0);
+    }
+
+    #[test]
+    fn ramp_needs_rate_and_known_speed() {
+        let mut smoothing = Smoothing::default();
+        assert!(!smoothing.ramp_to(&[Fan::Fan1], 50));
+
+        smoothing.behavior.ramp_rate = 10;
+        assert!(!smoothing.ramp_to(&[Fan::Fan1], 50));
+
+        smoothing.written(&[Fan::Fan1], 30);
+        assert!(smoothing.ramp_to(&[Fan::Fan1], 50));
+        assert!(!smoothing.ramp_to(&[Fan::Fan1, Fan::Fan2], 50));
+        assert_eq!(smoothing.fans[0].target, Some(50.0));
+        assert_eq!(smoothing.fans[0].current, Some(30.0));
+    }
+
+    #[test]
+    fn ramp_drops_followed_curve() {
+        let mut smoothing = Smoothing::default();
+        smoothing.behavior.ramp_rate = 10;
+        smoothing.written(&[Fan::Fan1], 30);
+        smoothing.follow(&curve(), &curve());
+        assert!(smoothing.curve().is_some());
+        assert!(smoothing.ramp_to(&[Fan::Fan1], 50));
+        assert!(smoothing.curve().is_none());
+    }
+
+    #[test]
+    fn follows_curves_with_either_setting() {
+        let mut smoothing = Smoothing::default();
+        assert!(!smoothing.follows_curves());
+        smoothing.behavior.hysteresis = 3;
+        assert!(smoothing.follows_curves());
+        smoothing.behavior = FanBehavior {
+            ramp_rate: 5,
+            ..Default::default()
+        };
+        assert!(smoothing.follows_curves());
+    }
+}
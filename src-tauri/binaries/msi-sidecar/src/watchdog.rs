@@ -0,0 +1,163 @@
+//! Failsafe for manual fan control.
+//!
+//! A fixed fan speed or Silent mode stops the firmware from ramping the fans
+//! up under load. While one of them is active, a watchdog thread checks the
+//! temperatures every second and takes control back (Auto mode, or Cooler
+//! Boost) once either reaches the configured limit, or when a connected
+//! client stops sending heartbeats. In a stdin session the same happens when
+//! the app disconnects; a daemon client that disconnects just stops being
+//! waited on, so the daemon's own settings stay in place.
+
+use crate::{lock_session, send_response, Outputs, Session};
+use msi_protocol::{FailsafeAction, FailsafeConfig, Response};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct Failsafe {
+    pub config: FailsafeConfig,
+    /// A fixed speed or Silent mode was written and not replaced since.
+    pub armed: bool,
+    /// Last heartbeat of each connected client that sent one, by client id.
+    heartbeats: HashMap<u64, Instant>,
+}
+
+impl Failsafe {
+    pub fn heartbeat(&mut self, client: u64) {
+        self.heartbeats.insert(client, Instant::now());
+    }
+
+    /// The client disconnected; its heartbeats are no longer expected.
+    pub fn forget(&mut self, client: u64) {
+        self.heartbeats.remove(&client);
+    }
+
+    fn heartbeat_lost(&self) -> bool {
+        let timeout = Duration::from_secs(self.config.heartbeat_timeout_secs);
+        !timeout.is_zero() && self.heartbeats.values().any(|at| at.elapsed() >= timeout)
+    }
+}
+
+/// Why the watchdog should trip now, if it should.
+fn trip_reason(session: &mut Session) -> Option<String> {
+    if session.failsafe.heartbeat_lost() {
+        return Some(format!(
+            "No heartbeat from the app for {} s",
+            session.failsafe.config.heartbeat_timeout_secs
+        ));
+    }
+    let reading = session.backend.read_lite().ok()?;
+    let max_temp = session.failsafe.config.max_temp;
+    let (sensor, temp) = if reading.cpu_temp >= reading.gpu_temp {
+        ("CPU", reading.cpu_temp)
+    } else {
+        ("GPU", reading.gpu_temp)
+    };
+    (temp >= max_temp).then(|| format!("{} reached {} °C (limit {} °C)", sensor, temp, max_temp))
+}
+
+/// Run the configured action and describe what happened.
+fn trip(session: &mut Session, reason: &str) -> String {
+    session.failsafe.armed = false;
+    session.smoothing.reset();
+    let (result, action) = match session.failsafe.config.action {
+        FailsafeAction::Auto => (session.backend.set_fan_mode("auto"), "fans back to Auto"),
+        FailsafeAction::CoolerBoost => (session.backend.set_cooler_boost(true), "Cooler Boost on"),
+    };
+    let message = match result {
+        Ok(()) => format!("{}: {}", reason, action),
+        Err(e) => format!("{}: failed to switch {}: {}", reason, action, e),
+    };
+    eprintln!("Failsafe: {}", message);
+    message
+}
+
+/// Trip if armed and over a limit. Returns the message pushed to the app.
+fn check(session: &mut Session) -> Option<String> {
+    if !session.failsafe.armed {
+        return None;
+    }
+    let reason = trip_reason(session)?;
+    Some(trip(session, &reason))
+}
+
+/// Check every second for as long as the process runs, telling every
+/// connected client when the failsafe trips.
+pub fn spawn(session: Arc<Mutex<Session>>, outputs: Outputs) {
+    thread::spawn(move || loop {
+        thread::sleep(CHECK_INTERVAL);
+        let Some(message) = check(&mut lock_session(&session)) else {
+            continue;
+        };
+        let response = Response::FailsafeTriggered { message };
+        if let Ok(outputs) = outputs.lock() {
+            for output in outputs.iter() {
+                send_response(output, &response);
+            }
+        }
+    });
+}
+
+/// The app went away while manual control was active.
+pub fn on_disconnect(session: &mut Session) {
+    if session.failsafe.armed {
+        trip(session, "App disconnected");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{serve, Output};
+    use std::io::Cursor;
+
+    fn armed_session() -> Mutex<Session> {
+        let mut session = Session::new(true);
+        session.failsafe.armed = true;
+        session.failsafe.config.heartbeat_timeout_secs = 5;
+        Mutex::new(session)
+    }
+
+    #[test]
+    fn stale_heartbeat_of_connected_client_trips() {
+        let session = armed_session();
+        let mut session = lock_session(&session);
+        session
+            .failsafe
+            .heartbeats
+            .insert(1, Instant::now() - Duration::from_secs(6));
+        assert!(session.failsafe.heartbeat_lost());
+        assert!(check(&mut session).is_some());
+        assert!(!session.failsafe.armed);
+    }
+
+    #[test]
+    fn disconnected_client_is_not_waited_on() {
+        let session = armed_session();
+        let output: Output = Arc::new(Mutex::new(Vec::new()));
+        let input = Cursor::new(b"{\"cmd\":\"heartbeat\"}\n{\"cmd\":\"heartbeat\"}\n".to_vec());
+        serve(&session, input, &output, false);
+
+        let mut session = lock_session(&session);
+        assert!(session.failsafe.heartbeats.is_empty());
+        assert!(!session.failsafe.heartbeat_lost());
+        assert!(check(&mut session).is_none());
+        assert!(session.failsafe.armed);
+    }
+
+    #[test]
+    fn heartbeat_timeout_zero_never_trips() {
+        let session = armed_session();
+        let mut session = lock_session(&session);
+        session.failsafe.config.heartbeat_timeout_secs = 0;
+        session
+            .failsafe
+            .heartbeats
+            .insert(1, Instant::now() - Duration::from_secs(600));
+        assert!(!session.failsafe.heartbeat_lost());
+    }
+}
@@ -0,0 +1,118 @@
+//! What a status costs with each way of reading the EC: the full 256-byte
+//! snapshot behind `get_status`, the three single-byte reads behind
+//! `get_status_lite`, and a cached reading refreshed once a second as the
+//! app does with pushed statuses.
+//!
+//! By default the EC is a temporary file seeded like the simulated one, so
+//! the numbers cover syscalls and parsing only. Point `MSI_EC_BENCH_PATH` at
+//! `/sys/kernel/debug/ec/ec0/io` (as root, with `ec_sys` loaded) to include
+//! the EC's own latency:
+//!
+//! ```sh
+//! sudo MSI_EC_BENCH_PATH=/sys/kernel/debug/ec/ec0/io cargo bench -p msi-ec-core
+//! ```
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use msi_ec_core::backend::{EcBackend, EcReading};
+use msi_ec_core::ec::RawEcBackend;
+use msi_ec_core::model::GENERIC;
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Polling rates compared, in reads per second.
+const POLL_RATES: [u32; 4] = [1, 2, 4, 10];
+/// How often the cached reading is refreshed, like the sidecar's pushes.
+const CACHE_REFRESH: Duration = Duration::from_secs(1);
+
+fn ec_path() -> PathBuf {
+    if let Some(path) = std::env::var_os("MSI_EC_BENCH_PATH") {
+        return PathBuf::from(path);
+    }
+    let image = RawEcBackend::simulated(&GENERIC)
+        .dump()
+        .expect("the simulated EC dumps");
+    let path = std::env::temp_dir().join(format!("msi-ec-bench-{}", std::process::id()));
+    std::fs::write(&path, image).expect("temporary EC image");
+    path
+}
+
+/// The last full reading, re-read once it's older than `CACHE_REFRESH`.
+struct Cache {
+    reading: Option<(Instant, EcReading)>,
+}
+
+impl Cache {
+    fn get(&mut self, backend: &mut RawEcBackend) -> EcReading {
+        match &self.reading {
+            Some((at, reading)) if at.elapsed() < CACHE_REFRESH => reading.clone(),
+            _ => {
+                let reading = backend.read().expect("EC read");
+                self.reading = Some((Instant::now(), reading.clone()));
+                reading
+            }
+        }
+    }
+}
+
+fn single_read(c: &mut Criterion, backend: &mut RawEcBackend) {
+    let mut group = c.benchmark_group("single_read");
+    group.bench_function("full_snapshot", |b| {
+        b.iter(|| black_box(backend.read().expect("EC read")))
+    });
+    group.bench_function("targeted", |b| {
+        b.iter(|| black_box(backend.read_lite().expect("EC read")))
+    });
+    let mut cache = Cache { reading: None };
+    group.bench_function("cached", |b| b.iter(|| black_box(cache.get(backend))));
+    group.finish();
+}
+
+/// One second of polling at each rate. The cache is hit `rate` times and
+/// refreshed once, whatever the rate.
+fn polling(c: &mut Criterion, backend: &mut RawEcBackend) {
+    let mut group = c.benchmark_group("one_second_of_polling");
+    for rate in POLL_RATES {
+        group.bench_with_input(
+            BenchmarkId::new("full_snapshot", rate),
+            &rate,
+            |b, &rate| {
+                b.iter(|| {
+                    for _ in 0..rate {
+                        black_box(backend.read().expect("EC read"));
+                    }
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("targeted", rate), &rate, |b, &rate| {
+            b.iter(|| {
+                for _ in 0..rate {
+                    black_box(backend.read_lite().expect("EC read"));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("cached", rate), &rate, |b, &rate| {
+            b.iter(|| {
+                // Expired, as it would be a second later
+                let mut cache = Cache { reading: None };
+                for _ in 0..rate {
+                    black_box(cache.get(backend));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn ec_access(c: &mut Criterion) {
+    let path = ec_path();
+    let mut backend = RawEcBackend::at_path(&GENERIC, &path);
+    single_read(c, &mut backend);
+    polling(c, &mut backend);
+    if std::env::var_os("MSI_EC_BENCH_PATH").is_none() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+criterion_group!(benches, ec_access);
+criterion_main!(benches);
@@ -0,0 +1,125 @@
+//! Hardware access backends.
+//!
+//! The sidecar prefers the upstream `msi-ec` platform driver when it is
+//! loaded, and falls back to raw EC I/O through `ec_sys` debugfs otherwise.
+
+use crate::ec::RawEcBackend;
+use crate::model::RegisterMap;
+use crate::msi_ec::MsiEcBackend;
+use crate::{CurvePoint, EcModuleError, Fan, FanReading};
+
+/// One reading of the values shown in the UI.
+#[derive(Debug, Clone)]
+pub struct EcReading {
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+    pub fan1_rpm: u32,
+    pub fan2_rpm: u32,
+    /// Every fan, CPU and GPU first.
+    pub fans: Vec<FanReading>,
+    pub cooler_boost: bool,
+    pub fan_mode: String,
+}
+
+/// The subset of `EcReading` the tray needs while the window is hidden.
+#[derive(Debug, Clone, Copy)]
+pub struct LiteReading {
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+    pub cooler_boost: bool,
+}
+
+/// `Send` so the failsafe watchdog can use it from its own thread.
+pub trait EcBackend: Send {
+    /// Short identifier reported in status responses.
+    fn name(&self) -> &'static str;
+    fn read(&mut self) -> Result<EcReading, String>;
+    /// Cheaper read of temperatures and Cooler Boost only.
+    fn read_lite(&mut self) -> Result<LiteReading, String> {
+        let reading = self.read()?;
+        Ok(LiteReading {
+            cpu_temp: reading.cpu_temp,
+            gpu_temp: reading.gpu_temp,
+            cooler_boost: reading.cooler_boost,
+        })
+    }
+    /// Raw EC register contents, for contributors mapping a new model.
+    fn dump(&mut self) -> Result<Vec<u8>, String> {
+        Err(format!(
+            "EC dumps are not supported by the {} backend",
+            self.name()
+        ))
+    }
+    fn set_cooler_boost(&mut self, enabled: bool) -> Result<(), String>;
+    fn set_fan_mode(&mut self, mode: &str) -> Result<(), String>;
+    /// Run both fans at a flat duty.
+    fn set_fan_speed(&mut self, percent: u8) -> Result<(), String>;
+    /// Run a single fan at a flat duty, leaving the other one untouched.
+    fn set_fan_speed_for(&mut self, fan: Fan, percent: u8) -> Result<(), String>;
+    /// Write full 7-point curves; input is already validated.
+    fn set_fan_curve(&mut self, cpu: &[CurvePoint], gpu: &[CurvePoint]) -> Result<(), String>;
+    /// How often a register write that doesn't read back is retried before
+    /// the whole command is rolled back. Only raw EC access verifies writes.
+    fn set_write_retries(&mut self, _retries: u8) {}
+    /// Switch to a corrected register map. Only raw EC access uses one.
+    fn set_register_map(&mut self, _map: &'static RegisterMap) -> Result<(), String> {
+        Err(format!(
+            "The {} backend doesn't use the register map",
+            self.name()
+        ))
+    }
+    /// Write one register as is, returning its previous value. Only raw EC
+    /// access can.
+    fn write_register(&mut self, _offset: u8, _value: u8) -> Result<u8, String> {
+        Err(format!(
+            "The {} backend doesn't give access to single registers",
+            self.name()
+        ))
+    }
+    /// Load the kernel module the backend reads through, if not loaded yet.
+    /// Only raw EC access needs one.
+    fn load_module(&mut self) -> Result<(), EcModuleError> {
+        Ok(())
+    }
+    /// Prove writes stick before the first real one on a non-verified
+    /// model. Only raw EC access writes registers directly; driver
+    /// backends validate writes themselves.
+    fn trial_write(&mut self) -> Result<String, String> {
+        Ok(format!(
+            "The {} backend doesn't write EC registers directly",
+            self.name()
+        ))
+    }
+    /// Why no write can work right now whatever the model allows, e.g. a
+    /// module loaded read-only.
+    fn write_blocker(&self) -> Option<String> {
+        None
+    }
+    /// Fixed speeds and custom curves, as opposed to modes and Cooler Boost.
+    fn supports_curves(&self) -> bool {
+        true
+    }
+    /// The in-memory EC when simulating, for `--demo` to animate.
+    fn simulated_ec(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+}
+
+/// In-memory EC for `--simulate`; never touches the hardware.
+pub fn simulated(map: &'static RegisterMap) -> Box<dyn EcBackend> {
+    eprintln!("Simulating the EC with the {} register map", map.name);
+    Box::new(RawEcBackend::simulated(map))
+}
+
+/// Pick the best available backend for this machine. The register map is
+/// only used by raw EC access; `msi-ec` carries its own per-model configs.
+pub fn detect(map: &'static RegisterMap) -> Box<dyn EcBackend> {
+    if let Some(backend) = MsiEcBackend::probe() {
+        eprintln!("Using msi-ec driver backend");
+        return Box::new(backend);
+    }
+    // The app can retry with `load_ec_module`
+    let _ = crate::ec::load_ec_module();
+    eprintln!("Using ec_sys backend with the {} register map", map.name);
+    Box::new(RawEcBackend::new(map))
+}
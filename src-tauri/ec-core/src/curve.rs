@@ -0,0 +1,75 @@
+//! Limits every fan curve and duty is checked against before it reaches
+//! a backend, and the translation to the model's EC duty scale.
+
+use crate::quirks::ModelQuirks;
+use crate::CurvePoint;
+
+/// Curve layout shared by all backends: 7 temperature/speed points per fan.
+pub const CURVE_POINTS: usize = 7;
+pub const MAX_CURVE_TEMP: u8 = 100;
+/// Highest user-facing duty: 100 % of the model's `ec_duty_max`.
+pub const MAX_CURVE_SPEED: u8 = 100;
+/// Highest duty the EC accepts, in its own units.
+pub const EC_DUTY_LIMIT: u8 = 150;
+
+pub fn check_duty(quirks: &ModelQuirks, duty: u8, force: bool) -> Result<(), String> {
+    if duty > MAX_CURVE_SPEED {
+        return Err(format!("Fan duty {} exceeds {}%", duty, MAX_CURVE_SPEED));
+    }
+    if duty > quirks.max_duty && !force {
+        return Err(format!(
+            "Fan duty {} exceeds this model's safe maximum of {}; resend with force to override",
+            duty, quirks.max_duty
+        ));
+    }
+    Ok(())
+}
+
+/// Translate a user-facing duty percentage to the model's EC scale. Never
+/// exceeds what the EC accepts.
+pub fn to_ec_duty(quirks: &ModelQuirks, percent: u8) -> u8 {
+    let ec = (percent as u32 * quirks.ec_duty_max as u32 + 50) / 100;
+    ec.min(EC_DUTY_LIMIT as u32) as u8
+}
+
+/// Translate a duty read from the EC back to the user-facing percentage.
+/// Above 100 when the firmware itself overdrives the fan, e.g. Cooler Boost.
+pub fn from_ec_duty(quirks: &ModelQuirks, duty: u8) -> u8 {
+    let max = quirks.ec_duty_max.max(1) as u32;
+    ((duty as u32 * 100 + max / 2) / max).min(u8::MAX as u32) as u8
+}
+
+pub fn validate_curve(name: &str, points: &[CurvePoint]) -> Result<(), String> {
+    if points.len() != CURVE_POINTS {
+        return Err(format!(
+            "{} curve needs {} points, got {}",
+            name,
+            CURVE_POINTS,
+            points.len()
+        ));
+    }
+    for (i, point) in points.iter().enumerate() {
+        if point.temp > MAX_CURVE_TEMP {
+            return Err(format!(
+                "{} curve point {}: temperature {} exceeds {}",
+                name,
+                i + 1,
+                point.temp,
+                MAX_CURVE_TEMP
+            ));
+        }
+        if point.speed > MAX_CURVE_SPEED {
+            return Err(format!(
+                "{} curve point {}: speed {} exceeds {}",
+                name,
+                i + 1,
+                point.speed,
+                MAX_CURVE_SPEED
+            ));
+        }
+    }
+    if points.windows(2).any(|w| w[1].temp < w[0].temp) {
+        return Err(format!("{} curve temperatures must not decrease", name));
+    }
+    Ok(())
+}
@@ -0,0 +1,713 @@
+//! Raw EC register access through the `ec_sys` debugfs interface.
+
+use crate::backend::{EcBackend, EcReading, LiteReading};
+use crate::model::RegisterMap;
+use crate::{CurvePoint, EcModuleError, Fan, FanReading, PersistenceReport};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::Command as ProcessCommand;
+
+const EC_IO_PATH: &str = "/sys/kernel/debug/ec/ec0/io";
+const WRITE_SUPPORT_PATH: &str = "/sys/module/ec_sys/parameters/write_support";
+const LOCKDOWN_PATH: &str = "/sys/kernel/security/lockdown";
+const LOAD_CONF_PATH: &str = "/etc/modules-load.d/ec_sys.conf";
+const MODPROBE_CONF_PATH: &str = "/etc/modprobe.d/ec_sys.conf";
+const SECURE_BOOT_PATH: &str =
+    "/sys/firmware/efi/efivars/SecureBoot-8be4df61-93ca-11d2-aa0d-00e098032b8c";
+
+const EC_SIZE: usize = 256;
+
+// Point counts are the same on every model
+const FAN_SPEED_POINTS: u64 = 7;
+// Curve temperature thresholds - 6 per fan, the first speed point has no
+// threshold (it applies below the first one)
+const FAN_TEMP_POINTS: u64 = 6;
+
+/// Registers a raw write may not touch, and why.
+const BLOCKED_REGISTERS: &[(RangeInclusive<u8>, &str)] = &[
+    (0xA0..=0xBF, "it holds the firmware version and build date"),
+    (
+        0xD7..=0xD7,
+        "it is the battery charge limit on older models, which can stop charging",
+    ),
+    (
+        0xEF..=0xEF,
+        "it is the battery charge limit, which can stop charging",
+    ),
+    (
+        0xF2..=0xF2,
+        "it is the shift mode, which can overclock the CPU",
+    ),
+];
+
+/// Why a raw write to `offset` is refused, if it is.
+pub fn blocked_register(offset: u8) -> Option<&'static str> {
+    BLOCKED_REGISTERS
+        .iter()
+        .find(|(range, _)| range.contains(&offset))
+        .map(|(_, reason)| *reason)
+}
+
+/// Extra attempts for a register that doesn't read back what was written.
+pub const DEFAULT_WRITE_RETRIES: u8 = 2;
+
+/// Load `ec_sys` with write support unless its debugfs node already exists.
+pub fn load_ec_module() -> Result<(), EcModuleError> {
+    if Path::new(EC_IO_PATH).exists() {
+        return Ok(());
+    }
+    eprintln!("EC module not loaded. Attempting to load...");
+    let detail = match ProcessCommand::new("modprobe")
+        .arg("ec_sys")
+        .arg("write_support=1")
+        .output()
+    {
+        Ok(output) if output.status.success() => {
+            eprintln!("Successfully loaded ec_sys");
+            return Ok(());
+        }
+        Ok(output) => String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        Err(e) => format!("Failed to run modprobe: {}", e),
+    };
+    eprintln!("Failed to load ec_sys: {}", detail);
+    Err(module_error(detail))
+}
+
+fn module_error(detail: String) -> EcModuleError {
+    let kernel = fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|release| release.trim().to_string())
+        .unwrap_or_default();
+    // Package managers delete the old kernel's modules on upgrade, while the
+    // old kernel keeps running until the next boot
+    let modules = Path::new("/lib/modules");
+    let reboot_required = !kernel.is_empty() && modules.exists() && !modules.join(&kernel).exists();
+    let module_missing = reboot_required || detail.contains("not found");
+    let suggested_package = if module_missing && !reboot_required {
+        suggested_package(&kernel)
+    } else {
+        None
+    };
+    EcModuleError {
+        kernel,
+        module_missing,
+        reboot_required,
+        suggested_package,
+        detail,
+    }
+}
+
+/// Distributions that ship `ec_sys` in a package of its own, going by
+/// `ID` and `ID_LIKE` in os-release.
+fn suggested_package(kernel: &str) -> Option<String> {
+    let os_release = fs::read_to_string("/etc/os-release").ok()?;
+    let ids: Vec<&str> = os_release
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("ID=")
+                .or_else(|| line.strip_prefix("ID_LIKE="))
+        })
+        .flat_map(|value| value.trim_matches('"').split_whitespace())
+        .collect();
+    if ids.contains(&"ubuntu") {
+        Some(format!("linux-modules-extra-{}", kernel))
+    } else if ids.contains(&"fedora") {
+        Some(format!("kernel-modules-extra-{}", kernel))
+    } else if ids.contains(&"suse") {
+        Some("kernel-default-extra".to_string())
+    } else {
+        None
+    }
+}
+
+/// The files that load `ec_sys` with write support at boot, with the
+/// contents the sidecar writes.
+const PERSISTENCE_FILES: &[(&str, &str)] = &[
+    (LOAD_CONF_PATH, "ec_sys\n"),
+    (MODPROBE_CONF_PATH, "options ec_sys write_support=1\n"),
+];
+
+/// Whether `ec_sys` is set to load with write support at boot. Readable
+/// without root.
+pub fn persistence_installed() -> bool {
+    PERSISTENCE_FILES
+        .iter()
+        .all(|(path, contents)| fs::read_to_string(path).is_ok_and(|c| c == *contents))
+}
+
+/// Write the boot-time files that are missing. A file that exists with
+/// other contents was written by someone else and is kept.
+pub fn install_persistence() -> Result<PersistenceReport, String> {
+    let mut report = PersistenceReport::default();
+    for &(path, contents) in PERSISTENCE_FILES {
+        match fs::read_to_string(path) {
+            Ok(existing) if existing == contents => {}
+            Ok(_) => report.kept.push(path.to_string()),
+            Err(_) => {
+                fs::write(path, contents)
+                    .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+                eprintln!("Created persistence: {}", path);
+                report.written.push(path.to_string());
+            }
+        }
+    }
+    report.installed = persistence_installed();
+    Ok(report)
+}
+
+/// Delete the boot-time files the sidecar wrote; edited ones are kept.
+pub fn remove_persistence() -> Result<PersistenceReport, String> {
+    let mut report = PersistenceReport::default();
+    for &(path, contents) in PERSISTENCE_FILES {
+        match fs::read_to_string(path) {
+            Ok(existing) if existing == contents => {
+                fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {}", path, e))?;
+                eprintln!("Removed persistence: {}", path);
+                report.removed.push(path.to_string());
+            }
+            Ok(_) => report.kept.push(path.to_string()),
+            Err(_) => {}
+        }
+    }
+    report.installed = persistence_installed();
+    Ok(report)
+}
+
+/// Active kernel lockdown mode (`integrity` or `confidentiality`), which
+/// keeps debugfs and so `ec_sys` out of reach; `None` when lockdown is off.
+/// Secure Boot usually turns it on.
+pub fn lockdown_mode() -> Option<String> {
+    let modes = fs::read_to_string(LOCKDOWN_PATH).ok()?;
+    // The active mode is bracketed: "none [integrity] confidentiality"
+    let active = modes.split_whitespace().find(|m| m.starts_with('['))?;
+    let active = active.trim_matches(|c| c == '[' || c == ']');
+    (active != "none").then(|| active.to_string())
+}
+
+/// Whether the firmware booted with Secure Boot on; `None` on a legacy BIOS
+/// boot or when the EFI variable can't be read.
+pub fn secure_boot_enabled() -> Option<bool> {
+    let var = fs::read(SECURE_BOOT_PATH).ok()?;
+    // Four bytes of attributes, then the value
+    var.get(4).map(|&value| value == 1)
+}
+
+/// Under lockdown debugfs fails with a bare EPERM or EIO even for root, so
+/// say why and what to do instead.
+fn explain_lockdown(e: io::Error) -> io::Error {
+    let Some(mode) = lockdown_mode() else {
+        return e;
+    };
+    let cause = if secure_boot_enabled() == Some(true) {
+        ", turned on by Secure Boot"
+    } else {
+        ""
+    };
+    io::Error::new(
+        e.kind(),
+        format!(
+            "{} (kernel lockdown is in {} mode{}, which blocks ec_sys; install the msi-ec \
+             driver or turn off Secure Boot in the firmware setup)",
+            e, mode, cause
+        ),
+    )
+}
+
+fn read_ec_snapshot(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn read_ec_byte(path: &Path, offset: u64) -> io::Result<u8> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut value = [0u8];
+    file.read_exact(&mut value)?;
+    Ok(value[0])
+}
+
+fn write_ec_byte(path: &Path, offset: u64, value: u8) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(&[value])?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Where register bytes live.
+enum EcIo {
+    /// The `ec_sys` debugfs node, or a file laid out like it.
+    Debugfs(PathBuf),
+    /// `--simulate`: a private copy of the EC that nothing else sees.
+    Memory(Vec<u8>),
+}
+
+impl EcIo {
+    fn snapshot(&self) -> io::Result<Vec<u8>> {
+        match self {
+            EcIo::Debugfs(path) => read_ec_snapshot(path).map_err(explain_lockdown),
+            EcIo::Memory(ec) => Ok(ec.clone()),
+        }
+    }
+
+    fn read_byte(&self, offset: u64) -> io::Result<u8> {
+        match self {
+            EcIo::Debugfs(path) => read_ec_byte(path, offset).map_err(explain_lockdown),
+            EcIo::Memory(ec) => ec
+                .get(offset as usize)
+                .copied()
+                .ok_or_else(|| io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+
+    fn write_byte(&mut self, offset: u64, value: u8) -> io::Result<()> {
+        match self {
+            EcIo::Debugfs(path) => write_ec_byte(path, offset, value).map_err(explain_lockdown),
+            EcIo::Memory(ec) => {
+                let byte = ec
+                    .get_mut(offset as usize)
+                    .ok_or(io::ErrorKind::UnexpectedEof)?;
+                *byte = value;
+                Ok(())
+            }
+        }
+    }
+}
+
+fn get_fan_rpm(buffer: &[u8], low_offset: usize, high_offset: usize, divisor: u32) -> u32 {
+    if high_offset >= buffer.len() || low_offset >= buffer.len() {
+        return 0;
+    }
+    let low = buffer[low_offset] as u32;
+    let high = buffer[high_offset] as u32;
+
+    let value = (high << 8) | low;
+
+    divisor.checked_div(value).unwrap_or(0)
+}
+
+fn get_fan1_rpm(map: &RegisterMap, buffer: &[u8]) -> u32 {
+    // First candidate with a plausible reading wins, else the last one
+    let mut rpm = 0;
+    for &(low, high) in map.fan1_rpm {
+        rpm = get_fan_rpm(buffer, low as usize, high as usize, map.rpm_divisor);
+        if rpm > 0 && rpm < 10000 {
+            break;
+        }
+    }
+    rpm
+}
+
+/// The speed point an Advanced mode curve selects at `temp`: the first
+/// point runs below the first threshold.
+fn curve_duty(buffer: &[u8], temp_start: u64, speed_start: u64, temp: u8) -> u8 {
+    let step = (0..FAN_TEMP_POINTS)
+        .take_while(|&i| buffer[(temp_start + i) as usize] <= temp)
+        .count();
+    buffer[speed_start as usize + step]
+}
+
+fn is_known_mode(map: &RegisterMap, value: u8) -> bool {
+    let m = map.modes;
+    [m.auto, m.silent, m.basic, m.advanced].contains(&value)
+}
+
+fn detect_fan_mode_address(map: &RegisterMap, buffer: &[u8]) -> u64 {
+    map.fan_mode
+        .iter()
+        .copied()
+        .find(|&addr| is_known_mode(map, buffer.get(addr as usize).copied().unwrap_or(0)))
+        .or_else(|| map.fan_mode.last().copied())
+        .unwrap_or(0)
+}
+
+fn get_fan_mode_string(map: &RegisterMap, buffer: &[u8]) -> String {
+    let fan_mode_addr = detect_fan_mode_address(map, buffer);
+    let mode_value = buffer.get(fan_mode_addr as usize).copied().unwrap_or(0);
+    let m = map.modes;
+    match mode_value {
+        v if v == m.auto => "auto".to_string(),
+        v if v == m.silent => "silent".to_string(),
+        v if v == m.basic => "basic".to_string(),
+        v if v == m.advanced => "advanced".to_string(),
+        _ => format!("unknown(0x{:02X})", mode_value),
+    }
+}
+
+/// Register writes for a curve: thresholds from point 1 on (point 0's
+/// temperature is implicit), then all seven speeds.
+fn fan_curve_writes(temp_start: u64, speed_start: u64, points: &[CurvePoint]) -> Vec<(u64, u8)> {
+    let temps = (0..FAN_TEMP_POINTS).map(|i| (temp_start + i, points[i as usize + 1].temp));
+    let speeds = (0..FAN_SPEED_POINTS).map(|i| (speed_start + i, points[i as usize].speed));
+    temps.chain(speeds).collect()
+}
+
+fn flat_speed_writes(speed_start: u64, percent: u8) -> impl Iterator<Item = (u64, u8)> {
+    (0..FAN_SPEED_POINTS).map(move |i| (speed_start + i, percent))
+}
+
+/// Direct register access via `/sys/kernel/debug/ec/ec0/io`, or an
+/// in-memory copy of the EC when simulating.
+pub struct RawEcBackend {
+    map: &'static RegisterMap,
+    io: EcIo,
+    write_retries: u8,
+}
+
+impl RawEcBackend {
+    pub fn new(map: &'static RegisterMap) -> Self {
+        Self::at_path(map, EC_IO_PATH)
+    }
+
+    /// Through another file laid out like the debugfs node, e.g. an EC dump
+    /// for the benchmarks.
+    pub fn at_path(map: &'static RegisterMap, path: impl Into<PathBuf>) -> Self {
+        RawEcBackend {
+            map,
+            io: EcIo::Debugfs(path.into()),
+            write_retries: DEFAULT_WRITE_RETRIES,
+        }
+    }
+
+    /// Backed by a 256-byte buffer seeded with a machine idling in Auto mode.
+    /// Writes land in the buffer only, so no root or MSI hardware is needed.
+    pub fn simulated(map: &'static RegisterMap) -> Self {
+        let mut ec = vec![0u8; EC_SIZE];
+        let mut set = |offset: u64, value: u8| ec[offset as usize] = value;
+        set(map.cpu_temp, 52);
+        set(map.gpu_temp, 47);
+        for &addr in map.fan_mode {
+            set(addr, map.modes.auto);
+        }
+        let tach = |rpm: u32| (map.rpm_divisor / rpm) as u16;
+        for &(low, high) in map.fan1_rpm {
+            set(low, tach(2600) as u8);
+            set(high, (tach(2600) >> 8) as u8);
+        }
+        if let Some((low, high)) = map.fan2_rpm {
+            set(low, tach(2400) as u8);
+            set(high, (tach(2400) >> 8) as u8);
+        }
+        for fan in map.extra_fans {
+            set(fan.rpm.0, tach(2200) as u8);
+            set(fan.rpm.1, (tach(2200) >> 8) as u8);
+        }
+        // A typical factory curve: thresholds 50-85 °C, speeds 0-80 %
+        for i in 0..FAN_TEMP_POINTS {
+            set(map.fan1_temp_start + i, 50 + 7 * i as u8);
+            set(map.fan2_temp_start + i, 50 + 7 * i as u8);
+        }
+        for i in 0..FAN_SPEED_POINTS {
+            let speed = if i == 0 { 0 } else { 20 + 10 * i as u8 };
+            set(map.fan1_speed_start + i, speed);
+            set(map.fan2_speed_start + i, speed);
+        }
+        RawEcBackend {
+            map,
+            io: EcIo::Memory(ec),
+            write_retries: DEFAULT_WRITE_RETRIES,
+        }
+    }
+
+    /// Write one register and read it back, retrying while it doesn't stick.
+    fn write_verified(&mut self, offset: u64, value: u8) -> Result<(), String> {
+        let mut read_back = None;
+        for _ in 0..=self.write_retries {
+            self.io
+                .write_byte(offset, value)
+                .map_err(|e| format!("0x{:02X}: {}", offset, e))?;
+            match self.io.read_byte(offset) {
+                Ok(v) if v == value => return Ok(()),
+                Ok(v) => read_back = Some(v),
+                Err(e) => return Err(format!("0x{:02X}: read back failed: {}", offset, e)),
+            }
+        }
+        Err(format!(
+            "0x{:02X}: wrote 0x{:02X}, read back 0x{:02X}",
+            offset,
+            value,
+            read_back.unwrap_or(0)
+        ))
+    }
+
+    /// Apply a group of register writes as a unit. If one doesn't stick,
+    /// every register touched so far is restored to its previous value so
+    /// the EC isn't left with half a curve.
+    fn write_all(&mut self, writes: &[(u64, u8)]) -> Result<(), String> {
+        let before = self.io.snapshot().map_err(|e| e.to_string())?;
+        for (done, &(offset, value)) in writes.iter().enumerate() {
+            let Err(failed) = self.write_verified(offset, value) else {
+                continue;
+            };
+            // Include the failing register, it may hold a partial write
+            let mut rollback_failed = Vec::new();
+            for &(offset, _) in writes[..=done].iter().rev() {
+                let Some(&original) = before.get(offset as usize) else {
+                    continue;
+                };
+                if let Err(e) = self.write_verified(offset, original) {
+                    rollback_failed.push(e);
+                }
+            }
+            return Err(if rollback_failed.is_empty() {
+                format!(
+                    "EC write verification failed ({}); {} register(s) rolled back",
+                    failed,
+                    done + 1
+                )
+            } else {
+                format!(
+                    "EC write verification failed ({}); rollback also failed for {}",
+                    failed,
+                    rollback_failed.join(", ")
+                )
+            });
+        }
+        Ok(())
+    }
+
+    /// The write that switches to Advanced mode, the only mode in which the
+    /// EC follows the speed and curve registers.
+    fn advanced_mode_write(&self) -> Result<(u64, u8), String> {
+        let buffer = self.io.snapshot().map_err(|e| e.to_string())?;
+        let fan_mode_addr = detect_fan_mode_address(self.map, &buffer);
+        Ok((fan_mode_addr, self.map.modes.advanced))
+    }
+}
+
+impl EcBackend for RawEcBackend {
+    fn name(&self) -> &'static str {
+        match self.io {
+            EcIo::Debugfs(_) => "ec_sys",
+            EcIo::Memory(_) => "simulated",
+        }
+    }
+
+    fn simulated_ec(&mut self) -> Option<&mut [u8]> {
+        match &mut self.io {
+            EcIo::Debugfs(_) => None,
+            EcIo::Memory(ec) => Some(ec),
+        }
+    }
+
+    fn write_blocker(&self) -> Option<String> {
+        if matches!(self.io, EcIo::Memory(_)) {
+            return None;
+        }
+        let write_support = fs::read_to_string(WRITE_SUPPORT_PATH).ok()?;
+        (write_support.trim() == "N")
+            .then(|| "ec_sys is loaded without write_support=1".to_string())
+    }
+
+    fn load_module(&mut self) -> Result<(), EcModuleError> {
+        match self.io {
+            EcIo::Debugfs(_) => load_ec_module(),
+            EcIo::Memory(_) => Ok(()),
+        }
+    }
+
+    fn read(&mut self) -> Result<EcReading, String> {
+        let buffer = self
+            .io
+            .snapshot()
+            .map_err(|e| format!("Failed to read EC: {}", e))?;
+
+        // Safety check
+        if buffer.len() < 0xFF {
+            // Ensure we have enough data
+            return Err(format!("EC buffer too small: {} bytes", buffer.len()));
+        }
+
+        let map = self.map;
+        let cpu_temp = buffer.get(map.cpu_temp as usize).copied().unwrap_or(0);
+        let gpu_temp = buffer.get(map.gpu_temp as usize).copied().unwrap_or(0);
+
+        let cooler_boost_byte = buffer.get(map.cooler_boost as usize).copied().unwrap_or(0);
+        let cooler_boost = (cooler_boost_byte & map.cooler_boost_bit) != 0;
+
+        let fan1_rpm = get_fan1_rpm(map, &buffer);
+        let fan2_rpm = map.fan2_rpm.map_or(0, |(low, high)| {
+            get_fan_rpm(&buffer, low as usize, high as usize, map.rpm_divisor)
+        });
+        let fan_mode = get_fan_mode_string(map, &buffer);
+
+        // Only Advanced mode runs the curve in the registers; the other
+        // modes use the firmware's own tables
+        let advanced = fan_mode == "advanced";
+        let target = |temp_start, speed_start, temp| {
+            advanced.then(|| curve_duty(&buffer, temp_start, speed_start, temp))
+        };
+        let mut fans = vec![FanReading {
+            name: "CPU".to_string(),
+            rpm: fan1_rpm,
+            target_percent: target(map.fan1_temp_start, map.fan1_speed_start, cpu_temp),
+        }];
+        if map.fan2_rpm.is_some() {
+            fans.push(FanReading {
+                name: "GPU".to_string(),
+                rpm: fan2_rpm,
+                target_percent: target(map.fan2_temp_start, map.fan2_speed_start, gpu_temp),
+            });
+        }
+        fans.extend(map.extra_fans.iter().map(|fan| FanReading {
+            name: fan.name.to_string(),
+            rpm: get_fan_rpm(
+                &buffer,
+                fan.rpm.0 as usize,
+                fan.rpm.1 as usize,
+                map.rpm_divisor,
+            ),
+            target_percent: fan.duty.and_then(|addr| buffer.get(addr as usize).copied()),
+        }));
+
+        Ok(EcReading {
+            cpu_temp,
+            gpu_temp,
+            fan1_rpm,
+            fan2_rpm,
+            fans,
+            cooler_boost,
+            fan_mode,
+        })
+    }
+
+    fn dump(&mut self) -> Result<Vec<u8>, String> {
+        self.io
+            .snapshot()
+            .map_err(|e| format!("Failed to read EC: {}", e))
+    }
+
+    fn read_lite(&mut self) -> Result<LiteReading, String> {
+        // Three single-byte reads instead of the full 256-byte snapshot
+        let read = |offset| {
+            self.io
+                .read_byte(offset)
+                .map_err(|e| format!("Failed to read EC: {}", e))
+        };
+        Ok(LiteReading {
+            cpu_temp: read(self.map.cpu_temp)?,
+            gpu_temp: read(self.map.gpu_temp)?,
+            cooler_boost: (read(self.map.cooler_boost)? & self.map.cooler_boost_bit) != 0,
+        })
+    }
+
+    fn set_cooler_boost(&mut self, enabled: bool) -> Result<(), String> {
+        // Read current state first
+        let buffer = self.io.snapshot().map_err(|e| e.to_string())?;
+        // Or just open and read single byte?? Snapshot is safer.
+        let current = buffer
+            .get(self.map.cooler_boost as usize)
+            .copied()
+            .ok_or("Cannot read cooler boost reg")?;
+
+        let new_value = if enabled {
+            current | self.map.cooler_boost_bit
+        } else {
+            current & !self.map.cooler_boost_bit
+        };
+
+        self.write_all(&[(self.map.cooler_boost, new_value)])
+    }
+
+    fn set_fan_mode(&mut self, mode: &str) -> Result<(), String> {
+        let buffer = self.io.snapshot().map_err(|e| e.to_string())?;
+        let fan_mode_addr = detect_fan_mode_address(self.map, &buffer);
+
+        let modes = self.map.modes;
+        let mode_value = match mode {
+            "auto" => modes.auto,
+            "silent" => modes.silent,
+            "basic" => modes.basic,
+            "advanced" => modes.advanced,
+            _ => return Err(format!("Unknown mode: {}", mode)),
+        };
+
+        self.write_all(&[(fan_mode_addr, mode_value)])
+    }
+
+    fn set_fan_speed(&mut self, percent: u8) -> Result<(), String> {
+        // Advanced mode, then all 7 speed points of Fan 1 (CPU) and of Fan 2
+        // (GPU) if there is one
+        let mut writes = vec![self.advanced_mode_write()?];
+        writes.extend(flat_speed_writes(self.map.fan1_speed_start, percent));
+        if self.map.fan2_rpm.is_some() {
+            writes.extend(flat_speed_writes(self.map.fan2_speed_start, percent));
+        }
+        self.write_all(&writes)
+    }
+
+    fn set_fan_speed_for(&mut self, fan: Fan, percent: u8) -> Result<(), String> {
+        if fan == Fan::Fan2 && self.map.fan2_rpm.is_none() {
+            return Err("This model has no GPU fan".to_string());
+        }
+        let speed_start = match fan {
+            Fan::Fan1 => self.map.fan1_speed_start,
+            Fan::Fan2 => self.map.fan2_speed_start,
+        };
+        let mut writes = vec![self.advanced_mode_write()?];
+        writes.extend(flat_speed_writes(speed_start, percent));
+        self.write_all(&writes)
+    }
+
+    fn set_fan_curve(&mut self, cpu: &[CurvePoint], gpu: &[CurvePoint]) -> Result<(), String> {
+        let map = self.map;
+        let mut writes = vec![self.advanced_mode_write()?];
+        writes.extend(fan_curve_writes(
+            map.fan1_temp_start,
+            map.fan1_speed_start,
+            cpu,
+        ));
+        if map.fan2_rpm.is_some() {
+            writes.extend(fan_curve_writes(
+                map.fan2_temp_start,
+                map.fan2_speed_start,
+                gpu,
+            ));
+        }
+        self.write_all(&writes)
+    }
+
+    fn set_write_retries(&mut self, retries: u8) {
+        self.write_retries = retries;
+    }
+
+    fn set_register_map(&mut self, map: &'static RegisterMap) -> Result<(), String> {
+        self.map = map;
+        Ok(())
+    }
+
+    fn write_register(&mut self, offset: u8, value: u8) -> Result<u8, String> {
+        let before = self
+            .io
+            .read_byte(offset as u64)
+            .map_err(|e| format!("Failed to read EC: {}", e))?;
+        self.write_verified(offset as u64, value)?;
+        Ok(before)
+    }
+
+    fn trial_write(&mut self) -> Result<String, String> {
+        // The first CPU fan speed point runs below the lowest threshold, so
+        // nudging it by 1 % for a moment can't change what the fan does
+        let offset = self.map.fan1_speed_start;
+        let original = self
+            .io
+            .read_byte(offset)
+            .map_err(|e| format!("Failed to read EC: {}", e))?;
+        let trial = self.write_verified(offset, original ^ 1);
+        let restore = self.write_verified(offset, original);
+        match (trial, restore) {
+            (Ok(()), Ok(())) => Ok(format!(
+                "Trial write to 0x{:02X} read back correctly and was restored",
+                offset
+            )),
+            (Err(e), Ok(())) => Err(format!("Trial write failed ({}); register restored", e)),
+            (_, Err(e)) => Err(format!(
+                "Trial write failed and 0x{:02X} could not be restored to 0x{:02X} ({})",
+                offset, original, e
+            )),
+        }
+    }
+}
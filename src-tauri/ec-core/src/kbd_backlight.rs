@@ -0,0 +1,51 @@
+//! Keyboard backlight through the LED class device registered by the
+//! platform driver (`msi-ec`, `msi-wmi`, ...), e.g.
+//! `/sys/class/leds/platform::kbd_backlight`.
+
+use msi_protocol::KeyboardBacklight;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LEDS_PATH: &str = "/sys/class/leds";
+
+fn find_device() -> Result<PathBuf, String> {
+    fs::read_dir(LEDS_PATH)
+        .map_err(|e| format!("Failed to list {}: {}", LEDS_PATH, e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with("kbd_backlight"))
+        })
+        .ok_or_else(|| "No keyboard backlight device found".to_string())
+}
+
+fn read_value(device: &Path, attr: &str) -> Result<u8, String> {
+    fs::read_to_string(device.join(attr))
+        .map_err(|e| format!("Failed to read {}: {}", attr, e))?
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid {}: {}", attr, e))
+}
+
+pub fn read() -> Result<KeyboardBacklight, String> {
+    let device = find_device()?;
+    Ok(KeyboardBacklight {
+        brightness: read_value(&device, "brightness")?,
+        max_brightness: read_value(&device, "max_brightness")?,
+    })
+}
+
+pub fn set(brightness: u8) -> Result<(), String> {
+    let device = find_device()?;
+    let max = read_value(&device, "max_brightness")?;
+    if brightness > max {
+        return Err(format!(
+            "Brightness {} out of range (max {})",
+            brightness, max
+        ));
+    }
+    fs::write(device.join("brightness"), brightness.to_string())
+        .map_err(|e| format!("Failed to set keyboard backlight: {}", e))
+}
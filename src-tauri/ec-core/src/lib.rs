@@ -0,0 +1,17 @@
+//! Embedded Controller access for MSI laptops, independent of how it is
+//! driven: the backends (`ec_sys` debugfs, the `msi-ec` driver, an
+//! in-memory EC for simulation), per-model register maps and quirks, and
+//! the rules a fan curve has to follow.
+//!
+//! The privileged sidecar is one user; any other Linux tool can depend on
+//! this crate the same way. Reading and writing the real EC needs root.
+
+pub mod backend;
+pub mod curve;
+pub mod ec;
+pub mod kbd_backlight;
+pub mod model;
+pub mod msi_ec;
+pub mod quirks;
+
+pub use msi_protocol::{CurvePoint, EcModuleError, Fan, FanReading, PersistenceReport};
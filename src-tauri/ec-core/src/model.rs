@@ -0,0 +1,338 @@
+//! EC register maps per laptop model, selected from the DMI product name.
+//!
+//! MSI reuses a handful of EC layouts across families. The offsets below
+//! come from MControlCenter and the `msi-ec` driver's model configs. Where a
+//! family is known to have moved a register between generations, the map
+//! lists every candidate and the backend probes them in order.
+//!
+//! Power users on unlisted models can describe their layout in
+//! `/etc/msi-fan-control/ec-map.json` (same fields as `get_register_map`
+//! reports). A valid file takes precedence over the built-in table; an
+//! invalid one is reported and ignored.
+
+use msi_protocol::{
+    ActiveRegisterMap, ExtraFanSpec, FanModeValues, RegisterMapSource, RegisterMapSpec,
+    DEFAULT_RPM_DIVISOR,
+};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+const OVERRIDE_PATH: &str = "/etc/msi-fan-control/ec-map.json";
+
+/// Register offsets and values used by the raw `ec_sys` backend.
+#[derive(Debug)]
+pub struct RegisterMap {
+    /// Reported in `get_detected_model`.
+    pub name: &'static str,
+    pub cpu_temp: u64,
+    pub gpu_temp: u64,
+    pub cooler_boost: u64,
+    pub cooler_boost_bit: u8,
+    /// (low, high) byte pairs of the CPU fan tachometer, in probe order.
+    pub fan1_rpm: &'static [(u64, u64)],
+    /// `None` on single-fan models.
+    pub fan2_rpm: Option<(u64, u64)>,
+    /// Fan mode register candidates, in probe order. The first one holding a
+    /// known mode value wins; the last one is used if none does.
+    pub fan_mode: &'static [u64],
+    pub modes: FanModeValues,
+    /// First of 7 speed points per fan.
+    pub fan1_speed_start: u64,
+    pub fan2_speed_start: u64,
+    /// First of 6 temperature thresholds per fan.
+    pub fan1_temp_start: u64,
+    pub fan2_temp_start: u64,
+    /// Tachometers hold `rpm_divisor / RPM`.
+    pub rpm_divisor: u32,
+    /// Fans beyond the CPU and GPU fans, monitored only.
+    pub extra_fans: &'static [ExtraFan],
+}
+
+#[derive(Debug)]
+pub struct ExtraFan {
+    pub name: &'static str,
+    /// (low, high) tachometer bytes.
+    pub rpm: (u64, u64),
+    /// Register holding the current duty in %, if known.
+    pub duty: Option<u64>,
+}
+
+const STANDARD_MODES: FanModeValues = FanModeValues {
+    auto: 0x0D,
+    silent: 0x1D,
+    basic: 0x4D,
+    advanced: 0x8D,
+};
+
+/// Probes every known location; used when the model isn't in `MODEL_MAPS`.
+pub const GENERIC: RegisterMap = RegisterMap {
+    name: "generic",
+    cpu_temp: 0x68,
+    gpu_temp: 0x80,
+    cooler_boost: 0x98,
+    cooler_boost_bit: 0x80,
+    // MControlCenter prefers 0xCC/0xCD if it reads a plausible value
+    fan1_rpm: &[(0xCD, 0xCC), (0xC9, 0xC8)],
+    fan2_rpm: Some((0xCB, 0xCA)),
+    fan_mode: &[0xD4, 0xF4],
+    modes: STANDARD_MODES,
+    fan1_speed_start: 0x72,
+    fan2_speed_start: 0x8A,
+    fan1_temp_start: 0x6A,
+    fan2_temp_start: 0x82,
+    rpm_divisor: DEFAULT_RPM_DIVISOR,
+    extra_fans: &[],
+};
+
+/// 8th/9th gen G-series (`msi-ec` WMI1 configs): mode at 0xF4.
+const G_SERIES_WMI1: RegisterMap = RegisterMap {
+    name: "g-series-wmi1",
+    fan1_rpm: &[(0xC9, 0xC8)],
+    fan_mode: &[0xF4],
+    ..GENERIC
+};
+
+/// 10th gen and later G-series (`msi-ec` WMI2 configs): mode at 0xD4.
+const G_SERIES_WMI2: RegisterMap = RegisterMap {
+    name: "g-series-wmi2",
+    fan1_rpm: &[(0xCD, 0xCC), (0xC9, 0xC8)],
+    fan_mode: &[0xD4],
+    ..GENERIC
+};
+
+/// Prestige/Modern ultrabooks: one fan, no separate GPU fan.
+const PRESTIGE: RegisterMap = RegisterMap {
+    name: "prestige",
+    fan1_rpm: &[(0xC9, 0xC8)],
+    fan2_rpm: None,
+    fan_mode: &[0xD4],
+    ..GENERIC
+};
+
+/// Known models, matched by product name prefix. First match wins, so
+/// list specific generations before the family prefix.
+const MODEL_MAPS: &[(&str, &RegisterMap)] = &[
+    ("GF63 Thin 8", &G_SERIES_WMI1),
+    ("GF63 Thin 9", &G_SERIES_WMI1),
+    ("GF63", &G_SERIES_WMI2),
+    ("GF65", &G_SERIES_WMI2),
+    ("GF75 Thin 8", &G_SERIES_WMI1),
+    ("GF75", &G_SERIES_WMI2),
+    ("GS65", &G_SERIES_WMI1),
+    ("GS66", &G_SERIES_WMI2),
+    ("GE75", &G_SERIES_WMI1),
+    ("GE66", &G_SERIES_WMI2),
+    ("GE76", &G_SERIES_WMI2),
+    ("GP66", &G_SERIES_WMI2),
+    ("GP76", &G_SERIES_WMI2),
+    ("Katana", &G_SERIES_WMI2),
+    ("Sword", &G_SERIES_WMI2),
+    ("Prestige", &PRESTIGE),
+    ("Modern", &PRESTIGE),
+];
+
+/// The map the session uses, and where it came from.
+pub struct Selection {
+    pub map: &'static RegisterMap,
+    pub source: RegisterMapSource,
+    pub override_error: Option<String>,
+}
+
+impl Selection {
+    pub fn describe(&self) -> ActiveRegisterMap {
+        ActiveRegisterMap {
+            source: self.source,
+            map: to_spec(self.map),
+            override_error: self.override_error.clone(),
+        }
+    }
+}
+
+/// Built-in register map for this machine, falling back to `GENERIC`.
+fn lookup(product_name: &str) -> &'static RegisterMap {
+    MODEL_MAPS
+        .iter()
+        .find(|(prefix, _)| product_name.starts_with(prefix))
+        .map(|(_, map)| *map)
+        .unwrap_or(&GENERIC)
+}
+
+/// Pick the override file if present and valid, the built-in map otherwise.
+pub fn detect(product_name: &str) -> Selection {
+    let builtin = |override_error| Selection {
+        map: lookup(product_name),
+        source: RegisterMapSource::BuiltIn,
+        override_error,
+    };
+    match load_override() {
+        Ok(Some(map)) => Selection {
+            map,
+            source: RegisterMapSource::Override,
+            override_error: None,
+        },
+        Ok(None) => builtin(None),
+        Err(e) => {
+            eprintln!("Ignoring {}: {}", OVERRIDE_PATH, e);
+            builtin(Some(e))
+        }
+    }
+}
+
+/// The selected map with another tachometer constant.
+pub fn with_rpm_divisor(selection: &Selection, divisor: u32) -> Result<Selection, String> {
+    let mut spec = to_spec(selection.map);
+    spec.rpm_divisor = divisor;
+    validate(&spec)?;
+    Ok(Selection {
+        map: Box::leak(Box::new(from_spec(spec))),
+        source: selection.source,
+        override_error: None,
+    })
+}
+
+/// Write `map` to the override file, where the next start picks it up.
+pub fn save_override(map: &RegisterMap) -> Result<(), String> {
+    if let Some(dir) = Path::new(OVERRIDE_PATH).parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(&to_spec(map)).map_err(|e| e.to_string())?;
+    fs::write(OVERRIDE_PATH, json + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", OVERRIDE_PATH, e))
+}
+
+fn load_override() -> Result<Option<&'static RegisterMap>, String> {
+    let content = match fs::read_to_string(OVERRIDE_PATH) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read: {}", e)),
+    };
+    let spec: RegisterMapSpec =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid JSON: {}", e))?;
+    validate(&spec)?;
+    // Loaded once per process, so leaking keeps it as 'static as the table
+    Ok(Some(Box::leak(Box::new(from_spec(spec)))))
+}
+
+fn validate(spec: &RegisterMapSpec) -> Result<(), String> {
+    if spec.name.trim().is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    if spec.cooler_boost_bit.count_ones() != 1 {
+        return Err(format!(
+            "cooler_boost_bit 0x{:02X} must have exactly one bit set",
+            spec.cooler_boost_bit
+        ));
+    }
+    if spec.fan1_rpm.is_empty() {
+        return Err("fan1_rpm needs at least one register pair".to_string());
+    }
+    // Idle fans read a few hundred RPM from a few thousand tach counts
+    if spec.rpm_divisor < 1000 {
+        return Err(format!(
+            "rpm_divisor {} is implausibly small (usually {})",
+            spec.rpm_divisor, DEFAULT_RPM_DIVISOR
+        ));
+    }
+    if spec.extra_fans.iter().any(|fan| fan.name.trim().is_empty()) {
+        return Err("extra_fans names must not be empty".to_string());
+    }
+    if spec.fan_mode.is_empty() {
+        return Err("fan_mode needs at least one register".to_string());
+    }
+    let m = spec.modes;
+    let values = [m.auto, m.silent, m.basic, m.advanced];
+    if values
+        .iter()
+        .enumerate()
+        .any(|(i, v)| values[i + 1..].contains(v))
+    {
+        return Err("modes must all have different values".to_string());
+    }
+    let ranges = [
+        ("fan1_speed_start", spec.fan1_speed_start, 7),
+        ("fan2_speed_start", spec.fan2_speed_start, 7),
+        ("fan1_temp_start", spec.fan1_temp_start, 6),
+        ("fan2_temp_start", spec.fan2_temp_start, 6),
+    ];
+    for (field, start, len) in ranges {
+        if start as usize + len > 0x100 {
+            return Err(format!(
+                "{} 0x{:02X} leaves no room for {} registers",
+                field, start, len
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn from_spec(spec: RegisterMapSpec) -> RegisterMap {
+    let pair = |(low, high): (u8, u8)| (low as u64, high as u64);
+    RegisterMap {
+        name: Box::leak(spec.name.into_boxed_str()),
+        cpu_temp: spec.cpu_temp as u64,
+        gpu_temp: spec.gpu_temp as u64,
+        cooler_boost: spec.cooler_boost as u64,
+        cooler_boost_bit: spec.cooler_boost_bit,
+        fan1_rpm: spec
+            .fan1_rpm
+            .into_iter()
+            .map(pair)
+            .collect::<Vec<_>>()
+            .leak(),
+        fan2_rpm: spec.fan2_rpm.map(pair),
+        fan_mode: spec
+            .fan_mode
+            .into_iter()
+            .map(u64::from)
+            .collect::<Vec<_>>()
+            .leak(),
+        modes: spec.modes,
+        fan1_speed_start: spec.fan1_speed_start as u64,
+        fan2_speed_start: spec.fan2_speed_start as u64,
+        fan1_temp_start: spec.fan1_temp_start as u64,
+        fan2_temp_start: spec.fan2_temp_start as u64,
+        rpm_divisor: spec.rpm_divisor,
+        extra_fans: spec
+            .extra_fans
+            .into_iter()
+            .map(|fan| ExtraFan {
+                name: Box::leak(fan.name.into_boxed_str()),
+                rpm: pair(fan.rpm),
+                duty: fan.duty.map(u64::from),
+            })
+            .collect::<Vec<_>>()
+            .leak(),
+    }
+}
+
+fn to_spec(map: &RegisterMap) -> RegisterMapSpec {
+    // Every offset in a map fits in a byte; the u64s are for seeking
+    let pair = |&(low, high): &(u64, u64)| (low as u8, high as u8);
+    RegisterMapSpec {
+        name: map.name.to_string(),
+        cpu_temp: map.cpu_temp as u8,
+        gpu_temp: map.gpu_temp as u8,
+        cooler_boost: map.cooler_boost as u8,
+        cooler_boost_bit: map.cooler_boost_bit,
+        fan1_rpm: map.fan1_rpm.iter().map(pair).collect(),
+        fan2_rpm: map.fan2_rpm.as_ref().map(pair),
+        fan_mode: map.fan_mode.iter().map(|&addr| addr as u8).collect(),
+        modes: map.modes,
+        fan1_speed_start: map.fan1_speed_start as u8,
+        fan2_speed_start: map.fan2_speed_start as u8,
+        fan1_temp_start: map.fan1_temp_start as u8,
+        fan2_temp_start: map.fan2_temp_start as u8,
+        rpm_divisor: map.rpm_divisor,
+        extra_fans: map
+            .extra_fans
+            .iter()
+            .map(|fan| ExtraFanSpec {
+                name: fan.name.to_string(),
+                rpm: pair(&fan.rpm),
+                duty: fan.duty.map(|addr| addr as u8),
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,123 @@
+//! Backend for the upstream `msi-ec` platform driver.
+//!
+//! The driver exposes temperatures, Cooler Boost and fan mode as sysfs
+//! attributes, so neither debugfs nor `ec_sys write_support=1` is needed.
+//! It does not expose fan RPM or the curve registers.
+
+use crate::backend::{EcBackend, EcReading, LiteReading};
+use crate::{CurvePoint, Fan, FanReading};
+use std::fs;
+use std::path::PathBuf;
+
+const MSI_EC_PATH: &str = "/sys/devices/platform/msi-ec";
+
+pub struct MsiEcBackend {
+    root: PathBuf,
+}
+
+impl MsiEcBackend {
+    /// Returns the backend if the driver is loaded and exposes the attributes
+    /// we rely on.
+    pub fn probe() -> Option<Self> {
+        let root = PathBuf::from(MSI_EC_PATH);
+        let required = ["cooler_boost", "fan_mode", "cpu/realtime_temperature"];
+        if required.iter().all(|attr| root.join(attr).exists()) {
+            Some(MsiEcBackend { root })
+        } else {
+            None
+        }
+    }
+
+    fn read_attr(&self, attr: &str) -> Result<String, String> {
+        fs::read_to_string(self.root.join(attr))
+            .map(|s| s.trim().to_string())
+            .map_err(|e| format!("Failed to read msi-ec {}: {}", attr, e))
+    }
+
+    fn write_attr(&self, attr: &str, value: &str) -> Result<(), String> {
+        fs::write(self.root.join(attr), value)
+            .map_err(|e| format!("Failed to write msi-ec {}: {}", attr, e))
+    }
+
+    fn read_temp(&self, attr: &str) -> u8 {
+        self.read_attr(attr)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0)
+    }
+
+    fn available_fan_modes(&self) -> Vec<String> {
+        self.read_attr("available_fan_modes")
+            .map(|v| v.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl EcBackend for MsiEcBackend {
+    fn name(&self) -> &'static str {
+        "msi-ec"
+    }
+
+    fn read(&mut self) -> Result<EcReading, String> {
+        Ok(EcReading {
+            cpu_temp: self.read_temp("cpu/realtime_temperature"),
+            gpu_temp: self.read_temp("gpu/realtime_temperature"),
+            // The driver reports fan duty, not tachometer RPM
+            fan1_rpm: 0,
+            fan2_rpm: 0,
+            fans: ["CPU", "GPU"]
+                .into_iter()
+                .map(|name| FanReading {
+                    name: name.to_string(),
+                    rpm: 0,
+                    target_percent: self
+                        .read_attr(&format!("{}/realtime_fan_speed", name.to_lowercase()))
+                        .ok()
+                        .and_then(|v| v.parse().ok()),
+                })
+                .collect(),
+            cooler_boost: self.read_attr("cooler_boost")? == "on",
+            fan_mode: self.read_attr("fan_mode")?,
+        })
+    }
+
+    fn read_lite(&mut self) -> Result<LiteReading, String> {
+        Ok(LiteReading {
+            cpu_temp: self.read_temp("cpu/realtime_temperature"),
+            gpu_temp: self.read_temp("gpu/realtime_temperature"),
+            cooler_boost: self.read_attr("cooler_boost")? == "on",
+        })
+    }
+
+    fn set_cooler_boost(&mut self, enabled: bool) -> Result<(), String> {
+        self.write_attr("cooler_boost", if enabled { "on" } else { "off" })
+    }
+
+    fn set_fan_mode(&mut self, mode: &str) -> Result<(), String> {
+        let available = self.available_fan_modes();
+        if !available.is_empty() && !available.iter().any(|m| m == mode) {
+            return Err(format!(
+                "Mode {} not supported by msi-ec (available: {})",
+                mode,
+                available.join(", ")
+            ));
+        }
+        self.write_attr("fan_mode", mode)
+    }
+
+    fn supports_curves(&self) -> bool {
+        false
+    }
+
+    fn set_fan_speed(&mut self, _percent: u8) -> Result<(), String> {
+        Err("Fixed fan speed is not supported by the msi-ec driver".to_string())
+    }
+
+    fn set_fan_speed_for(&mut self, _fan: Fan, _percent: u8) -> Result<(), String> {
+        Err("Per-fan speed is not supported by the msi-ec driver".to_string())
+    }
+
+    fn set_fan_curve(&mut self, _cpu: &[CurvePoint], _gpu: &[CurvePoint]) -> Result<(), String> {
+        Err("Custom fan curves are not supported by the msi-ec driver".to_string())
+    }
+}
@@ -0,0 +1,162 @@
+//! Per-model quirks, keyed by DMI product name.
+
+use msi_protocol::SupportLevel;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DMI_PRODUCT_NAME_PATH: &str = "/sys/class/dmi/id/product_name";
+const EXPERIMENTAL_LOG_DIR: &str = "/var/log/msi-fan-control";
+const EXPERIMENTAL_LOG_FILE: &str = "/var/log/msi-fan-control/experimental.jsonl";
+const REGISTER_LOG_FILE: &str = "/var/log/msi-fan-control/register-writes.jsonl";
+
+/// Product name prefixes of MSI laptop families. A model matching one of
+/// these but missing from `QUIRKS_DB` is treated as experimental.
+const MSI_FAMILY_PREFIXES: &[&str] = &[
+    "GF",
+    "GL",
+    "GP",
+    "GE",
+    "GS",
+    "GT",
+    "Alpha",
+    "Bravo",
+    "Creator",
+    "Crosshair",
+    "Cyborg",
+    "Delta",
+    "Katana",
+    "Modern",
+    "Prestige",
+    "Pulse",
+    "Raider",
+    "Stealth",
+    "Summit",
+    "Sword",
+    "Titan",
+    "Vector",
+];
+
+/// Safety limits enforced before writing fan settings.
+#[derive(Debug, Clone, Copy)]
+pub struct ModelQuirks {
+    /// Highest fan duty, in percent of `ec_duty_max`, accepted without an
+    /// explicit override. Duties are capped at 100 % either way.
+    pub max_duty: u8,
+    /// EC duty value the user-facing 100 % maps to; 0-100 % maps onto
+    /// 0..=`ec_duty_max`, and readings are converted back. The EC takes up
+    /// to 150, past its nominal full speed of 100. Models stay at 100 until
+    /// the RPM a higher duty reaches has been measured on one, so that no
+    /// model is overdriven on a guess.
+    pub ec_duty_max: u8,
+    /// Continuous Cooler Boost time after which a cooldown is suggested.
+    pub max_boost_secs: u64,
+}
+
+const DEFAULT_QUIRKS: ModelQuirks = ModelQuirks {
+    max_duty: 100,
+    ec_duty_max: 100,
+    max_boost_secs: 30 * 60,
+};
+
+/// Known models, matched by product name prefix. First match wins.
+///
+/// An entry marks a model as verified, i.e. its register map was checked
+/// on real hardware, and carries limits where one differs from
+/// `DEFAULT_QUIRKS`. A limit only changes with the measurement or
+/// documentation behind it noted next to the value; none has been
+/// measured yet, so the entries use the defaults.
+const QUIRKS_DB: &[(&str, ModelQuirks)] = &[(
+    // Main development target (GF65 Thin 10SDR); verified, limits not
+    // measured
+    "GF65 Thin 10S",
+    DEFAULT_QUIRKS,
+)];
+
+pub fn read_product_name() -> String {
+    fs::read_to_string(DMI_PRODUCT_NAME_PATH)
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+pub fn lookup(product_name: &str) -> ModelQuirks {
+    QUIRKS_DB
+        .iter()
+        .find(|(prefix, _)| product_name.starts_with(prefix))
+        .map(|(_, quirks)| *quirks)
+        .unwrap_or(DEFAULT_QUIRKS)
+}
+
+pub fn support_level(product_name: &str) -> SupportLevel {
+    if QUIRKS_DB
+        .iter()
+        .any(|(prefix, _)| product_name.starts_with(prefix))
+    {
+        SupportLevel::Verified
+    } else if MSI_FAMILY_PREFIXES
+        .iter()
+        .any(|prefix| product_name.starts_with(prefix))
+    {
+        SupportLevel::Experimental
+    } else {
+        SupportLevel::Unsupported
+    }
+}
+
+/// Append the outcome of a write on a non-verified model, so testers can
+/// attach the log when reporting whether their machine works.
+pub fn log_experimental_write(product_name: &str, feature: &str, result: &Result<(), String>) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let entry = serde_json::json!({
+        "timestamp": timestamp,
+        "model": product_name,
+        "feature": feature,
+        "ok": result.is_ok(),
+        "error": result.as_ref().err(),
+    });
+
+    let _ = fs::create_dir_all(EXPERIMENTAL_LOG_DIR);
+    if let Ok(mut f) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(EXPERIMENTAL_LOG_FILE)
+    {
+        let _ = writeln!(f, "{}", entry);
+    }
+}
+
+/// Append every raw register write, refused ones included, so a machine
+/// left in a strange state can be traced back.
+pub fn log_register_write(
+    product_name: &str,
+    offset: u8,
+    value: u8,
+    before: Option<u8>,
+    result: &Result<(), String>,
+) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let entry = serde_json::json!({
+        "timestamp": timestamp,
+        "model": product_name,
+        "offset": offset,
+        "value": value,
+        "before": before,
+        "ok": result.is_ok(),
+        "error": result.as_ref().err(),
+    });
+
+    let _ = fs::create_dir_all(EXPERIMENTAL_LOG_DIR);
+    if let Ok(mut f) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(REGISTER_LOG_FILE)
+    {
+        let _ = writeln!(f, "{}", entry);
+    }
+}
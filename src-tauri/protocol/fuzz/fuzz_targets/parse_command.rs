@@ -0,0 +1,25 @@
+//! Raw client input through the sidecar's framing and command parser:
+//! `cargo +nightly fuzz run parse_command` from `src-tauri/protocol`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use msi_protocol::framing::{parse_command, read_line, Line};
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = data;
+    loop {
+        match read_line(&mut input) {
+            Ok(Line::Message(line)) => {
+                if let Ok(command) = parse_command(&line) {
+                    // Anything accepted must survive a round trip
+                    let json = serde_json::to_string(&command).expect("commands serialize");
+                    assert!(!json.contains('\n'));
+                    parse_command(&json).expect("serialized commands parse");
+                }
+            }
+            Ok(Line::TooLong) => {}
+            Ok(Line::Eof) | Err(_) => break,
+        }
+    }
+});
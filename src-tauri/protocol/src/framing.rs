@@ -0,0 +1,105 @@
+//! Reading and parsing the JSON lines. The sidecar runs as root and reads
+//! whatever a client sends, so lines are bounded and every input, however
+//! malformed, ends in a `Command` or an error message.
+
+use crate::{Command, Request};
+use std::io::{self, BufRead, Read};
+
+/// Longest accepted line; a full fan curve takes well under 1 KiB.
+pub const MAX_LINE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    /// One line without its terminator. Invalid UTF-8 is replaced, so it
+    /// fails to parse instead of closing the connection.
+    Message(String),
+    /// Longer than `MAX_LINE_BYTES`; the rest of it was skipped. `id` is
+    /// the request's, when the line starts with it as the app writes them.
+    TooLong { id: Option<u64> },
+    /// The input closed.
+    Eof,
+}
+
+/// Read up to and including the next newline; a last line without one
+/// counts as well.
+pub fn read_line(input: &mut impl BufRead) -> io::Result<Line> {
+    let mut buf = Vec::new();
+    let read = input
+        .by_ref()
+        .take(MAX_LINE_BYTES as u64 + 1)
+        .read_until(b'\n', &mut buf)?;
+    if read == 0 {
+        return Ok(Line::Eof);
+    }
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+    } else if buf.len() > MAX_LINE_BYTES {
+        skip_line(input)?;
+        return Ok(Line::TooLong {
+            id: leading_id(&buf),
+        });
+    }
+    Ok(Line::Message(String::from_utf8_lossy(&buf).into_owned()))
+}
+
+/// The `id` of a line starting `{"id":<number>`. Only looks at the start,
+/// as the rest may be cut off.
+fn leading_id(line: &[u8]) -> Option<u64> {
+    let start = String::from_utf8_lossy(&line[..line.len().min(64)]);
+    let rest = start
+        .trim_start()
+        .strip_prefix('{')?
+        .trim_start()
+        .strip_prefix(r#""id""#)?
+        .trim_start()
+        .strip_prefix(':')?
+        .trim_start();
+    let end = rest.find(|c: char| !c.is_ascii_digit())?;
+    rest[..end].parse().ok()
+}
+
+/// Drop input up to and including the next newline without buffering it.
+fn skip_line(input: &mut impl BufRead) -> io::Result<()> {
+    loop {
+        let available = input.fill_buf()?;
+        if available.is_empty() {
+            return Ok(());
+        }
+        match available.iter().position(|&b| b == b'\n') {
+            Some(end) => {
+                input.consume(end + 1);
+                return Ok(());
+            }
+            None => {
+                let len = available.len();
+                input.consume(len);
+            }
+        }
+    }
+}
+
+/// Parse one line; the error is the message sent back to the client.
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    serde_json::from_str(line).map_err(|e| format!("Invalid command: {}", e))
+}
+
+/// A line that isn't a valid request, with its `id` when that much could be
+/// read, so the error still reaches the right caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRequest {
+    pub id: Option<u64>,
+    pub message: String,
+}
+
+/// Parse one line with its optional `id`.
+pub fn parse_request(line: &str) -> Result<Request, InvalidRequest> {
+    serde_json::from_str(line).map_err(|e| InvalidRequest {
+        id: serde_json::from_str::<serde_json::Value>(line)
+            .ok()
+            .and_then(|json| json.get("id")?.as_u64()),
+        message: format!("Invalid command: {}", e),
+    })
+}
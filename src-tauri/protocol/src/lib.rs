@@ -0,0 +1,703 @@
+//! Messages exchanged between the app and the privileged sidecar.
+//!
+//! Each message is one JSON object per line: the app writes a `Command` to
+//! the sidecar's stdin and reads `Response`s from its stdout. Both sides use
+//! these types, so a field added here can't be forgotten on the other end.
+//! Fields added after the first release are `#[serde(default)]` so a newer
+//! app still reads an older sidecar.
+//!
+//! On the wire a command may carry an `id` next to `cmd` (a [`Request`]);
+//! the reply then carries it back (a [`Reply`]), so the app can match
+//! replies to commands with several in flight. Pushed lines have none, and
+//! a sidecar that predates ids ignores them and answers in order.
+//!
+//! The `schema` feature derives JSON Schemas for the app's `get_api_schema`
+//! and `ts` the frontend's TypeScript bindings; the sidecar builds without
+//! either.
+
+pub mod framing;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "cmd", content = "data")]
+pub enum Command {
+    /// First command after connecting: the app's protocol version, answered
+    /// with the sidecar's and what it supports. Sidecars from before the
+    /// handshake answer with an error.
+    #[serde(rename = "hello")]
+    Hello { protocol_version: u32 },
+    #[serde(rename = "get_status")]
+    GetStatus,
+    /// Temperatures and Cooler Boost only, for tray-only polling.
+    #[serde(rename = "get_status_lite")]
+    GetStatusLite,
+    /// Push status lines every `interval_ms` until changed; 0 stops pushing.
+    #[serde(rename = "subscribe")]
+    Subscribe {
+        interval_ms: u64,
+        #[serde(default)]
+        lite: bool,
+    },
+    /// Raw EC contents; read-only, so allowed on every model.
+    #[serde(rename = "dump_ec")]
+    DumpEc,
+    /// Product name and the register map picked for it.
+    #[serde(rename = "get_detected_model")]
+    GetDetectedModel,
+    /// Register map in use and where it came from.
+    #[serde(rename = "get_register_map")]
+    GetRegisterMap,
+    /// Correct the tachometer constant of the register map, saving it to
+    /// the override file. Answered with `register_map`.
+    #[serde(rename = "set_rpm_divisor")]
+    SetRpmDivisor { divisor: u32 },
+    /// What works on this machine right now, and why the rest doesn't.
+    #[serde(rename = "get_capabilities")]
+    GetCapabilities,
+    #[serde(rename = "get_keyboard_backlight")]
+    GetKeyboardBacklight,
+    #[serde(rename = "set_keyboard_backlight")]
+    SetKeyboardBacklight { brightness: u8 },
+    #[serde(rename = "set_cooler_boost")]
+    SetCoolerBoost { enabled: bool },
+    #[serde(rename = "set_fan_speed")]
+    SetFanSpeed {
+        percent: u8,
+        /// Confirms a duty above the model's safe maximum.
+        #[serde(default)]
+        force: bool,
+    },
+    #[serde(rename = "set_fan_speed_per_fan")]
+    SetFanSpeedPerFan {
+        fan: Fan,
+        percent: u8,
+        #[serde(default)]
+        force: bool,
+    },
+    #[serde(rename = "set_fan_mode")]
+    SetFanMode { mode: String },
+    #[serde(rename = "set_fan_curve")]
+    SetFanCurve {
+        cpu: Vec<CurvePoint>,
+        gpu: Vec<CurvePoint>,
+        #[serde(default)]
+        force: bool,
+    },
+    /// Write features the user has enabled on a non-verified model.
+    #[serde(rename = "set_opt_ins")]
+    SetOptIns { features: Vec<String> },
+    /// Extra attempts for an EC register write that doesn't read back
+    /// before the command is rolled back and fails.
+    #[serde(rename = "set_write_retries")]
+    SetWriteRetries { retries: u8 },
+    /// Thresholds and action of the watchdog that guards fixed speeds and
+    /// Silent mode.
+    #[serde(rename = "set_failsafe")]
+    SetFailsafe(FailsafeConfig),
+    /// Smoothing of fan speed changes.
+    #[serde(rename = "set_fan_behavior")]
+    SetFanBehavior(FanBehavior),
+    /// Load `ec_sys` if it isn't loaded yet. Answered with `ec_module`.
+    #[serde(rename = "load_ec_module")]
+    LoadEcModule,
+    /// Have `ec_sys` load with write support at boot, through files in
+    /// `/etc/modules-load.d` and `/etc/modprobe.d`. Only on the user's
+    /// say-so; answered with `persistence`.
+    #[serde(rename = "install_persistence")]
+    InstallPersistence,
+    /// Delete the files `install_persistence` wrote, unless edited since.
+    #[serde(rename = "remove_persistence")]
+    RemovePersistence,
+    /// Remove the root-owned files the app and sidecar created outside a
+    /// package, before uninstalling. Answered with `cleanup`.
+    #[serde(rename = "cleanup_system_files")]
+    CleanupSystemFiles,
+    /// Change one register and restore it, checking both read back. On a
+    /// non-verified model this must pass once per session before any
+    /// other write is allowed.
+    #[serde(rename = "trial_write")]
+    TrialWrite,
+    /// Write one EC register directly, for working out a model's layout.
+    /// `confirm` must be [`RAW_WRITE_CONFIRMATION`]; some registers are
+    /// refused whatever it says.
+    #[serde(rename = "write_ec_register")]
+    WriteEcRegister {
+        offset: u8,
+        value: u8,
+        confirm: String,
+    },
+    /// Samples from the last `range_secs` seconds, oldest first. Only the
+    /// daemon records them.
+    #[serde(rename = "get_history")]
+    GetHistory { range_secs: u64 },
+    /// The client is still alive; arms the watchdog's heartbeat timeout
+    /// for this connection until it disconnects.
+    #[serde(rename = "heartbeat")]
+    Heartbeat,
+    #[serde(rename = "exit")]
+    Exit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum Response {
+    #[serde(rename = "hello")]
+    Hello(SidecarHello),
+    #[serde(rename = "status")]
+    Status(Status),
+    #[serde(rename = "status_lite")]
+    StatusLite(StatusLite),
+    /// Unsolicited, sent while subscribed.
+    #[serde(rename = "status_update")]
+    StatusUpdate(Status),
+    /// Unsolicited, sent while subscribed with `lite`.
+    #[serde(rename = "status_lite_update")]
+    StatusLiteUpdate(StatusLite),
+    #[serde(rename = "detected_model")]
+    DetectedModel(DetectedModel),
+    #[serde(rename = "register_map")]
+    RegisterMap(ActiveRegisterMap),
+    #[serde(rename = "capabilities")]
+    Capabilities { capabilities: Vec<CapabilityStatus> },
+    #[serde(rename = "keyboard_backlight")]
+    KeyboardBacklight(KeyboardBacklight),
+    #[serde(rename = "ec_dump")]
+    EcDump { data: Vec<u8> },
+    #[serde(rename = "history")]
+    History { samples: Vec<HistorySample> },
+    /// `error` is `None` once the module is loaded, or when the backend
+    /// doesn't need it.
+    #[serde(rename = "ec_module")]
+    EcModule { error: Option<EcModuleError> },
+    #[serde(rename = "persistence")]
+    Persistence(PersistenceReport),
+    #[serde(rename = "cleanup")]
+    Cleanup(CleanupReport),
+    /// Unsolicited: the watchdog took fan control back.
+    #[serde(rename = "failsafe_triggered")]
+    FailsafeTriggered { message: String },
+    #[serde(rename = "ok")]
+    Ok { message: String },
+    #[serde(rename = "error")]
+    Error { message: String },
+}
+
+/// A command as written on the wire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Request {
+    /// Echoed in the reply; chosen by the client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+/// A response as written on the wire: a reply to the request with `id`, or
+/// unsolicited (pushes, and the status greeting a new client) without one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Reply {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub response: Response,
+}
+
+/// Which fan a per-fan command targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum Fan {
+    /// CPU fan
+    Fan1,
+    /// GPU fan
+    Fan2,
+}
+
+/// What the watchdog does when it trips.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum FailsafeAction {
+    /// Hand the fans back to the firmware.
+    #[default]
+    Auto,
+    CoolerBoost,
+}
+
+/// Why `ec_sys` could not be loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct EcModuleError {
+    /// Running kernel release, as `uname -r` reports it.
+    pub kernel: String,
+    /// No `ec_sys` is installed for the running kernel.
+    pub module_missing: bool,
+    /// The running kernel's modules are gone, usually because an update
+    /// replaced the kernel; rebooting into the new one fixes it.
+    pub reboot_required: bool,
+    /// Package shipping the module on this distribution, when it is split
+    /// out of the kernel package.
+    pub suggested_package: Option<String>,
+    /// What modprobe reported.
+    pub detail: String,
+}
+
+/// What `install_persistence` or `remove_persistence` did to the files
+/// that load `ec_sys` at boot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct PersistenceReport {
+    /// Both files are in place with the expected contents.
+    pub installed: bool,
+    pub written: Vec<String>,
+    pub removed: Vec<String>,
+    /// Left alone because their contents aren't what the sidecar writes.
+    pub kept: Vec<String>,
+}
+
+/// What `cleanup_system_files` did. Entries are paths, with the reason for
+/// kept and failed ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct CleanupReport {
+    pub removed: Vec<String>,
+    /// Owned by the package, or edited by someone else.
+    pub kept: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+/// Watchdog settings. It only acts while a fixed fan speed or Silent mode
+/// is active.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(default)]
+pub struct FailsafeConfig {
+    /// Trip when the CPU or GPU reaches this temperature, in °C.
+    pub max_temp: u8,
+    /// Trip when no heartbeat arrived for this long; 0 disables. Only
+    /// armed once the first heartbeat is received.
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub heartbeat_timeout_secs: u64,
+    pub action: FailsafeAction,
+}
+
+impl Default for FailsafeConfig {
+    fn default() -> Self {
+        FailsafeConfig {
+            max_temp: 90,
+            heartbeat_timeout_secs: 30,
+            action: FailsafeAction::Auto,
+        }
+    }
+}
+
+/// Lowest and highest failsafe temperatures accepted, in °C. Lower trips on
+/// ordinary load; higher is past the point the EC throttles anyway.
+pub const FAILSAFE_TEMP_RANGE: std::ops::RangeInclusive<u8> = 60..=100;
+
+/// Heartbeat timeouts accepted besides 0, in seconds. Shorter trips when
+/// the app is merely busy; longer leaves a fixed speed unguarded too long.
+pub const FAILSAFE_HEARTBEAT_RANGE: std::ops::RangeInclusive<u64> = 5..=600;
+
+impl FailsafeConfig {
+    /// Error message if a limit is out of range.
+    pub fn validate(&self) -> Result<(), String> {
+        if !FAILSAFE_TEMP_RANGE.contains(&self.max_temp) {
+            return Err(format!(
+                "Failsafe temperature must be between {} and {} °C",
+                FAILSAFE_TEMP_RANGE.start(),
+                FAILSAFE_TEMP_RANGE.end()
+            ));
+        }
+        if self.heartbeat_timeout_secs != 0
+            && !FAILSAFE_HEARTBEAT_RANGE.contains(&self.heartbeat_timeout_secs)
+        {
+            return Err(format!(
+                "Heartbeat timeout must be 0 or between {} and {} s",
+                FAILSAFE_HEARTBEAT_RANGE.start(),
+                FAILSAFE_HEARTBEAT_RANGE.end()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// How the sidecar smooths fan speed changes. All zeros (the default)
+/// writes speeds at once and leaves curves to the EC.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(default)]
+pub struct FanBehavior {
+    /// °C a temperature has to fall before a curve steps down. Non-zero
+    /// makes the sidecar follow curves itself instead of the EC.
+    pub hysteresis: u8,
+    /// Fastest speed change in % per second; 0 changes at once. Non-zero
+    /// also makes the sidecar follow curves itself.
+    pub ramp_rate: u8,
+}
+
+/// One point of an EC fan curve: at `temp` °C and above, run at `speed` %.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct CurvePoint {
+    pub temp: u8,
+    pub speed: u8,
+}
+
+/// How much we trust the register layout on this machine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum SupportLevel {
+    /// Listed in `QUIRKS_DB`; writes are allowed.
+    Verified,
+    /// Looks like a known MSI family; read-only until the user opts in.
+    Experimental,
+    /// Unknown machine; read-only until the user opts in.
+    #[default]
+    Unsupported,
+}
+
+impl SupportLevel {
+    /// Same spelling as on the wire.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SupportLevel::Verified => "verified",
+            SupportLevel::Experimental => "experimental",
+            SupportLevel::Unsupported => "unsupported",
+        }
+    }
+}
+
+/// Something the app can do, reported on its own so a failure in one
+/// leaves the others usable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Temperatures, fan speeds and the current mode.
+    Monitor,
+    /// Fan modes and flat speeds.
+    Write,
+    CoolerBoost,
+    FanCurves,
+    /// Stop charging at a set level; checked by the app, not the sidecar.
+    BatteryThreshold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilityState {
+    Available,
+    /// Works, with a caveat given in the reason.
+    Degraded,
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct CapabilityStatus {
+    pub capability: Capability,
+    pub state: CapabilityState,
+    /// Why it is degraded or unavailable; `None` when available.
+    pub reason: Option<String>,
+}
+
+impl CapabilityStatus {
+    pub fn available(capability: Capability) -> Self {
+        CapabilityStatus {
+            capability,
+            state: CapabilityState::Available,
+            reason: None,
+        }
+    }
+
+    pub fn degraded(capability: Capability, reason: impl Into<String>) -> Self {
+        CapabilityStatus {
+            capability,
+            state: CapabilityState::Degraded,
+            reason: Some(reason.into()),
+        }
+    }
+
+    pub fn unavailable(capability: Capability, reason: impl Into<String>) -> Self {
+        CapabilityStatus {
+            capability,
+            state: CapabilityState::Unavailable,
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+/// Bumped when a command or response changes in a way the other side
+/// can't ignore; fields and commands added behind `#[serde(default)]` or a
+/// feature don't need it.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Reply to `hello`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct SidecarHello {
+    pub protocol_version: u32,
+    /// The sidecar's crate version.
+    pub sidecar_version: String,
+    pub model: DetectedModel,
+    pub features: Vec<SidecarFeature>,
+}
+
+/// Commands and fields a sidecar may lack, so the app can tell "not
+/// supported by this sidecar" from a failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarFeature {
+    /// `set_fan_speed_per_fan` works with this backend.
+    PerFanControl,
+    /// `status` lists every fan in `fans`.
+    FanList,
+    /// Added by a newer sidecar than this app.
+    #[serde(other)]
+    Unknown,
+}
+
+/// What `write_ec_register` needs in `confirm`, typed out by the user.
+pub const RAW_WRITE_CONFIRMATION: &str = "I know what I'm doing";
+
+/// Start of the stderr line a panicking sidecar writes its [`Crash`] to,
+/// as JSON, for the app to save as a crash report.
+pub const CRASH_PREFIX: &str = "msi-sidecar crash: ";
+
+/// What a panic hook captured, for a crash report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Crash {
+    /// Version of the crate that panicked.
+    pub version: String,
+    pub message: String,
+    /// `file:line:column` of the panic.
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+}
+
+impl Crash {
+    /// Capture the panic being reported to a panic hook, with a backtrace
+    /// whatever `RUST_BACKTRACE` says.
+    pub fn capture(version: &str, info: &std::panic::PanicHookInfo) -> Self {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        Crash {
+            version: version.to_string(),
+            message,
+            location: info.location().map(|l| l.to_string()),
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export, rename = "FanStatus"))]
+pub struct Status {
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+    /// The first two entries of `fans`, for clients that predate it.
+    pub fan1_rpm: u32,
+    pub fan2_rpm: u32,
+    /// Every fan, the CPU fan first and the GPU fan second.
+    #[serde(default)]
+    pub fans: Vec<FanReading>,
+    pub cooler_boost: bool,
+    pub fan_mode: String,
+    /// Cooler Boost has run longer than the model's recommended limit.
+    #[serde(default)]
+    pub boost_cooldown_suggested: bool,
+    /// Hardware backend that served this reading (`ec_sys` or `msi-ec`).
+    #[serde(default)]
+    pub backend: String,
+    /// DMI product name.
+    #[serde(default)]
+    pub model: String,
+    /// Writes on anything but `Verified` need a per-feature opt-in and a
+    /// trial write.
+    #[serde(default)]
+    pub support_level: SupportLevel,
+    /// The sidecar runs with `--simulate` against an in-memory EC.
+    #[serde(default)]
+    pub simulated: bool,
+    /// `--demo`: simulated, with animated readings for screenshots and
+    /// trying the UI.
+    #[serde(default)]
+    pub demo: bool,
+    /// Fan trouble seen right now, e.g. a stalled fan. Filled in by the
+    /// app from its anomaly checks; the sidecar leaves it empty.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// One fan in a status reading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct FanReading {
+    /// `CPU`, `GPU`, or the name the register map gives it.
+    pub name: String,
+    pub rpm: u32,
+    /// Duty in % the EC is driving the fan at, when the backend can tell.
+    pub target_percent: Option<u8>,
+}
+
+/// One reading in the daemon's history.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HistorySample {
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+    pub fan1_rpm: u32,
+    pub fan2_rpm: u32,
+}
+
+/// Reduced status served by `get_status_lite`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct StatusLite {
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+    pub cooler_boost: bool,
+    pub boost_cooldown_suggested: bool,
+}
+
+/// Keyboard backlight level as exposed by the LED class device.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct KeyboardBacklight {
+    pub brightness: u8,
+    pub max_brightness: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct DetectedModel {
+    /// DMI product name.
+    pub model: String,
+    /// Register map used for raw EC access; `generic` when the model isn't
+    /// in the built-in table.
+    pub register_map: String,
+    /// Backend in use; with `msi-ec` the driver's own config applies instead.
+    pub backend: String,
+}
+
+/// EC values the fan mode register takes in each mode.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct FanModeValues {
+    pub auto: u8,
+    pub silent: u8,
+    pub basic: u8,
+    pub advanced: u8,
+}
+
+/// Register layout for raw EC access. Also the format of the user override
+/// file, so a map reported by `get_register_map` can be saved and edited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct RegisterMapSpec {
+    pub name: String,
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+    pub cooler_boost: u8,
+    pub cooler_boost_bit: u8,
+    /// `[low, high]` tachometer byte pairs of the CPU fan, in probe order.
+    pub fan1_rpm: Vec<(u8, u8)>,
+    /// `None` on single-fan models.
+    pub fan2_rpm: Option<(u8, u8)>,
+    /// Fan mode register candidates, in probe order.
+    pub fan_mode: Vec<u8>,
+    pub modes: FanModeValues,
+    /// First of 7 speed points per fan.
+    pub fan1_speed_start: u8,
+    pub fan2_speed_start: u8,
+    /// First of 6 temperature thresholds per fan.
+    pub fan1_temp_start: u8,
+    pub fan2_temp_start: u8,
+    /// Tachometers hold `rpm_divisor / RPM`.
+    #[serde(default = "default_rpm_divisor")]
+    pub rpm_divisor: u32,
+    /// Fans beyond the CPU and GPU fans.
+    #[serde(default)]
+    pub extra_fans: Vec<ExtraFanSpec>,
+}
+
+/// A fan beyond the CPU and GPU fans, e.g. the third fan of GE and Titan
+/// models. It is monitored only; writes still cover the first two fans.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct ExtraFanSpec {
+    pub name: String,
+    /// `[low, high]` tachometer bytes.
+    pub rpm: (u8, u8),
+    /// Register holding the fan's current duty in %, if known.
+    #[serde(default)]
+    pub duty: Option<u8>,
+}
+
+/// Tachometer constant of every model mapped so far.
+pub const DEFAULT_RPM_DIVISOR: u32 = 470_000;
+
+fn default_rpm_divisor() -> u32 {
+    DEFAULT_RPM_DIVISOR
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+#[serde(rename_all = "snake_case")]
+pub enum RegisterMapSource {
+    /// From the built-in per-model table.
+    BuiltIn,
+    /// Loaded from the user's override file.
+    Override,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS), ts(export))]
+pub struct ActiveRegisterMap {
+    pub source: RegisterMapSource,
+    pub map: RegisterMapSpec,
+    /// Why an override file that exists was not used.
+    #[serde(default)]
+    pub override_error: Option<String>,
+}
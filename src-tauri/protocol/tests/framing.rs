@@ -0,0 +1,158 @@
+//! Property tests for the line framing and command parsing the sidecar runs
+//! as root, and for the responses it writes back.
+
+use msi_protocol::framing::{parse_command, parse_request, read_line, Line, MAX_LINE_BYTES};
+use msi_protocol::{Command, Reply, Response, StatusLite};
+use proptest::prelude::*;
+
+/// Every line in `input`, as the sidecar's reader sees them.
+fn read_all(mut input: &[u8]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    loop {
+        match read_line(&mut input).expect("reading a slice can't fail") {
+            Line::Eof => return lines,
+            line => lines.push(line),
+        }
+    }
+}
+
+fn command_json() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(r#"{"cmd":"get_status"}"#.to_string()),
+        (0u8..=150, any::<bool>()).prop_map(|(percent, force)| format!(
+            r#"{{"cmd":"set_fan_speed","data":{{"percent":{},"force":{}}}}}"#,
+            percent, force
+        )),
+        (any::<u64>(), any::<bool>()).prop_map(|(interval_ms, lite)| format!(
+            r#"{{"cmd":"subscribe","data":{{"interval_ms":{},"lite":{}}}}}"#,
+            interval_ms, lite
+        )),
+        any::<bool>().prop_map(|enabled| format!(
+            r#"{{"cmd":"set_cooler_boost","data":{{"enabled":{}}}}}"#,
+            enabled
+        )),
+    ]
+}
+
+fn response() -> impl Strategy<Value = Response> {
+    prop_oneof![
+        any::<String>().prop_map(|message| Response::Ok { message }),
+        any::<String>().prop_map(|message| Response::Error { message }),
+        any::<String>().prop_map(|message| Response::FailsafeTriggered { message }),
+        proptest::collection::vec(any::<u8>(), 0..256).prop_map(|data| Response::EcDump { data }),
+        (any::<u8>(), any::<u8>(), any::<bool>(), any::<bool>()).prop_map(
+            |(cpu_temp, gpu_temp, cooler_boost, boost_cooldown_suggested)| {
+                Response::StatusLite(StatusLite {
+                    cpu_temp,
+                    gpu_temp,
+                    cooler_boost,
+                    boost_cooldown_suggested,
+                })
+            }
+        ),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn parse_never_panics(line in any::<String>()) {
+        let _ = parse_command(&line);
+    }
+
+    #[test]
+    fn arbitrary_bytes_frame_and_parse(input in proptest::collection::vec(any::<u8>(), 0..4096)) {
+        for line in read_all(&input) {
+            if let Line::Message(line) = line {
+                prop_assert!(!line.contains('\n'));
+                let _ = parse_command(&line);
+            }
+        }
+    }
+
+    #[test]
+    fn lines_round_trip(lines in proptest::collection::vec("[^\r\n]{0,200}", 1..20), trailing_newline in any::<bool>()) {
+        let mut input = lines.join("\n");
+        // Without a newline an empty last line is no line at all
+        if trailing_newline || lines.last().is_some_and(|l| l.is_empty()) {
+            input.push('\n');
+        }
+        let expected: Vec<Line> = lines.into_iter().map(Line::Message).collect();
+        prop_assert_eq!(read_all(input.as_bytes()), expected);
+    }
+
+    #[test]
+    fn valid_commands_parse(json in command_json()) {
+        let command = parse_command(&json).map_err(TestCaseError::fail)?;
+        // What parses serializes back to something that parses
+        let reencoded = serde_json::to_string(&command).unwrap();
+        prop_assert!(parse_command(&reencoded).is_ok());
+    }
+
+    #[test]
+    fn truncated_commands_are_rejected(json in command_json(), cut in any::<prop::sample::Index>()) {
+        let end = cut.index(json.len());
+        prop_assert!(parse_command(&json[..end]).is_err());
+    }
+
+    #[test]
+    fn huge_line_is_skipped(extra in 1usize..4096, json in command_json()) {
+        let mut input = vec![b'x'; MAX_LINE_BYTES + extra];
+        input.push(b'\n');
+        input.extend_from_slice(json.as_bytes());
+        prop_assert_eq!(read_all(&input), vec![Line::TooLong { id: None }, Line::Message(json)]);
+    }
+
+    #[test]
+    fn huge_line_keeps_leading_id(id in any::<u64>(), extra in 1usize..4096) {
+        let mut input = format!(r#"{{"id":{},"cmd":"set_fan_curve","data":"#, id).into_bytes();
+        input.resize(MAX_LINE_BYTES + extra, b'x');
+        input.push(b'\n');
+        prop_assert_eq!(read_all(&input), vec![Line::TooLong { id: Some(id) }]);
+    }
+
+    #[test]
+    fn responses_are_single_lines_and_round_trip(response in response()) {
+        let json = serde_json::to_string(&response).unwrap();
+        prop_assert!(!json.contains('\n'));
+        let decoded: Response = serde_json::from_str(&json).map_err(|e| TestCaseError::fail(e.to_string()))?;
+        prop_assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    }
+
+    #[test]
+    fn request_ids_round_trip(json in command_json(), id in any::<u64>()) {
+        let with_id = format!(r#"{{"id":{},{}"#, id, &json[1..]);
+        let request = parse_request(&with_id).map_err(|e| TestCaseError::fail(e.message))?;
+        prop_assert_eq!(request.id, Some(id));
+        let reencoded = serde_json::to_string(&request).unwrap();
+        prop_assert_eq!(parse_request(&reencoded).map(|r| r.id), Ok(Some(id)));
+    }
+
+    #[test]
+    fn reply_ids_round_trip(response in response(), id in proptest::option::of(any::<u64>())) {
+        let json = serde_json::to_string(&Reply { id, response }).unwrap();
+        let decoded: Reply = serde_json::from_str(&json).map_err(|e| TestCaseError::fail(e.to_string()))?;
+        prop_assert_eq!(decoded.id, id);
+        prop_assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    }
+}
+
+#[test]
+fn exit_parses() {
+    assert!(matches!(
+        parse_command(r#"{"cmd":"exit"}"#),
+        Ok(Command::Exit)
+    ));
+}
+
+#[test]
+fn requests_without_id_parse() {
+    let request = parse_request(r#"{"cmd":"exit"}"#).unwrap();
+    assert!(request.id.is_none());
+    assert!(matches!(request.command, Command::Exit));
+}
+
+#[test]
+fn invalid_request_keeps_its_id() {
+    let error = parse_request(r#"{"id":7,"cmd":"no_such_command"}"#).unwrap_err();
+    assert_eq!(error.id, Some(7));
+}
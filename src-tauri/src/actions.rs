@@ -0,0 +1,179 @@
+//! Log of everything that changed the fans: profile loads, mode and speed
+//! writes, Cooler Boost toggles and automation such as the startup re-apply
+//! or the sidecar's failsafe. Each entry says who did what, why and when, so
+//! a fan suddenly spinning up can be traced back.
+//!
+//! Entries are appended to `~/.config/msi-fan-control/actions.jsonl` and
+//! the newest `MAX_ACTIONS` are served by `get_action_history`. New ones
+//! are also sent live as `action-recorded` events.
+
+use crate::events::{self, EventTopic};
+use crate::history::now_ms;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+const ACTIONS_FILE: &str = "actions.jsonl";
+const MAX_ACTIONS: usize = 500;
+
+/// Emitted with each new `ActionRecord`.
+pub(crate) const ACTION_EVENT: &str = "action-recorded";
+
+/// Who triggered an action.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionSource {
+    Window,
+    Tray,
+    Hotkey,
+    /// Re-applying the last settings when the app starts.
+    Startup,
+    /// The sidecar's failsafe watchdog.
+    Failsafe,
+    /// A per-application rule, on its process starting or exiting.
+    AppRule,
+    /// Switching between AC and battery.
+    PowerSource,
+    /// Re-applying the last settings after a wake from suspend.
+    Resume,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    ProfileApplied,
+    FanMode,
+    FanSpeed,
+    FanCurve,
+    CoolerBoost,
+    Failsafe,
+    /// A raw EC register write, for mapping a model.
+    RegisterWrite,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct ActionRecord {
+    /// Unix time in milliseconds
+    #[ts(type = "number")]
+    pub timestamp_ms: u64,
+    pub source: ActionSource,
+    pub kind: ActionKind,
+    /// What was done, e.g. "Fan mode set to silent".
+    pub detail: String,
+    /// Why, when it wasn't a direct user request.
+    pub reason: Option<String>,
+}
+
+#[derive(Default)]
+pub(crate) struct ActionLog(Mutex<Option<VecDeque<ActionRecord>>>);
+
+fn get_actions_path() -> Result<PathBuf, String> {
+    Ok(crate::get_config_dir()?.join(ACTIONS_FILE))
+}
+
+/// The newest `MAX_ACTIONS` entries on disk. The file is rewritten when it
+/// has grown past twice that, so it stays small without a rewrite per entry.
+fn load_actions() -> Result<VecDeque<ActionRecord>, String> {
+    let path = get_actions_path()?;
+    if !path.exists() {
+        return Ok(VecDeque::new());
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let lines: Vec<&str> = content.lines().collect();
+    let mut actions: VecDeque<ActionRecord> = lines
+        .iter()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    while actions.len() > MAX_ACTIONS {
+        actions.pop_front();
+    }
+    if lines.len() > 2 * MAX_ACTIONS {
+        let mut trimmed = String::new();
+        for action in &actions {
+            trimmed.push_str(&serde_json::to_string(action).map_err(|e| e.to_string())?);
+            trimmed.push('\n');
+        }
+        std::fs::write(&path, trimmed).map_err(|e| e.to_string())?;
+    }
+    Ok(actions)
+}
+
+fn append_action(action: &ActionRecord) -> Result<(), String> {
+    let path = get_actions_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let line = serde_json::to_string(action).map_err(|e| e.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+impl ActionLog {
+    fn with_actions<T>(&self, read: impl FnOnce(&mut VecDeque<ActionRecord>) -> T) -> T {
+        let mut log = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let actions = log.get_or_insert_with(|| {
+            load_actions().unwrap_or_else(|e| {
+                eprintln!("Failed to load action history: {}", e);
+                VecDeque::new()
+            })
+        });
+        read(actions)
+    }
+}
+
+/// Log a successful action.
+pub(crate) fn record(
+    app: &AppHandle,
+    source: ActionSource,
+    kind: ActionKind,
+    detail: impl Into<String>,
+    reason: Option<String>,
+) {
+    let action = ActionRecord {
+        timestamp_ms: now_ms(),
+        source,
+        kind,
+        detail: detail.into(),
+        reason,
+    };
+    // Loads the file before appending, so the entry isn't read back twice
+    app.state::<ActionLog>().with_actions(|actions| {
+        if actions.len() >= MAX_ACTIONS {
+            actions.pop_front();
+        }
+        actions.push_back(action.clone());
+    });
+    if let Err(e) = append_action(&action) {
+        eprintln!("Failed to record action: {}", e);
+    }
+    events::emit(app, EventTopic::Logs, ACTION_EVENT, &action);
+}
+
+/// The last `limit` actions (all kept ones by default), newest first.
+#[tauri::command]
+pub async fn get_action_history(
+    log: State<'_, ActionLog>,
+    limit: Option<usize>,
+) -> Result<Vec<ActionRecord>, String> {
+    Ok(log.with_actions(|actions| {
+        actions
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(MAX_ACTIONS))
+            .cloned()
+            .collect()
+    }))
+}
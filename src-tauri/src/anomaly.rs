@@ -0,0 +1,649 @@
+//! Flags unusual thermal behaviour in the status stream:
+//!
+//! - temperature spikes, as a rolling z-score against the last few minutes
+//! - idle temperatures creeping up, comparing the 10-minute minimum with a
+//!   slow long-term average
+//! - fans spinning slower than they used to at the same fixed duty, which
+//!   usually means dust or a worn bearing
+//! - a fan stuck at 0 RPM while its chip is hot, or one whose speed keeps
+//!   swinging up and down at a steady duty, as dying fans do
+//!
+//! Long-term baselines are persisted to `~/.config/msi-fan-control/`.
+//! Findings are kept for `get_anomalies` and emitted as `anomaly-detected`,
+//! at most once per kind every ten minutes. Fan findings also go to the
+//! notification sinks, and while they last to the pushed status's
+//! `warnings`.
+
+use crate::events::{self, EventTopic};
+use crate::format::{format_value, NumberFormat, Sensor};
+use crate::history::{History, HistorySample};
+use crate::notifications;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+const BASELINE_FILE: &str = "anomaly_baseline.json";
+
+/// Emitted with the `Anomaly` when something unusual is found.
+pub(crate) const ANOMALY_EVENT: &str = "anomaly-detected";
+
+const SPIKE_WINDOW_MS: u64 = 5 * 60 * 1000;
+const SPIKE_MIN_SAMPLES: usize = 30;
+const SPIKE_Z_SCORE: f64 = 4.0;
+/// Keeps a flat temperature trace from turning 1 °C into a huge z-score.
+const SPIKE_MIN_STD_DEV: f64 = 1.0;
+
+const IDLE_WINDOW_MS: u64 = 10 * 60 * 1000;
+const IDLE_CREEP_CELSIUS: f64 = 8.0;
+/// Weight of each 10-minute floor in the idle baseline (a time constant of
+/// about eight hours of uptime).
+const IDLE_BASELINE_ALPHA: f64 = 0.02;
+
+/// Fans need a moment to settle after a speed change.
+const FAN_SETTLE_MS: u64 = 30 * 1000;
+const FAN_MIN_SAMPLES: u32 = 60;
+/// The running mean stops moving noticeably after this many samples, so
+/// slow wear still shows up against it.
+const FAN_MAX_SAMPLES: u32 = 10_000;
+const FAN_RPM_DROP_RATIO: f64 = 0.8;
+
+/// A fan stopped for this long while its chip is hot has stalled; shorter
+/// stops are the firmware's zero-RPM mode catching up.
+const STALL_WINDOW_MS: u64 = 15 * 1000;
+const STALL_MIN_SAMPLES: usize = 3;
+
+const OSCILLATION_WINDOW_MS: u64 = 60 * 1000;
+const OSCILLATION_MIN_SAMPLES: usize = 20;
+/// A speed change counts as a swing from this share of the mean RPM up.
+const OSCILLATION_SWING_RATIO: f64 = 0.15;
+/// Swings changing direction this often within the window.
+const OSCILLATION_REVERSALS: usize = 6;
+
+const REPEAT_AFTER_MS: u64 = 10 * 60 * 1000;
+const MAX_ANOMALIES: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    CpuTempSpike,
+    GpuTempSpike,
+    IdleTempCreep,
+    Fan1RpmDrop,
+    Fan2RpmDrop,
+    Fan1Stalled,
+    Fan2Stalled,
+    Fan1Oscillating,
+    Fan2Oscillating,
+}
+
+impl AnomalyKind {
+    pub(crate) fn is_fan(self) -> bool {
+        !matches!(
+            self,
+            AnomalyKind::CpuTempSpike | AnomalyKind::GpuTempSpike | AnomalyKind::IdleTempCreep
+        )
+    }
+}
+
+/// One fan's readings in the history, with the chip it cools.
+#[derive(Clone, Copy)]
+struct FanSeries {
+    name: &'static str,
+    rpm: fn(&HistorySample) -> Option<u32>,
+    temp: fn(&HistorySample) -> u8,
+    stalled: AnomalyKind,
+    oscillating: AnomalyKind,
+}
+
+const FANS: [FanSeries; 2] = [
+    FanSeries {
+        name: "CPU",
+        rpm: |s| s.fan1_rpm,
+        temp: |s| s.cpu_temp,
+        stalled: AnomalyKind::Fan1Stalled,
+        oscillating: AnomalyKind::Fan1Oscillating,
+    },
+    FanSeries {
+        name: "GPU",
+        rpm: |s| s.fan2_rpm,
+        temp: |s| s.gpu_temp,
+        stalled: AnomalyKind::Fan2Stalled,
+        oscillating: AnomalyKind::Fan2Oscillating,
+    },
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    /// Unix time in milliseconds
+    #[ts(type = "number")]
+    pub timestamp_ms: u64,
+    pub message: String,
+}
+
+/// Mean RPM of both fans at one fixed duty.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+struct FanBaseline {
+    fan1_rpm: f64,
+    fan2_rpm: f64,
+    samples: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Baseline {
+    idle_cpu_temp: Option<f64>,
+    /// Keyed by fixed duty percent.
+    fans: BTreeMap<u8, FanBaseline>,
+}
+
+#[derive(Default)]
+struct Detector {
+    baseline: Option<Baseline>,
+    last_idle_check_ms: u64,
+    found: VecDeque<Anomaly>,
+    /// Single-fan models always report 0 RPM for fan 2.
+    fan2_seen: bool,
+    /// Fan anomalies in the last full sample, repeats included.
+    fan_warnings: Vec<Anomaly>,
+}
+
+#[derive(Default)]
+pub(crate) struct AnomalyState(Mutex<Detector>);
+
+fn get_baseline_path() -> Result<PathBuf, String> {
+    Ok(crate::get_config_dir()?.join(BASELINE_FILE))
+}
+
+fn load_baseline() -> Result<Baseline, String> {
+    let path = get_baseline_path()?;
+    if !path.exists() {
+        return Ok(Baseline::default());
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid anomaly baseline: {}", e))
+}
+
+fn save_baseline(baseline: &Baseline) -> Result<(), String> {
+    let path = get_baseline_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(baseline).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+fn mean_std_dev(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+/// Upward temperature jumps against the samples before `sample`.
+fn temp_spikes(recent: &[HistorySample], sample: &HistorySample) -> Vec<Anomaly> {
+    let window: Vec<&HistorySample> = recent
+        .iter()
+        .filter(|s| {
+            s.timestamp_ms < sample.timestamp_ms
+                && s.timestamp_ms + SPIKE_WINDOW_MS >= sample.timestamp_ms
+        })
+        .collect();
+    if window.len() < SPIKE_MIN_SAMPLES {
+        return Vec::new();
+    }
+
+    let series = |read: fn(&HistorySample) -> u8| -> Vec<f64> {
+        window.iter().map(|s| read(s) as f64).collect()
+    };
+    let sensors = [
+        (
+            AnomalyKind::CpuTempSpike,
+            "CPU",
+            sample.cpu_temp,
+            series(|s| s.cpu_temp),
+        ),
+        (
+            AnomalyKind::GpuTempSpike,
+            "GPU",
+            sample.gpu_temp,
+            series(|s| s.gpu_temp),
+        ),
+    ];
+    sensors
+        .into_iter()
+        .filter_map(|(kind, name, current, values)| {
+            let (mean, std_dev) = mean_std_dev(&values);
+            let z = (current as f64 - mean) / std_dev.max(SPIKE_MIN_STD_DEV);
+            (z >= SPIKE_Z_SCORE).then(|| {
+                let fmt = NumberFormat::for_user();
+                Anomaly {
+                    kind,
+                    timestamp_ms: sample.timestamp_ms,
+                    message: format!(
+                        "{} temperature jumped to {}, {:.1}σ above the last 5 minutes (mean {})",
+                        name,
+                        format_value(Sensor::Temperature, current as f64, &fmt),
+                        z,
+                        format_value(Sensor::Temperature, mean.round(), &fmt)
+                    ),
+                }
+            })
+        })
+        .collect()
+}
+
+/// A fan at 0 RPM in every sample of the last few seconds, with its chip
+/// at `stall_temp` or hotter.
+fn fan_stalled(
+    recent: &[HistorySample],
+    sample: &HistorySample,
+    fan: FanSeries,
+    stall_temp: u8,
+) -> Option<Anomaly> {
+    let celsius = (fan.temp)(sample);
+    let covered = recent
+        .first()
+        .is_some_and(|first| first.timestamp_ms + STALL_WINDOW_MS <= sample.timestamp_ms);
+    if celsius < stall_temp || !covered {
+        return None;
+    }
+    let window: Vec<Option<u32>> = recent
+        .iter()
+        .filter(|s| s.timestamp_ms + STALL_WINDOW_MS >= sample.timestamp_ms)
+        .map(fan.rpm)
+        .collect();
+    // Lite samples carry no RPM, so a window with one can't confirm a stall
+    if window.len() < STALL_MIN_SAMPLES || window.iter().any(|rpm| *rpm != Some(0)) {
+        return None;
+    }
+    Some(Anomaly {
+        kind: fan.stalled,
+        timestamp_ms: sample.timestamp_ms,
+        message: format!(
+            "{} fan has reported 0 rpm for {} s at {}",
+            fan.name,
+            STALL_WINDOW_MS / 1000,
+            format_value(
+                Sensor::Temperature,
+                celsius as f64,
+                &NumberFormat::for_user()
+            )
+        ),
+    })
+}
+
+/// A fan whose speed keeps swinging up and down over the last minute.
+fn fan_oscillating(
+    recent: &[HistorySample],
+    sample: &HistorySample,
+    fan: FanSeries,
+) -> Option<Anomaly> {
+    let rpms: Vec<f64> = recent
+        .iter()
+        .filter(|s| s.timestamp_ms + OSCILLATION_WINDOW_MS >= sample.timestamp_ms)
+        .filter_map(fan.rpm)
+        .map(|rpm| rpm as f64)
+        .collect();
+    if rpms.len() < OSCILLATION_MIN_SAMPLES {
+        return None;
+    }
+    let (mean, _) = mean_std_dev(&rpms);
+    let swing = mean * OSCILLATION_SWING_RATIO;
+    if swing <= 0.0 {
+        return None;
+    }
+    let mut reversals = 0;
+    let mut direction = 0.0;
+    for step in rpms.windows(2).map(|pair| pair[1] - pair[0]) {
+        if step.abs() < swing {
+            continue;
+        }
+        if direction != 0.0 && step.signum() != direction {
+            reversals += 1;
+        }
+        direction = step.signum();
+    }
+    (reversals >= OSCILLATION_REVERSALS).then(|| Anomaly {
+        kind: fan.oscillating,
+        timestamp_ms: sample.timestamp_ms,
+        message: format!(
+            "{} fan speed swung {} times in the last minute around {:.0} rpm",
+            fan.name, reversals, mean
+        ),
+    })
+}
+
+impl Detector {
+    fn baseline(&mut self) -> &mut Baseline {
+        self.baseline.get_or_insert_with(|| {
+            load_baseline().unwrap_or_else(|e| {
+                eprintln!("Failed to load anomaly baseline: {}", e);
+                Baseline::default()
+            })
+        })
+    }
+
+    /// Every 10 minutes, compare the lowest CPU temperature seen with the
+    /// long-term idle baseline, then fold it in and persist the baselines.
+    fn idle_creep(&mut self, recent: &[HistorySample], now_ms: u64) -> Option<Anomaly> {
+        if now_ms < self.last_idle_check_ms + IDLE_WINDOW_MS {
+            return None;
+        }
+        // Only judge a full window, e.g. not right after startup
+        let first = recent.first()?;
+        if first.timestamp_ms + IDLE_WINDOW_MS > now_ms + 60_000 {
+            return None;
+        }
+        self.last_idle_check_ms = now_ms;
+
+        let floor = recent.iter().map(|s| s.cpu_temp).min()? as f64;
+        let baseline = self.baseline();
+        let anomaly = baseline
+            .idle_cpu_temp
+            .filter(|usual| floor > usual + IDLE_CREEP_CELSIUS)
+            .map(|usual| {
+                let fmt = NumberFormat::for_user();
+                let temp = |sensor, celsius: f64| format_value(sensor, celsius.round(), &fmt);
+                Anomaly {
+                    kind: AnomalyKind::IdleTempCreep,
+                    timestamp_ms: now_ms,
+                    message: format!(
+                        "Idle CPU temperature is {}, {} above its usual {}",
+                        temp(Sensor::Temperature, floor),
+                        temp(Sensor::TemperatureDifference, floor - usual),
+                        temp(Sensor::Temperature, usual)
+                    ),
+                }
+            });
+        baseline.idle_cpu_temp = Some(match baseline.idle_cpu_temp {
+            Some(usual) => usual + IDLE_BASELINE_ALPHA * (floor - usual),
+            None => floor,
+        });
+        if let Err(e) = save_baseline(baseline) {
+            eprintln!("Failed to save anomaly baseline: {}", e);
+        }
+        anomaly
+    }
+
+    /// Compare fan RPM at a fixed duty with what the same duty used to give.
+    fn fan_rpm_drop(&mut self, sample: &HistorySample, percent: u8) -> Vec<Anomaly> {
+        let (Some(fan1), Some(fan2)) = (sample.fan1_rpm, sample.fan2_rpm) else {
+            return Vec::new();
+        };
+        let entry = self.baseline().fans.entry(percent).or_default();
+
+        let mut found = Vec::new();
+        if entry.samples >= FAN_MIN_SAMPLES {
+            let fans = [
+                (AnomalyKind::Fan1RpmDrop, "CPU", fan1, entry.fan1_rpm),
+                (AnomalyKind::Fan2RpmDrop, "GPU", fan2, entry.fan2_rpm),
+            ];
+            for (kind, name, rpm, usual) in fans {
+                if usual > 0.0 && (rpm as f64) < usual * FAN_RPM_DROP_RATIO {
+                    found.push(Anomaly {
+                        kind,
+                        timestamp_ms: sample.timestamp_ms,
+                        message: format!(
+                            "{} fan runs at {} rpm at {}% duty, usually {:.0} rpm",
+                            name, rpm, percent, usual
+                        ),
+                    });
+                }
+            }
+        }
+
+        entry.samples = (entry.samples + 1).min(FAN_MAX_SAMPLES);
+        let n = entry.samples as f64;
+        entry.fan1_rpm += (fan1 as f64 - entry.fan1_rpm) / n;
+        entry.fan2_rpm += (fan2 as f64 - entry.fan2_rpm) / n;
+        found
+    }
+
+    /// Stalls and oscillations of the fans the model has.
+    fn fan_faults(
+        &mut self,
+        recent: &[HistorySample],
+        sample: &HistorySample,
+        stall_temp: u8,
+    ) -> Vec<Anomaly> {
+        if sample.fan2_rpm.is_some_and(|rpm| rpm > 0) {
+            self.fan2_seen = true;
+        }
+        let fans = if self.fan2_seen {
+            &FANS[..]
+        } else {
+            &FANS[..1]
+        };
+        fans.iter()
+            .flat_map(|&fan| {
+                fan_stalled(recent, sample, fan, stall_temp)
+                    .into_iter()
+                    .chain(fan_oscillating(recent, sample, fan))
+            })
+            .collect()
+    }
+
+    /// Keep `anomaly` unless the same kind was reported recently.
+    fn record(&mut self, anomaly: &Anomaly) -> bool {
+        let repeated = self.found.iter().rev().any(|a| {
+            a.kind == anomaly.kind && a.timestamp_ms + REPEAT_AFTER_MS > anomaly.timestamp_ms
+        });
+        if repeated {
+            return false;
+        }
+        if self.found.len() == MAX_ANOMALIES {
+            self.found.pop_front();
+        }
+        self.found.push_back(anomaly.clone());
+        true
+    }
+}
+
+/// Check a new history sample. `fixed_speed` is the fixed duty and how long
+/// it has been applied, if any; fan checks are skipped under Cooler Boost.
+pub(crate) fn observe(
+    app: &AppHandle,
+    sample: &HistorySample,
+    cooler_boost: bool,
+    fixed_speed: Option<(u8, u64)>,
+) {
+    let recent = app
+        .state::<History>()
+        .since(sample.timestamp_ms.saturating_sub(IDLE_WINDOW_MS));
+    let stall_temp = notifications::stalled_fan_temp(app);
+    let state = app.state::<AnomalyState>();
+    let Ok(mut detector) = state.0.lock() else {
+        return;
+    };
+
+    let mut found = temp_spikes(&recent, sample);
+    found.extend(detector.idle_creep(&recent, sample.timestamp_ms));
+    let mut fan_found = detector.fan_faults(&recent, sample, stall_temp);
+    if let Some((percent, held_ms)) = fixed_speed {
+        if !cooler_boost && held_ms >= FAN_SETTLE_MS {
+            fan_found.extend(detector.fan_rpm_drop(sample, percent));
+        }
+    }
+    // Lite samples can't tell, so the last full one stands
+    if sample.fan1_rpm.is_some() {
+        detector.fan_warnings = fan_found.clone();
+    }
+    found.extend(fan_found);
+
+    let mut alerts = Vec::new();
+    for anomaly in found {
+        if detector.record(&anomaly) {
+            events::emit(app, EventTopic::Alerts, ANOMALY_EVENT, &anomaly);
+            if anomaly.kind.is_fan() {
+                alerts.push(anomaly);
+            }
+        }
+    }
+    drop(detector);
+    for anomaly in &alerts {
+        notifications::fan_anomaly(app, anomaly);
+    }
+}
+
+/// Messages for the fan anomalies in the last full sample, for the status
+/// pushed to the window.
+pub(crate) fn fan_warnings(app: &AppHandle) -> Vec<String> {
+    let state = app.state::<AnomalyState>();
+    let Ok(detector) = state.0.lock() else {
+        return Vec::new();
+    };
+    detector
+        .fan_warnings
+        .iter()
+        .map(|anomaly| anomaly.message.clone())
+        .collect()
+}
+
+/// Anomalies found since the app started, oldest first.
+#[tauri::command]
+pub async fn get_anomalies(state: State<'_, AnomalyState>) -> Result<Vec<Anomaly>, String> {
+    let detector = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(detector.found.iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STALL_TEMP: u8 = 70;
+
+    fn sample(secs: u64, temp: u8, rpm: Option<u32>) -> HistorySample {
+        HistorySample {
+            timestamp_ms: 1_000_000 + secs * 1000,
+            cpu_temp: temp,
+            gpu_temp: temp,
+            fan1_rpm: rpm,
+            fan2_rpm: rpm,
+        }
+    }
+
+    /// One sample a second for `secs` seconds, `rpm` giving each one's speed.
+    fn series(secs: u64, temp: u8, rpm: impl Fn(u64) -> Option<u32>) -> Vec<HistorySample> {
+        (0..=secs).map(|t| sample(t, temp, rpm(t))).collect()
+    }
+
+    fn stalled(recent: &[HistorySample]) -> Option<Anomaly> {
+        fan_stalled(recent, recent.last().unwrap(), FANS[0], STALL_TEMP)
+    }
+
+    fn oscillating(recent: &[HistorySample]) -> Option<Anomaly> {
+        fan_oscillating(recent, recent.last().unwrap(), FANS[0])
+    }
+
+    #[test]
+    fn stopped_fan_on_hot_chip_is_stalled() {
+        let recent = series(20, 80, |_| Some(0));
+        let anomaly = stalled(&recent).unwrap();
+        assert_eq!(anomaly.kind, AnomalyKind::Fan1Stalled);
+    }
+
+    #[test]
+    fn stall_needs_chip_at_threshold() {
+        assert!(stalled(&series(20, STALL_TEMP, |_| Some(0))).is_some());
+        assert!(stalled(&series(20, STALL_TEMP - 1, |_| Some(0))).is_none());
+    }
+
+    #[test]
+    fn stall_needs_history_covering_window() {
+        let window = STALL_WINDOW_MS / 1000;
+        assert!(stalled(&series(window, 80, |_| Some(0))).is_some());
+        assert!(stalled(&series(window - 1, 80, |_| Some(0))).is_none());
+    }
+
+    #[test]
+    fn stall_needs_enough_samples() {
+        // Covered by an old sample, but only two inside the window
+        let recent = vec![
+            sample(0, 80, Some(0)),
+            sample(19, 80, Some(0)),
+            sample(20, 80, Some(0)),
+        ];
+        assert!(stalled(&recent[1..]).is_none());
+        assert!(stalled(&recent).is_none());
+    }
+
+    #[test]
+    fn spinning_or_lite_sample_in_window_is_not_stalled() {
+        assert!(stalled(&series(20, 80, |t| Some(if t == 15 { 1200 } else { 0 }))).is_none());
+        assert!(stalled(&series(20, 80, |t| (t != 15).then_some(0))).is_none());
+        // A spin-up before the window doesn't clear a stall inside it
+        assert!(stalled(&series(20, 80, |t| Some(if t < 3 { 1200 } else { 0 }))).is_some());
+    }
+
+    #[test]
+    fn swinging_fan_is_oscillating() {
+        let recent = series(60, 60, |t| Some(if t % 2 == 0 { 2000 } else { 3000 }));
+        let anomaly = oscillating(&recent).unwrap();
+        assert_eq!(anomaly.kind, AnomalyKind::Fan1Oscillating);
+    }
+
+    #[test]
+    fn steady_or_ramping_fan_is_not_oscillating() {
+        // ±100 rpm of noise is under the swing threshold
+        assert!(oscillating(&series(60, 60, |t| Some(2500 + (t % 2) as u32 * 200))).is_none());
+        assert!(oscillating(&series(60, 60, |t| Some(2000 + t as u32 * 50))).is_none());
+        assert!(oscillating(&series(60, 60, |_| Some(0))).is_none());
+    }
+
+    #[test]
+    fn oscillation_needs_enough_reversals_and_samples() {
+        // Five reversals, then steady
+        let few = series(60, 60, |t| {
+            Some(if t <= 5 && t % 2 == 1 { 3000 } else { 2000 })
+        });
+        assert!(oscillating(&few).is_none());
+        let short = series(OSCILLATION_MIN_SAMPLES as u64 - 2, 60, |t| {
+            Some(if t % 2 == 0 { 2000 } else { 3000 })
+        });
+        assert!(oscillating(&short).is_none());
+    }
+
+    #[test]
+    fn oscillation_only_counts_the_last_minute() {
+        let recent = series(120, 60, |t| {
+            Some(if t < 50 && t % 2 == 0 { 3000 } else { 2000 })
+        });
+        assert!(oscillating(&recent).is_none());
+    }
+
+    #[test]
+    fn second_fan_is_checked_once_seen_spinning() {
+        let mut detector = Detector::default();
+        let mut recent = series(20, 80, |_| Some(0));
+        for s in &mut recent {
+            s.fan1_rpm = Some(2000);
+        }
+        let last = *recent.last().unwrap();
+        assert!(detector.fan_faults(&recent, &last, STALL_TEMP).is_empty());
+
+        detector.fan2_seen = true;
+        let found = detector.fan_faults(&recent, &last, STALL_TEMP);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, AnomalyKind::Fan2Stalled);
+    }
+
+    #[test]
+    fn repeats_are_recorded_once_per_interval() {
+        let mut detector = Detector::default();
+        let anomaly = stalled(&series(20, 80, |_| Some(0))).unwrap();
+        assert!(detector.record(&anomaly));
+        assert!(!detector.record(&anomaly));
+        let later = Anomaly {
+            timestamp_ms: anomaly.timestamp_ms + REPEAT_AFTER_MS,
+            ..anomaly
+        };
+        assert!(detector.record(&later));
+    }
+}
@@ -0,0 +1,178 @@
+//! Automatic profile switching while configured applications run.
+//!
+//! Every few seconds the process list is checked against the user's rules
+//! (process name to profile). The first rule in the list whose process is
+//! running wins: its profile is loaded and the window is told through
+//! `app-rule-changed`. When no rule matches any more, the profile that was
+//! active before the first one is loaded again, unless the user picked
+//! another profile by hand in the meantime.
+
+use crate::actions::ActionSource;
+use crate::events::{self, EventTopic};
+use crate::permissions::{self, Tier};
+use crate::profiles;
+use crate::{settings, SidecarState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+/// Emitted with the active `AppRule`, or `null` once none matches.
+pub(crate) const APP_RULE_EVENT: &str = "app-rule-changed";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+pub struct AppRule {
+    /// Process name as in `ps`, e.g. `steam` or `blender`; case-insensitive.
+    pub process: String,
+    /// Profile loaded while the process runs.
+    pub profile: String,
+}
+
+#[derive(Default)]
+struct Watch {
+    active: Option<AppRule>,
+    /// Profile to go back to once no rule matches.
+    revert_to: Option<String>,
+}
+
+#[derive(Default)]
+pub(crate) struct AppRuleState(Mutex<Watch>);
+
+impl AppRuleState {
+    /// The rule whose profile is loaded right now, if any.
+    pub(crate) fn active(&self) -> Option<AppRule> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .active
+            .clone()
+    }
+}
+
+fn running_processes(sys: &mut System) -> HashSet<String> {
+    sys.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+    sys.processes()
+        .values()
+        .map(|p| p.name().to_string_lossy().to_lowercase())
+        .collect()
+}
+
+fn matching_rule(rules: &[AppRule], running: &HashSet<String>) -> Option<AppRule> {
+    rules
+        .iter()
+        .find(|rule| running.contains(&rule.process.to_lowercase()))
+        .cloned()
+}
+
+async fn load(app: &AppHandle, name: &str, reason: String) {
+    let state = app.state::<SidecarState>().inner().clone();
+    match profiles::switch_profile(app, &state, name, ActionSource::AppRule, Some(reason)).await {
+        Ok(profile) => profiles::notify_profile_changed(app, &profile),
+        Err(e) => eprintln!("App rules: failed to load profile '{}': {}", name, e),
+    }
+}
+
+/// Apply a change of the matching rule.
+async fn on_change(app: &AppHandle, rule: Option<AppRule>) {
+    let (previous, revert_to) = {
+        let state = app.state::<AppRuleState>();
+        let mut watch = state.0.lock().unwrap_or_else(|e| e.into_inner());
+        if watch.active.is_none() {
+            watch.revert_to = settings::load_settings()
+                .ok()
+                .and_then(|s| s.active_profile);
+        }
+        let previous = std::mem::replace(&mut watch.active, rule.clone());
+        let revert_to = if rule.is_none() {
+            watch.revert_to.take()
+        } else {
+            None
+        };
+        (previous, revert_to)
+    };
+
+    match (&rule, previous) {
+        (Some(rule), _) => load(app, &rule.profile, format!("{} is running", rule.process)).await,
+        (None, Some(previous)) => {
+            let active = settings::load_settings()
+                .ok()
+                .and_then(|s| s.active_profile);
+            // Leave a profile the user chose by hand alone
+            if let Some(name) = revert_to.filter(|_| active.as_ref() == Some(&previous.profile)) {
+                load(app, &name, format!("{} exited", previous.process)).await;
+            }
+        }
+        (None, None) => {}
+    }
+    events::emit(app, EventTopic::Profile, APP_RULE_EVENT, &rule);
+}
+
+/// Watch the process list for as long as the app runs.
+pub(crate) fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut sys = System::new();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let rules = settings::load_settings()
+                .map(|s| s.app_rules)
+                .unwrap_or_default();
+            if rules.is_empty() && app.state::<AppRuleState>().active().is_none() {
+                continue;
+            }
+            let Ok((returned, running)) = tokio::task::spawn_blocking(move || {
+                let running = running_processes(&mut sys);
+                (sys, running)
+            })
+            .await
+            else {
+                return;
+            };
+            sys = returned;
+
+            let rule = matching_rule(&rules, &running);
+            if rule != app.state::<AppRuleState>().active() {
+                on_change(&app, rule).await;
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_app_rules() -> Result<Vec<AppRule>, String> {
+    Ok(settings::load_settings()?.app_rules)
+}
+
+#[tauri::command]
+pub async fn get_active_app_rule(
+    state: State<'_, AppRuleState>,
+) -> Result<Option<AppRule>, String> {
+    Ok(state.active())
+}
+
+/// Replace the rules; the first matching one in the list wins. Takes effect
+/// at the next process check.
+#[tauri::command]
+pub async fn set_app_rules(app: AppHandle, rules: Vec<AppRule>) -> Result<Vec<AppRule>, String> {
+    permissions::check(&app, Tier::Control)?;
+    let profiles = profiles::load_profiles()?;
+    for rule in &rules {
+        if rule.process.trim().is_empty() {
+            return Err("A rule needs a process name".to_string());
+        }
+        if !profiles.iter().any(|p| p.name == rule.profile) {
+            return Err(format!("Profile '{}' not found", rule.profile));
+        }
+    }
+    let mut settings = settings::load_settings()?;
+    settings.app_rules = rules.clone();
+    settings::save_settings(&settings)?;
+    Ok(rules)
+}
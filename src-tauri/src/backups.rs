@@ -0,0 +1,212 @@
+//! Backups of the EC configuration, persisted to
+//! `~/.config/msi-fan-control/backups.json`.
+//!
+//! A backup is a copy of the last applied settings (mode, speed or curve,
+//! Cooler Boost, backlight). One is taken on a schedule (weekly by default),
+//! one before every profile load unless nothing changed since the newest
+//! backup, and one whenever the user asks. Only the newest `keep` are kept.
+
+use crate::actions::{self, ActionKind, ActionSource};
+use crate::history::now_ms;
+use crate::permissions::{self, Tier};
+use crate::profiles::{self, Profile};
+use crate::{overrides, settings, SidecarState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use ts_rs::TS;
+
+const BACKUPS_FILE: &str = "backups.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupTrigger {
+    Scheduled,
+    /// Taken just before a profile was loaded.
+    BeforeProfile,
+    Manual,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct Backup {
+    /// Unix time in milliseconds; also identifies the backup.
+    #[ts(type = "number")]
+    pub timestamp_ms: u64,
+    pub trigger: BackupTrigger,
+    pub settings: Profile,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct BackupSettings {
+    /// Days between scheduled backups; 0 turns them off.
+    pub interval_days: u32,
+    /// Back up before each profile load.
+    pub before_profile: bool,
+    /// Backups kept; the oldest go first.
+    pub keep: u32,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings {
+            interval_days: 7,
+            before_profile: true,
+            keep: 30,
+        }
+    }
+}
+
+fn get_backups_path() -> Result<PathBuf, String> {
+    Ok(crate::get_config_dir()?.join(BACKUPS_FILE))
+}
+
+/// Oldest first, as they were taken.
+fn load_backups() -> Result<Vec<Backup>, String> {
+    let path = get_backups_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid backups file: {}", e))
+}
+
+fn save_backups(backups: &[Backup]) -> Result<(), String> {
+    let path = get_backups_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(backups).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+fn same_settings(a: &Profile, b: &Profile) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Back up the last applied settings. `None` when nothing was applied yet,
+/// or for a profile load when the newest backup already holds them.
+pub(crate) fn create(trigger: BackupTrigger) -> Result<Option<Backup>, String> {
+    let Some(current) = profiles::load_last_applied()? else {
+        return Ok(None);
+    };
+    let mut backups = load_backups()?;
+    let unchanged = backups
+        .last()
+        .is_some_and(|newest| same_settings(&newest.settings, &current));
+    if trigger == BackupTrigger::BeforeProfile && unchanged {
+        return Ok(None);
+    }
+
+    let backup = Backup {
+        timestamp_ms: now_ms(),
+        trigger,
+        settings: current,
+    };
+    backups.push(backup.clone());
+    let keep = settings::load_settings()?.backups.keep.max(1) as usize;
+    let excess = backups.len().saturating_sub(keep);
+    backups.drain(..excess);
+    save_backups(&backups)?;
+    Ok(Some(backup))
+}
+
+/// Take scheduled backups for as long as the app runs.
+pub(crate) fn spawn() {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let interval_days = settings::load_settings()
+                .map(|s| s.backups.interval_days)
+                .unwrap_or_default();
+            if interval_days == 0 {
+                continue;
+            }
+            let last_scheduled = load_backups()
+                .unwrap_or_default()
+                .iter()
+                .rev()
+                .find(|b| b.trigger == BackupTrigger::Scheduled)
+                .map(|b| b.timestamp_ms);
+            let due = match last_scheduled {
+                Some(at) => now_ms().saturating_sub(at) >= interval_days as u64 * DAY_MS,
+                None => true,
+            };
+            if due {
+                if let Err(e) = create(BackupTrigger::Scheduled) {
+                    eprintln!("Scheduled backup failed: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// All backups, newest first.
+#[tauri::command]
+pub async fn list_backups() -> Result<Vec<Backup>, String> {
+    let mut backups = load_backups()?;
+    backups.reverse();
+    Ok(backups)
+}
+
+#[tauri::command]
+pub async fn create_backup(app: AppHandle) -> Result<Backup, String> {
+    permissions::check(&app, Tier::Control)?;
+    create(BackupTrigger::Manual)?.ok_or_else(|| "Nothing has been applied yet".to_string())
+}
+
+/// Push a backup's settings to the EC; they become the last applied ones.
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    timestamp_ms: u64,
+) -> Result<Profile, String> {
+    permissions::check(&app, Tier::Control)?;
+    let backup = load_backups()?
+        .into_iter()
+        .find(|b| b.timestamp_ms == timestamp_ms)
+        .ok_or("Backup not found")?;
+
+    profiles::apply_profile(&state, &backup.settings).await?;
+    profiles::record_last_applied(|last| *last = backup.settings.clone());
+    match backup.settings.fixed_speed() {
+        Some(percent) => overrides::fixed_speed_set(&app, percent),
+        None => overrides::fixed_speed_cleared(&app),
+    }
+    actions::record(
+        &app,
+        ActionSource::Window,
+        ActionKind::ProfileApplied,
+        "Backup restored",
+        None,
+    );
+    Ok(backup.settings)
+}
+
+#[tauri::command]
+pub async fn get_backup_settings() -> Result<BackupSettings, String> {
+    Ok(settings::load_settings()?.backups)
+}
+
+/// Change the schedule and retention; a lower `keep` prunes at the next
+/// backup.
+#[tauri::command]
+pub async fn set_backup_settings(
+    app: AppHandle,
+    backups: BackupSettings,
+) -> Result<BackupSettings, String> {
+    permissions::check(&app, Tier::Control)?;
+    let mut settings = settings::load_settings()?;
+    settings.backups = backups.clone();
+    settings::save_settings(&settings)?;
+    Ok(backups)
+}
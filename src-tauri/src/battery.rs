@@ -0,0 +1,132 @@
+//! Battery charge and health from `/sys/class/power_supply/BAT*`.
+//!
+//! Drivers report either energy (µWh, µW) or charge (µAh, µA); charge
+//! readings are turned into watt-hours and watts with the battery's voltage
+//! so the window always gets the same units. Values a driver doesn't expose
+//! are `None`.
+
+use crate::{Capability, CapabilityStatus};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use ts_rs::TS;
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+/// Charge limit attribute of drivers that support one, `msi-ec` included.
+const THRESHOLD_FILE: &str = "charge_control_end_threshold";
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct BatteryInfo {
+    /// Kernel name, e.g. `BAT0`.
+    pub name: String,
+    /// As the driver reports it: `Charging`, `Discharging`, `Full` or
+    /// `Not charging`.
+    pub status: String,
+    pub charge_percent: Option<u8>,
+    /// Charge or discharge rate, whichever `status` says is happening.
+    pub power_watts: Option<f32>,
+    pub cycle_count: Option<u32>,
+    pub design_capacity_wh: Option<f32>,
+    /// What the battery holds when full today.
+    pub full_capacity_wh: Option<f32>,
+    /// Full capacity as a share of the design capacity.
+    pub health_percent: Option<f32>,
+}
+
+fn read_value(dir: &Path, file: &str) -> Option<f64> {
+    fs::read_to_string(dir.join(file)).ok()?.trim().parse().ok()
+}
+
+fn read_battery(dir: &Path, name: String) -> BatteryInfo {
+    let micro = |file: &str| read_value(dir, file).map(|v| v / 1e6);
+    // Charge (Ah) times voltage gives energy (Wh); current times voltage, power
+    let voltage = micro("voltage_min_design").or_else(|| micro("voltage_now"));
+    let energy = |kind: &str| {
+        micro(&format!("energy_{}", kind))
+            .or_else(|| Some(micro(&format!("charge_{}", kind))? * voltage?))
+    };
+
+    let design = energy("full_design");
+    let full = energy("full");
+    let power = micro("power_now")
+        .or_else(|| Some(micro("current_now")? * micro("voltage_now")?))
+        .map(f64::abs);
+    let health = match (full, design) {
+        (Some(full), Some(design)) if design > 0.0 => Some(full / design * 100.0),
+        _ => None,
+    };
+
+    BatteryInfo {
+        name,
+        status: fs::read_to_string(dir.join("status"))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_else(|_| "Unknown".to_string()),
+        charge_percent: read_value(dir, "capacity").map(|v| v.clamp(0.0, 100.0) as u8),
+        power_watts: power.map(|w| w as f32),
+        // Some drivers report 0 when they don't count cycles
+        cycle_count: read_value(dir, "cycle_count")
+            .filter(|&c| c > 0.0)
+            .map(|c| c as u32),
+        design_capacity_wh: design.map(|wh| wh as f32),
+        full_capacity_wh: full.map(|wh| wh as f32),
+        health_percent: health.map(|h| h as f32),
+    }
+}
+
+/// Every battery in the system, in name order; empty on a desktop.
+#[tauri::command]
+pub async fn get_battery_info() -> Result<Vec<BatteryInfo>, String> {
+    read_batteries()
+}
+
+/// Charge of the emptiest battery, if any reports one.
+pub(crate) fn lowest_charge() -> Option<u8> {
+    read_batteries()
+        .ok()?
+        .iter()
+        .filter_map(|b| b.charge_percent)
+        .min()
+}
+
+fn read_batteries() -> Result<Vec<BatteryInfo>, String> {
+    let entries = match fs::read_dir(POWER_SUPPLY_PATH) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", POWER_SUPPLY_PATH, e)),
+    };
+    let mut batteries: Vec<BatteryInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let dir = entry.path();
+            let is_battery =
+                fs::read_to_string(dir.join("type")).is_ok_and(|t| t.trim() == "Battery");
+            // `hid-*` batteries belong to mice and keyboards
+            (name.starts_with("BAT") && is_battery).then(|| read_battery(&dir, name))
+        })
+        .collect();
+    batteries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(batteries)
+}
+
+/// Whether any battery takes a charge limit.
+pub(crate) fn threshold_capability() -> CapabilityStatus {
+    let supported = fs::read_dir(POWER_SUPPLY_PATH).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.path().join(THRESHOLD_FILE).exists())
+    });
+    if supported {
+        CapabilityStatus::available(Capability::BatteryThreshold)
+    } else {
+        CapabilityStatus::unavailable(
+            Capability::BatteryThreshold,
+            format!(
+                "No battery exposes {}; the msi-ec driver adds it on supported models",
+                THRESHOLD_FILE
+            ),
+        )
+    }
+}
@@ -0,0 +1,175 @@
+//! Register bookmarks for people mapping a new model's EC: a label for an
+//! offset (e.g. `0xE8`, "keyboard?") and an optional watch expression
+//! checked against its value. They are persisted in the settings, label
+//! the changes the contribution flow captures, and go into its report.
+//!
+//! A watch expression is `bit N` (set), `!bit N` (clear), or a comparison
+//! `== V`, `!= V`, `< V`, `<= V`, `> V` or `>= V`, with V in decimal or
+//! `0x` hex.
+
+use crate::contribute::dump_ec;
+use crate::permissions::{self, Tier};
+use crate::{settings, SidecarState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use ts_rs::TS;
+
+const MAX_LABEL_LEN: usize = 64;
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct RegisterBookmark {
+    pub offset: u8,
+    pub label: String,
+    pub watch: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct BookmarkReading {
+    pub bookmark: RegisterBookmark,
+    pub value: u8,
+    /// Whether the watch expression holds; `None` without one.
+    pub watch_holds: Option<bool>,
+}
+
+enum Watch {
+    Bit { bit: u8, set: bool },
+    Compare { op: &'static str, operand: u8 },
+}
+
+impl Watch {
+    fn parse(expr: &str) -> Result<Watch, String> {
+        let expr = expr.trim();
+        let parse_value = |s: &str| {
+            let s = s.trim();
+            let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(hex) => u8::from_str_radix(hex, 16),
+                None => s.parse(),
+            };
+            parsed.map_err(|_| format!("'{}' is not a byte value", s))
+        };
+
+        let (set, rest) = match expr.strip_prefix('!') {
+            Some(rest) => (false, rest.trim_start()),
+            None => (true, expr),
+        };
+        if let Some(bit) = rest.strip_prefix("bit") {
+            let bit = parse_value(bit)?;
+            if bit > 7 {
+                return Err(format!("Bit {} is out of range 0-7", bit));
+            }
+            return Ok(Watch::Bit { bit, set });
+        }
+        // Two-character operators first, so `<=` isn't read as `<`
+        for op in ["==", "!=", "<=", ">=", "<", ">"] {
+            if let Some(operand) = expr.strip_prefix(op) {
+                return Ok(Watch::Compare {
+                    op,
+                    operand: parse_value(operand)?,
+                });
+            }
+        }
+        Err(format!(
+            "Invalid watch expression '{}'; use e.g. 'bit 7', '!bit 0' or '>= 0x40'",
+            expr
+        ))
+    }
+
+    fn holds(&self, value: u8) -> bool {
+        match *self {
+            Watch::Bit { bit, set } => (value >> bit & 1 == 1) == set,
+            Watch::Compare { op, operand } => match op {
+                "==" => value == operand,
+                "!=" => value != operand,
+                "<=" => value <= operand,
+                ">=" => value >= operand,
+                "<" => value < operand,
+                _ => value > operand,
+            },
+        }
+    }
+}
+
+/// Label of the bookmark at `offset`, if any.
+pub(crate) fn label_for(bookmarks: &[RegisterBookmark], offset: u8) -> Option<String> {
+    bookmarks
+        .iter()
+        .find(|b| b.offset == offset)
+        .map(|b| b.label.clone())
+}
+
+pub(crate) fn load() -> Vec<RegisterBookmark> {
+    settings::load_settings()
+        .map(|s| s.register_bookmarks)
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_register_bookmarks() -> Result<Vec<RegisterBookmark>, String> {
+    Ok(settings::load_settings()?.register_bookmarks)
+}
+
+/// Replace the bookmarks, sorted by offset. One bookmark per offset.
+#[tauri::command]
+pub async fn set_register_bookmarks(
+    app: AppHandle,
+    mut bookmarks: Vec<RegisterBookmark>,
+) -> Result<Vec<RegisterBookmark>, String> {
+    permissions::check(&app, Tier::Expert)?;
+    bookmarks.sort_by_key(|b| b.offset);
+    for pair in bookmarks.windows(2) {
+        if pair[0].offset == pair[1].offset {
+            return Err(format!("0x{:02X} is bookmarked twice", pair[0].offset));
+        }
+    }
+    for bookmark in &mut bookmarks {
+        bookmark.label = bookmark.label.trim().to_string();
+        if bookmark.label.is_empty() || bookmark.label.len() > MAX_LABEL_LEN {
+            return Err(format!(
+                "The label of 0x{:02X} must be 1 to {} characters",
+                bookmark.offset, MAX_LABEL_LEN
+            ));
+        }
+        if let Some(watch) = &bookmark.watch {
+            Watch::parse(watch)?;
+        }
+    }
+
+    let mut settings = settings::load_settings()?;
+    settings.register_bookmarks = bookmarks.clone();
+    settings::save_settings(&settings)?;
+    Ok(bookmarks)
+}
+
+/// Value of every bookmarked register in `dump`.
+pub(crate) fn readings(dump: &[u8]) -> Result<Vec<BookmarkReading>, String> {
+    load()
+        .into_iter()
+        .map(|bookmark| {
+            let value = *dump
+                .get(bookmark.offset as usize)
+                .ok_or_else(|| format!("0x{:02X} is past the end of the EC", bookmark.offset))?;
+            let watch_holds = match &bookmark.watch {
+                Some(watch) => Some(Watch::parse(watch)?.holds(value)),
+                None => None,
+            };
+            Ok(BookmarkReading {
+                bookmark,
+                value,
+                watch_holds,
+            })
+        })
+        .collect()
+}
+
+/// Current value of every bookmarked register, from a fresh EC dump.
+#[tauri::command]
+pub async fn read_register_bookmarks(
+    app: AppHandle,
+    sidecar: State<'_, SidecarState>,
+) -> Result<Vec<BookmarkReading>, String> {
+    permissions::check(&app, Tier::Expert)?;
+    readings(&dump_ec(&sidecar).await?)
+}
@@ -0,0 +1,344 @@
+//! Guided flow for users on models missing from the quirks DB.
+//!
+//! The user starts a session, then toggles firmware features (Fn keys, MSI
+//! Center presets, Cooler Boost button) and captures a step after each one.
+//! Every capture is diffed against the previous EC dump. Finishing produces
+//! a draft `QUIRKS_DB` entry and a Markdown report ready to attach to an
+//! issue. Changes to bookmarked registers carry their label, and the report
+//! lists the bookmarks. Everything here is read-only.
+
+use crate::actions::{self, ActionKind, ActionSource};
+use crate::bookmarks::{self, BookmarkReading, RegisterBookmark};
+use crate::permissions::{self, Tier};
+use crate::sidecar::{run_write, SidecarCommand};
+use crate::{fetch_status, query_sidecar, SidecarResponse, SidecarState};
+use msi_protocol::SupportLevel;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+use tokio::sync::Mutex;
+use ts_rs::TS;
+
+// Offsets of the sidecar's generic register map; mirrored from its `model.rs`
+const REG_CPU_TEMP: usize = 0x68;
+const REG_GPU_TEMP: usize = 0x80;
+const REG_COOLER_BOOST: usize = 0x98;
+const REG_FAN_MODE_CANDIDATES: [usize; 2] = [0xD4, 0xF4];
+const KNOWN_FAN_MODES: [u8; 4] = [0x0D, 0x1D, 0x4D, 0x8D];
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct RegisterChange {
+    pub offset: u8,
+    pub before: u8,
+    pub after: u8,
+    /// The register's bookmark label.
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct ContributionStep {
+    /// What the user did, e.g. "Pressed Fn+F8 (Cooler Boost on)".
+    pub label: String,
+    pub changes: Vec<RegisterChange>,
+}
+
+/// Result of a sanity check against the register layout we know.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct ProbeResult {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct ContributionReport {
+    pub model: String,
+    pub backend: String,
+    pub support_level: SupportLevel,
+    pub probes: Vec<ProbeResult>,
+    pub steps: Vec<ContributionStep>,
+    pub bookmarks: Vec<RegisterBookmark>,
+    /// Rust snippet for `quirks.rs`.
+    pub draft_quirks: String,
+    /// Markdown summary to paste into a GitHub issue.
+    pub report: String,
+}
+
+struct Contribution {
+    model: String,
+    backend: String,
+    support_level: SupportLevel,
+    probes: Vec<ProbeResult>,
+    last_dump: Vec<u8>,
+    steps: Vec<ContributionStep>,
+}
+
+#[derive(Default)]
+pub(crate) struct ContributionState(Mutex<Option<Contribution>>);
+
+pub(crate) async fn dump_ec(state: &SidecarState) -> Result<Vec<u8>, String> {
+    match query_sidecar(state, &SidecarCommand::DumpEc).await? {
+        SidecarResponse::EcDump { data } => Ok(data),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct EcDump {
+    pub data: Vec<u8>,
+    /// 16 bytes per line behind their offset, e.g. `60: 00 1f ...`.
+    pub hex: String,
+    /// Bookmarked registers and their values in this dump.
+    pub bookmarks: Vec<BookmarkReading>,
+}
+
+pub(crate) fn hex_lines(data: &[u8]) -> String {
+    data.chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{:02x}: {}\n", row * 16, bytes.join(" "))
+        })
+        .collect()
+}
+
+fn diff(before: &[u8], after: &[u8], bookmarks: &[RegisterBookmark]) -> Vec<RegisterChange> {
+    before
+        .iter()
+        .zip(after)
+        .enumerate()
+        .filter(|(_, (b, a))| b != a)
+        .map(|(offset, (b, a))| RegisterChange {
+            offset: offset as u8,
+            before: *b,
+            after: *a,
+            label: bookmarks::label_for(bookmarks, offset as u8),
+        })
+        .collect()
+}
+
+fn probe(dump: &[u8]) -> Vec<ProbeResult> {
+    let byte = |offset: usize| dump.get(offset).copied().unwrap_or(0);
+    let temp_probe = |name: &str, offset: usize| {
+        let value = byte(offset);
+        ProbeResult {
+            name: name.to_string(),
+            passed: (20..=105).contains(&value),
+            detail: format!("0x{:02X} reads {} °C", offset, value),
+        }
+    };
+
+    let fan_mode = REG_FAN_MODE_CANDIDATES
+        .iter()
+        .find(|&&offset| KNOWN_FAN_MODES.contains(&byte(offset)));
+
+    vec![
+        ProbeResult {
+            name: "EC size".to_string(),
+            passed: dump.len() >= 0xFF,
+            detail: format!("{} bytes", dump.len()),
+        },
+        temp_probe("CPU temperature", REG_CPU_TEMP),
+        temp_probe("GPU temperature", REG_GPU_TEMP),
+        ProbeResult {
+            name: "Fan mode register".to_string(),
+            passed: fan_mode.is_some(),
+            detail: match fan_mode {
+                Some(offset) => format!("0x{:02X} = 0x{:02X}", offset, byte(*offset)),
+                None => "No known fan mode value at 0xD4 or 0xF4".to_string(),
+            },
+        },
+        ProbeResult {
+            name: "Cooler Boost register".to_string(),
+            // Only bit 7 is ours; anything else set is unexpected but harmless
+            passed: true,
+            detail: format!(
+                "0x{:02X} = 0x{:02X}",
+                REG_COOLER_BOOST,
+                byte(REG_COOLER_BOOST)
+            ),
+        },
+    ]
+}
+
+fn draft_quirks(model: &str) -> String {
+    format!(
+        "(\n    // Contributed via the guided flow; verified, limits not measured\n    \"{}\",\n    DEFAULT_QUIRKS,\n),\n",
+        model
+    )
+}
+
+fn render_report(c: &Contribution, bookmarks: &[RegisterBookmark], draft: &str) -> String {
+    let mut out = format!(
+        "## Model report: {}\n\n- Backend: `{}`\n- Support level: `{}`\n\n### Probes\n\n",
+        c.model,
+        c.backend,
+        c.support_level.as_str()
+    );
+    for p in &c.probes {
+        out.push_str(&format!(
+            "- [{}] {}: {}\n",
+            if p.passed { "x" } else { " " },
+            p.name,
+            p.detail
+        ));
+    }
+    out.push_str("\n### Register changes\n");
+    for step in &c.steps {
+        out.push_str(&format!("\n**{}**\n\n", step.label));
+        if step.changes.is_empty() {
+            out.push_str("No changes\n");
+        }
+        for ch in &step.changes {
+            out.push_str(&format!(
+                "- `0x{:02X}`: `0x{:02X}` → `0x{:02X}`",
+                ch.offset, ch.before, ch.after
+            ));
+            match &ch.label {
+                Some(label) => out.push_str(&format!(" ({})\n", label)),
+                None => out.push('\n'),
+            }
+        }
+    }
+    if !bookmarks.is_empty() {
+        out.push_str("\n### Bookmarked registers\n\n");
+        for b in bookmarks {
+            out.push_str(&format!("- `0x{:02X}`: {}", b.offset, b.label));
+            match &b.watch {
+                Some(watch) => out.push_str(&format!(" (watch `{}`)\n", watch)),
+                None => out.push('\n'),
+            }
+        }
+    }
+    out.push_str(&format!(
+        "\n### Draft quirks entry\n\n```rust\n{}```\n",
+        draft
+    ));
+    out
+}
+
+/// The whole EC as it is now, to compare register states by hand, e.g.
+/// before and after toggling a feature in MSI Center on Windows.
+#[tauri::command]
+pub async fn get_ec_dump(
+    app: AppHandle,
+    sidecar: State<'_, SidecarState>,
+) -> Result<EcDump, String> {
+    permissions::check(&app, Tier::Expert)?;
+    let data = dump_ec(&sidecar).await?;
+    Ok(EcDump {
+        hex: hex_lines(&data),
+        bookmarks: bookmarks::readings(&data)?,
+        data,
+    })
+}
+
+/// Write one EC register directly. `confirm` must be the phrase the
+/// sidecar asks for; it also refuses registers that could do harm, and
+/// logs every write.
+#[tauri::command]
+pub async fn write_ec_register(
+    app: AppHandle,
+    sidecar: State<'_, SidecarState>,
+    offset: u8,
+    value: u8,
+    confirm: String,
+) -> Result<String, String> {
+    permissions::check(&app, Tier::Expert)?;
+    let message = run_write(
+        &sidecar,
+        &SidecarCommand::WriteEcRegister {
+            offset,
+            value,
+            confirm,
+        },
+    )
+    .await?;
+    actions::record(
+        &app,
+        ActionSource::Window,
+        ActionKind::RegisterWrite,
+        message.as_str(),
+        None,
+    );
+    Ok(message)
+}
+
+/// Run the probes and take the baseline dump. Restarts any session in progress.
+#[tauri::command]
+pub async fn start_contribution(
+    app: AppHandle,
+    sidecar: State<'_, SidecarState>,
+    contribution: State<'_, ContributionState>,
+) -> Result<Vec<ProbeResult>, String> {
+    permissions::check(&app, Tier::Expert)?;
+    let status = fetch_status(&sidecar).await?;
+    let dump = dump_ec(&sidecar).await?;
+    let probes = probe(&dump);
+
+    *contribution.0.lock().await = Some(Contribution {
+        model: status.model,
+        backend: status.backend,
+        support_level: status.support_level,
+        probes: probes.clone(),
+        last_dump: dump,
+        steps: Vec::new(),
+    });
+    Ok(probes)
+}
+
+/// Dump the EC after the user toggled something and record what changed.
+#[tauri::command]
+pub async fn capture_contribution_step(
+    app: AppHandle,
+    sidecar: State<'_, SidecarState>,
+    contribution: State<'_, ContributionState>,
+    label: String,
+) -> Result<ContributionStep, String> {
+    permissions::check(&app, Tier::Expert)?;
+    let mut guard = contribution.0.lock().await;
+    let session = guard
+        .as_mut()
+        .ok_or("No contribution session in progress")?;
+
+    let dump = dump_ec(&sidecar).await?;
+    let step = ContributionStep {
+        label,
+        changes: diff(&session.last_dump, &dump, &bookmarks::load()),
+    };
+    session.last_dump = dump;
+    session.steps.push(step.clone());
+    Ok(step)
+}
+
+#[tauri::command]
+pub async fn finish_contribution(
+    contribution: State<'_, ContributionState>,
+) -> Result<ContributionReport, String> {
+    let session = contribution
+        .0
+        .lock()
+        .await
+        .take()
+        .ok_or("No contribution session in progress")?;
+
+    let bookmarks = bookmarks::load();
+    let draft = draft_quirks(&session.model);
+    let report = render_report(&session, &bookmarks, &draft);
+    Ok(ContributionReport {
+        model: session.model,
+        backend: session.backend,
+        support_level: session.support_level,
+        probes: session.probes,
+        steps: session.steps,
+        bookmarks,
+        draft_quirks: draft,
+        report,
+    })
+}
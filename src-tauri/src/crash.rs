@@ -0,0 +1,192 @@
+//! Opt-in crash reports, saved to `~/.config/msi-fan-control/crashes/`.
+//!
+//! The app's panic hook and the sidecar's each capture a [`Crash`]: the
+//! version, panic message and location, and a backtrace. The sidecar's
+//! arrives as a tagged line on its stderr, which is otherwise kept as a
+//! short log tail that goes into every report. Only the newest reports are
+//! kept. A crash of the systemd daemon isn't seen by the app; it shows up in
+//! `journalctl -u msi-fan-control` instead.
+
+use crate::history::now_ms;
+use crate::permissions::{self, Tier};
+use crate::settings;
+use msi_protocol::{Crash, CRASH_PREFIX};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::ChildStderr;
+use ts_rs::TS;
+
+const CRASHES_DIR: &str = "crashes";
+/// Reports kept; the oldest go first.
+const MAX_REPORTS: usize = 20;
+/// Sidecar log lines kept for the next report.
+const LOG_TAIL_LINES: usize = 50;
+
+/// Mirrors the `crash_reports` setting, readable from inside a panic hook.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static LOG_TAIL: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashSource {
+    App,
+    Sidecar,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct CrashReport {
+    /// Unix time in milliseconds.
+    #[ts(type = "number")]
+    pub timestamp_ms: u64,
+    pub source: CrashSource,
+    /// Version of the app or sidecar that crashed.
+    pub version: String,
+    pub message: String,
+    /// `file:line:column` of the panic.
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    /// The last lines the sidecar logged, oldest first.
+    pub log_tail: Vec<String>,
+}
+
+fn get_crashes_dir() -> Result<PathBuf, String> {
+    Ok(crate::get_config_dir()?.join(CRASHES_DIR))
+}
+
+pub(crate) fn log_tail() -> Vec<String> {
+    let tail = LOG_TAIL.lock().unwrap_or_else(|e| e.into_inner());
+    tail.iter().cloned().collect()
+}
+
+fn save_report(source: CrashSource, crash: Crash) -> Result<PathBuf, String> {
+    let report = CrashReport {
+        timestamp_ms: now_ms(),
+        source,
+        version: crash.version,
+        message: crash.message,
+        location: crash.location,
+        thread: crash.thread,
+        backtrace: crash.backtrace,
+        log_tail: log_tail(),
+    };
+    let dir = get_crashes_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let source = match source {
+        CrashSource::App => "app",
+        CrashSource::Sidecar => "sidecar",
+    };
+    let path = dir.join(format!("{}-{}.json", report.timestamp_ms, source));
+    let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+    prune(&dir);
+    Ok(path)
+}
+
+/// Report files, oldest first; the timestamp prefix sorts them.
+fn report_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn prune(dir: &Path) {
+    let files = report_files(dir);
+    let excess = files.len().saturating_sub(MAX_REPORTS);
+    for file in &files[..excess] {
+        let _ = std::fs::remove_file(file);
+    }
+}
+
+/// Install the app's panic hook, reporting when the setting allows it.
+pub(crate) fn install() {
+    let enabled = settings::load_settings()
+        .map(|s| s.crash_reports)
+        .unwrap_or_default();
+    ENABLED.store(enabled, Ordering::Relaxed);
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if ENABLED.load(Ordering::Relaxed) {
+            let crash = Crash::capture(env!("CARGO_PKG_VERSION"), info);
+            match save_report(CrashSource::App, crash) {
+                Ok(path) => eprintln!("Crash report saved to {}", path.display()),
+                Err(e) => eprintln!("Failed to save crash report: {}", e),
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+/// Drain a spawned sidecar's stderr: forward it, keep the tail for reports
+/// and save the crash it announces before dying.
+pub(crate) fn spawn_stderr_reader(stderr: ChildStderr) {
+    tauri::async_runtime::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(json) = line.strip_prefix(CRASH_PREFIX) {
+                if !ENABLED.load(Ordering::Relaxed) {
+                    continue;
+                }
+                let saved = serde_json::from_str(json)
+                    .map_err(|e| format!("Invalid crash line: {}", e))
+                    .and_then(|crash| save_report(CrashSource::Sidecar, crash));
+                match saved {
+                    Ok(path) => eprintln!("Sidecar crash report saved to {}", path.display()),
+                    Err(e) => eprintln!("Failed to save sidecar crash report: {}", e),
+                }
+                continue;
+            }
+            eprintln!("sidecar: {}", line);
+            let mut tail = LOG_TAIL.lock().unwrap_or_else(|e| e.into_inner());
+            if tail.len() == LOG_TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+    });
+}
+
+/// Saved crash reports, newest first. Unreadable files are skipped.
+#[tauri::command]
+pub async fn list_crash_reports() -> Result<Vec<CrashReport>, String> {
+    let mut reports: Vec<CrashReport> = report_files(&get_crashes_dir()?)
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    reports.reverse();
+    Ok(reports)
+}
+
+#[tauri::command]
+pub async fn get_crash_reports_enabled() -> Result<bool, String> {
+    Ok(settings::load_settings()?.crash_reports)
+}
+
+#[tauri::command]
+pub async fn set_crash_reports_enabled(app: AppHandle, enabled: bool) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    let mut settings = settings::load_settings()?;
+    settings.crash_reports = enabled;
+    settings::save_settings(&settings)?;
+    ENABLED.store(enabled, Ordering::Relaxed);
+    Ok(format!(
+        "Crash reports {}",
+        if enabled { "enabled" } else { "disabled" }
+    ))
+}
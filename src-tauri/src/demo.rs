@@ -0,0 +1,33 @@
+//! `--demo`: the app without root or MSI hardware, for distro reviewers,
+//! screenshots and trying the UI before granting root access.
+//!
+//! The sidecar runs with `--demo` (a simulated EC with animated readings)
+//! instead of through polkit, and settings, profiles and logs go to a
+//! throwaway directory, so the user's own are neither read nor changed.
+//! The window title and every status carry a demo marker. Anything that
+//! changes the system outside the app (autostart, the polkit policy) is
+//! refused.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const DEMO_ARG: &str = "--demo";
+pub(crate) const WINDOW_TITLE: &str = "MSI Fan Control (Demo)";
+
+pub(crate) fn enabled() -> bool {
+    static DEMO: OnceLock<bool> = OnceLock::new();
+    *DEMO.get_or_init(|| std::env::args().skip(1).any(|arg| arg == DEMO_ARG))
+}
+
+/// Fresh for every demo run, so it always starts from the defaults.
+pub(crate) fn config_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("msi-fan-control-demo-{}", std::process::id()))
+}
+
+/// Fail a command that would change the system outside the app.
+pub(crate) fn refuse(what: &str) -> Result<(), String> {
+    if enabled() {
+        return Err(format!("{} is not available in demo mode", what));
+    }
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! One JSON document with everything needed to triage "it doesn't work on
+//! my model": the machine, kernel and EC driver, what the sidecar detected
+//! and can do, an EC dump, recent logs and the settings. Each part that
+//! can't be collected, e.g. with the sidecar stopped, holds the error
+//! instead, so the bundle is always produced.
+
+use crate::actions::{self, ActionLog};
+use crate::sidecar::SidecarCommand;
+use crate::{
+    contribute, crash, get_capabilities, query_sidecar, settings, SidecarResponse, SidecarState,
+};
+use chrono::{SecondsFormat, Utc};
+use msi_ec_core::ec;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fs;
+use tauri::State;
+
+const DMI_PATH: &str = "/sys/class/dmi/id";
+/// DMI fields that identify the machine; serials are left out.
+const DMI_FIELDS: &[&str] = &[
+    "sys_vendor",
+    "product_name",
+    "product_version",
+    "board_vendor",
+    "board_name",
+    "bios_vendor",
+    "bios_version",
+    "bios_date",
+];
+/// Kernel modules the backends read the EC through.
+const EC_MODULES: &[&str] = &["ec_sys", "msi_ec"];
+const RECENT_ACTIONS: usize = 50;
+
+fn read_trimmed(path: &str) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// The value, or `{ "error": ... }`.
+fn part<T: Serialize>(result: Result<T, String>) -> Value {
+    match result.and_then(|value| serde_json::to_value(value).map_err(|e| e.to_string())) {
+        Ok(value) => value,
+        Err(e) => json!({ "error": e }),
+    }
+}
+
+fn dmi() -> Map<String, Value> {
+    DMI_FIELDS
+        .iter()
+        .map(|field| {
+            let value = read_trimmed(&format!("{}/{}", DMI_PATH, field));
+            (field.to_string(), json!(value))
+        })
+        .collect()
+}
+
+fn os_name() -> Option<String> {
+    let os_release = fs::read_to_string("/etc/os-release").ok()?;
+    os_release.lines().find_map(|line| {
+        let value = line.strip_prefix("PRETTY_NAME=")?;
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+/// Which EC modules are loaded, with their parameters.
+fn ec_modules() -> Map<String, Value> {
+    EC_MODULES
+        .iter()
+        .map(|module| {
+            let dir = format!("/sys/module/{}", module);
+            let state = if fs::metadata(&dir).is_err() {
+                Value::Null
+            } else {
+                let parameters: Map<String, Value> = fs::read_dir(format!("{}/parameters", dir))
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .map(|entry| {
+                        let value = fs::read_to_string(entry.path())
+                            .map(|s| s.trim().to_string())
+                            .ok();
+                        (
+                            entry.file_name().to_string_lossy().into_owned(),
+                            json!(value),
+                        )
+                    })
+                    .collect();
+                json!({ "loaded": true, "parameters": parameters })
+            };
+            (module.to_string(), state)
+        })
+        .collect()
+}
+
+async fn query(state: &SidecarState, cmd: SidecarCommand) -> Result<SidecarResponse, String> {
+    match query_sidecar(state, &cmd).await? {
+        SidecarResponse::Error { message } => Err(message),
+        response => Ok(response),
+    }
+}
+
+/// Collect the bundle as pretty-printed JSON, for the window to save or
+/// attach to an issue.
+#[tauri::command]
+pub async fn export_diagnostics(
+    sidecar: State<'_, SidecarState>,
+    log: State<'_, ActionLog>,
+) -> Result<String, String> {
+    let detected_model = query(&sidecar, SidecarCommand::GetDetectedModel).await;
+    let register_map = query(&sidecar, SidecarCommand::GetRegisterMap).await;
+    let status = crate::fetch_status(&sidecar).await;
+    let ec_dump = contribute::dump_ec(&sidecar).await;
+
+    let bundle = json!({
+        "generated_at": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "dmi": dmi(),
+        "os": os_name(),
+        "kernel": read_trimmed("/proc/sys/kernel/osrelease"),
+        "ec_modules": ec_modules(),
+        "lockdown": ec::lockdown_mode(),
+        "secure_boot": ec::secure_boot_enabled(),
+        "detected_model": part(detected_model.and_then(|response| match response {
+            SidecarResponse::DetectedModel(model) => Ok(model),
+            _ => Err("Unexpected response".to_string()),
+        })),
+        "status": part(status),
+        "capabilities": part(get_capabilities(sidecar.clone()).await),
+        "register_map": part(register_map.and_then(|response| match response {
+            SidecarResponse::RegisterMap(map) => Ok(map),
+            _ => Err("Unexpected response".to_string()),
+        })),
+        "ec_dump": part(ec_dump.map(|data| contribute::hex_lines(&data))),
+        "sidecar_log": crash::log_tail(),
+        "recent_actions": part(actions::get_action_history(log, Some(RECENT_ACTIONS)).await),
+        "crash_reports": part(crash::list_crash_reports().await),
+        "settings": part(settings::load_settings()),
+    });
+    serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())
+}
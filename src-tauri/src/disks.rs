@@ -0,0 +1,182 @@
+//! Temperature and wear of the internal NVMe and SATA drives.
+//!
+//! The temperature comes from the drive's hwmon device (`nvme`, or
+//! `drivetemp` for SATA) when there is one. Wear, bytes written and the
+//! SMART verdict need `smartctl -j` (smartmontools 7+). Most systems only
+//! let root open the drive for SMART, so those fields are often `None`,
+//! with `smart_error` saying why.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use ts_rs::TS;
+
+const BLOCK_PATH: &str = "/sys/block";
+/// NVMe reports data units of 1000 sectors of 512 bytes.
+const NVME_DATA_UNIT: u64 = 512_000;
+/// ATA attribute holding the total LBAs written.
+const ATA_TOTAL_LBAS_WRITTEN: u64 = 241;
+/// smartctl exit status bit set when the device couldn't be opened.
+const SMARTCTL_OPEN_FAILED: i32 = 1 << 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct DiskHealth {
+    /// Kernel name, e.g. `nvme0n1` or `sda`.
+    pub device: String,
+    pub model: Option<String>,
+    /// °C
+    pub temperature: Option<f32>,
+    /// Share of the rated endurance used; may pass 100.
+    pub percentage_used: Option<u8>,
+    #[ts(type = "number | null")]
+    pub bytes_written: Option<u64>,
+    /// The drive's overall SMART self-assessment.
+    pub smart_passed: Option<bool>,
+    /// Why smartctl didn't report, e.g. not installed or needs root.
+    pub smart_error: Option<String>,
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// `temp1_input` of the drive's hwmon device, which sits under the NVMe
+/// controller or, with `drivetemp`, under the SCSI device's `hwmon` folder.
+fn hwmon_temperature(device_dir: &Path) -> Option<f32> {
+    let hwmon_dirs = |dir: &Path| -> Vec<_> {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("hwmon"))
+            })
+            .collect()
+    };
+    let mut candidates = hwmon_dirs(device_dir);
+    candidates.extend(hwmon_dirs(&device_dir.join("hwmon")));
+    candidates.iter().find_map(|dir| {
+        let millidegrees: f32 = read_trimmed(&dir.join("temp1_input"))?.parse().ok()?;
+        Some(millidegrees / 1000.0)
+    })
+}
+
+struct Smart {
+    temperature: Option<f32>,
+    percentage_used: Option<u8>,
+    bytes_written: Option<u64>,
+    passed: Option<bool>,
+}
+
+fn run_smartctl(device: &str) -> Result<Value, String> {
+    let output = Command::new("smartctl")
+        .args(["-j", "-a", &format!("/dev/{}", device)])
+        .output()
+        .map_err(|e| format!("Failed to run smartctl: {}", e))?;
+    let report: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Unexpected smartctl output: {}", e))?;
+    // Other bits report the drive's condition, not a failed query
+    if output.status.code().unwrap_or(0) & SMARTCTL_OPEN_FAILED != 0 {
+        let message = report["smartctl"]["messages"][0]["string"]
+            .as_str()
+            .unwrap_or("smartctl couldn't open the drive");
+        return Err(message.to_string());
+    }
+    Ok(report)
+}
+
+fn parse_smart(report: &Value) -> Smart {
+    let nvme = &report["nvme_smart_health_information_log"];
+    let ata_attribute = |id: u64| {
+        report["ata_smart_attributes"]["table"]
+            .as_array()?
+            .iter()
+            .find(|attr| attr["id"].as_u64() == Some(id))?["raw"]["value"]
+            .as_u64()
+    };
+    // ATA drives report endurance in the device statistics, where supported
+    let ata_percentage_used = || {
+        report["ata_device_statistics"]["pages"]
+            .as_array()?
+            .iter()
+            .flat_map(|page| page["table"].as_array().into_iter().flatten())
+            .find(|entry| entry["name"] == "Percentage Used Endurance Indicator")?["value"]
+            .as_u64()
+    };
+    let ata_bytes_written = || {
+        let block_size = report["logical_block_size"].as_u64().unwrap_or(512);
+        Some(ata_attribute(ATA_TOTAL_LBAS_WRITTEN)? * block_size)
+    };
+
+    Smart {
+        temperature: report["temperature"]["current"].as_f64().map(|t| t as f32),
+        percentage_used: nvme["percentage_used"]
+            .as_u64()
+            .or_else(ata_percentage_used)
+            .map(|p| p.min(u8::MAX as u64) as u8),
+        bytes_written: nvme["data_units_written"]
+            .as_u64()
+            .map(|units| units * NVME_DATA_UNIT)
+            .or_else(ata_bytes_written),
+        passed: report["smart_status"]["passed"].as_bool(),
+    }
+}
+
+fn disk_health(device: String, dir: &Path) -> DiskHealth {
+    let device_dir = dir.join("device");
+    let smart = run_smartctl(&device).map(|report| parse_smart(&report));
+    let (smart, smart_error) = match smart {
+        Ok(smart) => (Some(smart), None),
+        Err(e) => (None, Some(e)),
+    };
+    DiskHealth {
+        model: read_trimmed(&device_dir.join("model")),
+        temperature: hwmon_temperature(&device_dir)
+            .or_else(|| smart.as_ref().and_then(|s| s.temperature)),
+        percentage_used: smart.as_ref().and_then(|s| s.percentage_used),
+        bytes_written: smart.as_ref().and_then(|s| s.bytes_written),
+        smart_passed: smart.as_ref().and_then(|s| s.passed),
+        smart_error,
+        device,
+    }
+}
+
+/// NVMe namespaces and SATA disks, leaving out removable ones such as USB
+/// sticks.
+fn internal_disks() -> Result<Vec<(String, PathBuf)>, String> {
+    let entries =
+        fs::read_dir(BLOCK_PATH).map_err(|e| format!("Failed to read {}: {}", BLOCK_PATH, e))?;
+    let mut disks: Vec<_> = entries
+        .flatten()
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().into_owned(),
+                entry.path(),
+            )
+        })
+        .filter(|(name, dir)| {
+            (name.starts_with("nvme") || name.starts_with("sd"))
+                && read_trimmed(&dir.join("removable")).as_deref() != Some("1")
+        })
+        .collect();
+    disks.sort();
+    Ok(disks)
+}
+
+#[tauri::command]
+pub async fn get_disk_health() -> Result<Vec<DiskHealth>, String> {
+    tokio::task::spawn_blocking(|| {
+        Ok(internal_disks()?
+            .into_iter()
+            .map(|(device, dir)| disk_health(device, &dir))
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
@@ -0,0 +1,98 @@
+//! Events sent to the window, grouped into topics it subscribes to.
+//!
+//! Each event belongs to one topic, and the window picks the topics it shows
+//! with `subscribe_topics`; until it does, every topic is delivered. While
+//! the window is hidden only `alerts` and `connection` get through, so a
+//! window sitting in the tray isn't woken for every status push.
+
+use crate::scheduler;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+use ts_rs::TS;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum EventTopic {
+    /// Fan status pushes.
+    Status,
+    /// Failsafe trips and anomalies.
+    Alerts,
+    /// Sidecar connection changes and wakes from suspend.
+    Connection,
+    /// Profile loads, app rules, power source changes and history pauses
+    /// for a low battery.
+    Profile,
+    /// Every action recorded in the action history.
+    Logs,
+}
+
+impl EventTopic {
+    const ALL: [EventTopic; 5] = [
+        EventTopic::Status,
+        EventTopic::Alerts,
+        EventTopic::Connection,
+        EventTopic::Profile,
+        EventTopic::Logs,
+    ];
+
+    /// Delivered while the window is hidden.
+    fn urgent(self) -> bool {
+        matches!(self, EventTopic::Alerts | EventTopic::Connection)
+    }
+}
+
+/// Topics the window subscribed to; `None` until it first subscribes.
+#[derive(Default)]
+pub(crate) struct EventSubscriptions(Mutex<Option<HashSet<EventTopic>>>);
+
+impl EventSubscriptions {
+    fn topics(&self) -> Vec<EventTopic> {
+        let subscribed = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match &*subscribed {
+            None => EventTopic::ALL.to_vec(),
+            Some(topics) => EventTopic::ALL
+                .into_iter()
+                .filter(|topic| topics.contains(topic))
+                .collect(),
+        }
+    }
+}
+
+/// Send `event` if the window wants its topic right now.
+pub(crate) fn emit<S: Serialize + Clone>(
+    app: &AppHandle,
+    topic: EventTopic,
+    event: &str,
+    payload: S,
+) {
+    if !app.state::<EventSubscriptions>().topics().contains(&topic) {
+        return;
+    }
+    if !topic.urgent() && !scheduler::window_visible(app) {
+        return;
+    }
+    if let Err(e) = app.emit(event, payload) {
+        eprintln!("Failed to emit {}: {}", event, e);
+    }
+}
+
+#[tauri::command]
+pub async fn get_subscribed_topics(
+    subscriptions: State<'_, EventSubscriptions>,
+) -> Result<Vec<EventTopic>, String> {
+    Ok(subscriptions.topics())
+}
+
+/// Replace the window's topics; events of any other topic are dropped.
+#[tauri::command]
+pub async fn subscribe_topics(
+    subscriptions: State<'_, EventSubscriptions>,
+    topics: Vec<EventTopic>,
+) -> Result<Vec<EventTopic>, String> {
+    *subscriptions.0.lock().map_err(|e| e.to_string())? = Some(topics.into_iter().collect());
+    Ok(subscriptions.topics())
+}
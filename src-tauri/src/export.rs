@@ -0,0 +1,141 @@
+//! History exports (CSV/JSON) for correlating with other logs.
+//!
+//! Timestamps are always ISO-8601 in UTC. With `localized`, each row also
+//! carries the time in the system timezone (with its offset) and readings
+//! formatted for the user's locale.
+
+use crate::format::{format_value, NumberFormat, Sensor};
+use crate::history::{now_ms, History, HistorySample, RETENTION_SECS};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use ts_rs::TS;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Locale-formatted copy of a row's readings.
+#[derive(Debug, Serialize)]
+struct LocalizedRow {
+    /// RFC 3339 in the system timezone, e.g. `2024-05-01T14:03:12.500+02:00`
+    timestamp: String,
+    cpu_temp: String,
+    gpu_temp: String,
+    fan1_rpm: Option<String>,
+    fan2_rpm: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportRow {
+    /// RFC 3339 in UTC, e.g. `2024-05-01T12:03:12.500Z`
+    timestamp: String,
+    cpu_temp: u8,
+    gpu_temp: u8,
+    fan1_rpm: Option<u32>,
+    fan2_rpm: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    localized: Option<LocalizedRow>,
+}
+
+fn to_row(sample: &HistorySample, fmt: Option<&NumberFormat>) -> ExportRow {
+    let utc =
+        DateTime::<Utc>::from_timestamp_millis(sample.timestamp_ms as i64).unwrap_or_default();
+    ExportRow {
+        timestamp: utc.to_rfc3339_opts(SecondsFormat::Millis, true),
+        cpu_temp: sample.cpu_temp,
+        gpu_temp: sample.gpu_temp,
+        fan1_rpm: sample.fan1_rpm,
+        fan2_rpm: sample.fan2_rpm,
+        localized: fmt.map(|fmt| {
+            let rpm = |v: Option<u32>| v.map(|v| format_value(Sensor::FanRpm, v as f64, fmt));
+            LocalizedRow {
+                timestamp: utc
+                    .with_timezone(&Local)
+                    .to_rfc3339_opts(SecondsFormat::Millis, false),
+                cpu_temp: format_value(Sensor::Temperature, sample.cpu_temp as f64, fmt),
+                gpu_temp: format_value(Sensor::Temperature, sample.gpu_temp as f64, fmt),
+                fan1_rpm: rpm(sample.fan1_rpm),
+                fan2_rpm: rpm(sample.fan2_rpm),
+            }
+        }),
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or newline. Locale
+/// formatted numbers often contain commas.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(rows: &[ExportRow], localized: bool) -> String {
+    let mut header = vec!["timestamp", "cpu_temp", "gpu_temp", "fan1_rpm", "fan2_rpm"];
+    if localized {
+        header.extend([
+            "timestamp_local",
+            "cpu_temp_local",
+            "gpu_temp_local",
+            "fan1_rpm_local",
+            "fan2_rpm_local",
+        ]);
+    }
+
+    let mut out = header.join(",");
+    out.push('\n');
+    for row in rows {
+        let opt = |v: Option<u32>| v.map(|v| v.to_string()).unwrap_or_default();
+        let mut fields = vec![
+            row.timestamp.clone(),
+            row.cpu_temp.to_string(),
+            row.gpu_temp.to_string(),
+            opt(row.fan1_rpm),
+            opt(row.fan2_rpm),
+        ];
+        if let Some(local) = &row.localized {
+            fields.extend([
+                local.timestamp.clone(),
+                local.cpu_temp.clone(),
+                local.gpu_temp.clone(),
+                local.fan1_rpm.clone().unwrap_or_default(),
+                local.fan2_rpm.clone().unwrap_or_default(),
+            ]);
+        }
+        let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Export the last `range_secs` seconds of history. Returns the file
+/// contents; the frontend decides where to save them.
+#[tauri::command]
+pub async fn export_history(
+    history: State<'_, History>,
+    range_secs: u64,
+    format: ExportFormat,
+    localized: Option<bool>,
+) -> Result<String, String> {
+    let localized = localized.unwrap_or(false);
+    let fmt = localized.then(NumberFormat::for_user);
+    let since = now_ms().saturating_sub(range_secs.min(RETENTION_SECS) * 1000);
+    let rows: Vec<ExportRow> = history
+        .since(since)
+        .iter()
+        .map(|sample| to_row(sample, fmt.as_ref()))
+        .collect();
+
+    match format {
+        ExportFormat::Csv => Ok(to_csv(&rows, localized)),
+        ExportFormat::Json => serde_json::to_string_pretty(&rows).map_err(|e| e.to_string()),
+    }
+}
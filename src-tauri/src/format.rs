@@ -0,0 +1,170 @@
+//! Unit- and locale-aware formatting of sensor values.
+//!
+//! Everything that shows a reading outside the main window (tray, exports,
+//! notifications) goes through `format_value` so "3,412 RPM" and "72.5 °C"
+//! look the same everywhere, in the temperature unit the user picked.
+//! Readings are kept in °C everywhere else.
+
+use crate::settings::{self, TemperatureUnit};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Sensor {
+    /// Degrees Celsius.
+    Temperature,
+    /// A difference in degrees Celsius, e.g. "10 °C above usual".
+    TemperatureDifference,
+    FanRpm,
+    Percent,
+    /// Clock speed in MHz, shown in GHz.
+    FrequencyMhz,
+    /// Byte count, shown in GB.
+    Bytes,
+}
+
+/// Separators for the user's numeric locale, and the unit temperatures
+/// are shown in.
+#[derive(Debug, Clone, Copy)]
+pub struct NumberFormat {
+    pub decimal: char,
+    pub group: char,
+    pub temperature: TemperatureUnit,
+}
+
+impl NumberFormat {
+    /// Pick separators from `LC_ALL`, `LC_NUMERIC` or `LANG`, in that order.
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty())
+            .unwrap_or_default();
+        Self::for_locale(&locale)
+    }
+
+    /// `from_env` with the temperature unit from the settings.
+    pub fn for_user() -> Self {
+        NumberFormat {
+            temperature: settings::load_settings()
+                .map(|s| s.temperature_unit)
+                .unwrap_or_default(),
+            ..Self::from_env()
+        }
+    }
+
+    pub fn for_locale(locale: &str) -> Self {
+        let lang = locale
+            .split(['_', '.', '@', '-'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        match lang.as_str() {
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => NumberFormat {
+                decimal: ',',
+                group: '.',
+                temperature: TemperatureUnit::Celsius,
+            },
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "uk" | "hu" => NumberFormat {
+                decimal: ',',
+                group: '\u{202F}',
+                temperature: TemperatureUnit::Celsius,
+            },
+            _ => NumberFormat {
+                decimal: '.',
+                group: ',',
+                temperature: TemperatureUnit::Celsius,
+            },
+        }
+    }
+}
+
+/// Format `value` with a fixed number of decimals and grouped thousands.
+pub fn format_number(value: f64, precision: usize, fmt: &NumberFormat) -> String {
+    let fixed = format!("{:.*}", precision, value.abs());
+    let (int_part, frac_part) = match fixed.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (fixed.as_str(), None),
+    };
+
+    let mut grouped = String::new();
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            grouped.push(fmt.group);
+        }
+        grouped.push(c);
+    }
+
+    let is_zero = fixed.chars().all(|c| c == '0' || c == '.');
+    let mut out = String::new();
+    if value < 0.0 && !is_zero {
+        out.push('-');
+    }
+    out.push_str(&grouped);
+    if let Some(frac) = frac_part {
+        out.push(fmt.decimal);
+        out.push_str(frac);
+    }
+    out
+}
+
+/// A temperature in °C (or a difference, without the 32° offset) in the
+/// user's unit. EC temps are whole degrees; a decimal is only shown when
+/// there is one.
+fn format_temperature(celsius: f64, difference: bool, fmt: &NumberFormat) -> String {
+    let (value, symbol) = match fmt.temperature {
+        TemperatureUnit::Celsius => (celsius, "°C"),
+        TemperatureUnit::Fahrenheit if difference => (celsius * 9.0 / 5.0, "°F"),
+        TemperatureUnit::Fahrenheit => (celsius * 9.0 / 5.0 + 32.0, "°F"),
+    };
+    let value = (value * 10.0).round() / 10.0;
+    let precision = if value.fract() == 0.0 { 0 } else { 1 };
+    format!("{} {}", format_number(value, precision, fmt), symbol)
+}
+
+/// Render a raw sensor value with its unit and precision rules.
+pub fn format_value(sensor: Sensor, raw: f64, fmt: &NumberFormat) -> String {
+    match sensor {
+        Sensor::Temperature => format_temperature(raw, false, fmt),
+        Sensor::TemperatureDifference => format_temperature(raw, true, fmt),
+        Sensor::FanRpm => format!("{} RPM", format_number(raw.round(), 0, fmt)),
+        Sensor::Percent => format!("{} %", format_number(raw.round(), 0, fmt)),
+        Sensor::FrequencyMhz => format!("{} GHz", format_number(raw / 1000.0, 2, fmt)),
+        Sensor::Bytes => format!(
+            "{} GB",
+            format_number(raw / 1024.0 / 1024.0 / 1024.0, 1, fmt)
+        ),
+    }
+}
+
+/// `FanStatus` with every reading pre-formatted for display, in the user's
+/// units.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct FormattedFanStatus {
+    pub cpu_temp: String,
+    pub gpu_temp: String,
+    pub fan1_rpm: String,
+    pub fan2_rpm: String,
+    pub cooler_boost: bool,
+    pub fan_mode: String,
+    /// The readings as the sidecar reported them, in °C and RPM.
+    pub raw: crate::FanStatus,
+}
+
+impl FormattedFanStatus {
+    pub fn from_status(status: &crate::FanStatus, fmt: &NumberFormat) -> Self {
+        FormattedFanStatus {
+            cpu_temp: format_value(Sensor::Temperature, status.cpu_temp as f64, fmt),
+            gpu_temp: format_value(Sensor::Temperature, status.gpu_temp as f64, fmt),
+            fan1_rpm: format_value(Sensor::FanRpm, status.fan1_rpm as f64, fmt),
+            fan2_rpm: format_value(Sensor::FanRpm, status.fan2_rpm as f64, fmt),
+            cooler_boost: status.cooler_boost,
+            fan_mode: status.fan_mode.clone(),
+            raw: status.clone(),
+        }
+    }
+}
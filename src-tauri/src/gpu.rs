@@ -0,0 +1,116 @@
+//! NVIDIA GPU stats, read through NVML when built with the `nvml` feature and
+//! through `nvidia-smi` otherwise (or when NVML fails to load).
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use ts_rs::TS;
+
+/// Fields are `None` when the driver doesn't report them (common for
+/// clocks and utilization on Optimus laptops with the dGPU asleep).
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct GpuStats {
+    pub model: String,
+    pub temperature: Option<u32>,
+    /// Percent
+    pub utilization: Option<u32>,
+    /// Bytes
+    #[ts(type = "number | null")]
+    pub memory_used: Option<u64>,
+    /// Bytes
+    #[ts(type = "number | null")]
+    pub memory_total: Option<u64>,
+    pub graphics_clock_mhz: Option<u32>,
+    pub memory_clock_mhz: Option<u32>,
+    /// `nvml` or `nvidia-smi`
+    #[ts(type = "\"nvml\" | \"nvidia-smi\"")]
+    pub source: String,
+}
+
+pub(crate) fn query() -> Result<GpuStats, String> {
+    #[cfg(feature = "nvml")]
+    match nvml::query() {
+        Ok(stats) => return Ok(stats),
+        Err(e) => eprintln!("NVML unavailable, falling back to nvidia-smi: {}", e),
+    }
+    query_nvidia_smi()
+}
+
+/// GPU name for the hardware info card, if an NVIDIA GPU is present.
+pub(crate) fn model() -> Option<String> {
+    query().ok().map(|stats| stats.model)
+}
+
+fn query_nvidia_smi() -> Result<GpuStats, String> {
+    let output = Command::new("nvidia-smi")
+        .arg("--query-gpu=name,temperature.gpu,utilization.gpu,memory.used,memory.total,clocks.gr,clocks.mem")
+        .arg("--format=csv,noheader,nounits")
+        .output()
+        .map_err(|e| format!("Failed to run nvidia-smi: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "nvidia-smi failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // One line per GPU; laptops have one dGPU
+    let line = stdout.lines().next().ok_or("nvidia-smi reported no GPU")?;
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < 7 {
+        return Err(format!("Unexpected nvidia-smi output: {}", line));
+    }
+
+    // Unsupported fields read "[N/A]" and fail to parse
+    let mib = |s: &str| s.parse::<u64>().ok().map(|v| v * 1024 * 1024);
+    Ok(GpuStats {
+        model: fields[0].to_string(),
+        temperature: fields[1].parse().ok(),
+        utilization: fields[2].parse().ok(),
+        memory_used: mib(fields[3]),
+        memory_total: mib(fields[4]),
+        graphics_clock_mhz: fields[5].parse().ok(),
+        memory_clock_mhz: fields[6].parse().ok(),
+        source: "nvidia-smi".to_string(),
+    })
+}
+
+#[cfg(feature = "nvml")]
+mod nvml {
+    use super::GpuStats;
+    use nvml_wrapper::enum_wrappers::device::{Clock, TemperatureSensor};
+    use nvml_wrapper::Nvml;
+    use std::sync::OnceLock;
+
+    // Loading libnvidia-ml is slow, do it once
+    static NVML: OnceLock<Result<Nvml, String>> = OnceLock::new();
+
+    pub(super) fn query() -> Result<GpuStats, String> {
+        let nvml = NVML
+            .get_or_init(|| Nvml::init().map_err(|e| e.to_string()))
+            .as_ref()
+            .map_err(|e| e.clone())?;
+        let device = nvml.device_by_index(0).map_err(|e| e.to_string())?;
+        let memory = device.memory_info().ok();
+
+        Ok(GpuStats {
+            model: device.name().map_err(|e| e.to_string())?,
+            temperature: device.temperature(TemperatureSensor::Gpu).ok(),
+            utilization: device.utilization_rates().ok().map(|u| u.gpu),
+            memory_used: memory.as_ref().map(|m| m.used),
+            memory_total: memory.as_ref().map(|m| m.total),
+            graphics_clock_mhz: device.clock_info(Clock::Graphics).ok(),
+            memory_clock_mhz: device.clock_info(Clock::Memory).ok(),
+            source: "nvml".to_string(),
+        })
+    }
+}
+
+#[tauri::command]
+pub async fn get_gpu_stats() -> Result<GpuStats, String> {
+    tokio::task::spawn_blocking(query)
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
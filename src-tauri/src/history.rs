@@ -0,0 +1,88 @@
+//! In-memory history of temperatures and fan speeds, filled by the
+//! scheduler so it keeps recording while the window is hidden. Recording
+//! pauses on a low battery if the user set a limit (see `power.rs`),
+//! leaving a gap.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+use ts_rs::TS;
+
+/// How far back history is kept.
+pub(crate) const RETENTION_SECS: u64 = 30 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema, TS)]
+#[ts(export)]
+pub struct HistorySample {
+    /// Unix time in milliseconds
+    #[ts(type = "number")]
+    pub timestamp_ms: u64,
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+    /// `None` for samples taken with the lite query while the window was hidden.
+    pub fan1_rpm: Option<u32>,
+    pub fan2_rpm: Option<u32>,
+}
+
+#[derive(Default)]
+pub(crate) struct History {
+    samples: Mutex<VecDeque<HistorySample>>,
+    paused: AtomicBool,
+}
+
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl History {
+    /// Stop or resume recording. Returns whether that changed anything.
+    pub(crate) fn set_paused(&self, paused: bool) -> bool {
+        self.paused.swap(paused, Ordering::SeqCst) != paused
+    }
+
+    /// Append a sample and drop those older than `RETENTION_SECS`, unless
+    /// paused.
+    pub(crate) fn push(&self, sample: HistorySample) {
+        if self.paused.load(Ordering::SeqCst) {
+            return;
+        }
+        let Ok(mut samples) = self.samples.lock() else {
+            return;
+        };
+        let cutoff = sample.timestamp_ms.saturating_sub(RETENTION_SECS * 1000);
+        while samples.front().is_some_and(|s| s.timestamp_ms < cutoff) {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    pub(crate) fn since(&self, since_ms: u64) -> Vec<HistorySample> {
+        self.samples
+            .lock()
+            .map(|samples| {
+                samples
+                    .iter()
+                    .filter(|s| s.timestamp_ms >= since_ms)
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Samples from the last `range_secs` seconds, oldest first.
+#[tauri::command]
+pub async fn get_history(
+    history: State<'_, History>,
+    range_secs: u64,
+) -> Result<Vec<HistorySample>, String> {
+    let range_ms = range_secs.min(RETENTION_SECS) * 1000;
+    Ok(history.since(now_ms().saturating_sub(range_ms)))
+}
@@ -0,0 +1,83 @@
+//! Global keyboard shortcuts, active while the app runs even with the window
+//! hidden, e.g. to turn Cooler Boost on mid-game.
+//!
+//! Bindings use the accelerator syntax of the global shortcut plugin, such
+//! as `Ctrl+Alt+B` or `Super+F12`. Global shortcuts need X11 (or XWayland
+//! focus); Wayland compositors don't let apps grab keys.
+
+use crate::actions::ActionSource;
+use crate::permissions::{self, Tier};
+use crate::{settings, SidecarState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use ts_rs::TS;
+
+/// One binding per action; `None` leaves the action unbound.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct Hotkeys {
+    pub cooler_boost: Option<String>,
+}
+
+fn parse(binding: &str) -> Result<Shortcut, String> {
+    binding
+        .parse()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", binding, e))
+}
+
+fn toggle_cooler_boost(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<SidecarState>().inner().clone();
+        let result = match crate::fetch_status(&state).await {
+            Ok(status) => {
+                crate::apply_cooler_boost(&app, &state, !status.cooler_boost, ActionSource::Hotkey)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            eprintln!("Hotkey: failed to toggle Cooler Boost: {}", e);
+        }
+    });
+}
+
+/// Replace all registered shortcuts with `hotkeys`.
+pub(crate) fn register(app: &AppHandle, hotkeys: &Hotkeys) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts.unregister_all().map_err(|e| e.to_string())?;
+    if let Some(binding) = &hotkeys.cooler_boost {
+        shortcuts
+            .on_shortcut(parse(binding)?, |app, _, event| {
+                if event.state == ShortcutState::Pressed {
+                    toggle_cooler_boost(app);
+                }
+            })
+            .map_err(|e| format!("Failed to register '{}': {}", binding, e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_hotkeys() -> Result<Hotkeys, String> {
+    Ok(settings::load_settings()?.hotkeys)
+}
+
+/// Validated and registered before saving, so a binding another app holds
+/// is reported instead of silently not working.
+#[tauri::command]
+pub async fn set_hotkeys(app: AppHandle, hotkeys: Hotkeys) -> Result<Hotkeys, String> {
+    permissions::check(&app, Tier::Control)?;
+    let mut settings = settings::load_settings()?;
+    if let Err(e) = register(&app, &hotkeys) {
+        // Put the working bindings back
+        let _ = register(&app, &settings.hotkeys);
+        return Err(e);
+    }
+    settings.hotkeys = hotkeys.clone();
+    settings::save_settings(&settings)?;
+    Ok(hotkeys)
+}
@@ -0,0 +1,404 @@
+//! Profile files: our own, shared between users (e.g. a curve tuned for a
+//! model, posted on a forum), and those of other MSI fan tools, so people
+//! switching over don't recreate their curves by hand: MControlCenter's
+//! INI config and, best-effort, profiles exported from MSI Center as JSON.
+//!
+//! Our files are versioned JSON with the profile and the model it was
+//! tuned on. The other formats aren't documented, so both are flattened
+//! into `path = value` pairs and read by key name: a path naming the CPU fan (`cpu`, `fan1`)
+//! and `temp` holds its curve temperatures, one with `speed` its speeds,
+//! either as one list or one value per point; the GPU fan likewise (`gpu`,
+//! `fan2`). A `mode` key with a fan mode name sets the mode and a `boost`
+//! key Cooler Boost.
+
+use crate::permissions::{self, Tier};
+use crate::profiles::{self, FanCurve, Profile};
+use crate::CurvePoint;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+use ts_rs::TS;
+
+/// Marks a file as one of ours, whatever its name.
+const SHARED_FORMAT: &str = "msi-fan-control-profile";
+/// Bumped when the file layout changes; older files stay importable.
+const SHARED_VERSION: u32 = 1;
+const PRODUCT_NAME_PATH: &str = "/sys/class/dmi/id/product_name";
+/// The sidecar takes seven points per fan.
+const CURVE_POINTS: usize = 7;
+const FAN_MODES: &[&str] = &["auto", "silent", "basic", "advanced"];
+const CPU_KEYS: &[&str] = &["cpu", "fan1"];
+const GPU_KEYS: &[&str] = &["gpu", "fan2"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema, TS)]
+#[ts(export)]
+pub enum ImportFormat {
+    /// Written by `export_profile`.
+    #[serde(rename = "msi_fan_control")]
+    MsiFanControl,
+    /// `~/.config/MControlCenter/MControlCenter.conf`
+    #[serde(rename = "mcontrolcenter")]
+    MControlCenter,
+    /// A profile exported from MSI Center on Windows.
+    #[serde(rename = "msi_center")]
+    MsiCenter,
+}
+
+/// A shared profile file.
+#[derive(Debug, Serialize, Deserialize)]
+struct SharedProfile {
+    format: String,
+    version: u32,
+    /// DMI product name of the laptop it was tuned on.
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    app_version: Option<String>,
+    profile: Profile,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct ImportedProfile {
+    pub profile: Profile,
+    /// Model the profile was tuned on, when the file says, so the window
+    /// can warn when it isn't this one.
+    pub model: Option<String>,
+}
+
+fn parse_shared(content: &str) -> Result<SharedProfile, String> {
+    let json: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    if json.get("format").and_then(Value::as_str) != Some(SHARED_FORMAT) {
+        return Err("Not an MSI Fan Control profile".to_string());
+    }
+    let version = json.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > SHARED_VERSION as u64 {
+        return Err(format!(
+            "The profile was exported by a newer version (format {}); update the app to import it",
+            version
+        ));
+    }
+    serde_json::from_value(json).map_err(|e| format!("Invalid profile: {}", e))
+}
+
+/// `section/key = value` for every INI entry.
+fn flatten_ini(content: &str) -> Vec<(String, String)> {
+    let mut section = String::new();
+    let mut pairs = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.to_lowercase();
+        } else if let Some((key, value)) = line.split_once('=') {
+            let path = format!("{}/{}", section, key.trim().to_lowercase());
+            pairs.push((path, value.trim().trim_matches('"').to_string()));
+        }
+    }
+    pairs
+}
+
+/// `path/to/leaf = value` for every scalar in a JSON document, with array
+/// indices as path segments.
+fn flatten_json(value: &Value, path: &str, pairs: &mut Vec<(String, String)>) {
+    let child = |key: &str| format!("{}/{}", path, key.to_lowercase());
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .for_each(|(key, value)| flatten_json(value, &child(key), pairs)),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .for_each(|(i, value)| flatten_json(value, &child(&i.to_string()), pairs)),
+        Value::String(s) => pairs.push((path.to_string(), s.clone())),
+        Value::Null => {}
+        scalar => pairs.push((path.to_string(), scalar.to_string())),
+    }
+}
+
+fn numbers(value: &str) -> impl Iterator<Item = u8> + '_ {
+    value
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|n| n.parse().ok())
+}
+
+/// Every number under paths naming one of `fan_keys` and `field`, in order.
+fn values(pairs: &[(String, String)], fan_keys: &[&str], field: &str) -> Vec<u8> {
+    pairs
+        .iter()
+        .filter(|(path, _)| path.contains(field) && fan_keys.iter().any(|k| path.contains(k)))
+        .flat_map(|(_, value)| numbers(value))
+        .collect()
+}
+
+/// One fan's curve, or `None` if the file has none for it.
+fn curve(
+    pairs: &[(String, String)],
+    fan: &str,
+    fan_keys: &[&str],
+) -> Result<Option<Vec<CurvePoint>>, String> {
+    let mut temps = values(pairs, fan_keys, "temp");
+    let speeds = values(pairs, fan_keys, "speed");
+    if temps.is_empty() && speeds.is_empty() {
+        return Ok(None);
+    }
+    // The EC's first point has no threshold: it is the speed below the first
+    if temps.len() + 1 == speeds.len() {
+        temps.insert(0, 0);
+    }
+    if temps.len() != CURVE_POINTS || speeds.len() != CURVE_POINTS {
+        return Err(format!(
+            "The {} fan curve has {} temperatures and {} speeds; {} of each are needed",
+            fan,
+            temps.len(),
+            speeds.len(),
+            CURVE_POINTS
+        ));
+    }
+    Ok(Some(
+        temps
+            .into_iter()
+            .zip(speeds)
+            .map(|(temp, speed)| CurvePoint { temp, speed })
+            .collect(),
+    ))
+}
+
+fn convert(name: String, pairs: &[(String, String)]) -> Result<Profile, String> {
+    let cpu = curve(pairs, "CPU", CPU_KEYS)?;
+    let gpu = curve(pairs, "GPU", GPU_KEYS)?;
+    let curve = match (cpu, gpu) {
+        (Some(cpu), Some(gpu)) => Some(FanCurve { cpu, gpu }),
+        (None, None) => None,
+        (Some(cpu), None) => Some(FanCurve {
+            gpu: cpu.clone(),
+            cpu,
+        }),
+        (None, Some(gpu)) => Some(FanCurve {
+            cpu: gpu.clone(),
+            gpu,
+        }),
+    };
+    let mode = pairs.iter().find_map(|(path, value)| {
+        let value = value.to_lowercase();
+        (path.contains("mode") && FAN_MODES.contains(&value.as_str())).then_some(value)
+    });
+    let cooler_boost = pairs.iter().any(|(path, value)| {
+        path.contains("boost") && matches!(value.to_lowercase().as_str(), "true" | "1" | "on")
+    });
+    if curve.is_none() && mode.is_none() {
+        return Err("No fan curve or fan mode found in the file".to_string());
+    }
+
+    Ok(Profile {
+        name,
+        // A curve only takes effect in Advanced mode
+        fan_mode: mode
+            .unwrap_or_else(|| if curve.is_some() { "advanced" } else { "auto" }.to_string()),
+        fan_speed: None,
+        curve,
+        fan_speeds: Vec::new(),
+        cooler_boost,
+        keyboard_backlight: None,
+    })
+}
+
+/// Write a saved profile to `path` as a file to share.
+#[tauri::command]
+pub async fn export_profile(app: AppHandle, name: String, path: String) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    let profile = profiles::load_profiles()?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Profile '{}' not found", name))?;
+    let shared = SharedProfile {
+        format: SHARED_FORMAT.to_string(),
+        version: SHARED_VERSION,
+        model: fs::read_to_string(PRODUCT_NAME_PATH)
+            .ok()
+            .map(|s| s.trim().to_string()),
+        app_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        profile,
+    };
+    let json = serde_json::to_string_pretty(&shared).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(format!("Profile '{}' exported to {}", name, path))
+}
+
+/// Read a profile file, ours unless `format` says otherwise; other tools'
+/// configs give a profile named after the file. It is not saved; the
+/// window shows it for review and saves it with `save_profile`.
+#[tauri::command]
+pub async fn import_profile(
+    app: AppHandle,
+    path: String,
+    format: Option<ImportFormat>,
+) -> Result<ImportedProfile, String> {
+    permissions::check(&app, Tier::Control)?;
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let pairs = match format.unwrap_or(ImportFormat::MsiFanControl) {
+        ImportFormat::MsiFanControl => {
+            let shared =
+                parse_shared(&content).map_err(|e| format!("Can't import {}: {}", path, e))?;
+            return Ok(ImportedProfile {
+                profile: shared.profile,
+                model: shared.model,
+            });
+        }
+        ImportFormat::MControlCenter => flatten_ini(&content),
+        ImportFormat::MsiCenter => {
+            let json: Value = serde_json::from_str(&content)
+                .map_err(|e| format!("{} is not a JSON export: {}", path, e))?;
+            let mut pairs = Vec::new();
+            flatten_json(&json, "", &mut pairs);
+            pairs
+        }
+    };
+    let name = Path::new(&path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Imported".to_string());
+    Ok(ImportedProfile {
+        profile: convert(name, &pairs)?,
+        model: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(points: &[CurvePoint]) -> Vec<(u8, u8)> {
+        points.iter().map(|p| (p.temp, p.speed)).collect()
+    }
+
+    const MCC: &str = "[General]\n\
+        Fan1Temps = 50, 60, 70, 80, 85, 90\n\
+        Fan1Speeds = 0, 40, 50, 60, 70, 80, 100\n\
+        [Fan2]\n\
+        TempThreshold=55,65,75,85,90,95\n\
+        SpeedPercent=\"10,30,50,70,80,90,100\"\n\
+        FanMode = Advanced\n\
+        CoolerBoost = false\n";
+
+    #[test]
+    fn shared_profile_round_trips() {
+        let json = r#"{"format": "msi-fan-control-profile", "version": 1,
+            "model": "GF65 Thin 10SDR",
+            "profile": {"name": "Quiet", "fan_mode": "silent"}}"#;
+        let shared = parse_shared(json).unwrap();
+        assert_eq!(shared.profile.name, "Quiet");
+        assert_eq!(shared.profile.fan_mode, "silent");
+        assert_eq!(shared.model.as_deref(), Some("GF65 Thin 10SDR"));
+    }
+
+    #[test]
+    fn shared_profile_rejects_other_and_newer_formats() {
+        assert!(parse_shared("{").is_err());
+        assert!(parse_shared(r#"{"format": "other", "version": 1}"#).is_err());
+        let newer = r#"{"format": "msi-fan-control-profile", "version": 2,
+            "profile": {"name": "Quiet", "fan_mode": "silent"}}"#;
+        assert!(parse_shared(newer).unwrap_err().contains("newer version"));
+        let no_profile = r#"{"format": "msi-fan-control-profile", "version": 1}"#;
+        assert!(parse_shared(no_profile).is_err());
+    }
+
+    #[test]
+    fn ini_is_flattened_by_section() {
+        let pairs = flatten_ini("top=1\n[Fan1]\n Speed = \"40\" \n; comment\nnot a pair\n");
+        assert_eq!(
+            pairs,
+            [
+                ("/top".to_string(), "1".to_string()),
+                ("fan1/speed".to_string(), "40".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_is_flattened_with_indices() {
+        let json: Value =
+            serde_json::from_str(r#"{"Fan": {"CPU": [10, 20], "Skip": null, "On": true}}"#)
+                .unwrap();
+        let mut pairs = Vec::new();
+        flatten_json(&json, "", &mut pairs);
+        assert!(pairs.contains(&("/fan/cpu/0".to_string(), "10".to_string())));
+        assert!(pairs.contains(&("/fan/cpu/1".to_string(), "20".to_string())));
+        assert!(pairs.contains(&("/fan/on".to_string(), "true".to_string())));
+        assert_eq!(pairs.len(), 3);
+    }
+
+    #[test]
+    fn mcontrolcenter_config_converts() {
+        let profile = convert("mcc".to_string(), &flatten_ini(MCC)).unwrap();
+        assert_eq!(profile.fan_mode, "advanced");
+        assert!(!profile.cooler_boost);
+        let curve = profile.curve.unwrap();
+        // Six thresholds for seven speeds: the first point gets 0 °C
+        assert_eq!(
+            pairs(&curve.cpu),
+            [
+                (0, 0),
+                (50, 40),
+                (60, 50),
+                (70, 60),
+                (80, 70),
+                (85, 80),
+                (90, 100)
+            ]
+        );
+        assert_eq!(pairs(&curve.gpu)[..2], [(0, 10), (55, 30)]);
+        assert_eq!(pairs(&curve.gpu)[6], (95, 100));
+    }
+
+    #[test]
+    fn msi_center_export_converts_per_point_values() {
+        let points: Vec<String> = (0..7)
+            .map(|i| format!(r#"{{"Temp": {}, "Speed": {}}}"#, 40 + i * 10, i * 15))
+            .collect();
+        let json: Value = serde_json::from_str(&format!(
+            r#"{{"CpuFan": [{}], "Mode": "Silent", "CoolerBoost": 1}}"#,
+            points.join(",")
+        ))
+        .unwrap();
+        let mut flat = Vec::new();
+        flatten_json(&json, "", &mut flat);
+        let profile = convert("center".to_string(), &flat).unwrap();
+        assert_eq!(profile.fan_mode, "silent");
+        assert!(profile.cooler_boost);
+        let curve = profile.curve.unwrap();
+        assert_eq!(pairs(&curve.cpu)[6], (100, 90));
+        // No GPU curve in the file: the GPU fan follows the CPU's
+        assert_eq!(pairs(&curve.gpu), pairs(&curve.cpu));
+    }
+
+    #[test]
+    fn curve_with_wrong_point_count_is_rejected() {
+        let ini = "[Fan1]\ntemps = 50, 60, 70\nspeeds = 0, 40, 50\n";
+        let err = convert("short".to_string(), &flatten_ini(ini)).unwrap_err();
+        assert!(err.contains("CPU fan curve has 3 temperatures and 3 speeds"));
+    }
+
+    #[test]
+    fn out_of_range_values_are_dropped_and_rejected() {
+        // 300 isn't a u8, leaving six speeds for six temperatures
+        let ini = "[Fan1]\ntemps = 40,50,60,70,80,90\nspeeds = 0,10,20,30,40,300,60\n";
+        assert!(convert("bad".to_string(), &flatten_ini(ini)).is_err());
+    }
+
+    #[test]
+    fn mode_without_curve_is_enough() {
+        let profile = convert("m".to_string(), &flatten_ini("fan_mode = basic\n")).unwrap();
+        assert_eq!(profile.fan_mode, "basic");
+        assert!(profile.curve.is_none());
+    }
+
+    #[test]
+    fn unknown_mode_and_no_curve_is_rejected() {
+        let ini = "[General]\nmode = turbo\nboost = on\n";
+        assert!(convert("x".to_string(), &flatten_ini(ini)).is_err());
+        assert!(convert("x".to_string(), &[]).is_err());
+    }
+}
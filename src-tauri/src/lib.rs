@@ -1,383 +1,598 @@
+mod actions;
+mod anomaly;
+mod app_rules;
+mod backups;
+mod battery;
+mod bookmarks;
+mod contribute;
+mod crash;
+mod demo;
+mod diagnostics;
+mod disks;
+mod events;
+mod export;
+mod format;
+mod gpu;
+mod history;
+mod hotkeys;
+mod import;
+mod nbfc;
+mod notifications;
+mod onboarding;
+mod overrides;
+mod permissions;
+mod polkit;
+mod power;
+mod preflight;
+mod processes;
+mod profiles;
+mod resume;
+mod scheduler;
+mod schema;
+mod sensors;
+mod settings;
+mod sidecar;
+mod software_curve;
+mod temp_sources;
+mod thermal_test;
+mod tray;
+
+use actions::{ActionKind, ActionSource};
+pub use msi_protocol::{
+    ActiveRegisterMap, Capability, CapabilityStatus, CleanupReport, CurvePoint, DetectedModel,
+    EcModuleError, FailsafeConfig, Fan, FanBehavior, KeyboardBacklight, PersistenceReport,
+    SidecarFeature, Status as FanStatus, StatusLite,
+};
+use permissions::Tier;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::process::Stdio;
+use sidecar::{
+    connect_sidecar, query_sidecar, run_command, run_write, SidecarCommand, SidecarResponse,
+    SidecarState,
+};
 use std::sync::Arc;
-use std::time::Duration;
-use sysinfo::{CpuRefreshKind, System};
-use tauri::{Manager, State};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, Command};
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::{CpuRefreshKind, Networks, System};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
 // State to track the sidecar process
 struct SystemMonitor {
     sys: Arc<std::sync::Mutex<System>>,
+    /// Interface counters, and when they were last refreshed.
+    networks: Arc<std::sync::Mutex<(Networks, Instant)>>,
 }
-struct SidecarConnection {
-    child: Child,
-    reader: BufReader<tokio::process::ChildStdout>,
-}
-
-#[derive(Clone)]
-struct SidecarState {
-    connection: Arc<Mutex<Option<SidecarConnection>>>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct FanStatus {
-    pub cpu_temp: u8,
-    pub gpu_temp: u8,
-    pub fan1_rpm: u32,
-    pub fan2_rpm: u32,
-    pub cooler_boost: bool,
-    pub fan_mode: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(tag = "type")]
-enum SidecarResponse {
-    #[serde(rename = "status")]
-    Status {
-        cpu_temp: u8,
-        gpu_temp: u8,
-        fan1_rpm: u32,
-        fan2_rpm: u32,
-        cooler_boost: bool,
-        fan_mode: String,
-    },
-    #[serde(rename = "ok")]
-    Ok { message: String },
-    #[serde(rename = "error")]
-    Error { message: String },
-}
-
-fn get_sidecar_path() -> String {
-    // In development, use the compiled binary directly
-    // In production, Tauri bundles it with target triple suffix
-    let exe_dir = std::env::current_exe()
-        .ok()
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        .unwrap_or_default();
-
-    // Try to find the sidecar binary - check multiple locations
-    let possible_paths = [
-        // Production: bundled next to executable
-        exe_dir.join("msi-sidecar-x86_64-unknown-linux-gnu"),
-        exe_dir.join("msi-sidecar"),
-        // Development: in target/debug or target/release - allow standard cargo structures
-        exe_dir.join("../../binaries/msi-sidecar/target/release/msi-sidecar"),
-        exe_dir.join("../binaries/msi-sidecar/target/release/msi-sidecar"),
-        exe_dir.join("../../binaries/msi-sidecar/target/debug/msi-sidecar"),
-        exe_dir.join("../binaries/msi-sidecar/target/debug/msi-sidecar"),
-    ];
-
-    for path in &possible_paths {
-        if path.exists() {
-            return path
-                .canonicalize()
-                .unwrap_or_else(|_| path.clone())
-                .to_string_lossy()
-                .to_string();
-        }
-    }
 
-    // Fallback - let pkexec find it
-    "msi-sidecar".to_string()
+#[tauri::command]
+async fn start_sidecar(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+) -> Result<FanStatus, String> {
+    connect_sidecar(&app, &state).await
 }
 
-async fn read_response(
-    reader: &mut BufReader<tokio::process::ChildStdout>,
-) -> Result<SidecarResponse, String> {
-    let mut line = String::new();
+#[tauri::command]
+async fn stop_sidecar(app: AppHandle, state: State<'_, SidecarState>) -> Result<String, String> {
+    // An explicit disconnect must not be undone by the supervisor
+    state
+        .supervised
+        .store(false, std::sync::atomic::Ordering::SeqCst);
+    state.set_lifecycle(sidecar::lifecycle::Lifecycle::Active);
+    // Graceful exit first, then a kill to be sure
+    state.close(true, |_| true).await;
+    sidecar::supervisor::emit(&app, &sidecar::supervisor::ConnectionState::Disconnected);
 
-    reader
-        .read_line(&mut line)
-        .await
-        .map_err(|e| format!("Read error: {}", e))?;
+    Ok("Sidecar stopped".to_string())
+}
 
-    if line.is_empty() {
-        return Err("Empty response from sidecar - EOF".to_string());
+#[tauri::command]
+async fn get_status(state: State<'_, SidecarState>) -> Result<FanStatus, String> {
+    // Served from the pushed status when fresh, so polling never waits on the
+    // sidecar
+    if let Some(status) = state.latest_status() {
+        return Ok(status);
     }
-
-    serde_json::from_str(&line).map_err(|e| format!("Parse error: {} (line: {})", e, line.trim()))
+    fetch_status(&state).await
 }
 
-async fn send_command(child: &mut Child, cmd: &str) -> Result<(), String> {
-    let stdin = child.stdin.as_mut().ok_or("No stdin")?;
-    stdin
-        .write_all(format!("{}\n", cmd).as_bytes())
-        .await
-        .map_err(|e| format!("Write error: {}", e))?;
-    stdin
-        .flush()
-        .await
-        .map_err(|e| format!("Flush error: {}", e))?;
-    Ok(())
+/// Same as `get_status`, with readings rendered for the user's locale.
+#[tauri::command]
+async fn get_status_formatted(
+    state: State<'_, SidecarState>,
+) -> Result<format::FormattedFanStatus, String> {
+    let status = fetch_status(&state).await?;
+    Ok(format::FormattedFanStatus::from_status(
+        &status,
+        &format::NumberFormat::for_user(),
+    ))
 }
 
 #[tauri::command]
-async fn start_sidecar(state: State<'_, SidecarState>) -> Result<FanStatus, String> {
-    // Acquire lock asynchronously
-    let mut guard = state.connection.lock().await;
+async fn format_value(sensor: format::Sensor, raw: f64) -> Result<String, String> {
+    Ok(format::format_value(
+        sensor,
+        raw,
+        &format::NumberFormat::for_user(),
+    ))
+}
 
-    // Clean up existing connection if any
-    if let Some(mut conn) = guard.take() {
-        // We don't care about the result, just try to kill and wait
-        let _ = conn.child.kill().await;
-        let _ = conn.child.wait().await;
+async fn fetch_status(state: &SidecarState) -> Result<FanStatus, String> {
+    match query_sidecar(state, &SidecarCommand::GetStatus).await? {
+        SidecarResponse::Status(status) => Ok(status),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
     }
+}
 
-    let sidecar_path = get_sidecar_path();
-
-    // Spawn with pkexec for privilege escalation
-    // Note: tokio::process::Command is used here
-    let mut child = Command::new("pkexec")
-        .arg(&sidecar_path)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        // Important: kill on drop allows cleanup if the handle is dropped
-        .kill_on_drop(true)
-        .spawn()
-        .map_err(|e| format!("Failed to start sidecar: {}", e))?;
-
-    let stdout = child.stdout.take().ok_or("No stdout captured")?;
-    let mut reader = BufReader::new(stdout);
-
-    // Initial handshake with timeout
-    // We only need to timeout the read operation, not the whole setup
-    // And we can pass &mut reader to read_response directly
-    let response_result =
-        tokio::time::timeout(Duration::from_secs(5), read_response(&mut reader)).await;
-
-    match response_result {
-        Ok(Ok(response)) => {
-            // Success - store connection
-            *guard = Some(SidecarConnection { child, reader });
-
-            match response {
-                SidecarResponse::Status {
-                    cpu_temp,
-                    gpu_temp,
-                    fan1_rpm,
-                    fan2_rpm,
-                    cooler_boost,
-                    fan_mode,
-                } => Ok(FanStatus {
-                    cpu_temp,
-                    gpu_temp,
-                    fan1_rpm,
-                    fan2_rpm,
-                    cooler_boost,
-                    fan_mode,
-                }),
-                SidecarResponse::Error { message } => Err(message),
-                _ => Err("Unexpected initial response".to_string()),
-            }
-        }
-        Ok(Err(e)) => {
-            // Read error
-            let _ = child.kill().await;
-            Err(e)
-        }
-        Err(_) => {
-            // Timeout
-            let _ = child.kill().await;
-            Err("Sidecar startup timeout".to_string())
-        }
-    }
+/// Shared by the window command, the tray toggle and the hotkey.
+pub(crate) async fn apply_cooler_boost(
+    app: &tauri::AppHandle,
+    state: &SidecarState,
+    enabled: bool,
+    source: ActionSource,
+) -> Result<String, String> {
+    permissions::check(app, Tier::Control)?;
+    let message = run_write(state, &SidecarCommand::SetCoolerBoost { enabled }).await?;
+    profiles::record_last_applied(|last| last.cooler_boost = enabled);
+    tray::set_cooler_boost_checked(app, enabled);
+    actions::record(app, source, ActionKind::CoolerBoost, message.as_str(), None);
+    Ok(message)
 }
 
 #[tauri::command]
-async fn stop_sidecar(state: State<'_, SidecarState>) -> Result<String, String> {
-    let mut guard = state.connection.lock().await;
+async fn set_cooler_boost(
+    app: tauri::AppHandle,
+    state: State<'_, SidecarState>,
+    enabled: bool,
+) -> Result<String, String> {
+    apply_cooler_boost(&app, &state, enabled, ActionSource::Window).await
+}
 
-    if let Some(mut conn) = guard.take() {
-        // Try graceful exit first
-        let _ = send_command(&mut conn.child, r#"{"cmd":"exit"}"#).await;
+/// Fan writes need Control, and Expert when they bypass the duty limit.
+fn check_write(app: &AppHandle, force: Option<bool>) -> Result<(), String> {
+    let tier = if force.unwrap_or(false) {
+        Tier::Expert
+    } else {
+        Tier::Control
+    };
+    permissions::check(app, tier)
+}
 
-        // Force kill to be sure
-        let _ = conn.child.kill().await;
-        let _ = conn.child.wait().await;
-    }
+#[tauri::command]
+async fn set_fan_speed(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    percent: u8,
+    force: Option<bool>,
+) -> Result<String, String> {
+    check_write(&app, force)?;
+    let cmd = SidecarCommand::SetFanSpeed {
+        percent,
+        force: force.unwrap_or(false),
+    };
+    let message = run_write(&state, &cmd).await?;
+    profiles::record_last_applied(|last| {
+        last.fan_mode = "advanced".to_string();
+        last.fan_speed = Some(percent);
+        last.curve = None;
+        last.fan_speeds.clear();
+    });
+    overrides::fixed_speed_set(&app, percent);
+    actions::record(
+        &app,
+        ActionSource::Window,
+        ActionKind::FanSpeed,
+        message.as_str(),
+        None,
+    );
+    Ok(message)
+}
 
-    Ok("Sidecar stopped".to_string())
+#[tauri::command]
+async fn set_fan_speed_for(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    fan: Fan,
+    percent: u8,
+    force: Option<bool>,
+) -> Result<String, String> {
+    check_write(&app, force)?;
+    sidecar::handshake::require(&state, SidecarFeature::PerFanControl, "Per-fan speed").await?;
+    let cmd = SidecarCommand::SetFanSpeedPerFan {
+        fan,
+        percent,
+        force: force.unwrap_or(false),
+    };
+    let message = run_write(&state, &cmd).await?;
+    profiles::record_last_applied(|last| {
+        last.fan_mode = "advanced".to_string();
+        last.fan_speeds.retain(|speed| speed.fan != fan);
+        last.fan_speeds.push(profiles::FanSpeed { fan, percent });
+    });
+    overrides::fixed_speed_set(&app, percent);
+    actions::record(
+        &app,
+        ActionSource::Window,
+        ActionKind::FanSpeed,
+        message.as_str(),
+        None,
+    );
+    Ok(message)
+}
+
+/// Shared by the window command and the tray's Fan Mode submenu.
+pub(crate) async fn apply_fan_mode(
+    app: &AppHandle,
+    state: &SidecarState,
+    mode: String,
+    source: ActionSource,
+) -> Result<String, String> {
+    permissions::check(app, Tier::Control)?;
+    let cmd = SidecarCommand::SetFanMode { mode: mode.clone() };
+    let message = run_write(state, &cmd).await?;
+    tray::set_fan_mode_checked(app, &mode);
+    profiles::record_last_applied(|last| {
+        last.fan_mode = mode;
+        last.fan_speed = None;
+        last.curve = None;
+        last.fan_speeds.clear();
+    });
+    overrides::fixed_speed_cleared(app);
+    actions::record(app, source, ActionKind::FanMode, message.as_str(), None);
+    Ok(message)
 }
 
 #[tauri::command]
-async fn get_status(state: State<'_, SidecarState>) -> Result<FanStatus, String> {
-    // Acquire lock with timeout to prevent hanging if the lock is held indefinitely
-    let guard_result = tokio::time::timeout(Duration::from_secs(1), state.connection.lock()).await;
+async fn set_fan_mode(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    mode: String,
+) -> Result<String, String> {
+    apply_fan_mode(&app, &state, mode, ActionSource::Window).await
+}
 
-    // Check if we got the lock
-    let mut guard = match guard_result {
-        Ok(g) => g,
-        Err(_) => return Err("Failed to acquire lock (busy)".to_string()),
+#[tauri::command]
+async fn set_fan_curve(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    cpu: Vec<CurvePoint>,
+    gpu: Vec<CurvePoint>,
+    force: Option<bool>,
+) -> Result<String, String> {
+    check_write(&app, force)?;
+    let cmd = SidecarCommand::SetFanCurve {
+        cpu: cpu.clone(),
+        gpu: gpu.clone(),
+        force: force.unwrap_or(false),
     };
+    let message = run_write(&state, &cmd).await?;
+    profiles::record_last_applied(|last| {
+        last.fan_mode = "advanced".to_string();
+        last.fan_speed = None;
+        last.curve = Some(profiles::FanCurve { cpu, gpu });
+        last.fan_speeds.clear();
+    });
+    overrides::fixed_speed_cleared(&app);
+    actions::record(
+        &app,
+        ActionSource::Window,
+        ActionKind::FanCurve,
+        message.as_str(),
+        None,
+    );
+    Ok(message)
+}
 
-    // Check if connected
-    let conn = guard
-        .as_mut()
-        .ok_or("Sidecar not running. Click Connect first.")?;
+#[tauri::command]
+async fn get_detected_model(state: State<'_, SidecarState>) -> Result<DetectedModel, String> {
+    match query_sidecar(&state, &SidecarCommand::GetDetectedModel).await? {
+        SidecarResponse::DetectedModel(detected) => Ok(detected),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
+    }
+}
 
-    let request_future = async {
-        send_command(&mut conn.child, r#"{"cmd":"get_status"}"#).await?;
-        read_response(&mut conn.reader).await
-    };
+/// Load `ec_sys` if the sidecar couldn't at startup, e.g. after the user
+/// installed the module package for a new kernel. `None` once it's loaded.
+#[tauri::command]
+async fn load_ec_module(state: State<'_, SidecarState>) -> Result<Option<EcModuleError>, String> {
+    match query_sidecar(&state, &SidecarCommand::LoadEcModule).await? {
+        SidecarResponse::EcModule { error } => Ok(error),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
+    }
+}
 
-    // Overall operation timeout
-    match tokio::time::timeout(Duration::from_secs(3), request_future).await {
-        Ok(Ok(response)) => match response {
-            SidecarResponse::Status {
-                cpu_temp,
-                gpu_temp,
-                fan1_rpm,
-                fan2_rpm,
-                cooler_boost,
-                fan_mode,
-            } => Ok(FanStatus {
-                cpu_temp,
-                gpu_temp,
-                fan1_rpm,
-                fan2_rpm,
-                cooler_boost,
-                fan_mode,
-            }),
-            SidecarResponse::Error { message } => Err(message),
-            _ => Err("Unexpected response".to_string()),
-        },
-        Ok(Err(e)) => {
-            // IO Error - connection likely dead
-            // We should kill it so the next retry forces a clean reconnect
-            let _ = conn.child.kill().await;
-            *guard = None;
-            Err(format!("Communication error: {}", e))
-        }
-        Err(_) => {
-            // Timeout - connection hanging
-            let _ = conn.child.kill().await;
-            *guard = None;
-            Err("Sidecar request timeout".to_string())
-        }
+async fn persistence(
+    app: &AppHandle,
+    state: &SidecarState,
+    cmd: SidecarCommand,
+) -> Result<PersistenceReport, String> {
+    // Boot files outlast the session and switch on EC writes for everyone
+    permissions::check(app, Tier::Expert)?;
+    demo::refuse("Loading ec_sys at boot")?;
+    match query_sidecar(state, &cmd).await? {
+        SidecarResponse::Persistence(report) => Ok(report),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
     }
 }
 
+/// Have `ec_sys` load with write support at boot. The sidecar no longer
+/// does this on its own, so the window asks first.
 #[tauri::command]
-async fn set_cooler_boost(state: State<'_, SidecarState>, enabled: bool) -> Result<String, String> {
-    let mut guard = state.connection.lock().await;
-    let conn = guard.as_mut().ok_or("Sidecar not running")?;
+async fn install_persistence(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+) -> Result<PersistenceReport, String> {
+    persistence(&app, &state, SidecarCommand::InstallPersistence).await
+}
 
-    let cmd = format!(
-        r#"{{"cmd":"set_cooler_boost","data":{{"enabled":{}}}}}"#,
-        enabled
-    );
+/// Undo `install_persistence`.
+#[tauri::command]
+async fn remove_persistence(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+) -> Result<PersistenceReport, String> {
+    persistence(&app, &state, SidecarCommand::RemovePersistence).await
+}
 
-    let request_future = async {
-        send_command(&mut conn.child, &cmd).await?;
-        read_response(&mut conn.reader).await
-    };
+/// Remove the root-owned files the app created, before uninstalling: the
+/// `ec_sys` boot files, an AppImage's polkit policy, the daemon's boot
+/// enablement and `/etc` and `/var/log` directories. Starts the sidecar
+/// if needed; the package's own files are kept.
+#[tauri::command]
+async fn cleanup_system_files(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+) -> Result<CleanupReport, String> {
+    permissions::check(&app, Tier::Expert)?;
+    demo::refuse("Removing system files")?;
+    if !state.is_connected().await {
+        connect_sidecar(&app, &state).await?;
+    }
+    match query_sidecar(&state, &SidecarCommand::CleanupSystemFiles).await? {
+        SidecarResponse::Cleanup(report) => Ok(report),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
+    }
+}
 
-    match tokio::time::timeout(Duration::from_secs(3), request_future).await {
-        Ok(Ok(response)) => match response {
-            SidecarResponse::Ok { message } => Ok(message),
-            SidecarResponse::Error { message } => Err(message),
-            _ => Err("Unexpected response".to_string()),
-        },
-        Ok(Err(e)) => {
-            let _ = conn.child.kill().await;
-            *guard = None;
-            Err(format!("Communication error: {}", e))
-        }
-        Err(_) => {
-            let _ = conn.child.kill().await;
-            *guard = None;
-            Err("Command timeout".to_string())
+/// What works on this machine and why the rest doesn't. Without a sidecar
+/// the EC capabilities are unavailable with the connection error as the
+/// reason, and the app's own checks are still reported.
+#[tauri::command]
+async fn get_capabilities(state: State<'_, SidecarState>) -> Result<Vec<CapabilityStatus>, String> {
+    let mut capabilities = match query_sidecar(&state, &SidecarCommand::GetCapabilities).await {
+        Ok(SidecarResponse::Capabilities { capabilities }) => capabilities,
+        result => {
+            let reason = match result {
+                Ok(SidecarResponse::Error { message }) | Err(message) => message,
+                _ => "Unexpected response".to_string(),
+            };
+            [
+                Capability::Monitor,
+                Capability::Write,
+                Capability::CoolerBoost,
+                Capability::FanCurves,
+            ]
+            .into_iter()
+            .map(|capability| CapabilityStatus::unavailable(capability, reason.clone()))
+            .collect()
         }
+    };
+    capabilities.push(battery::threshold_capability());
+    Ok(capabilities)
+}
+
+/// Register map the sidecar uses for raw EC access, built-in or overridden.
+#[tauri::command]
+async fn get_register_map(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+) -> Result<ActiveRegisterMap, String> {
+    permissions::check(&app, Tier::Expert)?;
+    match query_sidecar(&state, &SidecarCommand::GetRegisterMap).await? {
+        SidecarResponse::RegisterMap(map) => Ok(map),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
     }
 }
 
+/// Correct the tachometer constant from a fan's true speed, e.g. as MSI
+/// Center reports it, while it runs steadily. Saved to the register map
+/// override file.
 #[tauri::command]
-async fn set_fan_speed(state: State<'_, SidecarState>, percent: u8) -> Result<String, String> {
-    let mut guard = state.connection.lock().await;
-    let conn = guard.as_mut().ok_or("Sidecar not running")?;
+async fn calibrate_fan_rpm(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    fan: Fan,
+    actual_rpm: u32,
+) -> Result<ActiveRegisterMap, String> {
+    permissions::check(&app, Tier::Expert)?;
+    let status = fetch_status(&state).await?;
+    let reported = match fan {
+        Fan::Fan1 => status.fan1_rpm,
+        Fan::Fan2 => status.fan2_rpm,
+    };
+    if reported == 0 || actual_rpm == 0 {
+        return Err("Calibrate while the fan is spinning".to_string());
+    }
+    let current = match query_sidecar(&state, &SidecarCommand::GetRegisterMap).await? {
+        SidecarResponse::RegisterMap(map) => map.map.rpm_divisor,
+        SidecarResponse::Error { message } => return Err(message),
+        _ => return Err("Unexpected response".to_string()),
+    };
+    // The tach count stays put, so the constant scales with the speed
+    let divisor = (current as u64 * actual_rpm as u64 / reported as u64)
+        .try_into()
+        .map_err(|_| format!("{} RPM is implausibly high", actual_rpm))?;
+    match query_sidecar(&state, &SidecarCommand::SetRpmDivisor { divisor }).await? {
+        SidecarResponse::RegisterMap(map) => Ok(map),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
+    }
+}
 
-    let cmd = format!(
-        r#"{{"cmd":"set_fan_speed","data":{{"percent":{}}}}}"#,
-        percent
-    );
+#[tauri::command]
+async fn get_keyboard_backlight(
+    state: State<'_, SidecarState>,
+) -> Result<KeyboardBacklight, String> {
+    match query_sidecar(&state, &SidecarCommand::GetKeyboardBacklight).await? {
+        SidecarResponse::KeyboardBacklight(backlight) => Ok(backlight),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
+    }
+}
 
-    let request_future = async {
-        send_command(&mut conn.child, &cmd).await?;
-        read_response(&mut conn.reader).await
-    };
+#[tauri::command]
+async fn set_keyboard_backlight(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    brightness: u8,
+) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    let cmd = SidecarCommand::SetKeyboardBacklight { brightness };
+    let message = run_write(&state, &cmd).await?;
+    profiles::record_last_applied(|last| last.keyboard_backlight = Some(brightness));
+    Ok(message)
+}
 
-    match tokio::time::timeout(Duration::from_secs(3), request_future).await {
-        Ok(Ok(response)) => match response {
-            SidecarResponse::Ok { message } => Ok(message),
-            SidecarResponse::Error { message } => Err(message),
-            _ => Err("Unexpected response".to_string()),
-        },
-        Ok(Err(e)) => {
-            let _ = conn.child.kill().await;
-            *guard = None;
-            Err(format!("Communication error: {}", e))
-        }
-        Err(_) => {
-            let _ = conn.child.kill().await;
-            *guard = None;
-            Err("Command timeout".to_string())
-        }
+/// Write features that can be opted into on non-verified models.
+const EXPERIMENTAL_FEATURES: [&str; 4] = ["cooler_boost", "fan_mode", "fan_speed", "fan_curve"];
+
+fn opt_ins_command(features: &[String]) -> SidecarCommand {
+    SidecarCommand::SetOptIns {
+        features: features.to_vec(),
     }
 }
 
 #[tauri::command]
-async fn set_fan_mode(state: State<'_, SidecarState>, mode: String) -> Result<String, String> {
-    let mut guard = state.connection.lock().await;
-    let conn = guard.as_mut().ok_or("Sidecar not running")?;
+async fn get_experimental_opt_ins() -> Result<Vec<String>, String> {
+    Ok(settings::load_settings()?.experimental_opt_ins)
+}
 
-    let cmd = format!(r#"{{"cmd":"set_fan_mode","data":{{"mode":"{}"}}}}"#, mode);
+/// Allow or forbid one write feature on an experimental/unsupported model.
+/// Persisted, and pushed to the running sidecar if connected.
+#[tauri::command]
+async fn set_experimental_opt_in(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    feature: String,
+    enabled: bool,
+) -> Result<Vec<String>, String> {
+    permissions::check(&app, Tier::Expert)?;
+    if !EXPERIMENTAL_FEATURES.contains(&feature.as_str()) {
+        return Err(format!("Unknown feature: {}", feature));
+    }
 
-    let request_future = async {
-        send_command(&mut conn.child, &cmd).await?;
-        read_response(&mut conn.reader).await
-    };
+    let mut settings = settings::load_settings()?;
+    settings.experimental_opt_ins.retain(|f| f != &feature);
+    if enabled {
+        settings.experimental_opt_ins.push(feature);
+    }
+    settings::save_settings(&settings)?;
 
-    match tokio::time::timeout(Duration::from_secs(3), request_future).await {
-        Ok(Ok(response)) => match response {
-            SidecarResponse::Ok { message } => Ok(message),
-            SidecarResponse::Error { message } => Err(message),
-            _ => Err("Unexpected response".to_string()),
-        },
-        Ok(Err(e)) => {
-            let _ = conn.child.kill().await;
-            *guard = None;
-            Err(format!("Communication error: {}", e))
-        }
-        Err(_) => {
-            let _ = conn.child.kill().await;
-            *guard = None;
-            Err("Command timeout".to_string())
-        }
+    if state.is_connected().await {
+        run_command(&state, &opt_ins_command(&settings.experimental_opt_ins)).await?;
+    }
+    Ok(settings.experimental_opt_ins)
+}
+
+/// First write on a non-verified model: the sidecar changes one harmless
+/// register and restores it before real writes are allowed. Called once the
+/// user confirms; the model is remembered so later sessions run it on
+/// connect.
+#[tauri::command]
+async fn run_trial_write(app: AppHandle, state: State<'_, SidecarState>) -> Result<String, String> {
+    permissions::check(&app, Tier::Expert)?;
+    let message = run_write(&state, &SidecarCommand::TrialWrite).await?;
+    let model = fetch_status(&state).await?.model;
+    let mut settings = settings::load_settings()?;
+    if !settings.trial_write_models.contains(&model) {
+        settings.trial_write_models.push(model);
+        settings::save_settings(&settings)?;
+    }
+    Ok(message)
+}
+
+#[tauri::command]
+async fn get_failsafe() -> Result<FailsafeConfig, String> {
+    Ok(settings::load_settings()?.failsafe.unwrap_or_default())
+}
+
+/// Configure the sidecar's watchdog for fixed speeds and Silent mode.
+/// Persisted, and pushed to the running sidecar if connected. Loosening a
+/// safety limit counts as expert access.
+#[tauri::command]
+async fn set_failsafe(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    config: FailsafeConfig,
+) -> Result<FailsafeConfig, String> {
+    permissions::check(&app, Tier::Expert)?;
+    config.validate()?;
+    let mut settings = settings::load_settings()?;
+    settings.failsafe = Some(config);
+    settings::save_settings(&settings)?;
+
+    if state.is_connected().await {
+        run_command(&state, &SidecarCommand::SetFailsafe(config)).await?;
+    }
+    Ok(config)
+}
+
+#[tauri::command]
+async fn get_fan_behavior() -> Result<FanBehavior, String> {
+    Ok(settings::load_settings()?.fan_behavior.unwrap_or_default())
+}
+
+/// Hysteresis and ramp rate the sidecar applies to fan changes. Persisted,
+/// and pushed to the running sidecar if connected.
+#[tauri::command]
+async fn set_fan_behavior(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    behavior: FanBehavior,
+) -> Result<FanBehavior, String> {
+    permissions::check(&app, Tier::Control)?;
+    if state.is_connected().await {
+        run_command(&state, &SidecarCommand::SetFanBehavior(behavior)).await?;
     }
+    let mut settings = settings::load_settings()?;
+    settings.fan_behavior = Some(behavior);
+    settings::save_settings(&settings)?;
+    Ok(behavior)
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
 pub struct HardwareInfo {
     pub cpu_model: String,
     pub gpu_model: String,
+    #[ts(type = "number")]
     pub memory_total: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
 pub struct SystemStats {
+    #[ts(type = "number")]
     pub memory_used: u64,
+    #[ts(type = "number")]
     pub memory_total: u64,
+    #[ts(type = "number")]
     pub swap_used: u64,
+    #[ts(type = "number")]
     pub swap_total: u64,
+    #[ts(type = "number")]
     pub cpu_global_frequency: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
 pub struct CpuCoreDetail {
     pub name: String,
+    #[ts(type = "number")]
     pub frequency: u64,
     pub usage: f32,
 }
@@ -402,7 +617,7 @@ async fn get_hardware_info(state: State<'_, SystemMonitor>) -> Result<HardwareIn
 
         Ok::<HardwareInfo, String>(HardwareInfo {
             cpu_model,
-            gpu_model: "GeForce GTX 1660 Ti Mobile".to_string(),
+            gpu_model: gpu::model().unwrap_or_else(|| "Unknown GPU".to_string()),
             memory_total,
         })
     })
@@ -474,6 +689,67 @@ async fn get_cpu_details(state: State<'_, SystemMonitor>) -> Result<Vec<CpuCoreD
     Ok(details)
 }
 
+#[derive(Debug, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct InterfaceStats {
+    pub name: String,
+    /// Bytes per second since the previous call.
+    pub received_per_sec: f64,
+    pub transmitted_per_sec: f64,
+    /// Bytes since the interface came up.
+    #[ts(type = "number")]
+    pub total_received: u64,
+    #[ts(type = "number")]
+    pub total_transmitted: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, TS)]
+#[ts(export)]
+pub struct NetworkStats {
+    /// Sum over all interfaces, loopback excluded.
+    pub received_per_sec: f64,
+    pub transmitted_per_sec: f64,
+    pub interfaces: Vec<InterfaceStats>,
+}
+
+/// Throughput of each network interface since the previous call, so the
+/// first call after startup averages over the whole uptime of the app.
+#[tauri::command]
+async fn get_network_stats(state: State<'_, SystemMonitor>) -> Result<NetworkStats, String> {
+    let networks_arc = state.networks.clone();
+
+    let stats = tokio::task::spawn_blocking(move || {
+        let mut guard = networks_arc.lock().map_err(|e| e.to_string())?;
+        let (networks, last_refresh) = &mut *guard;
+        networks.refresh(true);
+        let elapsed = last_refresh.elapsed().as_secs_f64().max(f64::EPSILON);
+        *last_refresh = Instant::now();
+
+        let mut interfaces: Vec<InterfaceStats> = networks
+            .iter()
+            .filter(|(name, _)| name.as_str() != "lo")
+            .map(|(name, data)| InterfaceStats {
+                name: name.clone(),
+                received_per_sec: data.received() as f64 / elapsed,
+                transmitted_per_sec: data.transmitted() as f64 / elapsed,
+                total_received: data.total_received(),
+                total_transmitted: data.total_transmitted(),
+            })
+            .collect();
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok::<NetworkStats, String>(NetworkStats {
+            received_per_sec: interfaces.iter().map(|i| i.received_per_sec).sum(),
+            transmitted_per_sec: interfaces.iter().map(|i| i.transmitted_per_sec).sum(),
+            interfaces,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))??;
+
+    Ok(stats)
+}
+
 const AUTOSTART_DESKTOP_ENTRY: &str = r#"[Desktop Entry]
 Type=Application
 Name=MSI Fan Control
@@ -492,6 +768,15 @@ fn get_autostart_path() -> Result<std::path::PathBuf, String> {
     Ok(autostart_dir.join("msi-fan-control.desktop"))
 }
 
+/// Per-user config directory (`~/.config/msi-fan-control`).
+fn get_config_dir() -> Result<std::path::PathBuf, String> {
+    if demo::enabled() {
+        return Ok(demo::config_dir());
+    }
+    let home = std::env::var("HOME").map_err(|_| "HOME not set")?;
+    Ok(std::path::PathBuf::from(home).join(".config/msi-fan-control"))
+}
+
 #[tauri::command]
 async fn get_autostart_enabled() -> Result<bool, String> {
     let path = get_autostart_path()?;
@@ -500,6 +785,7 @@ async fn get_autostart_enabled() -> Result<bool, String> {
 
 #[tauri::command]
 async fn set_autostart_enabled(enabled: bool) -> Result<String, String> {
+    demo::refuse("Autostart")?;
     let path = get_autostart_path()?;
 
     if enabled {
@@ -519,82 +805,252 @@ async fn set_autostart_enabled(enabled: bool) -> Result<String, String> {
     }
 }
 
+/// How long the startup restore waits for the EC to become readable.
+const EC_READY_TIMEOUT: Duration = Duration::from_secs(30);
+const EC_READY_RETRY: Duration = Duration::from_secs(2);
+
+/// Wait until the sidecar can read the EC. Early in a session `ec_sys` may
+/// still be loading, so the sidecar is asked once to load it.
+async fn wait_for_ec(state: &SidecarState) -> Result<(), String> {
+    let deadline = Instant::now() + EC_READY_TIMEOUT;
+    let mut module_requested = false;
+    loop {
+        let error = match query_sidecar(state, &SidecarCommand::GetStatus).await {
+            Ok(SidecarResponse::Status(_)) => return Ok(()),
+            Ok(SidecarResponse::Error { message }) | Err(message) => message,
+            Ok(_) => "Unexpected response".to_string(),
+        };
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "EC not ready after {} s: {}",
+                EC_READY_TIMEOUT.as_secs(),
+                error
+            ));
+        }
+        if !module_requested {
+            let _ = query_sidecar(state, &SidecarCommand::LoadEcModule).await;
+            module_requested = true;
+        }
+        tokio::time::sleep(EC_READY_RETRY).await;
+    }
+}
+
+/// Connect and re-apply the last applied settings, so the laptop doesn't
+/// stay on firmware defaults after a reboot. Waits the configured startup
+/// delay first, then for the EC to be readable.
+async fn apply_on_startup(app: &AppHandle, state: &SidecarState) -> Result<(), String> {
+    let Some(last) = profiles::load_last_applied()? else {
+        return Ok(());
+    };
+    let delay = settings::load_settings()?.startup_delay_secs;
+    tokio::time::sleep(Duration::from_secs(delay.into())).await;
+
+    // The window may have connected already; reuse it rather than respawning
+    let connected = state.is_connected().await;
+    if !connected {
+        // An EC that isn't readable yet fails the handshake, but the
+        // connection is kept for `wait_for_ec`
+        if let Err(e) = connect_sidecar(app, state).await {
+            if !state.is_connected().await {
+                return Err(e);
+            }
+        }
+    }
+    wait_for_ec(state).await?;
+    profiles::apply_profile(state, &last).await?;
+    if let Some(percent) = last.fixed_speed() {
+        overrides::fixed_speed_set(app, percent);
+    }
+    actions::record(
+        app,
+        ActionSource::Startup,
+        ActionKind::ProfileApplied,
+        "Last applied settings restored",
+        Some("Apply on startup is enabled".to_string()),
+    );
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    crash::install();
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
             let _ = app
                 .get_webview_window("main")
                 .expect("no main window")
                 .set_focus();
         }))
-        .manage(SidecarState {
-            connection: Arc::new(Mutex::new(None)),
-        })
+        .manage(SidecarState::new())
+        .manage(history::History::default())
+        .manage(anomaly::AnomalyState::default())
+        .manage(actions::ActionLog::default())
+        .manage(events::EventSubscriptions::default())
+        .manage(notifications::NotificationState::default())
+        .manage(software_curve::SoftwareCurveState::default())
+        .manage(app_rules::AppRuleState::default())
+        .manage(power::PowerState::default())
+        .manage(contribute::ContributionState::default())
+        .manage(overrides::OverrideState::default())
+        .manage(permissions::PermissionState::default())
         .manage(SystemMonitor {
             sys: Arc::new(std::sync::Mutex::new(System::new_all())),
+            networks: Arc::new(std::sync::Mutex::new((
+                Networks::new_with_refreshed_list(),
+                Instant::now(),
+            ))),
         })
         .invoke_handler(tauri::generate_handler![
             start_sidecar,
             stop_sidecar,
             get_status,
+            get_status_formatted,
+            history::get_history,
+            anomaly::get_anomalies,
+            export::export_history,
+            contribute::get_ec_dump,
+            contribute::write_ec_register,
+            diagnostics::export_diagnostics,
+            contribute::start_contribution,
+            contribute::capture_contribution_step,
+            contribute::finish_contribution,
+            bookmarks::get_register_bookmarks,
+            bookmarks::set_register_bookmarks,
+            bookmarks::read_register_bookmarks,
+            format_value,
             set_cooler_boost,
             set_fan_speed,
+            set_fan_speed_for,
             set_fan_mode,
+            set_fan_curve,
+            get_detected_model,
+            load_ec_module,
+            install_persistence,
+            remove_persistence,
+            cleanup_system_files,
+            get_register_map,
+            calibrate_fan_rpm,
+            get_capabilities,
+            sidecar::handshake::get_sidecar_info,
+            events::get_subscribed_topics,
+            events::subscribe_topics,
+            temp_sources::get_temp_sources,
+            sensors::get_all_sensors,
+            disks::get_disk_health,
+            crash::list_crash_reports,
+            crash::get_crash_reports_enabled,
+            crash::set_crash_reports_enabled,
+            get_keyboard_backlight,
+            set_keyboard_backlight,
+            get_experimental_opt_ins,
+            set_experimental_opt_in,
+            run_trial_write,
+            get_failsafe,
+            set_failsafe,
+            get_fan_behavior,
+            set_fan_behavior,
             get_hardware_info,
+            gpu::get_gpu_stats,
             get_system_stats,
             get_cpu_details,
+            get_network_stats,
+            processes::get_top_processes,
+            processes::kill_process,
             get_autostart_enabled,
-            set_autostart_enabled
+            set_autostart_enabled,
+            profiles::save_profile,
+            profiles::load_profile,
+            profiles::cycle_profile,
+            actions::get_action_history,
+            notifications::get_notification_settings,
+            notifications::set_notification_settings,
+            software_curve::get_software_curve,
+            software_curve::enable_software_curve,
+            software_curve::disable_software_curve,
+            profiles::list_profiles,
+            profiles::delete_profile,
+            import::import_profile,
+            import::export_profile,
+            nbfc::import_nbfc_config,
+            app_rules::get_app_rules,
+            app_rules::get_active_app_rule,
+            app_rules::set_app_rules,
+            backups::list_backups,
+            backups::create_backup,
+            backups::restore_backup,
+            backups::get_backup_settings,
+            backups::set_backup_settings,
+            battery::get_battery_info,
+            power::get_power_source,
+            power::get_power_profiles,
+            power::set_power_profiles,
+            settings::get_apply_on_startup,
+            settings::set_apply_on_startup,
+            settings::get_startup_delay,
+            settings::set_startup_delay,
+            settings::get_settings,
+            settings::update_settings,
+            settings::set_units,
+            polkit::install_polkit_policy,
+            hotkeys::get_hotkeys,
+            hotkeys::set_hotkeys,
+            tray::get_tray_menu_settings,
+            tray::set_tray_menu_settings,
+            permissions::get_permission_settings,
+            permissions::set_permission_settings,
+            permissions::confirm_permission_tier,
+            onboarding::get_onboarding_state,
+            onboarding::mark_step_done,
+            preflight::run_preflight_checks,
+            preflight::apply_fix,
+            thermal_test::run_thermal_test,
+            thermal_test::stop_thermal_test,
+            thermal_test::get_test_history,
+            schema::get_api_schema
         ])
         .setup(|app| {
             use tauri::image::Image;
-            use tauri::menu::{Menu, MenuItem};
-            use tauri::tray::TrayIconBuilder;
-            use tauri::Manager;
-
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
 
             let window_icon = Image::from_bytes(include_bytes!("../icons/128x128.png"))
                 .expect("Failed to load window icon");
-            let tray_icon = Image::from_bytes(include_bytes!("../icons/32x32.png"))
-                .expect("Failed to load tray icon");
-
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.set_icon(window_icon);
+                if demo::enabled() {
+                    let _ = window.set_title(demo::WINDOW_TITLE);
+                }
             }
 
-            let _tray = TrayIconBuilder::with_id("msi-main-tray")
-                .menu(&menu)
-                .show_menu_on_left_click(false)
-                .icon(tray_icon)
-                .tooltip("MSI Fan Control")
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "quit" => {
-                        app.exit(0);
-                    }
-                    "show" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
-                    _ => {}
-                })
-                .on_tray_icon_event(|tray, event| {
-                    use tauri::tray::TrayIconEvent;
-                    if let TrayIconEvent::Click { .. } = event {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
+            tray::build(app)?;
+            if let Ok(settings) = settings::load_settings() {
+                if let Err(e) = hotkeys::register(app.handle(), &settings.hotkeys) {
+                    eprintln!("Failed to register hotkeys: {}", e);
+                }
+                if let Some(curve) = settings.software_curve {
+                    software_curve::start(app.handle(), curve);
+                }
+            }
+            scheduler::spawn(app.handle().clone());
+            sidecar::supervisor::spawn(app.handle().clone());
+            app_rules::spawn(app.handle().clone());
+            power::spawn(app.handle().clone());
+            backups::spawn();
+            resume::spawn(app.handle().clone());
+
+            if settings::load_settings()
+                .map(|s| s.apply_on_startup)
+                .unwrap_or(false)
+            {
+                let handle = app.handle().clone();
+                let state = app.state::<SidecarState>().inner().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = apply_on_startup(&handle, &state).await {
+                        eprintln!("Failed to apply settings on startup: {}", e);
                     }
-                })
-                .build(app)?;
+                });
+            }
             Ok(())
         })
         .on_window_event(|window, event| {
@@ -0,0 +1,489 @@
+//! Reading NoteBook FanControl (NBFC) model configs, so the fan settings
+//! worked out for a model there carry over.
+//!
+//! NBFC reads temperatures from the OS and steps one speed register per fan
+//! through threshold pairs from software. Its configs name no temperature,
+//! tachometer or curve registers, so they can't become a register map.
+//! What carries over is translated to the nearest structure here: the
+//! thresholds to the software curve (which follows the temperature with
+//! hysteresis the same way) and a 7-point EC curve, the critical
+//! temperature to the failsafe limit, and every register the config
+//! touches to a labeled bookmark for mapping the model.
+
+use crate::bookmarks::RegisterBookmark;
+use crate::permissions::{self, Tier};
+use crate::profiles::FanCurve;
+use crate::software_curve::SoftwareCurve;
+use crate::CurvePoint;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::AppHandle;
+use ts_rs::TS;
+
+/// The EC takes seven points per fan.
+const EC_CURVE_POINTS: usize = 7;
+/// Most points the software curve accepts.
+const SOFTWARE_CURVE_POINTS: usize = 16;
+/// NBFC switches speed at once; the software curve needs some rate.
+const RAMP_RATE: u8 = 100;
+const MAX_LABEL_LEN: usize = 64;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Config {
+    notebook_model: String,
+    #[serde(default)]
+    read_write_words: bool,
+    #[serde(default)]
+    critical_temperature: Option<u8>,
+    #[serde(default)]
+    fan_configurations: FanConfigurations,
+    #[serde(default)]
+    register_write_configurations: RegisterWriteConfigurations,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FanConfigurations {
+    #[serde(rename = "FanConfiguration", default)]
+    fans: Vec<FanConfiguration>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FanConfiguration {
+    read_register: u16,
+    write_register: u16,
+    #[serde(default)]
+    fan_display_name: Option<String>,
+    #[serde(default)]
+    temperature_thresholds: TemperatureThresholds,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TemperatureThresholds {
+    #[serde(rename = "TemperatureThreshold", default)]
+    thresholds: Vec<TemperatureThreshold>,
+}
+
+/// Run at `fan_speed` % from `up_threshold` °C until the temperature falls
+/// to `down_threshold`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TemperatureThreshold {
+    up_threshold: u8,
+    down_threshold: u8,
+    fan_speed: f32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RegisterWriteConfigurations {
+    #[serde(rename = "RegisterWriteConfiguration", default)]
+    writes: Vec<RegisterWriteConfiguration>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RegisterWriteConfiguration {
+    #[serde(default)]
+    write_mode: String,
+    #[serde(default)]
+    write_occasion: String,
+    register: u16,
+    value: u16,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct NbfcTranslation {
+    pub model: String,
+    /// From the first two fans' thresholds; the second fan's curve is the
+    /// first's on single-fan configs.
+    pub curve: Option<FanCurve>,
+    /// From the first fan's thresholds.
+    pub software_curve: Option<SoftwareCurve>,
+    /// NBFC's critical temperature, for `set_failsafe`.
+    pub failsafe_max_temp: Option<u8>,
+    /// Every register the config reads or writes, for
+    /// `set_register_bookmarks`.
+    pub bookmarks: Vec<RegisterBookmark>,
+    /// What was left out or approximated.
+    pub notes: Vec<String>,
+}
+
+/// Thresholds by rising temperature, one per temperature.
+fn sorted(thresholds: &[TemperatureThreshold]) -> Vec<TemperatureThreshold> {
+    let mut sorted = thresholds.to_vec();
+    sorted.sort_by_key(|t| t.up_threshold);
+    sorted.dedup_by_key(|t| t.up_threshold);
+    sorted
+}
+
+fn percent(speed: f32) -> u8 {
+    speed.round().clamp(0.0, 100.0) as u8
+}
+
+/// NBFC's steps sampled at seven evenly spaced temperatures.
+fn ec_curve(thresholds: &[TemperatureThreshold]) -> Option<Vec<CurvePoint>> {
+    let (first, last) = (thresholds.first()?, thresholds.last()?);
+    let span = (last.up_threshold - first.up_threshold) as usize;
+    Some(
+        (0..EC_CURVE_POINTS)
+            .map(|i| {
+                let temp = first.up_threshold + (span * i / (EC_CURVE_POINTS - 1)) as u8;
+                let step = thresholds
+                    .iter()
+                    .rev()
+                    .find(|t| t.up_threshold <= temp)
+                    .unwrap_or(first);
+                CurvePoint {
+                    temp,
+                    speed: percent(step.fan_speed),
+                }
+            })
+            .collect(),
+    )
+}
+
+fn software_curve(thresholds: &[TemperatureThreshold]) -> Option<SoftwareCurve> {
+    if thresholds.len() < 2 {
+        return None;
+    }
+    // Evenly picked when there are more thresholds than points
+    let count = thresholds.len().min(SOFTWARE_CURVE_POINTS);
+    let points = (0..count)
+        .map(|i| {
+            let t = &thresholds[i * (thresholds.len() - 1) / (count - 1)];
+            CurvePoint {
+                temp: t.up_threshold,
+                speed: percent(t.fan_speed),
+            }
+        })
+        .collect();
+    let hysteresis = thresholds
+        .iter()
+        .map(|t| t.up_threshold.saturating_sub(t.down_threshold))
+        .max()
+        .unwrap_or(0);
+    Some(SoftwareCurve {
+        points,
+        hysteresis,
+        ramp_rate: RAMP_RATE,
+    })
+}
+
+fn truncate(label: String) -> String {
+    match label.char_indices().nth(MAX_LABEL_LEN) {
+        Some((end, _)) => label[..end].to_string(),
+        None => label,
+    }
+}
+
+/// Bookmark `offset` unless it is taken or outside the EC.
+fn bookmark(
+    bookmarks: &mut Vec<RegisterBookmark>,
+    notes: &mut Vec<String>,
+    offset: u16,
+    label: String,
+    watch: Option<String>,
+) {
+    let Ok(offset) = u8::try_from(offset) else {
+        notes.push(format!(
+            "Register {} is outside the EC and was skipped",
+            offset
+        ));
+        return;
+    };
+    if !bookmarks.iter().any(|b| b.offset == offset) {
+        bookmarks.push(RegisterBookmark {
+            offset,
+            label: truncate(format!("NBFC: {}", label)),
+            watch,
+        });
+    }
+}
+
+fn translate(config: Config) -> NbfcTranslation {
+    let mut notes = Vec::new();
+    let mut bookmarks = Vec::new();
+
+    let fans = &config.fan_configurations.fans;
+    for (i, fan) in fans.iter().enumerate() {
+        let name = fan
+            .fan_display_name
+            .clone()
+            .unwrap_or_else(|| format!("fan {}", i + 1));
+        let registers = if fan.read_register == fan.write_register {
+            vec![(fan.read_register, format!("{} speed", name))]
+        } else {
+            vec![
+                (fan.read_register, format!("{} speed (read)", name)),
+                (fan.write_register, format!("{} speed (write)", name)),
+            ]
+        };
+        for (offset, label) in registers {
+            bookmark(&mut bookmarks, &mut notes, offset, label, None);
+        }
+    }
+    if fans.len() > 2 {
+        notes.push(format!(
+            "{} fans are configured; curves use the first two",
+            fans.len()
+        ));
+    }
+    if config.read_write_words {
+        notes.push("Fan registers are 16-bit; bookmarks mark their first byte".to_string());
+    }
+
+    for write in &config.register_write_configurations.writes {
+        let label = write.description.clone().unwrap_or_else(|| {
+            format!(
+                "{} {} {}",
+                write.write_occasion, write.write_mode, write.value
+            )
+        });
+        // A plain set can be checked; And/Or masks can't without the old value
+        let watch = (write.write_mode == "Set" && write.value <= u8::MAX as u16)
+            .then(|| format!("== 0x{:02X}", write.value));
+        if write.write_mode != "Set" {
+            notes.push(format!(
+                "Register {} is written with {} 0x{:X} {}; not translated",
+                write.register, write.write_mode, write.value, write.write_occasion
+            ));
+        }
+        bookmark(&mut bookmarks, &mut notes, write.register, label, watch);
+    }
+
+    let thresholds: Vec<Vec<TemperatureThreshold>> = fans
+        .iter()
+        .take(2)
+        .map(|fan| sorted(&fan.temperature_thresholds.thresholds))
+        .collect();
+    let cpu = thresholds.first().and_then(|t| ec_curve(t));
+    let gpu = thresholds.get(1).and_then(|t| ec_curve(t));
+    let curve = match (cpu, gpu) {
+        (Some(cpu), Some(gpu)) => Some(FanCurve { cpu, gpu }),
+        (Some(cpu), None) => Some(FanCurve {
+            gpu: cpu.clone(),
+            cpu,
+        }),
+        _ => None,
+    };
+    if thresholds.iter().any(|t| t.len() != EC_CURVE_POINTS) {
+        notes.push(format!(
+            "The EC curve samples NBFC's thresholds at {} temperatures",
+            EC_CURVE_POINTS
+        ));
+    }
+
+    NbfcTranslation {
+        model: config.notebook_model,
+        curve,
+        software_curve: thresholds.first().and_then(|t| software_curve(t)),
+        failsafe_max_temp: config.critical_temperature,
+        bookmarks,
+        notes,
+    }
+}
+
+fn parse(content: &str) -> Result<NbfcTranslation, String> {
+    let config: Config = quick_xml::de::from_str(content).map_err(|e| e.to_string())?;
+    Ok(translate(config))
+}
+
+/// Parse an NBFC model config (its `FanControlConfigV2` XML) and translate
+/// it. Nothing is applied; each part goes to the command named on it.
+#[tauri::command]
+pub async fn import_nbfc_config(app: AppHandle, path: String) -> Result<NbfcTranslation, String> {
+    permissions::check(&app, Tier::Control)?;
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    parse(&content).map_err(|e| format!("{} is not an NBFC config: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threshold(up: u8, down: u8, speed: f32) -> String {
+        format!(
+            "<TemperatureThreshold><UpThreshold>{}</UpThreshold>\
+             <DownThreshold>{}</DownThreshold><FanSpeed>{}</FanSpeed></TemperatureThreshold>",
+            up, down, speed
+        )
+    }
+
+    fn fan(register: u16, thresholds: &[(u8, u8, f32)]) -> String {
+        let thresholds: String = thresholds
+            .iter()
+            .map(|&(up, down, speed)| threshold(up, down, speed))
+            .collect();
+        format!(
+            "<FanConfiguration><ReadRegister>{}</ReadRegister>\
+             <WriteRegister>{}</WriteRegister>\
+             <TemperatureThresholds>{}</TemperatureThresholds></FanConfiguration>",
+            register, register, thresholds
+        )
+    }
+
+    fn config(fans: &[String], extra: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\"?><FanControlConfigV2>\
+             <NotebookModel>MSI GF65</NotebookModel>{}\
+             <FanConfigurations>{}</FanConfigurations></FanControlConfigV2>",
+            extra,
+            fans.concat()
+        )
+    }
+
+    const STEPS: &[(u8, u8, f32)] = &[
+        (0, 0, 0.0),
+        (60, 48, 40.0),
+        (70, 62, 60.5),
+        (80, 72, 80.0),
+        (90, 85, 100.0),
+    ];
+
+    #[test]
+    fn rejects_malformed_xml() {
+        assert!(parse("<FanControlConfigV2><NotebookModel>x").is_err());
+        assert!(parse("not xml at all").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_model() {
+        assert!(parse("<FanControlConfigV2></FanControlConfigV2>").is_err());
+    }
+
+    #[test]
+    fn rejects_temperature_out_of_range() {
+        let xml = config(&[fan(0x71, STEPS)], "").replace("<UpThreshold>90<", "<UpThreshold>300<");
+        assert!(parse(&xml).is_err());
+    }
+
+    #[test]
+    fn config_without_fans_has_no_curves() {
+        let translation = parse(&config(&[], "")).unwrap();
+        assert_eq!(translation.model, "MSI GF65");
+        assert!(translation.curve.is_none());
+        assert!(translation.software_curve.is_none());
+        assert!(translation.bookmarks.is_empty());
+    }
+
+    #[test]
+    fn single_threshold_gives_no_software_curve() {
+        let translation = parse(&config(&[fan(0x71, &[(50, 40, 30.0)])], "")).unwrap();
+        assert!(translation.software_curve.is_none());
+        let curve = translation.curve.unwrap();
+        assert!(curve.cpu.iter().all(|p| p.temp == 50 && p.speed == 30));
+    }
+
+    fn pairs(points: &[CurvePoint]) -> Vec<(u8, u8)> {
+        points.iter().map(|p| (p.temp, p.speed)).collect()
+    }
+
+    #[test]
+    fn ec_curve_samples_thresholds_at_their_boundaries() {
+        let translation = parse(&config(&[fan(0x71, STEPS)], "")).unwrap();
+        let curve = translation.curve.unwrap();
+        // 60 and 90 °C fall exactly on a threshold, 75 °C between two
+        assert_eq!(
+            pairs(&curve.cpu),
+            [
+                (0, 0),
+                (15, 0),
+                (30, 0),
+                (45, 0),
+                (60, 40),
+                (75, 61),
+                (90, 100)
+            ]
+        );
+        // Single-fan configs drive the GPU fan with the same curve
+        assert_eq!(pairs(&curve.gpu), pairs(&curve.cpu));
+    }
+
+    fn parse_steps(steps: &[(u8, u8, f32)]) -> Vec<TemperatureThreshold> {
+        steps
+            .iter()
+            .map(
+                |&(up_threshold, down_threshold, fan_speed)| TemperatureThreshold {
+                    up_threshold,
+                    down_threshold,
+                    fan_speed,
+                },
+            )
+            .collect()
+    }
+
+    #[test]
+    fn software_curve_keeps_widest_hysteresis() {
+        let translation = parse(&config(&[fan(0x71, STEPS)], "")).unwrap();
+        let curve = translation.software_curve.unwrap();
+        assert_eq!(curve.points.len(), STEPS.len());
+        assert_eq!(curve.hysteresis, 12);
+        assert_eq!(curve.points[2].speed, 61);
+    }
+
+    #[test]
+    fn unsorted_and_duplicate_thresholds_are_ordered() {
+        let steps = [(80, 70, 80.0), (0, 0, 0.0), (80, 75, 90.0), (60, 50, 40.0)];
+        let sorted = sorted(&parse_steps(&steps));
+        let temps: Vec<u8> = sorted.iter().map(|t| t.up_threshold).collect();
+        assert_eq!(temps, [0, 60, 80]);
+    }
+
+    #[test]
+    fn fan_speeds_are_clamped_to_percent() {
+        assert_eq!(percent(-5.0), 0);
+        assert_eq!(percent(49.5), 50);
+        assert_eq!(percent(120.0), 100);
+    }
+
+    #[test]
+    fn registers_outside_ec_are_noted() {
+        let xml = config(&[fan(0x71, STEPS), fan(0x1FF, STEPS)], "");
+        let translation = parse(&xml).unwrap();
+        assert_eq!(translation.bookmarks.len(), 1);
+        assert!(translation.notes.iter().any(|n| n.contains("511")));
+    }
+
+    #[test]
+    fn register_writes_become_bookmarks() {
+        let writes = "<CriticalTemperature>95</CriticalTemperature>\
+            <RegisterWriteConfigurations>\
+            <RegisterWriteConfiguration><WriteMode>Set</WriteMode>\
+            <WriteOccasion>OnInitialization</WriteOccasion>\
+            <Register>244</Register><Value>140</Value>\
+            <Description>Fan mode</Description></RegisterWriteConfiguration>\
+            <RegisterWriteConfiguration><WriteMode>Or</WriteMode>\
+            <WriteOccasion>OnWriteFanSpeed</WriteOccasion>\
+            <Register>152</Register><Value>128</Value></RegisterWriteConfiguration>\
+            </RegisterWriteConfigurations>";
+        let translation = parse(&config(&[fan(0x71, STEPS)], writes)).unwrap();
+        assert_eq!(translation.failsafe_max_temp, Some(95));
+        let set = translation
+            .bookmarks
+            .iter()
+            .find(|b| b.offset == 244)
+            .unwrap();
+        assert_eq!(set.label, "NBFC: Fan mode");
+        assert_eq!(set.watch.as_deref(), Some("== 0x8C"));
+        let masked = translation
+            .bookmarks
+            .iter()
+            .find(|b| b.offset == 152)
+            .unwrap();
+        assert!(masked.watch.is_none());
+        assert!(translation.notes.iter().any(|n| n.contains("Or 0x80")));
+    }
+
+    #[test]
+    fn long_labels_are_truncated() {
+        let label = truncate("é".repeat(MAX_LABEL_LEN + 10));
+        assert_eq!(label.chars().count(), MAX_LABEL_LEN);
+    }
+}
@@ -0,0 +1,382 @@
+//! Alerts for thermal trouble: a CPU or GPU temperature crossing the
+//! warning or critical threshold, the sidecar connection dropping, and the
+//! fan anomalies found by `anomaly.rs` (a stalled fan, one spinning slower
+//! than it used to or swinging up and down).
+//!
+//! Fed by every status push, so they also fire with the window hidden.
+//! A temperature alert repeats only after the sensor cooled below the
+//! threshold again, and no alert repeats within five minutes. Each alert
+//! goes to the sinks routed for its kind (see `sinks.rs`).
+
+mod sinks;
+
+use crate::anomaly::{Anomaly, AnomalyKind};
+use crate::format::{format_value, NumberFormat, Sensor};
+use crate::history::HistorySample;
+use crate::permissions::{self, Tier};
+use crate::settings;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sinks::Notifier;
+pub use sinks::SinkConfig;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+const REPEAT_AFTER: Duration = Duration::from_secs(5 * 60);
+/// How far a temperature has to fall below a threshold before crossing it
+/// counts again, so one hovering around it doesn't keep notifying.
+const HYSTERESIS_CELSIUS: u8 = 5;
+const MAX_TEMP: u8 = 110;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    TempWarn,
+    TempCritical,
+    FanStalled,
+    /// A fan slower than usual at its duty, or oscillating.
+    FanAnomaly,
+    ConnectionLost,
+}
+
+const ALL_ALERTS: [AlertKind; 5] = [
+    AlertKind::TempWarn,
+    AlertKind::TempCritical,
+    AlertKind::FanStalled,
+    AlertKind::FanAnomaly,
+    AlertKind::ConnectionLost,
+];
+
+/// What a sink is handed; also the JSON body of webhook and MQTT alerts.
+#[derive(Debug, Serialize, Clone)]
+pub(crate) struct Alert {
+    pub kind: AlertKind,
+    pub title: String,
+    pub body: String,
+}
+
+/// One sink and the alert kinds sent to it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+pub struct SinkRoute {
+    pub sink: SinkConfig,
+    pub alerts: Vec<AlertKind>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// °C, for both CPU and GPU.
+    pub warn_temp: u8,
+    pub critical_temp: u8,
+    /// A fan at 0 RPM is reported once its chip is this hot.
+    pub stalled_fan_temp: u8,
+    pub routes: Vec<SinkRoute>,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            enabled: true,
+            warn_temp: 85,
+            critical_temp: 95,
+            stalled_fan_temp: 70,
+            routes: vec![
+                SinkRoute {
+                    sink: SinkConfig::Desktop,
+                    alerts: ALL_ALERTS.to_vec(),
+                },
+                SinkRoute {
+                    sink: SinkConfig::TrayBadge,
+                    alerts: ALL_ALERTS.to_vec(),
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+enum Level {
+    #[default]
+    Normal,
+    Warn,
+    Critical,
+}
+
+type Sinks = Vec<(Arc<dyn Notifier>, Vec<AlertKind>)>;
+
+/// Settings and their sinks, loaded together.
+struct Config {
+    settings: NotificationSettings,
+    sinks: Sinks,
+}
+
+impl Config {
+    fn new(settings: NotificationSettings) -> Self {
+        let sinks = settings
+            .routes
+            .iter()
+            .map(|route| (Arc::from(sinks::build(&route.sink)), route.alerts.clone()))
+            .collect();
+        Config { settings, sinks }
+    }
+}
+
+#[derive(Default)]
+struct AlertEngine {
+    /// Loaded on first use, replaced by `set_notification_settings`.
+    config: Option<Config>,
+    /// Last alerted level of the CPU and GPU.
+    levels: [Level; 2],
+    last_sent: HashMap<&'static str, Instant>,
+}
+
+#[derive(Default)]
+pub(crate) struct NotificationState(Mutex<AlertEngine>);
+
+impl AlertEngine {
+    fn config(&mut self) -> &Config {
+        self.config.get_or_insert_with(|| {
+            Config::new(
+                settings::load_settings()
+                    .map(|s| s.notifications)
+                    .unwrap_or_default(),
+            )
+        })
+    }
+
+    /// Whether an alert under `key` may be sent now.
+    fn allow(&mut self, key: &'static str) -> bool {
+        let now = Instant::now();
+        if self
+            .last_sent
+            .get(key)
+            .is_some_and(|sent| now.duration_since(*sent) < REPEAT_AFTER)
+        {
+            return false;
+        }
+        self.last_sent.insert(key, now);
+        true
+    }
+
+    /// Track one sensor's level; returns it when it went up.
+    fn raise(&mut self, sensor: usize, temp: u8, config: &NotificationSettings) -> Option<Level> {
+        let level = if temp >= config.critical_temp {
+            Level::Critical
+        } else if temp >= config.warn_temp {
+            Level::Warn
+        } else {
+            Level::Normal
+        };
+        let previous = self.levels[sensor];
+        if level > previous {
+            self.levels[sensor] = level;
+            return Some(level);
+        }
+        // Step down only once clearly below the threshold
+        let floor = match previous {
+            Level::Critical => config.critical_temp,
+            Level::Warn => config.warn_temp,
+            Level::Normal => return None,
+        };
+        if temp.saturating_add(HYSTERESIS_CELSIUS) <= floor {
+            self.levels[sensor] = level;
+        }
+        None
+    }
+
+    /// Sinks routed for `kind`.
+    fn sinks_for(&mut self, kind: AlertKind) -> Vec<Arc<dyn Notifier>> {
+        self.config()
+            .sinks
+            .iter()
+            .filter(|(_, alerts)| alerts.contains(&kind))
+            .map(|(sink, _)| sink.clone())
+            .collect()
+    }
+}
+
+/// Hand alerts to their sinks off the calling thread.
+fn dispatch(app: &AppHandle, deliveries: Vec<(Alert, Vec<Arc<dyn Notifier>>)>) {
+    if deliveries.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        for (alert, sinks) in deliveries {
+            for sink in sinks {
+                if let Err(e) = sink.send(&app, &alert) {
+                    eprintln!("Failed to send alert: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Check a status sample against the thresholds.
+pub(crate) fn observe(app: &AppHandle, sample: &HistorySample) {
+    let mut deliveries = Vec::new();
+    let mut resolved = Vec::new();
+    {
+        let state = app.state::<NotificationState>();
+        let Ok(mut engine) = state.0.lock() else {
+            return;
+        };
+        let config = engine.config().settings.clone();
+        if !config.enabled {
+            return;
+        }
+
+        let sensors = [("CPU", sample.cpu_temp), ("GPU", sample.gpu_temp)];
+        let was_alerting = engine.levels.iter().any(|&l| l != Level::Normal);
+        for (sensor, &(name, temp)) in sensors.iter().enumerate() {
+            let alert = match engine.raise(sensor, temp, &config) {
+                Some(Level::Critical) => Some((
+                    ["cpu_critical", "gpu_critical"][sensor],
+                    AlertKind::TempCritical,
+                    format!("{} temperature critical", name),
+                )),
+                Some(Level::Warn) => Some((
+                    ["cpu_warn", "gpu_warn"][sensor],
+                    AlertKind::TempWarn,
+                    format!("{} running hot", name),
+                )),
+                _ => None,
+            };
+            if let Some((key, kind, title)) = alert {
+                if engine.allow(key) {
+                    let alert = Alert {
+                        kind,
+                        title,
+                        body: format!(
+                            "{} is at {}",
+                            name,
+                            format_value(
+                                Sensor::Temperature,
+                                temp as f64,
+                                &NumberFormat::for_user()
+                            )
+                        ),
+                    };
+                    deliveries.push((alert, engine.sinks_for(kind)));
+                }
+            }
+        }
+        if was_alerting && engine.levels.iter().all(|&l| l == Level::Normal) {
+            resolved = engine
+                .config()
+                .sinks
+                .iter()
+                .map(|(s, _)| s.clone())
+                .collect();
+        }
+    }
+    for sink in resolved {
+        sink.clear(app);
+    }
+    dispatch(app, deliveries);
+}
+
+/// Temperature from which a fan at 0 RPM counts as stalled.
+pub(crate) fn stalled_fan_temp(app: &AppHandle) -> u8 {
+    let state = app.state::<NotificationState>();
+    let Ok(mut engine) = state.0.lock() else {
+        return NotificationSettings::default().stalled_fan_temp;
+    };
+    engine.config().settings.stalled_fan_temp
+}
+
+/// Alert on a fan anomaly: a stall as `FanStalled`, the rest as
+/// `FanAnomaly`.
+pub(crate) fn fan_anomaly(app: &AppHandle, anomaly: &Anomaly) {
+    let (key, kind, title) = match anomaly.kind {
+        AnomalyKind::Fan1Stalled => ("fan1_stalled", AlertKind::FanStalled, "CPU fan stopped"),
+        AnomalyKind::Fan2Stalled => ("fan2_stalled", AlertKind::FanStalled, "GPU fan stopped"),
+        AnomalyKind::Fan1RpmDrop | AnomalyKind::Fan1Oscillating => (
+            "fan1_anomaly",
+            AlertKind::FanAnomaly,
+            "CPU fan may be failing",
+        ),
+        AnomalyKind::Fan2RpmDrop | AnomalyKind::Fan2Oscillating => (
+            "fan2_anomaly",
+            AlertKind::FanAnomaly,
+            "GPU fan may be failing",
+        ),
+        AnomalyKind::CpuTempSpike | AnomalyKind::GpuTempSpike | AnomalyKind::IdleTempCreep => {
+            return
+        }
+    };
+    let state = app.state::<NotificationState>();
+    let Ok(mut engine) = state.0.lock() else {
+        return;
+    };
+    if !engine.config().settings.enabled || !engine.allow(key) {
+        return;
+    }
+    let sinks = engine.sinks_for(kind);
+    drop(engine);
+    let alert = Alert {
+        kind,
+        title: title.to_string(),
+        body: anomaly.message.clone(),
+    };
+    dispatch(app, vec![(alert, sinks)]);
+}
+
+/// The sidecar went away while the user wanted it connected.
+pub(crate) fn connection_lost(app: &AppHandle) {
+    let state = app.state::<NotificationState>();
+    let Ok(mut engine) = state.0.lock() else {
+        return;
+    };
+    if !engine.config().settings.enabled || !engine.allow("connection_lost") {
+        return;
+    }
+    let sinks = engine.sinks_for(AlertKind::ConnectionLost);
+    drop(engine);
+    let alert = Alert {
+        kind: AlertKind::ConnectionLost,
+        title: "Fan control disconnected".to_string(),
+        body: "Lost the connection to the sidecar; reconnecting".to_string(),
+    };
+    dispatch(app, vec![(alert, sinks)]);
+}
+
+#[tauri::command]
+pub async fn get_notification_settings() -> Result<NotificationSettings, String> {
+    Ok(settings::load_settings()?.notifications)
+}
+
+#[tauri::command]
+pub async fn set_notification_settings(
+    app: AppHandle,
+    state: State<'_, NotificationState>,
+    notifications: NotificationSettings,
+) -> Result<NotificationSettings, String> {
+    permissions::check(&app, Tier::Control)?;
+    if notifications.warn_temp >= notifications.critical_temp {
+        return Err("The warning temperature must be below the critical one".to_string());
+    }
+    if notifications.critical_temp > MAX_TEMP || notifications.stalled_fan_temp > MAX_TEMP {
+        return Err(format!("Temperatures must be at most {} °C", MAX_TEMP));
+    }
+    let mut settings = settings::load_settings()?;
+    settings.notifications = notifications.clone();
+    settings::save_settings(&settings)?;
+    if let Ok(mut engine) = state.0.lock() {
+        // Drop a badge the new routes no longer manage
+        if let Some(old) = engine.config.take() {
+            for (sink, _) in old.sinks {
+                sink.clear(&app);
+            }
+        }
+        engine.config = Some(Config::new(notifications.clone()));
+    }
+    Ok(notifications)
+}
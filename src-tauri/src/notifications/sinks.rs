@@ -0,0 +1,212 @@
+//! Where alerts go. Each sink implements `Notifier`; adding one means a
+//! `SinkConfig` variant and a match arm in `build`, nothing in the alert
+//! engine.
+//!
+//! Network sinks shell out to `curl` and `mosquitto_pub` rather than
+//! pulling an HTTP and MQTT stack into the app. They run on a blocking
+//! thread, so a slow endpoint doesn't hold up status pushes.
+//! Alert text and configured addresses are passed so that neither program
+//! can take them for an option or, in curl's case, an `@file` to upload.
+
+use super::{Alert, AlertKind};
+use crate::tray;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use ts_rs::TS;
+
+const NTFY_SERVER: &str = "https://ntfy.sh";
+const MQTT_PORT: u16 = 1883;
+/// Seconds before a network sink gives up.
+const TIMEOUT_SECS: &str = "10";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// Desktop notification through the notification daemon.
+    Desktop,
+    /// A badge on the tray icon, with the alert in its tooltip.
+    TrayBadge,
+    /// The alert as JSON, POSTed to `url`.
+    Webhook { url: String },
+    /// A push through an ntfy server (ntfy.sh by default).
+    Ntfy {
+        #[serde(default)]
+        server: Option<String>,
+        topic: String,
+    },
+    /// A push through a self-hosted Gotify server, with an application
+    /// token created there.
+    Gotify { server: String, token: String },
+    /// The alert as JSON, published to an MQTT broker.
+    Mqtt {
+        host: String,
+        #[serde(default)]
+        port: Option<u16>,
+        topic: String,
+    },
+}
+
+pub(crate) trait Notifier: Send + Sync {
+    fn send(&self, app: &AppHandle, alert: &Alert) -> Result<(), String>;
+    /// Every alert condition cleared. Only sinks that show a lasting state
+    /// need this.
+    fn clear(&self, _app: &AppHandle) {}
+}
+
+pub(crate) fn build(config: &SinkConfig) -> Box<dyn Notifier> {
+    match config.clone() {
+        SinkConfig::Desktop => Box::new(Desktop),
+        SinkConfig::TrayBadge => Box::new(TrayBadge),
+        SinkConfig::Webhook { url } => Box::new(Webhook { url }),
+        SinkConfig::Ntfy { server, topic } => Box::new(Ntfy {
+            server: server.unwrap_or_else(|| NTFY_SERVER.to_string()),
+            topic,
+        }),
+        SinkConfig::Gotify { server, token } => Box::new(Gotify { server, token }),
+        SinkConfig::Mqtt { host, port, topic } => Box::new(Mqtt {
+            host,
+            port: port.unwrap_or(MQTT_PORT),
+            topic,
+        }),
+    }
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse a setting that the program could read as one of its options.
+fn not_an_option(name: &str, value: &str) -> Result<(), String> {
+    if value.starts_with('-') {
+        return Err(format!("{} can't start with '-': {}", name, value));
+    }
+    Ok(())
+}
+
+fn alert_json(alert: &Alert) -> Result<String, String> {
+    serde_json::to_string(alert).map_err(|e| e.to_string())
+}
+
+struct Desktop;
+
+impl Notifier for Desktop {
+    fn send(&self, app: &AppHandle, alert: &Alert) -> Result<(), String> {
+        app.notification()
+            .builder()
+            .title(&alert.title)
+            .body(&alert.body)
+            .show()
+            .map_err(|e| e.to_string())
+    }
+}
+
+struct TrayBadge;
+
+impl Notifier for TrayBadge {
+    fn send(&self, app: &AppHandle, alert: &Alert) -> Result<(), String> {
+        tray::set_alert(app, Some(alert.title.clone()));
+        Ok(())
+    }
+
+    fn clear(&self, app: &AppHandle) {
+        tray::set_alert(app, None);
+    }
+}
+
+struct Webhook {
+    url: String,
+}
+
+impl Notifier for Webhook {
+    fn send(&self, _app: &AppHandle, alert: &Alert) -> Result<(), String> {
+        run(Command::new("curl")
+            .args(["-sSf", "-m", TIMEOUT_SECS, "-X", "POST"])
+            .args(["-H", "Content-Type: application/json"])
+            .args(["--data-raw", &alert_json(alert)?])
+            .args(["--url", &self.url]))
+    }
+}
+
+struct Ntfy {
+    server: String,
+    topic: String,
+}
+
+impl Notifier for Ntfy {
+    fn send(&self, _app: &AppHandle, alert: &Alert) -> Result<(), String> {
+        let priority = match alert.kind {
+            AlertKind::TempCritical | AlertKind::FanStalled => "urgent",
+            _ => "default",
+        };
+        run(Command::new("curl")
+            .args(["-sSf", "-m", TIMEOUT_SECS])
+            .args(["-H", &format!("Title: {}", alert.title)])
+            .args(["-H", &format!("Priority: {}", priority)])
+            .args(["--data-raw", &alert.body])
+            .arg("--url")
+            .arg(format!(
+                "{}/{}",
+                self.server.trim_end_matches('/'),
+                self.topic
+            )))
+    }
+}
+
+struct Gotify {
+    server: String,
+    token: String,
+}
+
+impl Notifier for Gotify {
+    fn send(&self, _app: &AppHandle, alert: &Alert) -> Result<(), String> {
+        let priority = match alert.kind {
+            AlertKind::TempCritical | AlertKind::FanStalled => 8,
+            _ => 5,
+        };
+        let message = serde_json::json!({
+            "title": alert.title,
+            "message": alert.body,
+            "priority": priority,
+        });
+        run(Command::new("curl")
+            .args(["-sSf", "-m", TIMEOUT_SECS, "-X", "POST"])
+            .args(["-H", "Content-Type: application/json"])
+            .args(["-H", &format!("X-Gotify-Key: {}", self.token)])
+            .args(["--data-raw", &message.to_string()])
+            .arg("--url")
+            .arg(format!("{}/message", self.server.trim_end_matches('/'))))
+    }
+}
+
+struct Mqtt {
+    host: String,
+    port: u16,
+    topic: String,
+}
+
+impl Notifier for Mqtt {
+    fn send(&self, _app: &AppHandle, alert: &Alert) -> Result<(), String> {
+        not_an_option("MQTT host", &self.host)?;
+        not_an_option("MQTT topic", &self.topic)?;
+        run(Command::new("mosquitto_pub")
+            .args(["-h", &self.host])
+            .args(["-p", &self.port.to_string()])
+            .args(["-t", &self.topic])
+            .args(["-m", &alert_json(alert)?]))
+    }
+}
@@ -0,0 +1,66 @@
+//! Which onboarding steps the user has completed, kept in settings so the
+//! frontend doesn't have to track it in localStorage.
+
+use crate::settings;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    FirstConnect,
+    FirstProfileSaved,
+    CalibrationCompleted,
+}
+
+const ALL_STEPS: [OnboardingStep; 3] = [
+    OnboardingStep::FirstConnect,
+    OnboardingStep::FirstProfileSaved,
+    OnboardingStep::CalibrationCompleted,
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct OnboardingState {
+    pub completed: Vec<OnboardingStep>,
+    /// Steps still to do, in the order the frontend should suggest them.
+    pub pending: Vec<OnboardingStep>,
+}
+
+fn state_from(completed: Vec<OnboardingStep>) -> OnboardingState {
+    let pending = ALL_STEPS
+        .iter()
+        .filter(|step| !completed.contains(step))
+        .copied()
+        .collect();
+    OnboardingState { completed, pending }
+}
+
+fn mark(step: OnboardingStep) -> Result<OnboardingState, String> {
+    let mut current = settings::load_settings()?;
+    if !current.onboarding.contains(&step) {
+        current.onboarding.push(step);
+        settings::save_settings(&current)?;
+    }
+    Ok(state_from(current.onboarding))
+}
+
+/// Record a step the backend observed itself. Best effort: onboarding must
+/// never fail the action that completed it.
+pub(crate) fn mark_done(step: OnboardingStep) {
+    if let Err(e) = mark(step) {
+        eprintln!("Failed to record onboarding step: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn get_onboarding_state() -> Result<OnboardingState, String> {
+    Ok(state_from(settings::load_settings()?.onboarding))
+}
+
+#[tauri::command]
+pub async fn mark_step_done(step: OnboardingStep) -> Result<OnboardingState, String> {
+    mark(step)
+}
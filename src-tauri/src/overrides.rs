@@ -0,0 +1,99 @@
+//! Tracks manual overrides of the firmware's fan control (a fixed fan speed
+//! or Cooler Boost) and how long they have been on, for the tray.
+//!
+//! Fixed speeds are recorded when a write succeeds and dropped when another
+//! mode is written or a status push shows the EC left Advanced mode. Cooler
+//! Boost follows the pushed status only, so the Fn key and other tools are
+//! picked up too. The tray label is refreshed every scheduler tick.
+
+use crate::tray;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+#[derive(Default)]
+struct Overrides {
+    fixed_speed: Option<(u8, Instant)>,
+    boost_since: Option<Instant>,
+}
+
+#[derive(Default)]
+pub(crate) struct OverrideState(Mutex<Overrides>);
+
+fn update(app: &AppHandle, change: impl FnOnce(&mut Overrides)) {
+    if let Ok(mut overrides) = app.state::<OverrideState>().0.lock() {
+        change(&mut overrides);
+    }
+    refresh(app);
+}
+
+/// A flat fan speed was written.
+pub(crate) fn fixed_speed_set(app: &AppHandle, percent: u8) {
+    update(app, |o| {
+        // Keep the start time when only the speed changes
+        let since = o.fixed_speed.map_or_else(Instant::now, |(_, since)| since);
+        o.fixed_speed = Some((percent, since));
+    });
+}
+
+/// A fan mode or curve replaced any fixed speed.
+pub(crate) fn fixed_speed_cleared(app: &AppHandle) {
+    update(app, |o| o.fixed_speed = None);
+}
+
+/// Fixed duty in effect and for how many milliseconds, if any.
+pub(crate) fn fixed_speed(app: &AppHandle) -> Option<(u8, u64)> {
+    let overrides = app.state::<OverrideState>();
+    let overrides = overrides.0.lock().ok()?;
+    overrides
+        .fixed_speed
+        .map(|(percent, since)| (percent, since.elapsed().as_millis() as u64))
+}
+
+/// Reconcile with a status reading. `fan_mode` is `None` for lite pushes.
+pub(crate) fn on_status(app: &AppHandle, cooler_boost: bool, fan_mode: Option<&str>) {
+    update(app, |o| {
+        if cooler_boost {
+            o.boost_since.get_or_insert_with(Instant::now);
+        } else {
+            o.boost_since = None;
+        }
+        if fan_mode.is_some_and(|mode| mode != "advanced") {
+            o.fixed_speed = None;
+        }
+    });
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let mins = elapsed.as_secs() / 60;
+    if mins < 60 {
+        format!("{} min", mins)
+    } else {
+        format!("{} h {} min", mins / 60, mins % 60)
+    }
+}
+
+/// e.g. "Cooler Boost on for 12 min", `None` when the firmware is in charge.
+pub(crate) fn label(app: &AppHandle) -> Option<String> {
+    let overrides = app.state::<OverrideState>();
+    let overrides = overrides.0.lock().ok()?;
+    let mut parts = Vec::new();
+    if let Some(since) = overrides.boost_since {
+        parts.push(format!(
+            "Cooler Boost on for {}",
+            format_elapsed(since.elapsed())
+        ));
+    }
+    if let Some((percent, since)) = overrides.fixed_speed {
+        parts.push(format!(
+            "Fans fixed at {}% for {}",
+            percent,
+            format_elapsed(since.elapsed())
+        ));
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+pub(crate) fn refresh(app: &AppHandle) {
+    tray::set_override(app, label(app).as_deref());
+}
@@ -0,0 +1,159 @@
+//! Permission tiers for commands, so a shared machine can offer monitoring
+//! without letting everyone change fan settings or poke at the EC.
+//!
+//! `Settings::max_tier` caps what the UI and tray may do, and tiers listed in
+//! `Settings::confirm_tiers` additionally need a confirmation (through
+//! `confirm_permission_tier`) that stays valid for a minute. This guards the
+//! app's own surface; it is not a substitute for the Polkit policy.
+
+use crate::settings;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+/// How long a confirmation covers further commands of the same tier.
+const CONFIRMATION_TTL: Duration = Duration::from_secs(60);
+
+/// Error prefix the frontend matches to ask for confirmation and retry.
+const CONFIRMATION_REQUIRED: &str = "Confirmation required";
+
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    JsonSchema,
+    TS,
+)]
+#[ts(export, rename = "PermissionTier")]
+#[serde(rename_all = "snake_case")]
+pub enum Tier {
+    /// Reading temperatures, fan speeds and history. Always allowed.
+    Monitor,
+    /// Fan modes, speeds, curves, Cooler Boost, profiles, keyboard backlight.
+    Control,
+    /// Raw EC access and safety overrides: EC dumps, experimental write
+    /// opt-ins, duties above the model's limit.
+    #[default]
+    Expert,
+}
+
+impl Tier {
+    fn describe(self) -> &'static str {
+        match self {
+            Tier::Monitor => "monitoring",
+            Tier::Control => "fan control",
+            Tier::Expert => "expert EC access",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct PermissionSettings {
+    pub max_tier: Tier,
+    pub confirm_tiers: Vec<Tier>,
+}
+
+#[derive(Default)]
+pub(crate) struct PermissionState {
+    confirmed: Mutex<HashMap<Tier, Instant>>,
+}
+
+/// Fail unless commands of `tier` are allowed right now.
+pub(crate) fn check(app: &AppHandle, tier: Tier) -> Result<(), String> {
+    if tier == Tier::Monitor {
+        return Ok(());
+    }
+    let settings = settings::load_settings()?;
+    if tier > settings.max_tier {
+        return Err(format!(
+            "{} is disabled in settings",
+            capitalize(tier.describe())
+        ));
+    }
+    if settings.confirm_tiers.contains(&tier) {
+        require_confirmation(app, tier)?;
+    }
+    Ok(())
+}
+
+/// Fail unless `tier` was confirmed within the last minute.
+fn require_confirmation(app: &AppHandle, tier: Tier) -> Result<(), String> {
+    let state = app.state::<PermissionState>();
+    let confirmed = state
+        .confirmed
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&tier)
+        .is_some_and(|at| at.elapsed() < CONFIRMATION_TTL);
+    if !confirmed {
+        return Err(format!("{}: {}", CONFIRMATION_REQUIRED, tier.describe()));
+    }
+    Ok(())
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_permission_settings() -> Result<PermissionSettings, String> {
+    let settings = settings::load_settings()?;
+    Ok(PermissionSettings {
+        max_tier: settings.max_tier,
+        confirm_tiers: settings.confirm_tiers,
+    })
+}
+
+/// Tightening the gate is always allowed. Loosening it needs an expert
+/// confirmation whatever `max_tier` is, so lowering it can't lock the
+/// user out of raising it again.
+#[tauri::command]
+pub async fn set_permission_settings(
+    app: AppHandle,
+    permissions: PermissionSettings,
+) -> Result<PermissionSettings, String> {
+    let mut settings = settings::load_settings()?;
+    let loosens = permissions.max_tier > settings.max_tier
+        || settings
+            .confirm_tiers
+            .iter()
+            .any(|tier| !permissions.confirm_tiers.contains(tier));
+    if loosens {
+        require_confirmation(&app, Tier::Expert)?;
+    }
+    settings.max_tier = permissions.max_tier;
+    settings.confirm_tiers = permissions.confirm_tiers.clone();
+    settings::save_settings(&settings)?;
+    Ok(permissions)
+}
+
+/// The user confirmed an action of `tier`; allow that tier for a minute.
+#[tauri::command]
+pub async fn confirm_permission_tier(
+    state: State<'_, PermissionState>,
+    tier: Tier,
+) -> Result<(), String> {
+    state
+        .confirmed
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(tier, Instant::now());
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Getting the sidecar started as root.
+//!
+//! The sidecar runs through pkexec under the `org.msifancontrol.run-sidecar`
+//! action, whose `auth_admin_keep` default asks for the password once and
+//! then reuses the authorization for the session. The deb package installs
+//! the policy; `install_polkit_policy` writes it for a sidecar installed
+//! elsewhere. pkexec only applies the action to the exact path in the
+//! policy, so the policy names the sidecar this app runs, and only one that
+//! nobody but root can replace: a cached authorization would otherwise run
+//! whatever binary a user put there as root. An AppImage mounts under
+//! `/tmp` and a dev build lives in the user's home, so neither qualifies;
+//! pkexec asks for the password on every connect for them. Systems without
+//! polkit fall back to sudo.
+
+use crate::permissions::{self, Tier};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use tauri::AppHandle;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+const POLICY_PATH: &str = "/usr/share/polkit-1/actions/org.msifancontrol.policy";
+const POLICY_TEMPLATE: &str = include_str!("../policy/org.msifancontrol.policy");
+/// Sidecar path in the bundled policy.
+const PACKAGED_SIDECAR_PATH: &str = "/usr/bin/msi-sidecar";
+
+fn find_program(name: &str) -> Option<PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// What starts the sidecar as root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Launcher {
+    Pkexec,
+    Sudo,
+}
+
+impl Launcher {
+    pub(crate) fn program(self) -> &'static str {
+        match self {
+            Launcher::Pkexec => "pkexec",
+            Launcher::Sudo => "sudo",
+        }
+    }
+
+    /// Why the sidecar exited before answering, told from the launcher's
+    /// exit status.
+    pub(crate) fn exit_error(self, status: ExitStatus) -> String {
+        match (self, status.code()) {
+            (Launcher::Pkexec, Some(126)) => {
+                "Authentication cancelled; connect again to enter the password".to_string()
+            }
+            (Launcher::Pkexec, Some(127)) => "Not authorized to start the sidecar: the password \
+                 was wrong or no polkit authentication agent is running"
+                .to_string(),
+            (Launcher::Sudo, Some(1)) => "sudo refused to start the sidecar: set SUDO_ASKPASS \
+                 or add a NOPASSWD sudoers rule for it"
+                .to_string(),
+            (_, Some(code)) => format!("The sidecar exited with code {} before answering", code),
+            (_, None) => "The sidecar was killed before answering".to_string(),
+        }
+    }
+}
+
+/// A command running `program` as root: pkexec when polkit is installed,
+/// sudo otherwise.
+pub(crate) fn elevated_command(program: &str) -> Result<(Command, Launcher), String> {
+    if find_program("pkexec").is_some() {
+        let mut command = Command::new(Launcher::Pkexec.program());
+        command.arg(program);
+        return Ok((command, Launcher::Pkexec));
+    }
+    if find_program("sudo").is_some() {
+        let mut command = Command::new(Launcher::Sudo.program());
+        // There is no terminal to prompt on: ask through SUDO_ASKPASS when
+        // one is configured, otherwise rely on a NOPASSWD rule
+        if std::env::var_os("SUDO_ASKPASS").is_some() {
+            command.arg("-A");
+        } else {
+            command.arg("-n");
+        }
+        command.arg(program);
+        return Ok((command, Launcher::Sudo));
+    }
+    Err(
+        "Neither pkexec nor sudo is installed to start the sidecar as root; install polkit"
+            .to_string(),
+    )
+}
+
+pub(crate) fn policy_installed() -> bool {
+    Path::new(POLICY_PATH).exists()
+}
+
+/// Whether `program`, a path or a name looked up in `PATH`, exists.
+pub(crate) fn program_exists(program: &str) -> bool {
+    if program.contains('/') {
+        Path::new(program).is_file()
+    } else {
+        find_program(program).is_some()
+    }
+}
+
+/// Why a policy for `path` would let someone other than root swap the
+/// binary it runs, if it would: the file or a directory above it is owned
+/// by another user, or writable by its group or everyone.
+pub(crate) fn unsafe_path_reason(path: &Path) -> Option<String> {
+    for part in path.ancestors() {
+        let meta = match std::fs::symlink_metadata(part) {
+            Ok(meta) => meta,
+            Err(e) => return Some(format!("Can't check {}: {}", part.display(), e)),
+        };
+        if meta.uid() != 0 {
+            return Some(format!("{} is not owned by root", part.display()));
+        }
+        if meta.mode() & 0o022 != 0 {
+            return Some(format!(
+                "{} is writable by users other than root",
+                part.display()
+            ));
+        }
+    }
+    None
+}
+
+/// Install the policy for the sidecar this app runs. pkexec asks for the
+/// admin password once to write it. Counts as expert access, as it lets
+/// the sidecar start without a password from then on.
+#[tauri::command]
+pub async fn install_polkit_policy(app: AppHandle) -> Result<String, String> {
+    permissions::check(&app, Tier::Expert)?;
+    crate::demo::refuse("Installing the polkit policy")?;
+    if find_program("pkexec").is_none() {
+        return Err(
+            "Polkit is not installed; the sidecar is started through sudo instead".to_string(),
+        );
+    }
+    let sidecar_path = crate::sidecar::get_sidecar_path();
+    if !sidecar_path.starts_with('/') {
+        return Err("Sidecar binary not found".to_string());
+    }
+    if let Some(reason) = unsafe_path_reason(Path::new(&sidecar_path)) {
+        return Err(format!(
+            "Not installing a polkit policy for {}: {}. Install the deb package, \
+             or the sidecar under a root-owned directory such as /usr/local/bin",
+            sidecar_path, reason
+        ));
+    }
+    let policy = POLICY_TEMPLATE.replace(PACKAGED_SIDECAR_PATH, &sidecar_path);
+
+    // Piped rather than staged in /tmp, where it could be swapped before
+    // root copies it
+    let mut child = Command::new("pkexec")
+        .arg("tee")
+        .arg(POLICY_PATH)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run pkexec: {}", e))?;
+    let mut stdin = child.stdin.take().ok_or("No stdin")?;
+    stdin
+        .write_all(policy.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write policy: {}", e))?;
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("Failed to run pkexec: {}", e))?;
+    if !status.success() {
+        return Err(format!("Failed to install {} ({})", POLICY_PATH, status));
+    }
+    Ok(format!("Polkit policy installed for {}", sidecar_path))
+}
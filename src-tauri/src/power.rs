@@ -0,0 +1,178 @@
+//! Profiles per power source: one for the AC adapter, one for battery.
+//!
+//! `/sys/class/power_supply` is polled every few seconds; a `Mains` supply
+//! that is online means AC. When the source changes, `power-source-changed`
+//! is emitted and the profile mapped to the new source is loaded. The
+//! source found at startup is only a baseline, so the profile applied on
+//! startup stays, and an active app rule (see `app_rules.rs`) takes
+//! precedence over the power source.
+//!
+//! The same check pauses history recording on battery at or below
+//! `pause_history_below_battery_percent`, and resumes it on AC or once the
+//! charge is back above it, emitting `history-paused` each time.
+
+use crate::actions::ActionSource;
+use crate::app_rules::AppRuleState;
+use crate::battery;
+use crate::events::{self, EventTopic};
+use crate::history::History;
+use crate::permissions::{self, Tier};
+use crate::profiles;
+use crate::{settings, SidecarState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+/// Emitted with the new `PowerSource`.
+pub(crate) const POWER_SOURCE_EVENT: &str = "power-source-changed";
+/// Emitted with `true` when history recording pauses for a low battery,
+/// `false` when it resumes.
+pub(crate) const HISTORY_PAUSED_EVENT: &str = "history-paused";
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+}
+
+/// Profile names per source; a source without one leaves the fans alone.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct PowerProfiles {
+    pub ac: Option<String>,
+    pub battery: Option<String>,
+}
+
+/// Last source seen; `None` before the first check or without an adapter.
+#[derive(Default)]
+pub(crate) struct PowerState(Mutex<Option<PowerSource>>);
+
+/// `None` when there is no mains supply to ask, e.g. on a desktop.
+fn read_power_source() -> Option<PowerSource> {
+    let read = |path: &Path| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    let mut found = false;
+    for entry in fs::read_dir(POWER_SUPPLY_PATH).ok()?.flatten() {
+        let dir = entry.path();
+        if read(&dir.join("type")).as_deref() != Some("Mains") {
+            continue;
+        }
+        found = true;
+        if read(&dir.join("online")).as_deref() == Some("1") {
+            return Some(PowerSource::Ac);
+        }
+    }
+    found.then_some(PowerSource::Battery)
+}
+
+async fn on_change(app: &AppHandle, source: PowerSource) {
+    events::emit(app, EventTopic::Profile, POWER_SOURCE_EVENT, source);
+    if app.state::<AppRuleState>().active().is_some() {
+        return;
+    }
+    let mapped = settings::load_settings().ok().and_then(|s| match source {
+        PowerSource::Ac => s.power_profiles.ac,
+        PowerSource::Battery => s.power_profiles.battery,
+    });
+    let Some(name) = mapped else {
+        return;
+    };
+    let reason = match source {
+        PowerSource::Ac => "AC adapter plugged in",
+        PowerSource::Battery => "Running on battery",
+    };
+    let state = app.state::<SidecarState>().inner().clone();
+    match profiles::switch_profile(
+        app,
+        &state,
+        &name,
+        ActionSource::PowerSource,
+        Some(reason.to_string()),
+    )
+    .await
+    {
+        Ok(profile) => profiles::notify_profile_changed(app, &profile),
+        Err(e) => eprintln!("Power profiles: failed to load profile '{}': {}", name, e),
+    }
+}
+
+fn update_history_pause(app: &AppHandle, source: PowerSource) {
+    let limit = settings::current().pause_history_below_battery_percent;
+    let paused = source == PowerSource::Battery
+        && limit.is_some_and(|limit| battery::lowest_charge().is_some_and(|c| c <= limit));
+    if app.state::<History>().set_paused(paused) {
+        events::emit(app, EventTopic::Profile, HISTORY_PAUSED_EVENT, paused);
+    }
+}
+
+/// The source seen by the last check.
+pub(crate) fn current(app: &AppHandle) -> Option<PowerSource> {
+    *app.state::<PowerState>()
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Watch the power supply for as long as the app runs.
+pub(crate) fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(source) = read_power_source() else {
+                continue;
+            };
+            let previous = {
+                let state = app.state::<PowerState>();
+                let mut last = state.0.lock().unwrap_or_else(|e| e.into_inner());
+                last.replace(source)
+            };
+            if previous.is_some_and(|previous| previous != source) {
+                on_change(&app, source).await;
+            }
+            update_history_pause(&app, source);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_power_source(state: State<'_, PowerState>) -> Result<Option<PowerSource>, String> {
+    Ok(*state.0.lock().map_err(|e| e.to_string())?)
+}
+
+#[tauri::command]
+pub async fn get_power_profiles() -> Result<PowerProfiles, String> {
+    Ok(settings::load_settings()?.power_profiles)
+}
+
+/// Map profiles to the power sources; takes effect at the next plug or
+/// unplug.
+#[tauri::command]
+pub async fn set_power_profiles(
+    app: AppHandle,
+    ac: Option<String>,
+    battery: Option<String>,
+) -> Result<PowerProfiles, String> {
+    permissions::check(&app, Tier::Control)?;
+    let saved = profiles::load_profiles()?;
+    for name in ac.iter().chain(&battery) {
+        if !saved.iter().any(|p| &p.name == name) {
+            return Err(format!("Profile '{}' not found", name));
+        }
+    }
+    let power_profiles = PowerProfiles { ac, battery };
+    let mut settings = settings::load_settings()?;
+    settings.power_profiles = power_profiles.clone();
+    settings::save_settings(&settings)?;
+    Ok(power_profiles)
+}
@@ -0,0 +1,394 @@
+//! Checks for a first-run setup wizard: what the machine needs for the
+//! sidecar to reach the EC, each with what to do when it's missing and, where
+//! the app can do it, a fix the wizard runs with `apply_fix`.
+//!
+//! Everything is read as the user, without the sidecar: `/proc/mounts`, the
+//! module lists in `/lib/modules`, sysfs and DMI. With the `msi-ec` driver
+//! loaded the `ec_sys` checks don't apply and are skipped.
+
+use crate::permissions::{self, Tier};
+use crate::sidecar::{connect_sidecar, query_sidecar, SidecarCommand, SidecarResponse};
+use crate::{polkit, SidecarState};
+use msi_ec_core::{ec, quirks};
+use msi_protocol::SupportLevel;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, State};
+use ts_rs::TS;
+
+const MOUNTS_PATH: &str = "/proc/mounts";
+const DEBUGFS_PATH: &str = "/sys/kernel/debug";
+const EC_SYS_PATH: &str = "/sys/module/ec_sys";
+const MSI_EC_PATH: &str = "/sys/devices/platform/msi-ec";
+const VENDOR_PATH: &str = "/sys/class/dmi/id/sys_vendor";
+const PRODUCT_NAME_PATH: &str = "/sys/class/dmi/id/product_name";
+const MSI_VENDOR: &str = "Micro-Star International";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckId {
+    Debugfs,
+    EcSys,
+    /// `ec_sys` set to load at boot.
+    Persistence,
+    SecureBoot,
+    MsiEc,
+    Polkit,
+    Model,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// Works, with a caveat given in the detail.
+    Warning,
+    Failed,
+    /// Doesn't apply on this machine.
+    Skipped,
+}
+
+/// Fixes the app can run; each asks for the admin password.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Fix {
+    MountDebugfs,
+    /// Start the sidecar, which loads `ec_sys` with write support.
+    LoadEcSys,
+    /// Load `ec_sys` at boot, through `install_persistence`.
+    InstallPersistence,
+    InstallPolkitPolicy,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct PreflightCheck {
+    pub id: CheckId,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about it by hand, when it isn't fine.
+    pub advice: Option<String>,
+    pub fix: Option<Fix>,
+}
+
+fn check(id: CheckId, status: CheckStatus, detail: impl Into<String>) -> PreflightCheck {
+    PreflightCheck {
+        id,
+        status,
+        detail: detail.into(),
+        advice: None,
+        fix: None,
+    }
+}
+
+impl PreflightCheck {
+    fn advice(mut self, advice: impl Into<String>) -> Self {
+        self.advice = Some(advice.into());
+        self
+    }
+
+    fn fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn skipped_for_msi_ec(id: CheckId) -> PreflightCheck {
+    check(
+        id,
+        CheckStatus::Skipped,
+        "Not needed: the msi-ec driver is loaded",
+    )
+}
+
+fn debugfs() -> PreflightCheck {
+    let mounted = fs::read_to_string(MOUNTS_PATH)
+        .is_ok_and(|mounts| mounts.lines().any(|line| line.contains(" debugfs ")));
+    if mounted {
+        return check(CheckId::Debugfs, CheckStatus::Ok, "debugfs is mounted");
+    }
+    check(
+        CheckId::Debugfs,
+        CheckStatus::Failed,
+        format!("debugfs is not mounted at {}", DEBUGFS_PATH),
+    )
+    .advice("Mount it, or add it to /etc/fstab to mount it at boot")
+    .fix(Fix::MountDebugfs)
+}
+
+/// Whether the running kernel has `ec_sys`, as a module or built in.
+fn ec_sys_available() -> bool {
+    let Some(release) = read_trimmed("/proc/sys/kernel/osrelease") else {
+        return false;
+    };
+    let modules = Path::new("/lib/modules").join(release);
+    ["modules.dep", "modules.builtin"].iter().any(|list| {
+        fs::read_to_string(modules.join(list))
+            .is_ok_and(|content| content.lines().any(|line| line.contains("/ec_sys.ko")))
+    })
+}
+
+fn ec_sys() -> PreflightCheck {
+    if Path::new(EC_SYS_PATH).exists() {
+        return check(
+            CheckId::EcSys,
+            CheckStatus::Ok,
+            "The ec_sys module is loaded",
+        );
+    }
+    if ec_sys_available() {
+        return check(
+            CheckId::EcSys,
+            CheckStatus::Warning,
+            "The ec_sys module is installed but not loaded",
+        )
+        .advice("Starting the sidecar loads it with write support")
+        .fix(Fix::LoadEcSys);
+    }
+    check(
+        CheckId::EcSys,
+        CheckStatus::Failed,
+        "The running kernel has no ec_sys module",
+    )
+    .advice(
+        "Install your distribution's extra kernel modules package (e.g. linux-modules-extra on \
+         Ubuntu), or a kernel built with CONFIG_ACPI_EC_DEBUGFS, and reboot if the kernel was \
+         updated",
+    )
+}
+
+fn persistence() -> PreflightCheck {
+    if ec::persistence_installed() {
+        return check(
+            CheckId::Persistence,
+            CheckStatus::Ok,
+            "ec_sys loads with write support at boot",
+        );
+    }
+    check(
+        CheckId::Persistence,
+        CheckStatus::Warning,
+        "ec_sys is not set to load at boot; the sidecar loads it when it starts",
+    )
+    .advice("Optional: load it at boot so the EC is ready before the app starts")
+    .fix(Fix::InstallPersistence)
+}
+
+fn secure_boot() -> PreflightCheck {
+    let enabled = ec::secure_boot_enabled() == Some(true);
+    match ec::lockdown_mode() {
+        Some(mode) => check(
+            CheckId::SecureBoot,
+            CheckStatus::Failed,
+            format!(
+                "The kernel is locked down ({} mode){}, which blocks ec_sys",
+                mode,
+                if enabled { " by Secure Boot" } else { "" }
+            ),
+        )
+        .advice(
+            "Install the msi-ec driver, which works under lockdown, or turn off Secure Boot in \
+             the firmware setup",
+        ),
+        None if enabled => check(
+            CheckId::SecureBoot,
+            CheckStatus::Ok,
+            "Secure Boot is on, but the kernel isn't locked down",
+        ),
+        None => check(CheckId::SecureBoot, CheckStatus::Ok, "Secure Boot is off"),
+    }
+}
+
+fn msi_ec(locked_down: bool) -> PreflightCheck {
+    if Path::new(MSI_EC_PATH).exists() {
+        return check(
+            CheckId::MsiEc,
+            CheckStatus::Ok,
+            "The msi-ec driver is loaded and will be used",
+        );
+    }
+    if locked_down {
+        return check(
+            CheckId::MsiEc,
+            CheckStatus::Warning,
+            "The msi-ec driver is not loaded, and lockdown blocks ec_sys",
+        )
+        .advice("Install msi-ec (github.com/BeardOverflow/msi-ec) for your kernel");
+    }
+    check(
+        CheckId::MsiEc,
+        CheckStatus::Skipped,
+        "The msi-ec driver is not loaded; ec_sys is used instead",
+    )
+}
+
+fn polkit() -> PreflightCheck {
+    if !polkit::program_exists("pkexec") {
+        if polkit::program_exists("sudo") {
+            return check(
+                CheckId::Polkit,
+                CheckStatus::Warning,
+                "Polkit is not installed; the sidecar is started through sudo",
+            )
+            .advice("Set SUDO_ASKPASS or add a NOPASSWD sudoers rule for msi-sidecar");
+        }
+        return check(
+            CheckId::Polkit,
+            CheckStatus::Failed,
+            "Neither pkexec nor sudo is installed to start the sidecar as root",
+        )
+        .advice("Install polkit");
+    }
+    if !polkit::policy_installed() {
+        let missing = check(
+            CheckId::Polkit,
+            CheckStatus::Warning,
+            "The polkit policy is not installed; pkexec asks for the password on every connect",
+        );
+        let sidecar_path = crate::sidecar::get_sidecar_path();
+        return match polkit::unsafe_path_reason(Path::new(&sidecar_path)) {
+            None => missing.fix(Fix::InstallPolkitPolicy),
+            Some(reason) => missing.advice(format!(
+                "Install the deb package to get the policy ({})",
+                reason
+            )),
+        };
+    }
+    check(
+        CheckId::Polkit,
+        CheckStatus::Ok,
+        "pkexec and the polkit policy are installed",
+    )
+}
+
+fn model() -> PreflightCheck {
+    let vendor = read_trimmed(VENDOR_PATH).unwrap_or_default();
+    let product = read_trimmed(PRODUCT_NAME_PATH).unwrap_or_default();
+    if !vendor.starts_with(MSI_VENDOR) {
+        return check(
+            CheckId::Model,
+            CheckStatus::Failed,
+            format!("This is not an MSI laptop ({} {})", vendor, product),
+        );
+    }
+    match quirks::support_level(&product) {
+        SupportLevel::Verified => check(
+            CheckId::Model,
+            CheckStatus::Ok,
+            format!("{} is verified", product),
+        ),
+        SupportLevel::Experimental => check(
+            CheckId::Model,
+            CheckStatus::Warning,
+            format!("{} is experimental: writes are off until opted in", product),
+        )
+        .advice("Enable the experimental toggles you need once monitoring reads right"),
+        SupportLevel::Unsupported => check(
+            CheckId::Model,
+            CheckStatus::Warning,
+            format!(
+                "{} is not in the model table: monitoring uses the generic register map",
+                product
+            ),
+        )
+        .advice("Check the readings against MSI Center, and consider contributing the model"),
+    }
+}
+
+/// The setup checklist, in the order the wizard walks it.
+#[tauri::command]
+pub async fn run_preflight_checks() -> Result<Vec<PreflightCheck>, String> {
+    let locked_down = ec::lockdown_mode().is_some();
+    let mut checks = vec![model(), polkit(), msi_ec(locked_down)];
+    if Path::new(MSI_EC_PATH).exists() {
+        checks.extend(
+            [
+                CheckId::SecureBoot,
+                CheckId::Debugfs,
+                CheckId::EcSys,
+                CheckId::Persistence,
+            ]
+            .map(skipped_for_msi_ec),
+        );
+    } else {
+        checks.extend([secure_boot(), debugfs(), ec_sys(), persistence()]);
+    }
+    Ok(checks)
+}
+
+async fn load_ec_sys(app: &AppHandle, state: &SidecarState) -> Result<String, String> {
+    if !state.is_connected().await {
+        connect_sidecar(app, state).await?;
+    }
+    match query_sidecar(state, &SidecarCommand::LoadEcModule).await? {
+        SidecarResponse::EcModule { error: None } => Ok("ec_sys is loaded".to_string()),
+        SidecarResponse::EcModule { error: Some(e) } => Err(match e.suggested_package {
+            Some(package) => format!("Failed to load ec_sys; install {}: {}", package, e.detail),
+            None if e.reboot_required => {
+                "Failed to load ec_sys; reboot into the updated kernel".to_string()
+            }
+            None => format!("Failed to load ec_sys: {}", e.detail),
+        }),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
+    }
+}
+
+async fn install_persistence(app: &AppHandle, state: &SidecarState) -> Result<String, String> {
+    if !state.is_connected().await {
+        connect_sidecar(app, state).await?;
+    }
+    let report = crate::persistence(app, state, SidecarCommand::InstallPersistence).await?;
+    if !report.kept.is_empty() {
+        return Err(format!(
+            "{} already exist with other contents; check they load ec_sys with write_support=1",
+            report.kept.join(" and ")
+        ));
+    }
+    Ok("ec_sys will load with write support at boot".to_string())
+}
+
+async fn mount_debugfs() -> Result<String, String> {
+    let (mut command, _) = polkit::elevated_command("mount")?;
+    let status = command
+        .args(["-t", "debugfs", "debugfs", DEBUGFS_PATH])
+        .status()
+        .await
+        .map_err(|e| format!("Failed to run mount: {}", e))?;
+    if !status.success() {
+        return Err(format!("Mounting debugfs failed ({})", status));
+    }
+    Ok(format!("debugfs mounted at {}", DEBUGFS_PATH))
+}
+
+/// Run a fix from `run_preflight_checks`; run the checks again afterwards.
+#[tauri::command]
+pub async fn apply_fix(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    id: Fix,
+) -> Result<String, String> {
+    // Boot files and the polkit policy outlast the session, like the
+    // commands that install them on their own
+    let tier = match id {
+        Fix::MountDebugfs | Fix::LoadEcSys => Tier::Control,
+        Fix::InstallPersistence | Fix::InstallPolkitPolicy => Tier::Expert,
+    };
+    permissions::check(&app, tier)?;
+    crate::demo::refuse("Setup fixes")?;
+    match id {
+        Fix::MountDebugfs => mount_debugfs().await,
+        Fix::LoadEcSys => load_ec_sys(&app, &state).await,
+        Fix::InstallPersistence => install_persistence(&app, &state).await,
+        Fix::InstallPolkitPolicy => polkit::install_polkit_policy(app).await,
+    }
+}
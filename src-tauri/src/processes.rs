@@ -0,0 +1,107 @@
+//! The processes using the most CPU, to see what is heating the machine,
+//! and a way to end one.
+
+use crate::permissions::{self, Tier};
+use crate::SystemMonitor;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, Signal};
+use tauri::{AppHandle, State};
+use ts_rs::TS;
+
+/// Longest list `get_top_processes` returns.
+const MAX_PROCESSES: usize = 50;
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    /// Percent of one core since the previous call, so up to 100 × cores.
+    pub cpu_usage: f32,
+    /// Resident memory in bytes.
+    #[ts(type = "number")]
+    pub memory: u64,
+}
+
+/// The `n` processes using the most CPU, then the most memory. CPU usage is
+/// measured between calls, so the first call reports 0 for everything.
+#[tauri::command]
+pub async fn get_top_processes(
+    state: State<'_, SystemMonitor>,
+    n: usize,
+) -> Result<Vec<ProcessInfo>, String> {
+    let sys_arc = state.sys.clone();
+
+    let processes = tokio::task::spawn_blocking(move || {
+        let mut sys = sys_arc.lock().map_err(|e| e.to_string())?;
+        sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+
+        let mut processes: Vec<ProcessInfo> = sys
+            .processes()
+            .iter()
+            // Threads show up as processes of their own on Linux
+            .filter(|(_, p)| p.thread_kind().is_none())
+            .map(|(pid, p)| ProcessInfo {
+                pid: pid.as_u32(),
+                name: p.name().to_string_lossy().into_owned(),
+                cpu_usage: p.cpu_usage(),
+                memory: p.memory(),
+            })
+            .collect();
+        processes.sort_by(|a, b| {
+            b.cpu_usage
+                .total_cmp(&a.cpu_usage)
+                .then(b.memory.cmp(&a.memory))
+        });
+        processes.truncate(n.min(MAX_PROCESSES));
+
+        Ok::<Vec<ProcessInfo>, String>(processes)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))??;
+
+    Ok(processes)
+}
+
+/// Ask a process to exit with SIGTERM. Only the user's own processes can be
+/// ended; the app and the sidecar are refused.
+#[tauri::command]
+pub async fn kill_process(
+    app: AppHandle,
+    state: State<'_, SystemMonitor>,
+    pid: u32,
+) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    if pid == std::process::id() {
+        return Err("Refusing to end the app itself".to_string());
+    }
+    let sys_arc = state.sys.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mut sys = sys_arc.lock().map_err(|e| e.to_string())?;
+        let pid = Pid::from_u32(pid);
+        sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+        let process = sys
+            .process(pid)
+            .ok_or_else(|| format!("No process with PID {}", pid))?;
+        let name = process.name().to_string_lossy().into_owned();
+        if name.starts_with("msi-sidecar") {
+            return Err("Refusing to end the sidecar; use stop_sidecar".to_string());
+        }
+        match process.kill_with(Signal::Term) {
+            Some(true) => Ok(format!("Sent SIGTERM to {} ({})", name, pid)),
+            Some(false) => Err(format!(
+                "Failed to end {} ({}); it may belong to another user",
+                name, pid
+            )),
+            None => Err("SIGTERM is not supported on this platform".to_string()),
+        }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
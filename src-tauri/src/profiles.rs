@@ -0,0 +1,330 @@
+//! Named fan configurations persisted to `~/.config/msi-fan-control/profiles.json`.
+
+use crate::actions::{self, ActionKind, ActionSource};
+use crate::backups::{self, BackupTrigger};
+use crate::events::{self, EventTopic};
+use crate::onboarding::{self, OnboardingStep};
+use crate::overrides;
+use crate::permissions::{self, Tier};
+use crate::sidecar::SidecarCommand;
+use crate::{run_command, settings, tray, CurvePoint, SidecarState};
+use msi_protocol::Fan;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+use ts_rs::TS;
+
+const PROFILES_FILE: &str = "profiles.json";
+const LAST_APPLIED_FILE: &str = "last_applied.json";
+const LAST_APPLIED_NAME: &str = "Last applied";
+
+/// Emitted with the new `Profile` whenever a profile is loaded, from any source.
+pub(crate) const PROFILE_CHANGED_EVENT: &str = "profile-changed";
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct FanCurve {
+    pub cpu: Vec<CurvePoint>,
+    pub gpu: Vec<CurvePoint>,
+}
+
+/// A speed set for one fan by `set_fan_speed_for`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema, TS)]
+#[ts(export)]
+pub struct FanSpeed {
+    pub fan: Fan,
+    pub percent: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum CycleDirection {
+    Next,
+    Previous,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct Profile {
+    pub name: String,
+    pub fan_mode: String,
+    /// Flat speed for all curve points, as set by `set_fan_speed`.
+    #[serde(default)]
+    pub fan_speed: Option<u8>,
+    #[serde(default)]
+    pub curve: Option<FanCurve>,
+    /// Speeds for single fans, applied over `fan_speed` or `curve`.
+    #[serde(default)]
+    pub fan_speeds: Vec<FanSpeed>,
+    #[serde(default)]
+    pub cooler_boost: bool,
+    /// Keyboard backlight level; untouched when `None`.
+    #[serde(default)]
+    pub keyboard_backlight: Option<u8>,
+}
+
+impl Profile {
+    /// The speed a fan is pinned to, if any: the last one set for a single
+    /// fan, or the flat speed.
+    pub(crate) fn fixed_speed(&self) -> Option<u8> {
+        self.fan_speeds.last().map(|s| s.percent).or(self.fan_speed)
+    }
+}
+
+fn get_profiles_path() -> Result<PathBuf, String> {
+    Ok(crate::get_config_dir()?.join(PROFILES_FILE))
+}
+
+/// Profiles in the user's order; a missing file means no profiles yet.
+pub(crate) fn load_profiles() -> Result<Vec<Profile>, String> {
+    let path = get_profiles_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid profiles file: {}", e))
+}
+
+fn save_profiles(profiles: &[Profile]) -> Result<(), String> {
+    let path = get_profiles_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(profiles).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+/// The settings most recently pushed to the EC, if any were recorded.
+pub(crate) fn load_last_applied() -> Result<Option<Profile>, String> {
+    let path = crate::get_config_dir()?.join(LAST_APPLIED_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Invalid last applied file: {}", e))
+}
+
+/// Update the last applied settings after a successful EC write.
+/// Best effort: failing to persist must not fail the write itself.
+pub(crate) fn record_last_applied(update: impl FnOnce(&mut Profile)) {
+    let mut profile = match load_last_applied() {
+        Ok(Some(p)) => p,
+        _ => Profile {
+            name: LAST_APPLIED_NAME.to_string(),
+            fan_mode: "auto".to_string(),
+            fan_speed: None,
+            curve: None,
+            fan_speeds: Vec::new(),
+            cooler_boost: false,
+            keyboard_backlight: None,
+        },
+    };
+    update(&mut profile);
+    profile.name = LAST_APPLIED_NAME.to_string();
+
+    let result = crate::get_config_dir().and_then(|dir| {
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(LAST_APPLIED_FILE), json).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record last applied settings: {}", e);
+    }
+}
+
+/// Push a profile to the EC: mode first, then the fixed speed or curve
+/// (both switch the EC to Advanced) and any single-fan speeds over it, then
+/// Cooler Boost on top, and finally the keyboard backlight if the profile
+/// sets one.
+pub(crate) async fn apply_profile(state: &SidecarState, profile: &Profile) -> Result<(), String> {
+    let _writes = state.writes.lock().await;
+
+    let mode_cmd = SidecarCommand::SetFanMode {
+        mode: profile.fan_mode.clone(),
+    };
+    run_command(state, &mode_cmd).await?;
+
+    if let Some(percent) = profile.fan_speed {
+        let speed_cmd = SidecarCommand::SetFanSpeed {
+            percent,
+            force: false,
+        };
+        run_command(state, &speed_cmd).await?;
+    }
+
+    if let Some(curve) = &profile.curve {
+        let curve_cmd = SidecarCommand::SetFanCurve {
+            cpu: curve.cpu.clone(),
+            gpu: curve.gpu.clone(),
+            force: false,
+        };
+        run_command(state, &curve_cmd).await?;
+    }
+
+    for speed in &profile.fan_speeds {
+        let speed_cmd = SidecarCommand::SetFanSpeedPerFan {
+            fan: speed.fan,
+            percent: speed.percent,
+            force: false,
+        };
+        run_command(state, &speed_cmd).await?;
+    }
+
+    let boost_cmd = SidecarCommand::SetCoolerBoost {
+        enabled: profile.cooler_boost,
+    };
+    run_command(state, &boost_cmd).await?;
+
+    if let Some(brightness) = profile.keyboard_backlight {
+        let backlight_cmd = SidecarCommand::SetKeyboardBacklight { brightness };
+        run_command(state, &backlight_cmd).await?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn save_profile(app: AppHandle, profile: Profile) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    if profile.name.trim().is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+
+    let mut profiles = load_profiles()?;
+    let message = match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => {
+            *existing = profile.clone();
+            format!("Profile '{}' updated", profile.name)
+        }
+        None => {
+            profiles.push(profile.clone());
+            format!("Profile '{}' saved", profile.name)
+        }
+    };
+    save_profiles(&profiles)?;
+    if let Err(e) = tray::rebuild_tray_menu(&app) {
+        eprintln!("Failed to rebuild tray menu: {}", e);
+    }
+    onboarding::mark_done(OnboardingStep::FirstProfileSaved);
+    Ok(message)
+}
+
+/// Apply a saved profile by name and record it as last applied.
+/// Shared by the window command, the tray menu and the app rules.
+pub(crate) async fn switch_profile(
+    app: &AppHandle,
+    state: &SidecarState,
+    name: &str,
+    source: ActionSource,
+    reason: Option<String>,
+) -> Result<Profile, String> {
+    permissions::check(app, Tier::Control)?;
+    let profile = load_profiles()?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Profile '{}' not found", name))?;
+
+    if settings::load_settings()?.backups.before_profile {
+        if let Err(e) = backups::create(BackupTrigger::BeforeProfile) {
+            eprintln!("Backup before loading '{}' failed: {}", profile.name, e);
+        }
+    }
+    apply_profile(state, &profile).await?;
+    record_last_applied(|last| *last = profile.clone());
+
+    let mut current = settings::load_settings()?;
+    current.active_profile = Some(profile.name.clone());
+    settings::save_settings(&current)?;
+    actions::record(
+        app,
+        source,
+        ActionKind::ProfileApplied,
+        format!("Profile '{}' applied", profile.name),
+        reason,
+    );
+    Ok(profile)
+}
+
+/// Tell the window and the tray that a profile was loaded.
+pub(crate) fn notify_profile_changed(app: &AppHandle, profile: &Profile) {
+    tray::set_active_profile(app, &profile.name);
+    tray::set_cooler_boost_checked(app, profile.cooler_boost);
+    tray::set_fan_mode_checked(app, &profile.fan_mode);
+    match profile.fixed_speed() {
+        Some(percent) => overrides::fixed_speed_set(app, percent),
+        None => overrides::fixed_speed_cleared(app),
+    }
+    events::emit(app, EventTopic::Profile, PROFILE_CHANGED_EVENT, profile);
+}
+
+#[tauri::command]
+pub async fn load_profile(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    name: String,
+) -> Result<Profile, String> {
+    let profile = switch_profile(&app, &state, &name, ActionSource::Window, None).await?;
+    notify_profile_changed(&app, &profile);
+    Ok(profile)
+}
+
+/// Load the profile after (or before) the active one, wrapping around.
+/// Starts from the first profile when none is active.
+pub(crate) async fn cycle(
+    app: &AppHandle,
+    state: &SidecarState,
+    direction: CycleDirection,
+    source: ActionSource,
+) -> Result<Profile, String> {
+    let profiles = load_profiles()?;
+    if profiles.is_empty() {
+        return Err("No profiles saved".to_string());
+    }
+
+    let active = settings::load_settings()?.active_profile;
+    let current = active.and_then(|name| profiles.iter().position(|p| p.name == name));
+    let len = profiles.len();
+    let index = match (current, direction) {
+        (None, _) => 0,
+        (Some(i), CycleDirection::Next) => (i + 1) % len,
+        (Some(i), CycleDirection::Previous) => (i + len - 1) % len,
+    };
+
+    let profile = switch_profile(app, state, &profiles[index].name, source, None).await?;
+    notify_profile_changed(app, &profile);
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn cycle_profile(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+    direction: CycleDirection,
+) -> Result<Profile, String> {
+    cycle(&app, &state, direction, ActionSource::Window).await
+}
+
+#[tauri::command]
+pub async fn list_profiles() -> Result<Vec<Profile>, String> {
+    load_profiles()
+}
+
+#[tauri::command]
+pub async fn delete_profile(app: AppHandle, name: String) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    let mut profiles = load_profiles()?;
+    let before = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == before {
+        return Err(format!("Profile '{}' not found", name));
+    }
+    save_profiles(&profiles)?;
+    if let Err(e) = tray::rebuild_tray_menu(&app) {
+        eprintln!("Failed to rebuild tray menu: {}", e);
+    }
+    Ok(format!("Profile '{}' deleted", name))
+}
@@ -0,0 +1,109 @@
+//! Re-applying the EC settings after the laptop wakes from suspend.
+//!
+//! Many firmwares drop Cooler Boost and the custom curve on suspend. A wake
+//! is spotted without D-Bus: `/proc/uptime` keeps counting while the system
+//! sleeps but the monotonic clock behind `Instant` does not, so a gap between
+//! the two that grows by more than a few seconds between checks is time
+//! spent asleep. Clock changes from NTP or the user move neither. After a
+//! wake the last applied settings are pushed again, as on startup, and
+//! `resumed` tells the window how it went.
+
+use crate::actions::{self, ActionKind, ActionSource};
+use crate::events::{self, EventTopic};
+use crate::{overrides, profiles, SidecarState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+/// Emitted with a `Resumed` after every wake.
+pub(crate) const RESUME_EVENT: &str = "resumed";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Sleep shorter than this is scheduling jitter, not a suspend.
+const MIN_SLEEP: Duration = Duration::from_secs(10);
+/// Time the firmware gets to finish its own resume before the writes.
+const SETTLE: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct Resumed {
+    /// Roughly how long the laptop was suspended.
+    pub slept_secs: u64,
+    /// Whether the last applied settings were written again.
+    pub reapplied: bool,
+    /// Why they weren't, when there was something to re-apply.
+    pub error: Option<String>,
+}
+
+/// Time since boot including suspend, from the first field of `/proc/uptime`.
+fn boot_time() -> Option<Duration> {
+    let uptime = std::fs::read_to_string("/proc/uptime").ok()?;
+    let secs: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_secs_f64(secs))
+}
+
+/// `Ok(false)` when there is nothing to re-apply or no sidecar to re-apply
+/// it through; the app doesn't ask for a password on its own after a wake.
+async fn reapply(app: &AppHandle) -> Result<bool, String> {
+    let Some(last) = profiles::load_last_applied()? else {
+        return Ok(false);
+    };
+    let state = app.state::<SidecarState>().inner().clone();
+    if !state.is_connected().await {
+        return Ok(false);
+    }
+    profiles::apply_profile(&state, &last).await?;
+    if let Some(percent) = last.fixed_speed() {
+        overrides::fixed_speed_set(app, percent);
+    }
+    actions::record(
+        app,
+        ActionSource::Resume,
+        ActionKind::ProfileApplied,
+        "Last applied settings restored",
+        Some("Woke from suspend".to_string()),
+    );
+    Ok(true)
+}
+
+async fn on_resume(app: &AppHandle, slept: Duration) {
+    tokio::time::sleep(SETTLE).await;
+    let result = reapply(app).await;
+    if let Err(e) = &result {
+        eprintln!("Failed to re-apply settings after resume: {}", e);
+    }
+    let resumed = Resumed {
+        slept_secs: slept.as_secs(),
+        reapplied: result.as_ref().is_ok_and(|&reapplied| reapplied),
+        error: result.err(),
+    };
+    events::emit(app, EventTopic::Connection, RESUME_EVENT, &resumed);
+}
+
+/// Watch for wakes for as long as the app runs.
+pub(crate) fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let Some(mut last_boot) = boot_time() else {
+            eprintln!("Resume detection disabled: /proc/uptime is unreadable");
+            return;
+        };
+        let mut last_check = Instant::now();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(boot) = boot_time() else {
+                continue;
+            };
+            let slept = boot
+                .saturating_sub(last_boot)
+                .saturating_sub(last_check.elapsed());
+            last_boot = boot;
+            last_check = Instant::now();
+            if slept >= MIN_SLEEP {
+                on_resume(&app, slept).await;
+            }
+        }
+    });
+}
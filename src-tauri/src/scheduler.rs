@@ -0,0 +1,197 @@
+//! Status updates pushed by the sidecar, and the schedule they arrive on.
+//!
+//! While the window is visible the sidecar pushes the full status, which is
+//! cached for `get_status` and forwarded to the frontend as `fan-status`
+//! events. Once the window is hidden only the tray needs live data, so we
+//! switch to lite pushes at a lower rate to cut EC traffic and wakeups,
+//! and on battery, if the user opted in, stop them altogether. Both rates
+//! are settings.
+//! Every push also goes into the history buffer, the anomaly detector and
+//! the thermal notifications.
+
+use crate::actions::{self, ActionKind, ActionSource};
+use crate::anomaly;
+use crate::events::{self, EventTopic};
+use crate::history::{now_ms, History, HistorySample};
+use crate::notifications;
+use crate::overrides;
+use crate::power::{self, PowerSource};
+use crate::settings;
+use crate::sidecar::lifecycle::{self, Lifecycle};
+use crate::sidecar::RequestError;
+use crate::thermal_test;
+use crate::{
+    query_sidecar, tray, FanStatus, SidecarCommand, SidecarResponse, SidecarState, StatusLite,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+pub(crate) const FAN_STATUS_EVENT: &str = "fan-status";
+/// Emitted with the sidecar's message when its watchdog took fan control back.
+pub(crate) const FAILSAFE_EVENT: &str = "failsafe-triggered";
+
+const FULL_PUSH_INTERVAL_MS: u64 = 1000;
+const LITE_PUSH_INTERVAL_MS: u64 = 10_000;
+/// How often the window visibility is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Well inside the sidecar's default 30 s heartbeat timeout.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Subscription {
+    /// Full status every so many milliseconds.
+    Full(u64),
+    /// Lite status every so many milliseconds.
+    Lite(u64),
+    /// No pushes. Notifications and anomaly checks wait for the next one;
+    /// the sidecar's failsafe watchdog keeps running.
+    Paused,
+}
+
+pub(crate) fn window_visible(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|w| w.is_visible().ok())
+        .unwrap_or(false)
+}
+
+pub(crate) fn on_status_push(app: &AppHandle, mut status: FanStatus) {
+    let sample = HistorySample {
+        timestamp_ms: now_ms(),
+        cpu_temp: status.cpu_temp,
+        gpu_temp: status.gpu_temp,
+        fan1_rpm: Some(status.fan1_rpm),
+        fan2_rpm: Some(status.fan2_rpm),
+    };
+    app.state::<History>().push(sample);
+    overrides::on_status(app, status.cooler_boost, Some(&status.fan_mode));
+    tray::set_fan_mode_checked(app, &status.fan_mode);
+    anomaly::observe(
+        app,
+        &sample,
+        status.cooler_boost,
+        overrides::fixed_speed(app),
+    );
+    status.warnings = anomaly::fan_warnings(app);
+    notifications::observe(app, &sample);
+    events::emit(app, EventTopic::Status, FAN_STATUS_EVENT, &status);
+    if let Ok(mut latest) = app.state::<SidecarState>().latest.lock() {
+        *latest = Some((Instant::now(), status));
+    }
+}
+
+pub(crate) fn on_status_lite_push(app: &AppHandle, status: StatusLite) {
+    let sample = HistorySample {
+        timestamp_ms: now_ms(),
+        cpu_temp: status.cpu_temp,
+        gpu_temp: status.gpu_temp,
+        fan1_rpm: None,
+        fan2_rpm: None,
+    };
+    app.state::<History>().push(sample);
+    overrides::on_status(app, status.cooler_boost, None);
+    anomaly::observe(app, &sample, status.cooler_boost, None);
+    notifications::observe(app, &sample);
+    tray::update_status(app, &status);
+}
+
+pub(crate) fn on_failsafe(app: &AppHandle, message: String) {
+    eprintln!("Sidecar failsafe: {}", message);
+    overrides::fixed_speed_cleared(app);
+    thermal_test::on_failsafe();
+    actions::record(
+        app,
+        ActionSource::Failsafe,
+        ActionKind::Failsafe,
+        "Failsafe took over the fans",
+        Some(message.clone()),
+    );
+    events::emit(app, EventTopic::Alerts, FAILSAFE_EVENT, &message);
+}
+
+fn desired_subscription(app: &AppHandle) -> Subscription {
+    let settings = settings::current();
+    if window_visible(app) {
+        let interval = settings.poll_interval_ms.map(u64::from);
+        return Subscription::Full(interval.unwrap_or(FULL_PUSH_INTERVAL_MS));
+    }
+    if settings.pause_polling_on_battery && power::current(app) == Some(PowerSource::Battery) {
+        return Subscription::Paused;
+    }
+    let interval = settings.hidden_poll_interval_secs.map(|s| s as u64 * 1000);
+    Subscription::Lite(interval.unwrap_or(LITE_PUSH_INTERVAL_MS))
+}
+
+/// Ask the connected sidecar for `desired` pushes if it isn't sending them
+/// already. Doesn't start the sidecar.
+async fn ensure_subscription(state: &SidecarState, desired: Subscription) -> Result<(), String> {
+    let current = state
+        .with(|conn| conn.map(|conn| (conn.subscription, conn.pending.clone())))
+        .await
+        .flatten();
+    let Some((subscription, pending)) = current else {
+        return Ok(());
+    };
+    if subscription == Some(desired) {
+        return Ok(());
+    }
+
+    // An interval of 0 stops the pushes
+    let cmd = match desired {
+        Subscription::Full(interval_ms) => SidecarCommand::Subscribe {
+            interval_ms,
+            lite: false,
+        },
+        Subscription::Lite(interval_ms) => SidecarCommand::Subscribe {
+            interval_ms,
+            lite: true,
+        },
+        Subscription::Paused => SidecarCommand::Subscribe {
+            interval_ms: 0,
+            lite: false,
+        },
+    };
+    match state.request(cmd).await {
+        Ok(SidecarResponse::Ok { .. }) => {
+            // Unless a new connection took its place meanwhile
+            state
+                .with(move |conn| {
+                    if let Some(conn) = conn.filter(|conn| Arc::ptr_eq(&conn.pending, &pending)) {
+                        conn.subscription = Some(desired);
+                    }
+                })
+                .await;
+            Ok(())
+        }
+        Ok(SidecarResponse::Error { message }) => Err(message),
+        Ok(_) => Err("Unexpected response".to_string()),
+        Err(RequestError::NotRunning) => Ok(()),
+        Err(RequestError::Communication(e)) => Err(e),
+        Err(RequestError::Timeout) => Err("Subscribe timeout".to_string()),
+    }
+}
+
+pub(crate) fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_heartbeat: Option<Instant> = None;
+        loop {
+            let state = app.state::<SidecarState>().inner().clone();
+            lifecycle::check(&app, &state, window_visible(&app)).await;
+            let desired = desired_subscription(&app);
+            if let Err(e) = ensure_subscription(&state, desired).await {
+                eprintln!("Failed to subscribe to status updates: {}", e);
+            }
+            // Tell the sidecar's watchdog we're alive; fails harmlessly
+            // while disconnected; skipped while idle, as it would restart it
+            if state.lifecycle() != Lifecycle::Idle
+                && last_heartbeat.is_none_or(|at| at.elapsed() >= HEARTBEAT_INTERVAL)
+            {
+                last_heartbeat = Some(Instant::now());
+                let _ = query_sidecar(&state, &SidecarCommand::Heartbeat).await;
+            }
+            // Keeps the elapsed time in the tray current between pushes
+            overrides::refresh(&app);
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
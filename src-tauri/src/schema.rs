@@ -0,0 +1,243 @@
+//! Machine-readable description of the app's API for the frontend: every
+//! command with its arguments and result, every event with its topic and
+//! payload, and the sidecar protocol, as JSON Schema.
+//!
+//! Argument names are the camelCase keys `invoke` expects. The command list
+//! mirrors `generate_handler!` in `lib.rs`; add new commands to both.
+
+use crate::actions::ActionRecord;
+use crate::anomaly::Anomaly;
+use crate::app_rules::AppRule;
+use crate::backups::{Backup, BackupSettings};
+use crate::battery::BatteryInfo;
+use crate::bookmarks::{BookmarkReading, RegisterBookmark};
+use crate::contribute::{ContributionReport, ContributionStep, EcDump, ProbeResult};
+use crate::crash::CrashReport;
+use crate::disks::DiskHealth;
+use crate::events::EventTopic;
+use crate::export::ExportFormat;
+use crate::format::{FormattedFanStatus, Sensor};
+use crate::gpu::GpuStats;
+use crate::history::HistorySample;
+use crate::hotkeys::Hotkeys;
+use crate::import::{ImportFormat, ImportedProfile};
+use crate::nbfc::NbfcTranslation;
+use crate::notifications::NotificationSettings;
+use crate::onboarding::{OnboardingState, OnboardingStep};
+use crate::permissions::{PermissionSettings, Tier};
+use crate::power::{PowerProfiles, PowerSource};
+use crate::preflight::{Fix, PreflightCheck};
+use crate::processes::ProcessInfo;
+use crate::profiles::{CycleDirection, Profile};
+use crate::resume::Resumed;
+use crate::sensors::HwmonSensor;
+use crate::settings::{Settings, SettingsPatch, TemperatureUnit};
+use crate::sidecar::handshake::SidecarInfo;
+use crate::sidecar::supervisor::ConnectionState;
+use crate::software_curve::SoftwareCurve;
+use crate::temp_sources::TempSource;
+use crate::thermal_test::{ThermalTestEntry, ThermalTestReport};
+use crate::tray::TrayMenuSettings;
+use crate::{
+    ActiveRegisterMap, CapabilityStatus, CleanupReport, CpuCoreDetail, CurvePoint, DetectedModel,
+    EcModuleError, FailsafeConfig, Fan, FanBehavior, FanStatus, HardwareInfo, KeyboardBacklight,
+    NetworkStats, PersistenceReport, SystemStats,
+};
+use msi_protocol::{Reply, Request};
+use schemars::gen::SchemaGenerator;
+use serde_json::{json, Map, Value};
+
+/// Bumped whenever a command, payload or event changes incompatibly.
+const SCHEMA_VERSION: u32 = 1;
+
+/// `{ "name": ..., "args": { arg: schema }, "returns": schema }`
+macro_rules! command {
+    ($gen:ident, $name:literal ( $($arg:literal : $ty:ty),* ) -> $ret:ty) => {
+        json!({
+            "name": $name,
+            "args": { $($arg: $gen.subschema_for::<$ty>()),* },
+            "returns": $gen.subschema_for::<$ret>(),
+        })
+    };
+}
+
+macro_rules! event {
+    ($gen:ident, $topic:ident, $name:expr, $ty:ty) => {
+        json!({
+            "name": $name,
+            "topic": EventTopic::$topic,
+            "payload": $gen.subschema_for::<$ty>(),
+        })
+    };
+}
+
+fn commands(gen: &mut SchemaGenerator) -> Vec<Value> {
+    vec![
+        command!(gen, "start_sidecar"() -> FanStatus),
+        command!(gen, "stop_sidecar"() -> String),
+        command!(gen, "get_status"() -> FanStatus),
+        command!(gen, "get_status_formatted"() -> FormattedFanStatus),
+        command!(gen, "get_history"("rangeSecs": u64) -> Vec<HistorySample>),
+        command!(gen, "get_anomalies"() -> Vec<Anomaly>),
+        command!(gen, "get_action_history"("limit": Option<usize>) -> Vec<ActionRecord>),
+        command!(gen, "export_history"("rangeSecs": u64, "format": ExportFormat, "localized": Option<bool>) -> String),
+        command!(gen, "get_ec_dump"() -> EcDump),
+        command!(gen, "export_diagnostics"() -> String),
+        command!(gen, "write_ec_register"("offset": u8, "value": u8, "confirm": String) -> String),
+        command!(gen, "start_contribution"() -> Vec<ProbeResult>),
+        command!(gen, "capture_contribution_step"("label": String) -> ContributionStep),
+        command!(gen, "finish_contribution"() -> ContributionReport),
+        command!(gen, "get_register_bookmarks"() -> Vec<RegisterBookmark>),
+        command!(gen, "set_register_bookmarks"("bookmarks": Vec<RegisterBookmark>) -> Vec<RegisterBookmark>),
+        command!(gen, "read_register_bookmarks"() -> Vec<BookmarkReading>),
+        command!(gen, "format_value"("sensor": Sensor, "raw": f64) -> String),
+        command!(gen, "set_cooler_boost"("enabled": bool) -> String),
+        command!(gen, "set_fan_speed"("percent": u8, "force": Option<bool>) -> String),
+        command!(gen, "set_fan_speed_for"("fan": Fan, "percent": u8, "force": Option<bool>) -> String),
+        command!(gen, "set_fan_mode"("mode": String) -> String),
+        command!(gen, "set_fan_curve"("cpu": Vec<CurvePoint>, "gpu": Vec<CurvePoint>, "force": Option<bool>) -> String),
+        command!(gen, "get_detected_model"() -> DetectedModel),
+        command!(gen, "load_ec_module"() -> Option<EcModuleError>),
+        command!(gen, "install_persistence"() -> PersistenceReport),
+        command!(gen, "remove_persistence"() -> PersistenceReport),
+        command!(gen, "cleanup_system_files"() -> CleanupReport),
+        command!(gen, "get_register_map"() -> ActiveRegisterMap),
+        command!(gen, "calibrate_fan_rpm"("fan": Fan, "actualRpm": u32) -> ActiveRegisterMap),
+        command!(gen, "get_capabilities"() -> Vec<CapabilityStatus>),
+        command!(gen, "get_sidecar_info"() -> SidecarInfo),
+        command!(gen, "get_subscribed_topics"() -> Vec<EventTopic>),
+        command!(gen, "subscribe_topics"("topics": Vec<EventTopic>) -> Vec<EventTopic>),
+        command!(gen, "get_temp_sources"() -> Vec<TempSource>),
+        command!(gen, "get_all_sensors"() -> Vec<HwmonSensor>),
+        command!(gen, "get_disk_health"() -> Vec<DiskHealth>),
+        command!(gen, "list_crash_reports"() -> Vec<CrashReport>),
+        command!(gen, "get_crash_reports_enabled"() -> bool),
+        command!(gen, "set_crash_reports_enabled"("enabled": bool) -> String),
+        command!(gen, "get_software_curve"() -> Option<SoftwareCurve>),
+        command!(gen, "enable_software_curve"("curve": SoftwareCurve) -> String),
+        command!(gen, "disable_software_curve"() -> String),
+        command!(gen, "get_keyboard_backlight"() -> KeyboardBacklight),
+        command!(gen, "set_keyboard_backlight"("brightness": u8) -> String),
+        command!(gen, "get_experimental_opt_ins"() -> Vec<String>),
+        command!(gen, "set_experimental_opt_in"("feature": String, "enabled": bool) -> Vec<String>),
+        command!(gen, "run_trial_write"() -> String),
+        command!(gen, "get_failsafe"() -> FailsafeConfig),
+        command!(gen, "set_failsafe"("config": FailsafeConfig) -> FailsafeConfig),
+        command!(gen, "get_fan_behavior"() -> FanBehavior),
+        command!(gen, "set_fan_behavior"("behavior": FanBehavior) -> FanBehavior),
+        command!(gen, "get_hardware_info"() -> HardwareInfo),
+        command!(gen, "get_gpu_stats"() -> GpuStats),
+        command!(gen, "get_system_stats"() -> SystemStats),
+        command!(gen, "get_cpu_details"() -> Vec<CpuCoreDetail>),
+        command!(gen, "get_network_stats"() -> NetworkStats),
+        command!(gen, "get_top_processes"("n": usize) -> Vec<ProcessInfo>),
+        command!(gen, "kill_process"("pid": u32) -> String),
+        command!(gen, "get_autostart_enabled"() -> bool),
+        command!(gen, "set_autostart_enabled"("enabled": bool) -> String),
+        command!(gen, "save_profile"("profile": Profile) -> String),
+        command!(gen, "load_profile"("name": String) -> Profile),
+        command!(gen, "cycle_profile"("direction": CycleDirection) -> Profile),
+        command!(gen, "list_profiles"() -> Vec<Profile>),
+        command!(gen, "delete_profile"("name": String) -> String),
+        command!(gen, "export_profile"("name": String, "path": String) -> String),
+        command!(gen, "import_profile"("path": String, "format": Option<ImportFormat>) -> ImportedProfile),
+        command!(gen, "import_nbfc_config"("path": String) -> NbfcTranslation),
+        command!(gen, "get_app_rules"() -> Vec<AppRule>),
+        command!(gen, "get_active_app_rule"() -> Option<AppRule>),
+        command!(gen, "set_app_rules"("rules": Vec<AppRule>) -> Vec<AppRule>),
+        command!(gen, "list_backups"() -> Vec<Backup>),
+        command!(gen, "create_backup"() -> Backup),
+        command!(gen, "restore_backup"("timestampMs": u64) -> Profile),
+        command!(gen, "get_backup_settings"() -> BackupSettings),
+        command!(gen, "set_backup_settings"("backups": BackupSettings) -> BackupSettings),
+        command!(gen, "get_battery_info"() -> Vec<BatteryInfo>),
+        command!(gen, "get_power_source"() -> Option<PowerSource>),
+        command!(gen, "get_power_profiles"() -> PowerProfiles),
+        command!(gen, "set_power_profiles"("ac": Option<String>, "battery": Option<String>) -> PowerProfiles),
+        command!(gen, "get_apply_on_startup"() -> bool),
+        command!(gen, "set_apply_on_startup"("enabled": bool) -> String),
+        command!(gen, "get_startup_delay"() -> u32),
+        command!(gen, "set_startup_delay"("seconds": u32) -> String),
+        command!(gen, "get_settings"() -> Settings),
+        command!(gen, "update_settings"("patch": SettingsPatch) -> Settings),
+        command!(gen, "set_units"("unit": TemperatureUnit) -> TemperatureUnit),
+        command!(gen, "install_polkit_policy"() -> String),
+        command!(gen, "get_hotkeys"() -> Hotkeys),
+        command!(gen, "set_hotkeys"("hotkeys": Hotkeys) -> Hotkeys),
+        command!(gen, "get_tray_menu_settings"() -> TrayMenuSettings),
+        command!(gen, "set_tray_menu_settings"("menu": TrayMenuSettings) -> TrayMenuSettings),
+        command!(gen, "get_notification_settings"() -> NotificationSettings),
+        command!(gen, "set_notification_settings"("notifications": NotificationSettings) -> NotificationSettings),
+        command!(gen, "get_permission_settings"() -> PermissionSettings),
+        command!(gen, "set_permission_settings"("permissions": PermissionSettings) -> PermissionSettings),
+        command!(gen, "confirm_permission_tier"("tier": Tier) -> ()),
+        command!(gen, "get_onboarding_state"() -> OnboardingState),
+        command!(gen, "mark_step_done"("step": OnboardingStep) -> OnboardingState),
+        command!(gen, "run_preflight_checks"() -> Vec<PreflightCheck>),
+        command!(gen, "apply_fix"("id": Fix) -> String),
+        command!(gen, "run_thermal_test"("durationSecs": u32) -> ThermalTestReport),
+        command!(gen, "stop_thermal_test"() -> bool),
+        command!(gen, "get_test_history"("profile": Option<String>) -> Vec<ThermalTestEntry>),
+        command!(gen, "get_api_schema"() -> Value),
+    ]
+}
+
+fn events(gen: &mut SchemaGenerator) -> Vec<Value> {
+    vec![
+        event!(gen, Status, crate::scheduler::FAN_STATUS_EVENT, FanStatus),
+        event!(gen, Alerts, crate::scheduler::FAILSAFE_EVENT, String),
+        event!(
+            gen,
+            Profile,
+            crate::profiles::PROFILE_CHANGED_EVENT,
+            Profile
+        ),
+        event!(
+            gen,
+            Connection,
+            crate::sidecar::supervisor::CONNECTION_EVENT,
+            ConnectionState
+        ),
+        event!(gen, Logs, crate::actions::ACTION_EVENT, ActionRecord),
+        event!(gen, Alerts, crate::anomaly::ANOMALY_EVENT, Anomaly),
+        event!(
+            gen,
+            Profile,
+            crate::app_rules::APP_RULE_EVENT,
+            Option<AppRule>
+        ),
+        event!(gen, Profile, crate::power::POWER_SOURCE_EVENT, PowerSource),
+        event!(gen, Connection, crate::resume::RESUME_EVENT, Resumed),
+    ]
+}
+
+pub(crate) fn api_schema() -> Value {
+    let mut gen = SchemaGenerator::default();
+    let commands = commands(&mut gen);
+    let events = events(&mut gen);
+    let sidecar = json!({
+        "command": gen.subschema_for::<Request>(),
+        "response": gen.subschema_for::<Reply>(),
+    });
+    let definitions: Map<String, Value> = gen
+        .take_definitions()
+        .into_iter()
+        .map(|(name, schema)| (name, json!(schema)))
+        .collect();
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "version": SCHEMA_VERSION,
+        "commands": commands,
+        "events": events,
+        "sidecar": sidecar,
+        "definitions": definitions,
+    })
+}
+
+/// JSON Schema of every command, event and sidecar message, for generating
+/// typed frontend bindings.
+#[tauri::command]
+pub async fn get_api_schema() -> Result<Value, String> {
+    Ok(api_schema())
+}
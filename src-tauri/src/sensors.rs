@@ -0,0 +1,143 @@
+//! Every hwmon sensor the kernel exposes, beyond the two EC temperatures:
+//! NVMe drives, chipset, Wi-Fi, battery and ambient sensors, fans and
+//! voltage rails, as found under `/sys/class/hwmon`.
+
+use crate::temp_sources::HWMON_PATH;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use ts_rs::TS;
+
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, JsonSchema, TS,
+)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    /// °C
+    Temperature,
+    /// RPM
+    Fan,
+    /// V
+    Voltage,
+    /// W
+    Power,
+}
+
+impl SensorKind {
+    /// sysfs attribute prefix, and the factor from its raw value to the unit.
+    fn attribute(self) -> (&'static str, f64) {
+        match self {
+            SensorKind::Temperature => ("temp", 1000.0),
+            SensorKind::Fan => ("fan", 1.0),
+            SensorKind::Voltage => ("in", 1000.0),
+            SensorKind::Power => ("power", 1_000_000.0),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct HwmonSensor {
+    /// Driver name, e.g. `nvme`, `coretemp` or `acpitz`.
+    pub chip: String,
+    /// hwmon device, e.g. `hwmon3`; tells two chips with one driver apart.
+    pub device: String,
+    /// The driver's label, e.g. `Composite`, or the attribute name without
+    /// one, e.g. `temp1`.
+    pub label: String,
+    pub kind: SensorKind,
+    /// In the kind's unit.
+    pub value: f64,
+}
+
+const KINDS: [SensorKind; 4] = [
+    SensorKind::Temperature,
+    SensorKind::Fan,
+    SensorKind::Voltage,
+    SensorKind::Power,
+];
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Index of an input attribute such as `temp3_input`, for `prefix` `temp`.
+/// Power meters report `power1_average` when they have no instant reading.
+fn input_index(file: &str, prefix: &str) -> Option<u32> {
+    let rest = file.strip_prefix(prefix)?;
+    let index = rest
+        .strip_suffix("_input")
+        .or_else(|| rest.strip_suffix("_average").filter(|_| prefix == "power"))?;
+    index.parse().ok()
+}
+
+fn chip_sensors(dir: &Path, device: String) -> Vec<HwmonSensor> {
+    let chip = read_trimmed(&dir.join("name")).unwrap_or_else(|| device.clone());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let files: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+
+    let mut inputs: Vec<(SensorKind, u32, &String)> = files
+        .iter()
+        .flat_map(|file| {
+            KINDS.into_iter().filter_map(move |kind| {
+                input_index(file, kind.attribute().0).map(|index| (kind, index, file))
+            })
+        })
+        .collect();
+    inputs.sort();
+    // A meter with both `power1_input` and `power1_average` counts once
+    inputs.dedup_by_key(|(kind, index, _)| (*kind, *index));
+
+    inputs
+        .into_iter()
+        .filter_map(|(kind, index, file)| {
+            let (prefix, scale) = kind.attribute();
+            // Unreadable while the device sleeps, e.g. a powered-down dGPU
+            let raw: f64 = read_trimmed(&dir.join(file))?.parse().ok()?;
+            let name = format!("{}{}", prefix, index);
+            Some(HwmonSensor {
+                chip: chip.clone(),
+                device: device.clone(),
+                label: read_trimmed(&dir.join(format!("{}_label", name))).unwrap_or(name),
+                kind,
+                value: raw / scale,
+            })
+        })
+        .collect()
+}
+
+/// All readable hwmon sensors, grouped by device.
+#[tauri::command]
+pub async fn get_all_sensors() -> Result<Vec<HwmonSensor>, String> {
+    tokio::task::spawn_blocking(|| {
+        let entries = fs::read_dir(HWMON_PATH)
+            .map_err(|e| format!("Failed to read {}: {}", HWMON_PATH, e))?;
+        let mut devices: Vec<_> = entries
+            .flatten()
+            .map(|entry| {
+                (
+                    entry.file_name().to_string_lossy().into_owned(),
+                    entry.path(),
+                )
+            })
+            .collect();
+        // hwmon10 after hwmon9
+        devices.sort_by_key(|(device, _)| {
+            let index = device.trim_start_matches("hwmon").parse::<u32>().ok();
+            (index, device.clone())
+        });
+        Ok(devices
+            .into_iter()
+            .flat_map(|(device, dir)| chip_sensors(&dir, device))
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
@@ -0,0 +1,353 @@
+//! User settings persisted to `~/.config/msi-fan-control/settings.json`.
+//!
+//! The file carries a `version`. A file from a newer version is copied
+//! aside before it is overwritten, so going back to that version keeps
+//! what only it understood. A file that can't be parsed is moved aside and
+//! the defaults are used, rather than failing every command that reads
+//! the settings. Saves go through a temporary file and a rename, one at a
+//! time, so a crash or a second writer never leaves half a file. The last
+//! settings loaded or saved are kept in memory for the scheduler, which
+//! checks them every second.
+
+use crate::app_rules::AppRule;
+use crate::backups::BackupSettings;
+use crate::bookmarks::RegisterBookmark;
+use crate::history::now_ms;
+use crate::hotkeys::{self, Hotkeys};
+use crate::notifications::{self, NotificationSettings, NotificationState};
+use crate::onboarding::OnboardingStep;
+use crate::permissions::{self, Tier};
+use crate::power::PowerProfiles;
+use crate::software_curve::SoftwareCurve;
+use crate::tray::{self, TrayMenuSettings};
+use msi_protocol::{FailsafeConfig, FanBehavior};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+const SETTINGS_FILE: &str = "settings.json";
+/// Bumped when a field is renamed, moved or reinterpreted, together with
+/// a step in `load_settings` converting older files. New fields with a
+/// default need no bump.
+const SETTINGS_VERSION: u64 = 1;
+/// Longest startup delay accepted, in seconds.
+const MAX_STARTUP_DELAY: u32 = 300;
+/// Range accepted for the status interval while the window is visible, in
+/// milliseconds.
+const POLL_INTERVAL_RANGE: std::ops::RangeInclusive<u32> = 500..=10_000;
+/// Range accepted for the status interval while hidden, in seconds.
+const HIDDEN_POLL_INTERVAL_RANGE: std::ops::RangeInclusive<u32> = 10..=30;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct Settings {
+    /// How often the sidecar pushes the status while the window is
+    /// visible, in milliseconds; 1000 when unset.
+    pub poll_interval_ms: Option<u32>,
+    /// How often it pushes the lite status for the tray while the window
+    /// is hidden, in seconds; 10 when unset.
+    pub hidden_poll_interval_secs: Option<u32>,
+    /// Stop status pushes while the window is hidden on battery.
+    pub pause_polling_on_battery: bool,
+    /// Stop recording history on battery at or below this charge; never
+    /// when unset.
+    pub pause_history_below_battery_percent: Option<u8>,
+    /// Stop a spawned sidecar once the window has been hidden this many
+    /// minutes with nothing needing it; never when unset.
+    pub stop_sidecar_when_hidden_mins: Option<u32>,
+    /// Unit temperatures are shown in, by the window and by `format`.
+    /// Thresholds and curves are always stored in °C.
+    pub temperature_unit: TemperatureUnit,
+    /// Start the sidecar and re-apply the last settings when the app launches.
+    pub apply_on_startup: bool,
+    /// Seconds to wait before that, for distros that load `ec_sys` late.
+    pub startup_delay_secs: u32,
+    /// Write features the user enabled on an unverified model.
+    pub experimental_opt_ins: Vec<String>,
+    /// Models the user confirmed a trial write on; it is re-run without
+    /// asking on every connect.
+    pub trial_write_models: Vec<String>,
+    /// Name of the profile last loaded, used as the starting point for cycling.
+    pub active_profile: Option<String>,
+    /// Onboarding steps completed so far.
+    pub onboarding: Vec<OnboardingStep>,
+    /// Highest permission tier the UI and tray may use.
+    pub max_tier: Tier,
+    /// Tiers that need a confirmation before their commands run.
+    pub confirm_tiers: Vec<Tier>,
+    /// Retries for EC writes that don't read back; the sidecar's default
+    /// when unset.
+    pub write_retries: Option<u8>,
+    /// Failsafe watchdog thresholds; the sidecar's defaults when unset.
+    pub failsafe: Option<FailsafeConfig>,
+    /// Hysteresis and ramp rate for fan changes; none when unset.
+    pub fan_behavior: Option<FanBehavior>,
+    /// Entries shown in the tray menu.
+    pub tray_menu: TrayMenuSettings,
+    /// Global keyboard shortcuts.
+    pub hotkeys: Hotkeys,
+    /// Thermal and connection notifications.
+    pub notifications: NotificationSettings,
+    /// Fan curve run by the app instead of the EC, while enabled.
+    pub software_curve: Option<SoftwareCurve>,
+    /// Profiles loaded while given applications run, first match wins.
+    pub app_rules: Vec<AppRule>,
+    /// Profiles loaded when switching between AC and battery.
+    pub power_profiles: PowerProfiles,
+    /// When configuration backups are taken and how many are kept.
+    pub backups: BackupSettings,
+    /// Save a report when the app or sidecar panics.
+    pub crash_reports: bool,
+    /// Labeled EC registers, for mapping a new model.
+    pub register_bookmarks: Vec<RegisterBookmark>,
+}
+
+/// Fields to change with `update_settings`; those left out keep their value.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct SettingsPatch {
+    pub poll_interval_ms: Option<u32>,
+    pub hidden_poll_interval_secs: Option<u32>,
+    pub pause_polling_on_battery: Option<bool>,
+    /// 0 keeps recording history at any charge.
+    pub pause_history_below_battery_percent: Option<u8>,
+    /// 0 turns stopping the idle sidecar off.
+    pub stop_sidecar_when_hidden_mins: Option<u32>,
+    pub temperature_unit: Option<TemperatureUnit>,
+    pub apply_on_startup: Option<bool>,
+    pub startup_delay_secs: Option<u32>,
+    /// The autostart desktop entry, which lives outside the settings file;
+    /// `get_autostart_enabled` reads it.
+    pub autostart: Option<bool>,
+    pub hotkeys: Option<Hotkeys>,
+    pub tray_menu: Option<TrayMenuSettings>,
+    pub notifications: Option<NotificationSettings>,
+}
+
+fn get_settings_path() -> Result<PathBuf, String> {
+    Ok(crate::get_config_dir()?.join(SETTINGS_FILE))
+}
+
+/// Held while the file is written, or read for the cache.
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+/// The settings as last loaded or saved; `None` until first read.
+static CURRENT: Mutex<Option<Settings>> = Mutex::new(None);
+
+fn remember(settings: &Settings) {
+    *CURRENT.lock().unwrap_or_else(|e| e.into_inner()) = Some(settings.clone());
+}
+
+/// The settings as last loaded or saved, read from the file only the
+/// first time. For checks that run every tick; commands that change the
+/// settings load them afresh.
+pub(crate) fn current() -> Settings {
+    let cached = CURRENT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    cached.unwrap_or_else(|| load_settings().unwrap_or_default())
+}
+
+fn parse(content: &str) -> Result<(Settings, u64), String> {
+    let json: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    // Unversioned files already have version 1's layout, and newer files
+    // are read for the fields this version knows
+    let version = json.get("version").and_then(Value::as_u64).unwrap_or(0);
+    let settings = serde_json::from_value(json).map_err(|e| e.to_string())?;
+    Ok((settings, version))
+}
+
+pub(crate) fn load_settings() -> Result<Settings, String> {
+    // So a save can't land between the read and `remember`
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let settings = read_settings()?;
+    remember(&settings);
+    Ok(settings)
+}
+
+fn read_settings() -> Result<Settings, String> {
+    let path = get_settings_path()?;
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let (settings, version) = match parse(&content) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let backup = path.with_file_name(format!("settings.invalid-{}.json", now_ms()));
+            eprintln!(
+                "Invalid settings file ({}), moved to {} and using defaults",
+                e,
+                backup.display()
+            );
+            // Another load may have moved it already
+            if let Err(e) = std::fs::rename(&path, &backup) {
+                eprintln!("Failed to move the settings file aside: {}", e);
+            }
+            return Ok(Settings::default());
+        }
+    };
+    if version > SETTINGS_VERSION {
+        let copy = path.with_file_name(format!("settings.v{}.json", version));
+        if !copy.exists() {
+            std::fs::copy(&path, &copy).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(settings)
+}
+
+pub(crate) fn save_settings(settings: &Settings) -> Result<(), String> {
+    let path = get_settings_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut json = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    if let Value::Object(map) = &mut json {
+        map.insert("version".to_string(), SETTINGS_VERSION.into());
+    }
+    let json = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+    let _guard = SAVE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+    remember(settings);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_settings() -> Result<Settings, String> {
+    load_settings()
+}
+
+/// Unit the tray, notifications, localized exports and
+/// `get_status_formatted` show temperatures in. Raw readings stay in °C.
+#[tauri::command]
+pub async fn set_units(app: AppHandle, unit: TemperatureUnit) -> Result<TemperatureUnit, String> {
+    permissions::check(&app, Tier::Control)?;
+    let mut settings = load_settings()?;
+    settings.temperature_unit = unit;
+    save_settings(&settings)?;
+    tray::rebuild_tray_menu(&app).map_err(|e| e.to_string())?;
+    Ok(unit)
+}
+
+/// Change several settings at once. Each field is validated and applied
+/// the way its own command does (hotkeys are registered, the tray menu is
+/// rebuilt), so a failure part way leaves the earlier fields changed.
+#[tauri::command]
+pub async fn update_settings(app: AppHandle, patch: SettingsPatch) -> Result<Settings, String> {
+    permissions::check(&app, Tier::Control)?;
+    if let Some(ms) = patch.poll_interval_ms {
+        if !POLL_INTERVAL_RANGE.contains(&ms) {
+            return Err(format!(
+                "Polling interval must be {} to {} ms",
+                POLL_INTERVAL_RANGE.start(),
+                POLL_INTERVAL_RANGE.end()
+            ));
+        }
+    }
+    if let Some(secs) = patch.hidden_poll_interval_secs {
+        if !HIDDEN_POLL_INTERVAL_RANGE.contains(&secs) {
+            return Err(format!(
+                "Polling interval while hidden must be {} to {} s",
+                HIDDEN_POLL_INTERVAL_RANGE.start(),
+                HIDDEN_POLL_INTERVAL_RANGE.end()
+            ));
+        }
+    }
+    if patch
+        .pause_history_below_battery_percent
+        .is_some_and(|percent| percent > 100)
+    {
+        return Err("Battery limit for history must be 0 to 100%".to_string());
+    }
+    // The scheduler picks these up on its next check
+    let mut settings = load_settings()?;
+    settings.poll_interval_ms = patch.poll_interval_ms.or(settings.poll_interval_ms);
+    settings.hidden_poll_interval_secs = patch
+        .hidden_poll_interval_secs
+        .or(settings.hidden_poll_interval_secs);
+    settings.pause_polling_on_battery = patch
+        .pause_polling_on_battery
+        .unwrap_or(settings.pause_polling_on_battery);
+    if let Some(percent) = patch.pause_history_below_battery_percent {
+        settings.pause_history_below_battery_percent = (percent > 0).then_some(percent);
+    }
+    if let Some(minutes) = patch.stop_sidecar_when_hidden_mins {
+        settings.stop_sidecar_when_hidden_mins = (minutes > 0).then_some(minutes);
+    }
+    save_settings(&settings)?;
+    if let Some(unit) = patch.temperature_unit {
+        set_units(app.clone(), unit).await?;
+    }
+    if let Some(enabled) = patch.apply_on_startup {
+        set_apply_on_startup(app.clone(), enabled).await?;
+    }
+    if let Some(seconds) = patch.startup_delay_secs {
+        set_startup_delay(app.clone(), seconds).await?;
+    }
+    if let Some(enabled) = patch.autostart {
+        crate::set_autostart_enabled(enabled).await?;
+    }
+    if let Some(keys) = patch.hotkeys {
+        hotkeys::set_hotkeys(app.clone(), keys).await?;
+    }
+    if let Some(menu) = patch.tray_menu {
+        tray::set_tray_menu_settings(app.clone(), menu).await?;
+    }
+    if let Some(alerts) = patch.notifications {
+        let state = app.state::<NotificationState>();
+        notifications::set_notification_settings(app.clone(), state, alerts).await?;
+    }
+    load_settings()
+}
+
+#[tauri::command]
+pub async fn get_apply_on_startup() -> Result<bool, String> {
+    Ok(load_settings()?.apply_on_startup)
+}
+
+#[tauri::command]
+pub async fn set_apply_on_startup(app: AppHandle, enabled: bool) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    let mut settings = load_settings()?;
+    settings.apply_on_startup = enabled;
+    save_settings(&settings)?;
+    Ok(format!(
+        "Apply on startup {}",
+        if enabled { "enabled" } else { "disabled" }
+    ))
+}
+
+#[tauri::command]
+pub async fn get_startup_delay() -> Result<u32, String> {
+    Ok(load_settings()?.startup_delay_secs)
+}
+
+/// Delay before the settings are re-applied on startup.
+#[tauri::command]
+pub async fn set_startup_delay(app: AppHandle, seconds: u32) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    if seconds > MAX_STARTUP_DELAY {
+        return Err(format!(
+            "Startup delay must be at most {} seconds",
+            MAX_STARTUP_DELAY
+        ));
+    }
+    let mut settings = load_settings()?;
+    settings.startup_delay_secs = seconds;
+    save_settings(&settings)?;
+    Ok(format!("Startup delay set to {} s", seconds))
+}
@@ -0,0 +1,199 @@
+//! The task that owns the sidecar connection. Commands, connects and
+//! hang-ups reach it as messages, in the order they were sent, and nothing
+//! else touches the link, so a slow status query can't hold off a write
+//! behind a lock.
+//!
+//! Writing a command is all the actor waits for; the reply is matched by
+//! the reader task and awaited by the caller, so commands are in flight
+//! together.
+
+use super::demux::{self, Pending};
+use super::{send_command, SidecarCommand, SidecarConnection, SidecarResponse, SidecarState};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+/// How long a command may take, from sending to its reply.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+/// For commands that run other programs or retry writes: `modprobe`,
+/// `systemctl` and `udevadm`, and the trial write's verify-and-retry.
+const SLOW_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn timeout(cmd: &SidecarCommand) -> Duration {
+    match cmd {
+        SidecarCommand::LoadEcModule
+        | SidecarCommand::CleanupSystemFiles
+        | SidecarCommand::TrialWrite => SLOW_REQUEST_TIMEOUT,
+        _ => REQUEST_TIMEOUT,
+    }
+}
+
+/// How a command sent with `SidecarState::request` failed.
+pub(crate) enum RequestError {
+    NotRunning,
+    Communication(String),
+    Timeout,
+}
+
+type Inspect = Box<dyn FnOnce(Option<&mut SidecarConnection>) + Send>;
+type Filter = Box<dyn FnOnce(&mut SidecarConnection) -> bool + Send>;
+
+pub(crate) enum Message {
+    /// Send `cmd` on the connection and hand its reply to `reply`.
+    Request {
+        cmd: SidecarCommand,
+        reply: oneshot::Sender<Result<SidecarResponse, RequestError>>,
+    },
+    /// Take over a freshly connected sidecar, closing the one before.
+    Install(Box<SidecarConnection>),
+    /// Hang up if `only_if` holds for the connection, sending `exit`
+    /// first if asked; `done` tells whether it did.
+    Close {
+        exit: bool,
+        only_if: Filter,
+        done: oneshot::Sender<bool>,
+    },
+    /// Look at or update the connection.
+    Inspect(Inspect),
+}
+
+pub(crate) fn spawn() -> mpsc::UnboundedSender<Message> {
+    let (tx, messages) = mpsc::unbounded_channel();
+    tauri::async_runtime::spawn(run(messages, tx.clone()));
+    tx
+}
+
+async fn run(
+    mut messages: mpsc::UnboundedReceiver<Message>,
+    actor: mpsc::UnboundedSender<Message>,
+) {
+    let mut connection: Option<SidecarConnection> = None;
+    while let Some(message) = messages.recv().await {
+        match message {
+            Message::Request { cmd, reply } => {
+                let Some(conn) = connection.as_mut() else {
+                    let _ = reply.send(Err(RequestError::NotRunning));
+                    continue;
+                };
+                let deadline = Instant::now() + timeout(&cmd);
+                let pending = conn.pending.clone();
+                let sent =
+                    tokio::time::timeout_at(deadline, pending.send(&mut conn.link, &cmd)).await;
+                let sent = sent
+                    .map_err(|_| RequestError::Timeout)
+                    .and_then(|sent| sent.map_err(RequestError::Communication));
+                tauri::async_runtime::spawn(forward(sent, deadline, pending, reply, actor.clone()));
+            }
+            Message::Install(conn) => {
+                if let Some(mut old) = connection.replace(*conn) {
+                    old.link.close().await;
+                }
+            }
+            Message::Close {
+                exit,
+                only_if,
+                done,
+            } => {
+                let close = connection.as_mut().is_some_and(only_if);
+                if let Some(mut conn) = connection.take_if(|_| close) {
+                    if exit {
+                        let _ = send_command(&mut conn.link, &SidecarCommand::Exit).await;
+                    }
+                    // A daemon just sees us hang up
+                    conn.link.close().await;
+                }
+                let _ = done.send(close);
+            }
+            Message::Inspect(inspect) => inspect(connection.as_mut()),
+        }
+    }
+}
+
+/// Wait for the reply to a sent command and pass it on. A connection that
+/// failed, or hung while taking the command, is dropped so the next
+/// Connect starts clean, unless it was replaced meanwhile. A reply that
+/// is merely late leaves it open, as the sidecar may still be partway
+/// through a privileged operation, unless the sidecar predates ids and the
+/// reply would be taken for the next command's.
+async fn forward(
+    sent: Result<oneshot::Receiver<demux::ReplyResult>, RequestError>,
+    deadline: Instant,
+    pending: Arc<Pending>,
+    reply: oneshot::Sender<Result<SidecarResponse, RequestError>>,
+    actor: mpsc::UnboundedSender<Message>,
+) {
+    let (result, broken) = match sent {
+        Ok(waiting) => match tokio::time::timeout_at(deadline, demux::reply(waiting)).await {
+            Ok(result) => {
+                let broken = result.is_err();
+                (result.map_err(RequestError::Communication), broken)
+            }
+            Err(_) => (Err(RequestError::Timeout), !pending.echoes_ids()),
+        },
+        Err(e) => (Err(e), true),
+    };
+    if broken {
+        let (done, _) = oneshot::channel();
+        let _ = actor.send(Message::Close {
+            exit: false,
+            only_if: Box::new(move |conn| Arc::ptr_eq(&conn.pending, &pending)),
+            done,
+        });
+    }
+    let _ = reply.send(result);
+}
+
+impl SidecarState {
+    fn send(&self, message: Message) {
+        // The actor runs as long as the state that holds its sender
+        let _ = self.actor.send(message);
+    }
+
+    /// Send `cmd` and wait for its reply. Doesn't start the sidecar.
+    pub(crate) async fn request(
+        &self,
+        cmd: SidecarCommand,
+    ) -> Result<SidecarResponse, RequestError> {
+        let (reply, waiting) = oneshot::channel();
+        self.send(Message::Request { cmd, reply });
+        waiting.await.unwrap_or(Err(RequestError::NotRunning))
+    }
+
+    /// Run `f` against the connection, which is `None` while disconnected.
+    /// Returns `None` only if the actor is gone.
+    pub(crate) async fn with<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(Option<&mut SidecarConnection>) -> T + Send + 'static,
+    ) -> Option<T> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Message::Inspect(Box::new(move |conn| {
+            let _ = tx.send(f(conn));
+        })));
+        rx.await.ok()
+    }
+
+    pub(crate) async fn is_connected(&self) -> bool {
+        self.with(|conn| conn.is_some()).await.unwrap_or(false)
+    }
+
+    pub(crate) fn install(&self, conn: SidecarConnection) {
+        self.send(Message::Install(Box::new(conn)));
+    }
+
+    /// Hang up if `only_if` holds, after sending `exit` if asked. Returns
+    /// whether it did.
+    pub(crate) async fn close(
+        &self,
+        exit: bool,
+        only_if: impl FnOnce(&mut SidecarConnection) -> bool + Send + 'static,
+    ) -> bool {
+        let (done, closed) = oneshot::channel();
+        self.send(Message::Close {
+            exit,
+            only_if: Box::new(only_if),
+            done,
+        });
+        closed.await.unwrap_or(false)
+    }
+}
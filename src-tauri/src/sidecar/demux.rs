@@ -0,0 +1,206 @@
+//! Matching the sidecar's replies to the commands waiting for them.
+//!
+//! Every command gets an `id` the reply carries back, so a late reply to a
+//! command that timed out can't be taken for the next one, and commands
+//! can be in flight together. Replies without an id, from a sidecar that
+//! predates ids, go to the oldest waiting command, as it answers in order.
+//! Once the sidecar has echoed an id, a line without one answers nothing:
+//! an error that couldn't be tied to its request, or the goodbye to an
+//! `exit`.
+
+use super::{Link, SidecarCommand, SidecarResponse};
+use msi_protocol::Request;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+pub(crate) type ReplyResult = Result<SidecarResponse, String>;
+
+struct Waiting {
+    next_id: u64,
+    /// In the order the commands were sent.
+    replies: VecDeque<(u64, oneshot::Sender<ReplyResult>)>,
+    /// Set once the connection ended, with the reason.
+    closed: Option<String>,
+    /// The sidecar answered with an id, so it sends one with every reply.
+    ids_seen: bool,
+}
+
+/// Commands sent on one connection and not answered yet.
+pub(crate) struct Pending {
+    waiting: Mutex<Waiting>,
+}
+
+impl Pending {
+    /// Also returns the wait for the status the sidecar greets every client
+    /// with, taken like the reply to a command sent before any other.
+    pub(crate) fn new() -> (Self, oneshot::Receiver<ReplyResult>) {
+        let pending = Pending {
+            waiting: Mutex::new(Waiting {
+                next_id: 1,
+                replies: VecDeque::new(),
+                closed: None,
+                ids_seen: false,
+            }),
+        };
+        let (_, greeting) = pending.register();
+        (pending, greeting)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Waiting> {
+        self.waiting.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn register(&self) -> (u64, oneshot::Receiver<ReplyResult>) {
+        let (tx, rx) = oneshot::channel();
+        let mut waiting = self.lock();
+        let id = waiting.next_id;
+        waiting.next_id += 1;
+        match &waiting.closed {
+            Some(reason) => {
+                let _ = tx.send(Err(reason.clone()));
+            }
+            None => waiting.replies.push_back((id, tx)),
+        }
+        (id, rx)
+    }
+
+    /// Send `cmd` and return the wait for its reply. The command is written
+    /// before the link is given back, so replies to commands sent in turn
+    /// come in turn.
+    pub(crate) async fn send(
+        &self,
+        link: &mut Link,
+        cmd: &SidecarCommand,
+    ) -> Result<oneshot::Receiver<ReplyResult>, String> {
+        let (id, reply) = self.register();
+        let request = Request {
+            id: Some(id),
+            command: cmd.clone(),
+        };
+        let mut line =
+            serde_json::to_string(&request).map_err(|e| format!("Encode error: {}", e))?;
+        line.push('\n');
+        link.write_all(line.as_bytes())
+            .await
+            .map_err(|e| format!("Write error: {}", e))?;
+        Ok(reply)
+    }
+
+    /// Hand a reply to the command it answers. Returns `false` if nothing
+    /// waits for it.
+    pub(crate) fn resolve(&self, id: Option<u64>, reply: ReplyResult) -> bool {
+        let mut waiting = self.lock();
+        let position = match id {
+            Some(id) => {
+                waiting.ids_seen = true;
+                waiting
+                    .replies
+                    .iter()
+                    .position(|(waiting, _)| *waiting == id)
+            }
+            None if waiting.ids_seen || waiting.replies.is_empty() => None,
+            None => Some(0),
+        };
+        match position.and_then(|i| waiting.replies.remove(i)) {
+            // The caller may have given up on it already
+            Some((_, tx)) => {
+                let _ = tx.send(reply);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the sidecar sends replies with their ids, so a late one
+    /// can't be taken for another command's.
+    pub(crate) fn echoes_ids(&self) -> bool {
+        self.lock().ids_seen
+    }
+
+    /// Fail every waiting command and any sent later.
+    pub(crate) fn close(&self, reason: String) {
+        let mut waiting = self.lock();
+        for (_, tx) in waiting.replies.drain(..) {
+            let _ = tx.send(Err(reason.clone()));
+        }
+        waiting.closed = Some(reason);
+    }
+}
+
+/// Wait for a reply from [`Pending::send`].
+pub(crate) async fn reply(reply: oneshot::Receiver<ReplyResult>) -> ReplyResult {
+    reply
+        .await
+        .unwrap_or_else(|_| Err("Empty response from sidecar - EOF".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(message: &str) -> ReplyResult {
+        Ok(SidecarResponse::Error {
+            message: message.to_string(),
+        })
+    }
+
+    fn ok(message: &str) -> ReplyResult {
+        Ok(SidecarResponse::Ok {
+            message: message.to_string(),
+        })
+    }
+
+    fn message(reply: ReplyResult) -> String {
+        match reply {
+            Ok(SidecarResponse::Ok { message } | SidecarResponse::Error { message }) => message,
+            other => panic!("unexpected reply {:?}", other),
+        }
+    }
+
+    #[test]
+    fn replies_go_to_their_ids() {
+        let (pending, _greeting) = Pending::new();
+        let (first, mut first_rx) = pending.register();
+        let (second, mut second_rx) = pending.register();
+        assert!(pending.resolve(Some(second), ok("second")));
+        assert!(pending.resolve(Some(first), ok("first")));
+        assert_eq!(message(first_rx.try_recv().unwrap()), "first");
+        assert_eq!(message(second_rx.try_recv().unwrap()), "second");
+        assert!(!pending.resolve(Some(first), ok("late")));
+    }
+
+    #[test]
+    fn id_less_replies_go_to_oldest_until_ids_are_seen() {
+        let (pending, mut greeting) = Pending::new();
+        let (_, mut legacy) = pending.register();
+        assert!(pending.resolve(None, ok("status")));
+        assert_eq!(message(greeting.try_recv().unwrap()), "status");
+        assert!(pending.resolve(None, error("legacy")));
+        assert_eq!(message(legacy.try_recv().unwrap()), "legacy");
+    }
+
+    #[test]
+    fn id_less_error_does_not_take_a_waiters_reply() {
+        let (pending, greeting) = Pending::new();
+        assert!(pending.resolve(None, ok("status")));
+        drop(greeting);
+        let (hello, _hello_rx) = pending.register();
+        assert!(pending.resolve(Some(hello), ok("hello")));
+
+        let (id, mut rx) = pending.register();
+        assert!(!pending.resolve(None, error("Command longer than 65536 bytes ignored")));
+        assert!(rx.try_recv().is_err());
+        assert!(pending.resolve(Some(id), ok("curve applied")));
+        assert_eq!(message(rx.try_recv().unwrap()), "curve applied");
+    }
+
+    #[test]
+    fn close_fails_waiting_and_later_commands() {
+        let (pending, mut greeting) = Pending::new();
+        pending.close("gone".to_string());
+        assert_eq!(greeting.try_recv().unwrap().unwrap_err(), "gone");
+        let (_, mut later) = pending.register();
+        assert_eq!(later.try_recv().unwrap().unwrap_err(), "gone");
+    }
+}
@@ -0,0 +1,109 @@
+//! The `hello` exchanged on connecting, so an app and sidecar of different
+//! versions find out what the other supports instead of failing on an
+//! unknown command. The bundled sidecar always matches; a daemon installed
+//! from another release may not.
+//!
+//! Features the sidecar doesn't report are refused by the app with a reason
+//! naming the sidecar, and `get_sidecar_info` lets the window hide them.
+
+use super::demux::Pending;
+use super::{exchange, Link, SidecarState};
+use crate::{SidecarCommand, SidecarResponse};
+use msi_protocol::{SidecarFeature, SidecarHello, PROTOCOL_VERSION};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::State;
+use ts_rs::TS;
+
+const HELLO_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct SidecarInfo {
+    pub connected: bool,
+    /// The protocol this app speaks.
+    pub protocol_version: u32,
+    /// What the sidecar reported; `None` from one older than the handshake.
+    pub hello: Option<SidecarHello>,
+    /// Why some features may be missing, when the versions differ.
+    pub mismatch: Option<String>,
+}
+
+fn mismatch(hello: Option<&SidecarHello>) -> Option<String> {
+    match hello {
+        None => {
+            Some("The sidecar predates version checks; update it to use newer features".to_string())
+        }
+        Some(h) if h.protocol_version < PROTOCOL_VERSION => Some(format!(
+            "Sidecar {} speaks an older protocol ({} < {}); update it to use newer features",
+            h.sidecar_version, h.protocol_version, PROTOCOL_VERSION
+        )),
+        Some(h) if h.protocol_version > PROTOCOL_VERSION => Some(format!(
+            "Sidecar {} is newer than the app (protocol {} > {}); update the app",
+            h.sidecar_version, h.protocol_version, PROTOCOL_VERSION
+        )),
+        Some(_) => None,
+    }
+}
+
+/// Greet a freshly connected sidecar. Anything but a `hello` back means it
+/// predates the handshake.
+pub(super) async fn hello(link: &mut Link, pending: &Pending) -> Option<SidecarHello> {
+    let cmd = SidecarCommand::Hello {
+        protocol_version: PROTOCOL_VERSION,
+    };
+    let hello = match tokio::time::timeout(HELLO_TIMEOUT, exchange(link, pending, &cmd)).await {
+        Ok(Ok(SidecarResponse::Hello(hello))) => Some(hello),
+        _ => None,
+    };
+    if let Some(reason) = mismatch(hello.as_ref()) {
+        eprintln!("{}", reason);
+    }
+    hello
+}
+
+/// Refuse `what` up front if the connected sidecar lacks `feature`.
+pub(crate) async fn require(
+    state: &SidecarState,
+    feature: SidecarFeature,
+    what: &str,
+) -> Result<(), String> {
+    super::lifecycle::wake(state).await?;
+    // Left to the command itself when nothing is connected
+    let Some(hello) = state
+        .with(|conn| conn.map(|conn| conn.hello.clone()))
+        .await
+        .flatten()
+    else {
+        return Ok(());
+    };
+    match hello {
+        Some(hello) if hello.features.contains(&feature) => Ok(()),
+        Some(hello) => Err(format!(
+            "{} isn't supported by sidecar {} with the {} backend",
+            what, hello.sidecar_version, hello.model.backend
+        )),
+        None => Err(format!(
+            "{} needs a newer sidecar; update it to match the app",
+            what
+        )),
+    }
+}
+
+/// Versions of the app and the connected sidecar, and what the sidecar
+/// supports.
+#[tauri::command]
+pub async fn get_sidecar_info(state: State<'_, SidecarState>) -> Result<SidecarInfo, String> {
+    let connection = state.with(|conn| conn.map(|conn| conn.hello.clone())).await;
+    let (connected, hello) = match connection.flatten() {
+        Some(hello) => (true, hello),
+        None => (false, None),
+    };
+    Ok(SidecarInfo {
+        connected,
+        protocol_version: PROTOCOL_VERSION,
+        mismatch: connected.then(|| mismatch(hello.as_ref())).flatten(),
+        hello,
+    })
+}
@@ -0,0 +1,110 @@
+//! Stopping an idle sidecar while the window sits in the tray, to keep the
+//! time a root process runs short.
+//!
+//! Off unless `stop_sidecar_when_hidden_mins` is set. Once the window has
+//! been hidden that long and nothing needs the sidecar between commands,
+//! it is stopped. Needing it means a software curve, fixed speed,
+//! sidecar-run fan behavior or Silent mode (all guarded by its failsafe),
+//! or something acting on its readings: app rules, AC/battery profiles,
+//! notifications and anomaly checks, or scheduled backups. The next command, or the window showing again, starts it
+//! again; the session's Polkit authorization usually spares a second
+//! password prompt. A systemd daemon is left alone, as hanging up doesn't
+//! stop it.
+
+use super::{connect_sidecar, supervisor, Link, SidecarState};
+use crate::{overrides, settings};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Lifecycle {
+    /// The window is visible, or the sidecar's state is up to the user.
+    Active,
+    /// The window has been hidden since then.
+    Hidden(Instant),
+    /// Stopped for being idle; the next command starts it again.
+    Idle,
+}
+
+/// Whether the sidecar is doing something between commands that stopping
+/// it would undo (its failsafe hands the fans back to the EC when we hang
+/// up), or the app relies on its readings while hidden.
+fn needed(app: &AppHandle, state: &SidecarState) -> bool {
+    let settings = settings::current();
+    // Any age will do: pushes slow down or turn lite while hidden
+    let silent = state
+        .latest
+        .lock()
+        .ok()
+        .and_then(|latest| latest.as_ref().map(|(_, s)| s.fan_mode == "silent"))
+        .unwrap_or(false);
+    settings.software_curve.is_some()
+        || settings
+            .fan_behavior
+            .is_some_and(|b| b.hysteresis > 0 || b.ramp_rate > 0)
+        || overrides::fixed_speed(app).is_some()
+        || silent
+        || !settings.app_rules.is_empty()
+        || settings.power_profiles.ac.is_some()
+        || settings.power_profiles.battery.is_some()
+        || settings.notifications.enabled
+        || settings.backups.interval_days > 0
+}
+
+/// Stop a spawned sidecar.
+async fn stop(app: &AppHandle, state: &SidecarState) {
+    let owner = state.clone();
+    let stopped = state
+        .close(true, move |conn| {
+            let process = matches!(conn.link, Link::Process(_));
+            if process {
+                // Keep the supervisor from restarting it
+                owner.supervised.store(false, Ordering::SeqCst);
+                owner.set_lifecycle(Lifecycle::Idle);
+            }
+            process
+        })
+        .await;
+    if stopped {
+        eprintln!("Stopped the idle sidecar");
+        supervisor::emit(app, &supervisor::ConnectionState::Idle);
+    }
+}
+
+/// Start the sidecar again if it was stopped for being idle.
+pub(crate) async fn wake(state: &SidecarState) -> Result<(), String> {
+    if state.lifecycle() != Lifecycle::Idle {
+        return Ok(());
+    }
+    let Some(app) = state.app.get() else {
+        return Ok(());
+    };
+    connect_sidecar(app, state).await.map(|_| ())
+}
+
+/// Advance the lifecycle; called every scheduler tick.
+pub(crate) async fn check(app: &AppHandle, state: &SidecarState, visible: bool) {
+    if visible {
+        if let Err(e) = wake(state).await {
+            eprintln!("Failed to restart the sidecar: {}", e);
+        }
+        state.set_lifecycle(Lifecycle::Active);
+        return;
+    }
+    match state.lifecycle() {
+        Lifecycle::Active => state.set_lifecycle(Lifecycle::Hidden(Instant::now())),
+        Lifecycle::Hidden(since) => {
+            let Some(minutes) = settings::current().stop_sidecar_when_hidden_mins else {
+                return;
+            };
+            if since.elapsed() >= Duration::from_secs(minutes as u64 * 60)
+                && state.supervised.load(Ordering::SeqCst)
+                && !needed(app, state)
+            {
+                stop(app, state).await;
+            }
+        }
+        Lifecycle::Idle => {}
+    }
+}
@@ -0,0 +1,527 @@
+//! Connection to the privileged sidecar: connecting to a running
+//! `msi-sidecar --daemon` or spawning one as root, the JSON-lines
+//! request/response plumbing, and routing of pushed status lines.
+
+mod actor;
+mod demux;
+pub(crate) mod handshake;
+pub(crate) mod lifecycle;
+pub(crate) mod supervisor;
+
+use crate::onboarding::{self, OnboardingStep};
+use crate::polkit::{self, Launcher};
+use crate::{opt_ins_command, scheduler, settings, FanStatus};
+pub(crate) use actor::RequestError;
+use demux::{Pending, ReplyResult};
+use lifecycle::Lifecycle;
+use msi_protocol::{Reply, SidecarHello, SupportLevel};
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::net::UnixStream;
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+pub(crate) use msi_protocol::{Command as SidecarCommand, Response as SidecarResponse};
+
+/// How commands reach the sidecar.
+pub(crate) enum Link {
+    /// A sidecar we spawned through pkexec or sudo, talking over
+    /// stdin/stdout.
+    Process(Child),
+    /// The systemd daemon's control socket. `alive` is cleared by the reader
+    /// when the daemon closes the connection.
+    Daemon {
+        writer: OwnedWriteHalf,
+        alive: Arc<AtomicBool>,
+    },
+}
+
+impl Link {
+    async fn write_all(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        match self {
+            Link::Process(child) => {
+                let stdin = child
+                    .stdin
+                    .as_mut()
+                    .ok_or_else(|| std::io::Error::other("No stdin"))?;
+                stdin.write_all(bytes).await?;
+                stdin.flush().await
+            }
+            Link::Daemon { writer, .. } => {
+                writer.write_all(bytes).await?;
+                writer.flush().await
+            }
+        }
+    }
+
+    /// Whether the other end is still there.
+    pub(crate) fn is_alive(&mut self) -> bool {
+        match self {
+            Link::Process(child) => matches!(child.try_wait(), Ok(None)),
+            Link::Daemon { alive, .. } => alive.load(Ordering::SeqCst),
+        }
+    }
+
+    /// How a spawned sidecar exited, once its output closed. `None` for the
+    /// daemon, or if it is still running after a moment.
+    async fn exit_status(&mut self) -> Option<ExitStatus> {
+        match self {
+            Link::Process(child) => tokio::time::timeout(EXIT_WAIT, child.wait())
+                .await
+                .ok()?
+                .ok(),
+            Link::Daemon { .. } => None,
+        }
+    }
+
+    /// Kill a spawned sidecar, or just hang up on the daemon, which keeps
+    /// running for other clients.
+    pub(crate) async fn close(&mut self) {
+        match self {
+            Link::Process(child) => {
+                let _ = child.kill().await;
+                let _ = child.wait().await;
+            }
+            Link::Daemon { writer, .. } => {
+                let _ = writer.shutdown().await;
+            }
+        }
+    }
+}
+
+pub(crate) struct SidecarConnection {
+    pub(crate) link: Link,
+    /// Commands waiting for their replies; pushed status lines are routed
+    /// elsewhere by the reader task.
+    pub(crate) pending: Arc<Pending>,
+    /// Status push schedule requested from this sidecar, if any.
+    pub(crate) subscription: Option<scheduler::Subscription>,
+    /// What the sidecar said it supports; `None` if it predates `hello`.
+    pub(crate) hello: Option<SidecarHello>,
+}
+
+#[derive(Clone)]
+pub(crate) struct SidecarState {
+    /// The task that owns the connection.
+    actor: mpsc::UnboundedSender<actor::Message>,
+    /// Held while connecting, so two connects don't start two sidecars.
+    connecting: Arc<Mutex<()>>,
+    /// Held for the whole of a write, so multi-command writes (profiles) from
+    /// the window and the tray never interleave.
+    pub(crate) writes: Arc<Mutex<()>>,
+    /// Most recent pushed status and when it arrived.
+    pub(crate) latest: Arc<std::sync::Mutex<Option<(Instant, FanStatus)>>>,
+    /// Set once the user connects and cleared when they disconnect; while
+    /// set, the supervisor restarts a sidecar that died.
+    pub(crate) supervised: Arc<AtomicBool>,
+    pub(crate) lifecycle: Arc<std::sync::Mutex<Lifecycle>>,
+    /// Set on the first connect, for restarting the sidecar on demand.
+    pub(crate) app: Arc<OnceLock<AppHandle>>,
+}
+
+/// How long a new sidecar may take to send its first status.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+/// The same while pkexec or sudo may be asking for a password.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long to wait for a sidecar whose output closed to exit.
+const EXIT_WAIT: Duration = Duration::from_secs(1);
+
+/// A pushed status older than this is ignored and `get_status` asks the
+/// sidecar directly.
+const LATEST_STATUS_MAX_AGE: Duration = Duration::from_secs(3);
+
+const SIMULATE_ENV: &str = "MSI_FAN_CONTROL_SIMULATE";
+
+/// Control socket of `msi-sidecar --daemon`; the same variable moves it on
+/// both sides.
+const DAEMON_SOCKET_PATH: &str = "/run/msi-fan-control.sock";
+const DAEMON_SOCKET_ENV: &str = "MSI_FAN_CONTROL_SOCKET";
+
+impl SidecarState {
+    pub(crate) fn new() -> Self {
+        SidecarState {
+            actor: actor::spawn(),
+            connecting: Arc::new(Mutex::new(())),
+            writes: Arc::new(Mutex::new(())),
+            latest: Arc::new(std::sync::Mutex::new(None)),
+            supervised: Arc::new(AtomicBool::new(false)),
+            lifecycle: Arc::new(std::sync::Mutex::new(Lifecycle::Active)),
+            app: Arc::new(OnceLock::new()),
+        }
+    }
+
+    pub(crate) fn lifecycle(&self) -> Lifecycle {
+        *self.lifecycle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn set_lifecycle(&self, lifecycle: Lifecycle) {
+        *self.lifecycle.lock().unwrap_or_else(|e| e.into_inner()) = lifecycle;
+    }
+
+    pub(crate) fn latest_status(&self) -> Option<FanStatus> {
+        let latest = self.latest.lock().ok()?;
+        latest
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < LATEST_STATUS_MAX_AGE)
+            .map(|(_, status)| status.clone())
+    }
+}
+
+pub(crate) fn get_sidecar_path() -> String {
+    // In development, use the compiled binary directly
+    // In production, Tauri bundles it with target triple suffix
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .unwrap_or_default();
+
+    // Try to find the sidecar binary - check multiple locations
+    let possible_paths = [
+        // Production: bundled next to executable
+        exe_dir.join("msi-sidecar-x86_64-unknown-linux-gnu"),
+        exe_dir.join("msi-sidecar"),
+        // Development: the workspace builds the app and the sidecar into
+        // the same target directory, so a debug app may use a release sidecar
+        exe_dir.join("../release/msi-sidecar"),
+    ];
+
+    for path in &possible_paths {
+        if path.exists() {
+            return path
+                .canonicalize()
+                .unwrap_or_else(|_| path.clone())
+                .to_string_lossy()
+                .to_string();
+        }
+    }
+
+    // Fallback - let pkexec find it
+    "msi-sidecar".to_string()
+}
+
+/// Run the sidecar against an in-memory EC, for development and for trying
+/// the UI on non-MSI hardware: `MSI_FAN_CONTROL_SIMULATE=1`.
+fn simulate_requested() -> bool {
+    std::env::var(SIMULATE_ENV).is_ok_and(|v| v == "1")
+}
+
+/// Send `cmd` and wait for its reply, holding the link throughout.
+pub(crate) async fn exchange(
+    link: &mut Link,
+    pending: &Pending,
+    cmd: &SidecarCommand,
+) -> Result<SidecarResponse, String> {
+    let reply = pending.send(link, cmd).await?;
+    demux::reply(reply).await
+}
+
+fn daemon_socket_path() -> String {
+    std::env::var(DAEMON_SOCKET_ENV).unwrap_or_else(|_| DAEMON_SOCKET_PATH.to_string())
+}
+
+/// Read sidecar output for the lifetime of the connection. Pushed status
+/// lines go to the scheduler; everything else is a reply, handed to the
+/// command it answers. `alive` is cleared at end of stream. Also returns
+/// the wait for the sidecar's greeting.
+fn spawn_reader(
+    app: AppHandle,
+    mut reader: impl AsyncBufRead + Unpin + Send + 'static,
+    alive: Arc<AtomicBool>,
+) -> (Arc<Pending>, oneshot::Receiver<ReplyResult>) {
+    let (pending, greeting) = Pending::new();
+    let pending = Arc::new(pending);
+    let replies = pending.clone();
+    tauri::async_runtime::spawn(async move {
+        let reason = loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line).await {
+                Ok(0) => break "Empty response from sidecar - EOF".to_string(),
+                Ok(_) => {}
+                Err(e) => break format!("Read error: {}", e),
+            }
+
+            let (id, reply) = match serde_json::from_str::<Reply>(&line) {
+                Ok(Reply { id, response }) => (id, Ok(response)),
+                // Still fails the command it answers, if that can be told
+                Err(e) => (
+                    serde_json::from_str::<serde_json::Value>(&line)
+                        .ok()
+                        .and_then(|json| json.get("id")?.as_u64()),
+                    Err(format!("Parse error: {} (line: {})", e, line.trim())),
+                ),
+            };
+            match reply {
+                Ok(SidecarResponse::StatusUpdate(status)) => {
+                    scheduler::on_status_push(&app, status)
+                }
+                Ok(SidecarResponse::StatusLiteUpdate(status)) => {
+                    scheduler::on_status_lite_push(&app, status)
+                }
+                Ok(SidecarResponse::FailsafeTriggered { message }) => {
+                    scheduler::on_failsafe(&app, message)
+                }
+                reply => {
+                    let error = match &reply {
+                        Ok(SidecarResponse::Error { message }) | Err(message) => {
+                            Some(message.clone())
+                        }
+                        _ => None,
+                    };
+                    if !replies.resolve(id, reply) {
+                        match (id, error) {
+                            (Some(id), _) => {
+                                eprintln!("Dropped a sidecar reply to request {}", id)
+                            }
+                            (None, Some(error)) => eprintln!("Sidecar error: {}", error),
+                            // The goodbye to an `exit` nobody waits for
+                            (None, None) => {}
+                        }
+                    }
+                }
+            }
+        };
+        replies.close(reason);
+        alive.store(false, Ordering::SeqCst);
+    });
+    (pending, greeting)
+}
+
+/// Send `cmd` without waiting for a reply, e.g. `exit`.
+pub(crate) async fn send_command(link: &mut Link, cmd: &SidecarCommand) -> Result<(), String> {
+    let mut line = serde_json::to_string(cmd).map_err(|e| format!("Encode error: {}", e))?;
+    line.push('\n');
+    link.write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("Write error: {}", e))
+}
+
+/// A link just opened, before the sidecar's greeting.
+struct Opened {
+    link: Link,
+    pending: Arc<Pending>,
+    greeting: oneshot::Receiver<ReplyResult>,
+    /// What started the sidecar as root, which may be waiting for the
+    /// user's password.
+    launcher: Option<Launcher>,
+}
+
+/// Connect to a running daemon, if there is one.
+async fn connect_daemon(app: &AppHandle) -> Option<Opened> {
+    let stream = UnixStream::connect(daemon_socket_path()).await.ok()?;
+    let (reader, writer) = stream.into_split();
+    let alive = Arc::new(AtomicBool::new(true));
+    let (pending, greeting) = spawn_reader(app.clone(), BufReader::new(reader), alive.clone());
+    Some(Opened {
+        link: Link::Daemon { writer, alive },
+        pending,
+        greeting,
+        launcher: None,
+    })
+}
+
+/// Spawn a sidecar of our own: as root, or directly when simulating or in
+/// demo mode since a simulated EC needs no root.
+fn spawn_process(app: &AppHandle) -> Result<Opened, String> {
+    let sidecar_path = get_sidecar_path();
+    // pkexec would only report it after asking for the password
+    if !polkit::program_exists(&sidecar_path) {
+        return Err(format!(
+            "Sidecar binary not found ({}); reinstall the app or build msi-sidecar",
+            sidecar_path
+        ));
+    }
+
+    // Note: tokio::process::Command is used here
+    let (mut command, launcher) = if crate::demo::enabled() {
+        let mut command = Command::new(&sidecar_path);
+        command.arg("--demo");
+        (command, None)
+    } else if simulate_requested() {
+        let mut command = Command::new(&sidecar_path);
+        command.arg("--simulate");
+        (command, None)
+    } else {
+        let (command, launcher) = polkit::elevated_command(&sidecar_path)?;
+        (command, Some(launcher))
+    };
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Important: kill on drop allows cleanup if the handle is dropped
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| match launcher {
+            Some(launcher) if e.kind() == std::io::ErrorKind::NotFound => {
+                format!("{} is not installed", launcher.program())
+            }
+            _ => format!("Failed to start sidecar: {}", e),
+        })?;
+
+    let stdout = child.stdout.take().ok_or("No stdout captured")?;
+    if let Some(stderr) = child.stderr.take() {
+        crate::crash::spawn_stderr_reader(stderr);
+    }
+    // A process is watched through `try_wait` instead
+    let alive = Arc::new(AtomicBool::new(true));
+    let (pending, greeting) = spawn_reader(app.clone(), BufReader::new(stdout), alive);
+    Ok(Opened {
+        link: Link::Process(child),
+        pending,
+        greeting,
+        launcher,
+    })
+}
+
+/// Spawn the privileged sidecar (replacing any existing connection) and
+/// return its initial status.
+pub(crate) async fn connect_sidecar(
+    app: &AppHandle,
+    state: &SidecarState,
+) -> Result<FanStatus, String> {
+    let _ = state.app.set(app.clone());
+    let _connecting = state.connecting.lock().await;
+
+    // Clean up existing connection if any
+    state.close(false, |_| true).await;
+
+    // Prefer a daemon already running under systemd over a pkexec instance,
+    // except in demo mode which must not touch the real EC
+    let daemon = if crate::demo::enabled() {
+        None
+    } else {
+        connect_daemon(app).await
+    };
+    let Opened {
+        mut link,
+        pending,
+        greeting,
+        launcher,
+    } = match daemon {
+        Some(daemon) => daemon,
+        None => spawn_process(app)?,
+    };
+
+    // Initial handshake with timeout, leaving time to type a password
+    // We only need to timeout the read operation, not the whole setup
+    let startup_timeout = match launcher {
+        Some(_) => AUTH_TIMEOUT,
+        None => STARTUP_TIMEOUT,
+    };
+    let response_result = tokio::time::timeout(startup_timeout, demux::reply(greeting)).await;
+
+    match response_result {
+        Ok(Ok(response)) => {
+            let hello = handshake::hello(&mut link, &pending).await;
+
+            // Re-send per-session settings: experimental write opt-ins, the
+            // trial write the user confirmed for this model, the EC write
+            // retry count and the failsafe watchdog
+            let settings = settings::load_settings().unwrap_or_default();
+            let mut restore_cmds = Vec::new();
+            if !settings.experimental_opt_ins.is_empty() {
+                restore_cmds.push(opt_ins_command(&settings.experimental_opt_ins));
+            }
+            if let SidecarResponse::Status(status) = &response {
+                if status.support_level != SupportLevel::Verified
+                    && settings.trial_write_models.contains(&status.model)
+                {
+                    restore_cmds.push(SidecarCommand::TrialWrite);
+                }
+            }
+            if let Some(retries) = settings.write_retries {
+                restore_cmds.push(SidecarCommand::SetWriteRetries { retries });
+            }
+            if let Some(config) = settings.failsafe {
+                restore_cmds.push(SidecarCommand::SetFailsafe(config));
+            }
+            if let Some(behavior) = settings.fan_behavior {
+                restore_cmds.push(SidecarCommand::SetFanBehavior(behavior));
+            }
+            for cmd in &restore_cmds {
+                let restore = exchange(&mut link, &pending, cmd);
+                if !matches!(
+                    tokio::time::timeout(Duration::from_secs(3), restore).await,
+                    Ok(Ok(SidecarResponse::Ok { .. }))
+                ) {
+                    eprintln!("Failed to restore sidecar setting: {:?}", cmd);
+                }
+            }
+
+            // Success - store connection
+            state.install(SidecarConnection {
+                link,
+                pending,
+                subscription: None,
+                hello,
+            });
+            state.supervised.store(true, Ordering::SeqCst);
+            state.set_lifecycle(Lifecycle::Active);
+            onboarding::mark_done(OnboardingStep::FirstConnect);
+            supervisor::emit(app, &supervisor::ConnectionState::Connected);
+
+            match response {
+                SidecarResponse::Status(status) => Ok(status),
+                SidecarResponse::Error { message } => Err(message),
+                _ => Err("Unexpected initial response".to_string()),
+            }
+        }
+        Ok(Err(e)) => {
+            // Read error; usually the launcher gave up before the sidecar ran
+            let exited = link.exit_status().await;
+            link.close().await;
+            match (launcher, exited) {
+                (Some(launcher), Some(status)) => Err(launcher.exit_error(status)),
+                _ => Err(e),
+            }
+        }
+        Err(_) => {
+            // Timeout
+            link.close().await;
+            Err("Sidecar startup timeout".to_string())
+        }
+    }
+}
+
+/// Send a read-only query.
+pub(crate) async fn query_sidecar(
+    state: &SidecarState,
+    cmd: &SidecarCommand,
+) -> Result<SidecarResponse, String> {
+    lifecycle::wake(state).await?;
+    state.request(cmd.clone()).await.map_err(|e| match e {
+        RequestError::NotRunning => "Sidecar not running. Click Connect first.".to_string(),
+        RequestError::Communication(e) => format!("Communication error: {}", e),
+        RequestError::Timeout => "Sidecar request timeout".to_string(),
+    })
+}
+
+/// Send a control command and wait for its `ok`/`error` reply.
+pub(crate) async fn run_command(
+    state: &SidecarState,
+    cmd: &SidecarCommand,
+) -> Result<String, String> {
+    lifecycle::wake(state).await?;
+    match state.request(cmd.clone()).await {
+        Ok(SidecarResponse::Ok { message }) => Ok(message),
+        Ok(SidecarResponse::Error { message }) => Err(message),
+        Ok(_) => Err("Unexpected response".to_string()),
+        Err(RequestError::NotRunning) => Err("Sidecar not running".to_string()),
+        Err(RequestError::Communication(e)) => Err(format!("Communication error: {}", e)),
+        Err(RequestError::Timeout) => Err("Command timeout".to_string()),
+    }
+}
+
+/// `run_command` for single-command writes, serialized with other writes.
+pub(crate) async fn run_write(
+    state: &SidecarState,
+    cmd: &SidecarCommand,
+) -> Result<String, String> {
+    let _writes = state.writes.lock().await;
+    run_command(state, cmd).await
+}
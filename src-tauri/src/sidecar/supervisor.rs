@@ -0,0 +1,96 @@
+//! Restarts the sidecar when its connection dies.
+//!
+//! A connection is dead once a failed or timed-out command dropped it, or
+//! the process exited on its own. While the user wants to be connected, the
+//! supervisor reconnects with exponential backoff and reports every state
+//! change to the frontend as a `sidecar-connection` event.
+
+use super::{connect_sidecar, SidecarState};
+use crate::events::{self, EventTopic};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+pub(crate) const CONNECTION_EVENT: &str = "sidecar-connection";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connected,
+    Disconnected,
+    /// Stopped while the window was hidden; starts again when needed.
+    Idle,
+    /// Waiting `retry_in_ms` before reconnect attempt number `attempt`.
+    Reconnecting {
+        attempt: u32,
+        #[ts(type = "number")]
+        retry_in_ms: u64,
+    },
+}
+
+pub(crate) fn emit(app: &AppHandle, state: &ConnectionState) {
+    events::emit(app, EventTopic::Connection, CONNECTION_EVENT, state);
+}
+
+/// Drop the connection if the sidecar process has exited or the daemon hung
+/// up. Returns whether a live connection remains.
+async fn check_alive(state: &SidecarState) -> bool {
+    state.close(false, |conn| !conn.link.is_alive()).await;
+    state.is_connected().await
+}
+
+async fn reconnect(app: &AppHandle, state: &SidecarState) {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 1;
+    // The user may disconnect while we wait
+    while state.supervised.load(Ordering::SeqCst) {
+        emit(
+            app,
+            &ConnectionState::Reconnecting {
+                attempt,
+                retry_in_ms: backoff.as_millis() as u64,
+            },
+        );
+        tokio::time::sleep(backoff).await;
+        if !state.supervised.load(Ordering::SeqCst) {
+            break;
+        }
+
+        // Something else (the window, a tray action) may have reconnected
+        if state.is_connected().await {
+            return;
+        }
+        match connect_sidecar(app, state).await {
+            Ok(_) => return,
+            Err(e) => eprintln!("Sidecar reconnect attempt {} failed: {}", attempt, e),
+        }
+
+        attempt += 1;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+pub(crate) fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let state = app.state::<SidecarState>().inner().clone();
+            if !state.supervised.load(Ordering::SeqCst) || check_alive(&state).await {
+                continue;
+            }
+
+            eprintln!("Sidecar connection lost, reconnecting");
+            emit(&app, &ConnectionState::Disconnected);
+            crate::notifications::connection_lost(&app);
+            reconnect(&app, &state).await;
+        }
+    });
+}
@@ -0,0 +1,227 @@
+//! Fan control done by the app instead of the EC, for firmwares whose
+//! Advanced mode ignores or mangles the curve registers.
+//!
+//! Once a second the hotter of CPU and GPU is looked up on a user curve and
+//! both fans get that duty through `set_fan_speed`. The duty only follows
+//! the temperature down after it fell by the hysteresis, and moves toward
+//! its target at the ramp rate so the fans don't jump. The curve is
+//! persisted and picked up again on the next launch.
+//!
+//! Other fan writes are overridden at the next duty change, so the curve is
+//! meant to be disabled before switching modes.
+
+use crate::actions::{self, ActionKind, ActionSource};
+use crate::permissions::{self, Tier};
+use crate::sidecar::{query_sidecar, run_write, SidecarCommand, SidecarResponse, SidecarState};
+use crate::{settings, CurvePoint};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+const TICK: Duration = Duration::from_secs(1);
+const MAX_POINTS: usize = 16;
+const MAX_SPEED: u8 = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct SoftwareCurve {
+    /// By rising temperature. The end duties hold below the first and
+    /// above the last point.
+    pub points: Vec<CurvePoint>,
+    /// °C the temperature has to fall before the duty follows it down.
+    pub hysteresis: u8,
+    /// Fastest duty change, in % per second.
+    pub ramp_rate: u8,
+}
+
+/// The running control loop, if any.
+#[derive(Default)]
+pub(crate) struct SoftwareCurveState(Mutex<Option<JoinHandle<()>>>);
+
+fn validate(curve: &SoftwareCurve) -> Result<(), String> {
+    if curve.points.len() < 2 || curve.points.len() > MAX_POINTS {
+        return Err(format!("A curve needs 2 to {} points", MAX_POINTS));
+    }
+    if curve.points.windows(2).any(|w| w[0].temp >= w[1].temp) {
+        return Err("Curve temperatures must be strictly increasing".to_string());
+    }
+    if curve.points.iter().any(|p| p.speed > MAX_SPEED) {
+        return Err(format!("Curve speeds must be at most {}%", MAX_SPEED));
+    }
+    if curve.ramp_rate == 0 {
+        return Err("The ramp rate must be at least 1% per second".to_string());
+    }
+    Ok(())
+}
+
+/// Duty for `temp`, interpolated linearly between points.
+fn interpolate(points: &[CurvePoint], temp: f32) -> f32 {
+    let (first, last) = (&points[0], &points[points.len() - 1]);
+    if temp <= first.temp as f32 {
+        return first.speed as f32;
+    }
+    if temp >= last.temp as f32 {
+        return last.speed as f32;
+    }
+    for w in points.windows(2) {
+        let (low, high) = (&w[0], &w[1]);
+        if temp <= high.temp as f32 {
+            let t = (temp - low.temp as f32) / (high.temp - low.temp) as f32;
+            return low.speed as f32 + t * (high.speed as f32 - low.speed as f32);
+        }
+    }
+    last.speed as f32
+}
+
+struct Engine {
+    curve: SoftwareCurve,
+    /// Temperature the target is computed from; lags falling temperatures
+    /// by up to the hysteresis.
+    basis: Option<f32>,
+    duty: Option<f32>,
+    written: Option<u8>,
+}
+
+impl Engine {
+    fn new(curve: SoftwareCurve) -> Self {
+        Engine {
+            curve,
+            basis: None,
+            duty: None,
+            written: None,
+        }
+    }
+
+    /// Advance by one tick; returns a duty to write when it changed.
+    fn step(&mut self, temp: u8, elapsed: Duration) -> Option<u8> {
+        let temp = temp as f32;
+        let basis = match self.basis {
+            Some(basis) if temp < basis && basis - temp < self.curve.hysteresis as f32 => basis,
+            _ => temp,
+        };
+        self.basis = Some(basis);
+
+        let target = interpolate(&self.curve.points, basis);
+        let max_change = self.curve.ramp_rate as f32 * elapsed.as_secs_f32();
+        let duty = match self.duty {
+            // Start at the target: the fans' current duty is unknown
+            None => target,
+            Some(duty) => duty + (target - duty).clamp(-max_change, max_change),
+        };
+        self.duty = Some(duty);
+
+        let rounded = duty.round() as u8;
+        if self.written == Some(rounded) {
+            return None;
+        }
+        Some(rounded)
+    }
+}
+
+async fn hottest(state: &SidecarState) -> Result<u8, String> {
+    match query_sidecar(state, &SidecarCommand::GetStatusLite).await? {
+        SidecarResponse::StatusLite(status) => Ok(status.cpu_temp.max(status.gpu_temp)),
+        SidecarResponse::Error { message } => Err(message),
+        _ => Err("Unexpected response".to_string()),
+    }
+}
+
+async fn run(state: SidecarState, curve: SoftwareCurve) {
+    let mut engine = Engine::new(curve);
+    let mut interval = tokio::time::interval(TICK);
+    loop {
+        interval.tick().await;
+        // Not connected or busy: try again next tick
+        let Ok(temp) = hottest(&state).await else {
+            continue;
+        };
+        let Some(percent) = engine.step(temp, TICK) else {
+            continue;
+        };
+        let cmd = SidecarCommand::SetFanSpeed {
+            percent,
+            force: false,
+        };
+        match run_write(&state, &cmd).await {
+            Ok(_) => engine.written = Some(percent),
+            Err(e) => eprintln!("Software curve: failed to set {}%: {}", percent, e),
+        }
+    }
+}
+
+/// Start the loop with `curve`, replacing one already running.
+pub(crate) fn start(app: &AppHandle, curve: SoftwareCurve) {
+    let state = app.state::<SidecarState>().inner().clone();
+    let task = tauri::async_runtime::spawn(run(state, curve));
+    let running = app.state::<SoftwareCurveState>();
+    let mut running = running.0.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(previous) = running.replace(task) {
+        previous.abort();
+    }
+}
+
+fn stop(app: &AppHandle) -> bool {
+    let running = app.state::<SoftwareCurveState>();
+    let mut running = running.0.lock().unwrap_or_else(|e| e.into_inner());
+    match running.take() {
+        Some(task) => {
+            task.abort();
+            true
+        }
+        None => false,
+    }
+}
+
+#[tauri::command]
+pub async fn get_software_curve() -> Result<Option<SoftwareCurve>, String> {
+    Ok(settings::load_settings()?.software_curve)
+}
+
+#[tauri::command]
+pub async fn enable_software_curve(app: AppHandle, curve: SoftwareCurve) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    validate(&curve)?;
+    let mut settings = settings::load_settings()?;
+    settings.software_curve = Some(curve.clone());
+    settings::save_settings(&settings)?;
+    start(&app, curve);
+    let message = "Software fan curve enabled".to_string();
+    actions::record(
+        &app,
+        ActionSource::Window,
+        ActionKind::FanCurve,
+        message.as_str(),
+        None,
+    );
+    Ok(message)
+}
+
+/// Stop the loop and give the fans back to the EC in Auto mode.
+#[tauri::command]
+pub async fn disable_software_curve(
+    app: AppHandle,
+    state: State<'_, SidecarState>,
+) -> Result<String, String> {
+    permissions::check(&app, Tier::Control)?;
+    let mut settings = settings::load_settings()?;
+    settings.software_curve = None;
+    settings::save_settings(&settings)?;
+    if !stop(&app) {
+        return Ok("Software fan curve was not running".to_string());
+    }
+    actions::record(
+        &app,
+        ActionSource::Window,
+        ActionKind::FanCurve,
+        "Software fan curve disabled",
+        None,
+    );
+    if state.is_connected().await {
+        crate::apply_fan_mode(&app, &state, "auto".to_string(), ActionSource::Window).await?;
+    }
+    Ok("Software fan curve disabled".to_string())
+}
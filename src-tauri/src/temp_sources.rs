@@ -0,0 +1,109 @@
+//! Diagnostics: the EC's temperature registers next to the kernel's own
+//! sensors. On a model whose register map is wrong, the EC "temperature"
+//! is some other byte and drifts away from what hwmon reports.
+//!
+//! CPU readings come from the `coretemp`, `k10temp` or `zenpower` hwmon
+//! driver, GPU readings from NVIDIA (see `gpu.rs`) or an `amdgpu` hwmon.
+
+use crate::gpu;
+use crate::sidecar::{query_sidecar, SidecarCommand, SidecarResponse, SidecarState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::State;
+use ts_rs::TS;
+
+pub(crate) const HWMON_PATH: &str = "/sys/class/hwmon";
+const CPU_DRIVERS: [&str; 3] = ["coretemp", "k10temp", "zenpower"];
+/// Labels of the whole-package reading, preferred over single cores.
+const CPU_PACKAGE_LABELS: [&str; 3] = ["Package id 0", "Tctl", "Tdie"];
+/// EC and kernel sensors sit in different spots and update at different
+/// rates, so a few degrees apart is normal.
+const DIVERGENCE_THRESHOLD: f32 = 10.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct TempSource {
+    /// `CPU` or `GPU`
+    pub sensor: String,
+    /// EC register the temperature is read from, e.g. `0x68`.
+    pub ec_register: String,
+    pub ec_temp: u8,
+    /// °C from the kernel or driver; `None` when no sensor was found.
+    pub reference_temp: Option<f32>,
+    /// hwmon driver name, or `nvidia`.
+    pub reference_source: Option<String>,
+    /// The readings differ by more than the threshold.
+    pub diverged: bool,
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// First hwmon temperature of a driver in `drivers`, preferring inputs
+/// labelled as the package. Returns °C and the driver name.
+fn hwmon_temp(drivers: &[&str], preferred_labels: &[&str]) -> Option<(f32, String)> {
+    let entries = fs::read_dir(HWMON_PATH).ok()?;
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let Some(name) = read_trimmed(&dir.join("name")) else {
+            continue;
+        };
+        if !drivers.contains(&name.as_str()) {
+            continue;
+        }
+        let label_of = |i: u32| read_trimmed(&dir.join(format!("temp{}_label", i)));
+        let input = (1..=32)
+            .find(|&i| label_of(i).is_some_and(|l| preferred_labels.contains(&l.as_str())))
+            .unwrap_or(1);
+        let millidegrees: i64 = read_trimmed(&dir.join(format!("temp{}_input", input)))?
+            .parse()
+            .ok()?;
+        return Some((millidegrees as f32 / 1000.0, name));
+    }
+    None
+}
+
+fn gpu_temp() -> Option<(f32, String)> {
+    if let Some(temp) = gpu::query().ok().and_then(|stats| stats.temperature) {
+        return Some((temp as f32, "nvidia".to_string()));
+    }
+    hwmon_temp(&["amdgpu"], &["edge"])
+}
+
+fn source(sensor: &str, register: u8, ec_temp: u8, reference: Option<(f32, String)>) -> TempSource {
+    let diverged = reference
+        .as_ref()
+        .is_some_and(|(temp, _)| (temp - ec_temp as f32).abs() > DIVERGENCE_THRESHOLD);
+    let (reference_temp, reference_source) = reference.unzip();
+    TempSource {
+        sensor: sensor.to_string(),
+        ec_register: format!("0x{:02X}", register),
+        ec_temp,
+        reference_temp,
+        reference_source,
+        diverged,
+    }
+}
+
+/// CPU and GPU temperatures from the EC and from the kernel, side by side.
+#[tauri::command]
+pub async fn get_temp_sources(state: State<'_, SidecarState>) -> Result<Vec<TempSource>, String> {
+    let status = crate::fetch_status(&state).await?;
+    let map = match query_sidecar(&state, &SidecarCommand::GetRegisterMap).await? {
+        SidecarResponse::RegisterMap(active) => active.map,
+        SidecarResponse::Error { message } => return Err(message),
+        _ => return Err("Unexpected response".to_string()),
+    };
+    let (cpu, gpu) =
+        tokio::task::spawn_blocking(|| (hwmon_temp(&CPU_DRIVERS, &CPU_PACKAGE_LABELS), gpu_temp()))
+            .await
+            .map_err(|e| format!("Task failed: {}", e))?;
+
+    Ok(vec![
+        source("CPU", map.cpu_temp, status.cpu_temp, cpu),
+        source("GPU", map.gpu_temp, status.gpu_temp, gpu),
+    ])
+}
@@ -0,0 +1,354 @@
+//! A stress test for comparing fan curves: load every CPU core for a set
+//! time while sampling the EC once a second, then report the peak and
+//! average temperatures, the time spent at or above 85 °C and the average
+//! fan speeds. Run it once per curve under the same conditions and the
+//! numbers can be compared.
+//!
+//! The load is busy loops on plain threads, one per logical CPU, so nothing
+//! beyond the app is needed. They stop when the test ends, fails or is
+//! stopped with `stop_thermal_test`, and early when the CPU or GPU stays
+//! at 95 °C or the sidecar's failsafe takes over the fans.
+//!
+//! Every run is saved to `~/.config/msi-fan-control/thermal_tests.json`
+//! under the active profile. A warm room heats the chips as much as a weak
+//! curve, so `get_test_history` compares runs by how far the temperatures
+//! rose above the room: the ambient sensor when the machine has one,
+//! otherwise the idle temperature before the load.
+
+use crate::history::now_ms;
+use crate::permissions::{self, Tier};
+use crate::sensors::{self, SensorKind};
+use crate::{fetch_status, settings, SidecarState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+use ts_rs::TS;
+
+const MIN_DURATION_SECS: u32 = 10;
+const MAX_DURATION_SECS: u32 = 30 * 60;
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+const HOT_CELSIUS: u8 = 85;
+/// Readings missed in a row before the load is stopped, as nothing watches
+/// the temperatures then.
+const MAX_MISSED: u32 = 5;
+/// The load stops once the CPU or GPU has read this hot `ABORT_SAMPLES`
+/// times in a row.
+const ABORT_CELSIUS: u8 = 95;
+const ABORT_SAMPLES: u32 = 3;
+
+const HISTORY_FILE: &str = "thermal_tests.json";
+const MAX_RUNS: usize = 200;
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+static FAILSAFE_TRIPPED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct ThermalTestReport {
+    /// How long the load ran, which is shorter than asked when stopped.
+    pub duration_secs: u32,
+    /// `false` when stopped early.
+    pub completed: bool,
+    /// Why the load was stopped for safety, if it was.
+    pub aborted: Option<String>,
+    /// Threads loading the CPU, one per logical CPU.
+    pub workers: u32,
+    /// Fan mode when the test started, to tell runs apart.
+    pub fan_mode: String,
+    pub samples: u32,
+    pub max_cpu_temp: u8,
+    pub max_gpu_temp: u8,
+    pub avg_cpu_temp: f64,
+    pub avg_gpu_temp: f64,
+    /// Time the CPU or GPU spent at or above 85 °C.
+    pub secs_above_85: u32,
+    pub avg_fan1_rpm: u32,
+    pub avg_fan2_rpm: u32,
+    /// Before the load started.
+    pub idle_cpu_temp: u8,
+    pub idle_gpu_temp: u8,
+    /// From an hwmon sensor labeled ambient, on machines that have one.
+    pub ambient_temp: Option<f64>,
+}
+
+/// A saved test.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct ThermalTestRun {
+    /// Unix time in milliseconds
+    #[ts(type = "number")]
+    pub timestamp_ms: u64,
+    /// Active profile when the test started; `None` before any was loaded.
+    pub profile: Option<String>,
+    pub report: ThermalTestReport,
+}
+
+/// A saved test with its temperatures normalized to the room.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema, TS)]
+#[ts(export)]
+pub struct ThermalTestEntry {
+    pub run: ThermalTestRun,
+    /// What the rises are measured from: the ambient temperature, or the
+    /// idle CPU temperature without an ambient sensor.
+    pub baseline_temp: f64,
+    /// Peak temperature above the baseline, in °C.
+    pub cpu_rise: f64,
+    pub gpu_rise: f64,
+    /// `cpu_rise` against the previous completed run of the same profile;
+    /// negative means it ran cooler.
+    pub cpu_rise_change: Option<f64>,
+}
+
+/// Clears the running flag and stops the workers however the test ends.
+struct Load {
+    stop: Arc<AtomicBool>,
+}
+
+impl Load {
+    fn start(workers: usize) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        for _ in 0..workers {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let mut x: u64 = 1;
+                while !stop.load(Ordering::Relaxed) {
+                    for _ in 0..100_000 {
+                        x = std::hint::black_box(
+                            x.wrapping_mul(6364136223846793005).wrapping_add(1),
+                        );
+                    }
+                }
+            });
+        }
+        Load { stop }
+    }
+}
+
+impl Drop for Load {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+#[derive(Default)]
+struct Totals {
+    samples: u32,
+    max_cpu_temp: u8,
+    max_gpu_temp: u8,
+    cpu_temp: f64,
+    gpu_temp: f64,
+    hot: Duration,
+    fan1_rpm: f64,
+    fan2_rpm: f64,
+}
+
+fn history_path() -> Result<std::path::PathBuf, String> {
+    Ok(crate::get_config_dir()?.join(HISTORY_FILE))
+}
+
+fn load_runs() -> Result<Vec<ThermalTestRun>, String> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid thermal test history: {}", e))
+}
+
+fn save_run(run: ThermalTestRun) -> Result<(), String> {
+    let mut runs = load_runs()?;
+    runs.push(run);
+    if runs.len() > MAX_RUNS {
+        runs.drain(..runs.len() - MAX_RUNS);
+    }
+    let path = history_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(&runs).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+async fn ambient_temp() -> Option<f64> {
+    let sensors = sensors::get_all_sensors().await.ok()?;
+    sensors
+        .into_iter()
+        .find(|s| s.kind == SensorKind::Temperature && s.label.to_lowercase().contains("ambient"))
+        .map(|s| s.value)
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// Load the CPU for `duration_secs` while recording temperatures and fan
+/// speeds, and save the result. Only one test runs at a time.
+#[tauri::command]
+pub async fn run_thermal_test(
+    app: AppHandle,
+    sidecar: State<'_, SidecarState>,
+    duration_secs: u32,
+) -> Result<ThermalTestReport, String> {
+    permissions::check(&app, Tier::Control)?;
+    if !(MIN_DURATION_SECS..=MAX_DURATION_SECS).contains(&duration_secs) {
+        return Err(format!(
+            "The test runs between {} and {} seconds",
+            MIN_DURATION_SECS, MAX_DURATION_SECS
+        ));
+    }
+    let start_status = fetch_status(&sidecar).await?;
+    // Before the load: an app rule or power change may switch profiles
+    // during the run
+    let profile = settings::load_settings()
+        .ok()
+        .and_then(|s| s.active_profile);
+    let ambient_temp = ambient_temp().await;
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("A thermal test is already running".to_string());
+    }
+    STOP_REQUESTED.store(false, Ordering::SeqCst);
+    FAILSAFE_TRIPPED.store(false, Ordering::SeqCst);
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let load = Load::start(workers);
+
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(duration_secs as u64);
+    let mut totals = Totals::default();
+    let mut last_sample = started;
+    let mut missed = 0;
+    let mut hot_streak = 0;
+    let mut aborted = None;
+    let mut ticker = tokio::time::interval(SAMPLE_INTERVAL);
+    while Instant::now() < deadline && !STOP_REQUESTED.load(Ordering::SeqCst) {
+        ticker.tick().await;
+        if FAILSAFE_TRIPPED.load(Ordering::SeqCst) {
+            aborted = Some("The failsafe took over the fans".to_string());
+            break;
+        }
+        let status = match fetch_status(&sidecar).await {
+            Ok(status) => status,
+            Err(e) => {
+                missed += 1;
+                if missed == MAX_MISSED {
+                    return Err(format!("Stopped the test, the EC can't be read: {}", e));
+                }
+                continue;
+            }
+        };
+        missed = 0;
+        let now = Instant::now();
+        let hottest = status.cpu_temp.max(status.gpu_temp);
+        if hottest >= HOT_CELSIUS {
+            totals.hot += now - last_sample;
+        }
+        last_sample = now;
+        totals.samples += 1;
+        totals.max_cpu_temp = totals.max_cpu_temp.max(status.cpu_temp);
+        totals.max_gpu_temp = totals.max_gpu_temp.max(status.gpu_temp);
+        totals.cpu_temp += status.cpu_temp as f64;
+        totals.gpu_temp += status.gpu_temp as f64;
+        totals.fan1_rpm += status.fan1_rpm as f64;
+        totals.fan2_rpm += status.fan2_rpm as f64;
+
+        hot_streak = if hottest >= ABORT_CELSIUS {
+            hot_streak + 1
+        } else {
+            0
+        };
+        if hot_streak == ABORT_SAMPLES {
+            aborted = Some(format!("Reached {} °C", hottest));
+            break;
+        }
+    }
+    let completed = aborted.is_none() && Instant::now() >= deadline;
+    let elapsed = started.elapsed();
+    drop(load);
+
+    if totals.samples == 0 {
+        return Err("No readings could be taken during the test".to_string());
+    }
+    let n = totals.samples as f64;
+    let report = ThermalTestReport {
+        duration_secs: elapsed.as_secs() as u32,
+        completed,
+        aborted,
+        workers: workers as u32,
+        fan_mode: start_status.fan_mode,
+        samples: totals.samples,
+        max_cpu_temp: totals.max_cpu_temp,
+        max_gpu_temp: totals.max_gpu_temp,
+        avg_cpu_temp: round1(totals.cpu_temp / n),
+        avg_gpu_temp: round1(totals.gpu_temp / n),
+        secs_above_85: totals.hot.as_secs() as u32,
+        avg_fan1_rpm: (totals.fan1_rpm / n).round() as u32,
+        avg_fan2_rpm: (totals.fan2_rpm / n).round() as u32,
+        idle_cpu_temp: start_status.cpu_temp,
+        idle_gpu_temp: start_status.gpu_temp,
+        ambient_temp,
+    };
+    let run = ThermalTestRun {
+        timestamp_ms: now_ms(),
+        profile,
+        report: report.clone(),
+    };
+    if let Err(e) = save_run(run) {
+        eprintln!("Failed to save the thermal test: {}", e);
+    }
+    Ok(report)
+}
+
+/// Saved tests, oldest first, of one profile or of all. Runs stopped early
+/// are listed but not compared against.
+#[tauri::command]
+pub async fn get_test_history(profile: Option<String>) -> Result<Vec<ThermalTestEntry>, String> {
+    let runs = load_runs()?;
+    let mut previous_rise: HashMap<Option<String>, f64> = HashMap::new();
+    let mut entries = Vec::new();
+    for run in runs {
+        let report = &run.report;
+        let baseline_temp = report.ambient_temp.unwrap_or(report.idle_cpu_temp as f64);
+        let cpu_rise = round1(report.max_cpu_temp as f64 - baseline_temp);
+        let gpu_rise = round1(report.max_gpu_temp as f64 - baseline_temp);
+        let cpu_rise_change = if report.completed {
+            previous_rise
+                .insert(run.profile.clone(), cpu_rise)
+                .map(|previous| round1(cpu_rise - previous))
+        } else {
+            None
+        };
+        if profile.is_none() || run.profile == profile {
+            entries.push(ThermalTestEntry {
+                run,
+                baseline_temp,
+                cpu_rise,
+                gpu_rise,
+                cpu_rise_change,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// The sidecar's failsafe tripped; a running test stops its load.
+pub(crate) fn on_failsafe() {
+    if RUNNING.load(Ordering::SeqCst) {
+        FAILSAFE_TRIPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+/// End a running test early; it still returns what it recorded.
+#[tauri::command]
+pub async fn stop_thermal_test() -> Result<bool, String> {
+    let running = RUNNING.load(Ordering::SeqCst);
+    if running {
+        STOP_REQUESTED.store(true, Ordering::SeqCst);
+    }
+    Ok(running)
+}
@@ -0,0 +1,481 @@
+//! System tray icon and menu.
+//!
+//! Tray actions go through the same helpers as the window commands, so both
+//! share the sidecar connection and the write lock in `SidecarState`. Check
+//! marks are updated optimistically and rolled back if the write fails.
+//! Which entries the menu shows is a setting; changing it, or the saved
+//! profiles, rebuilds the whole menu.
+
+use crate::actions::ActionSource;
+use crate::format::{format_value, NumberFormat, Sensor};
+use crate::permissions::{self, Tier};
+use crate::{overrides, profiles, settings, SidecarState, StatusLite};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, MenuItemKind, PredefinedMenuItem, Submenu};
+use tauri::tray::{TrayIconBuilder, TrayIconEvent};
+use tauri::{App, AppHandle, Manager, Wry};
+use ts_rs::TS;
+
+const TRAY_ID: &str = "msi-main-tray";
+const TRAY_TOOLTIP: &str = "MSI Fan Control";
+const MONITOR_ID: &str = "monitor";
+const COOLER_BOOST_ID: &str = "cooler_boost";
+const FAN_MODE_ID_PREFIX: &str = "fan_mode:";
+/// Sidecar mode names and their menu labels.
+const FAN_MODES: [(&str, &str); 4] = [
+    ("auto", "Auto"),
+    ("silent", "Silent"),
+    ("basic", "Basic"),
+    ("advanced", "Advanced"),
+];
+const NEXT_PROFILE_ID: &str = "next_profile";
+const PROFILE_ID_PREFIX: &str = "profile:";
+const OVERRIDE_ID: &str = "override";
+
+/// Which entries the tray menu shows.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct TrayMenuSettings {
+    /// Read-only line with the latest temperatures.
+    pub monitor: bool,
+    pub cooler_boost: bool,
+    /// The Fan Mode submenu.
+    pub fan_modes: bool,
+    /// The Profiles submenu and Next Profile.
+    pub profiles: bool,
+    pub show: bool,
+    pub quit: bool,
+}
+
+impl Default for TrayMenuSettings {
+    fn default() -> Self {
+        TrayMenuSettings {
+            monitor: false,
+            cooler_boost: true,
+            fan_modes: true,
+            profiles: true,
+            show: true,
+            quit: true,
+        }
+    }
+}
+
+/// Menu items whose state follows the hardware; `None` when hidden.
+#[derive(Clone)]
+struct MenuItems {
+    menu: Menu<Wry>,
+    /// Shown at the top of the menu only while an override is active.
+    override_item: MenuItem<Wry>,
+    monitor: Option<MenuItem<Wry>>,
+    cooler_boost: Option<CheckMenuItem<Wry>>,
+    fan_modes: Option<Submenu<Wry>>,
+    profiles: Option<Submenu<Wry>>,
+}
+
+/// The current menu, replaced by `rebuild_tray_menu`.
+#[derive(Default)]
+pub(crate) struct TrayMenu {
+    items: Mutex<Option<MenuItems>>,
+    /// Keeps the monitor line filled across rebuilds.
+    last_status: Mutex<Option<StatusLite>>,
+    /// Latest unresolved alert, shown in the tooltip.
+    alert: Mutex<Option<String>>,
+}
+
+/// Cloned out of the lock: menu calls block on the main thread, which may
+/// be waiting for the lock in a menu event handler.
+fn menu_items(app: &AppHandle) -> Option<MenuItems> {
+    app.try_state::<TrayMenu>()?.items.lock().ok()?.clone()
+}
+
+pub(crate) fn build(app: &App) -> tauri::Result<()> {
+    app.manage(TrayMenu::default());
+
+    let tray_icon =
+        Image::from_bytes(include_bytes!("../icons/32x32.png")).expect("Failed to load tray icon");
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .show_menu_on_left_click(false)
+        .icon(tray_icon)
+        .tooltip(TRAY_TOOLTIP)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "quit" => {
+                app.exit(0);
+            }
+            "show" => show_window(app),
+            COOLER_BOOST_ID => toggle_cooler_boost(app),
+            NEXT_PROFILE_ID => next_profile(app),
+            id => {
+                if let Some(name) = id.strip_prefix(PROFILE_ID_PREFIX) {
+                    switch_profile(app, name.to_string());
+                } else if let Some(mode) = id.strip_prefix(FAN_MODE_ID_PREFIX) {
+                    switch_fan_mode(app, mode.to_string());
+                }
+            }
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click { .. } = event {
+                show_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    rebuild_tray_menu(app.handle())
+}
+
+fn monitor_text(status: Option<&StatusLite>) -> String {
+    match status {
+        Some(status) => temperatures(status),
+        None => "CPU -- · GPU --".to_string(),
+    }
+}
+
+/// "CPU 72 °C · GPU 65 °C", in the user's unit.
+fn temperatures(status: &StatusLite) -> String {
+    let fmt = NumberFormat::for_user();
+    format!(
+        "CPU {} · GPU {}",
+        format_value(Sensor::Temperature, status.cpu_temp as f64, &fmt),
+        format_value(Sensor::Temperature, status.gpu_temp as f64, &fmt)
+    )
+}
+
+/// Build the menu from the tray settings and swap it in. Runs at startup
+/// and whenever the settings or the saved profiles change.
+pub(crate) fn rebuild_tray_menu(app: &AppHandle) -> tauri::Result<()> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let shown = settings::load_settings()
+        .map(|s| s.tray_menu)
+        .unwrap_or_default();
+    let menu = Menu::new(app)?;
+    let mut items = MenuItems {
+        menu: menu.clone(),
+        override_item: MenuItem::with_id(app, OVERRIDE_ID, "", false, None::<&str>)?,
+        monitor: None,
+        cooler_boost: None,
+        fan_modes: None,
+        profiles: None,
+    };
+
+    if shown.monitor {
+        let last_status = app
+            .state::<TrayMenu>()
+            .last_status
+            .lock()
+            .ok()
+            .and_then(|s| *s);
+        let text = monitor_text(last_status.as_ref());
+        let item = MenuItem::with_id(app, MONITOR_ID, text, false, None::<&str>)?;
+        menu.append(&item)?;
+        items.monitor = Some(item);
+    }
+    if shown.cooler_boost {
+        let item = CheckMenuItem::with_id(
+            app,
+            COOLER_BOOST_ID,
+            "Cooler Boost",
+            true,
+            profiles::load_last_applied()
+                .ok()
+                .flatten()
+                .is_some_and(|p| p.cooler_boost),
+            None::<&str>,
+        )?;
+        menu.append(&item)?;
+        items.cooler_boost = Some(item);
+    }
+    if shown.fan_modes {
+        let last_mode = profiles::load_last_applied()
+            .ok()
+            .flatten()
+            .map(|p| p.fan_mode);
+        let submenu = Submenu::with_id(app, "fan_modes", "Fan Mode", true)?;
+        for (mode, label) in FAN_MODES {
+            submenu.append(&CheckMenuItem::with_id(
+                app,
+                format!("{}{}", FAN_MODE_ID_PREFIX, mode),
+                label,
+                true,
+                last_mode.as_deref() == Some(mode),
+                None::<&str>,
+            )?)?;
+        }
+        menu.append(&submenu)?;
+        items.fan_modes = Some(submenu);
+    }
+    if shown.profiles {
+        let submenu = Submenu::with_id(app, "profiles", "Profiles", true)?;
+        fill_profiles(app, &submenu);
+        menu.append(&submenu)?;
+        menu.append(&MenuItem::with_id(
+            app,
+            NEXT_PROFILE_ID,
+            "Next Profile",
+            true,
+            None::<&str>,
+        )?)?;
+        items.profiles = Some(submenu);
+    }
+    if (shown.show || shown.quit) && !menu.items()?.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    if shown.show {
+        menu.append(&MenuItem::with_id(app, "show", "Show", true, None::<&str>)?)?;
+    }
+    if shown.quit {
+        menu.append(&MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?)?;
+    }
+
+    if let Ok(mut current) = app.state::<TrayMenu>().items.lock() {
+        *current = Some(items);
+    }
+    set_override(app, overrides::label(app).as_deref());
+    tray.set_menu(Some(menu))
+}
+
+#[tauri::command]
+pub async fn get_tray_menu_settings() -> Result<TrayMenuSettings, String> {
+    Ok(settings::load_settings()?.tray_menu)
+}
+
+#[tauri::command]
+pub async fn set_tray_menu_settings(
+    app: AppHandle,
+    menu: TrayMenuSettings,
+) -> Result<TrayMenuSettings, String> {
+    permissions::check(&app, Tier::Control)?;
+    let mut settings = settings::load_settings()?;
+    settings.tray_menu = menu;
+    settings::save_settings(&settings)?;
+    rebuild_tray_menu(&app).map_err(|e| e.to_string())?;
+    Ok(menu)
+}
+
+fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Keep the Cooler Boost check mark in sync after a write from anywhere.
+pub(crate) fn set_cooler_boost_checked(app: &AppHandle, enabled: bool) {
+    if let Some(item) = menu_items(app).and_then(|items| items.cooler_boost) {
+        let _ = item.set_checked(enabled);
+    }
+}
+
+/// Check only `mode` in the Fan Mode submenu.
+pub(crate) fn set_fan_mode_checked(app: &AppHandle, mode: &str) {
+    let active_id = format!("{}{}", FAN_MODE_ID_PREFIX, mode);
+    for item in check_items(menu_items(app).and_then(|items| items.fan_modes)) {
+        let _ = item.set_checked(item.id().as_ref() == active_id);
+    }
+}
+
+/// Show the latest background reading in the tooltip and menu.
+pub(crate) fn update_status(app: &AppHandle, status: &StatusLite) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let mut tooltip = format!("{}\n{}", TRAY_TOOLTIP, temperatures(status));
+        if status.boost_cooldown_suggested {
+            tooltip.push_str("\nCooler Boost has run long, consider turning it off");
+        }
+        if let Some(label) = overrides::label(app) {
+            tooltip.push('\n');
+            tooltip.push_str(&label);
+        }
+        if let Some(alert) = alert(app) {
+            tooltip.push_str("\n⚠ ");
+            tooltip.push_str(&alert);
+        }
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+    if let Some(tray) = app.try_state::<TrayMenu>() {
+        if let Ok(mut last_status) = tray.last_status.lock() {
+            *last_status = Some(*status);
+        }
+    }
+    if let Some(item) = menu_items(app).and_then(|items| items.monitor) {
+        let _ = item.set_text(monitor_text(Some(status)));
+    }
+    set_cooler_boost_checked(app, status.cooler_boost);
+}
+
+fn alert(app: &AppHandle) -> Option<String> {
+    app.try_state::<TrayMenu>()?.alert.lock().ok()?.clone()
+}
+
+/// Badge the tray icon with an alert, or clear it. The text is added to
+/// the tooltip on the next status update.
+pub(crate) fn set_alert(app: &AppHandle, alert: Option<String>) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_title(alert.as_ref().map(|_| "⚠"));
+    }
+    if let Some(menu) = app.try_state::<TrayMenu>() {
+        if let Ok(mut current) = menu.alert.lock() {
+            *current = alert;
+        }
+    }
+}
+
+/// Show the active manual override at the top of the menu, or remove the
+/// entry once the firmware is back in charge.
+pub(crate) fn set_override(app: &AppHandle, label: Option<&str>) {
+    let Some(items) = menu_items(app) else {
+        return;
+    };
+    let shown = items.menu.get(OVERRIDE_ID).is_some();
+    match label {
+        Some(label) => {
+            let _ = items.override_item.set_text(label);
+            if !shown {
+                let _ = items.menu.insert(&items.override_item, 0);
+            }
+        }
+        None if shown => {
+            let _ = items.menu.remove(&items.override_item);
+        }
+        None => {}
+    }
+}
+
+fn fill_profiles(app: &AppHandle, submenu: &Submenu<Wry>) {
+    let profiles = profiles::load_profiles().unwrap_or_default();
+    if profiles.is_empty() {
+        if let Ok(item) = MenuItem::new(app, "No profiles", false, None::<&str>) {
+            let _ = submenu.append(&item);
+        }
+        return;
+    }
+    let active = crate::settings::load_settings()
+        .ok()
+        .and_then(|s| s.active_profile);
+    for profile in profiles {
+        let id = format!("{}{}", PROFILE_ID_PREFIX, profile.name);
+        let checked = active.as_deref() == Some(profile.name.as_str());
+        if let Ok(item) =
+            CheckMenuItem::with_id(app, id, &profile.name, true, checked, None::<&str>)
+        {
+            let _ = submenu.append(&item);
+        }
+    }
+}
+
+/// Check only the named profile in the Profiles submenu.
+pub(crate) fn set_active_profile(app: &AppHandle, name: &str) {
+    let active_id = format!("{}{}", PROFILE_ID_PREFIX, name);
+    for item in profile_items(app) {
+        let _ = item.set_checked(item.id().as_ref() == active_id);
+    }
+}
+
+fn next_profile(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<SidecarState>().inner().clone();
+        if let Err(e) = profiles::cycle(
+            &app,
+            &state,
+            profiles::CycleDirection::Next,
+            ActionSource::Tray,
+        )
+        .await
+        {
+            eprintln!("Tray: failed to cycle profile: {}", e);
+        }
+    });
+}
+
+fn toggle_cooler_boost(app: &AppHandle) {
+    let Some(item) = menu_items(app).and_then(|items| items.cooler_boost) else {
+        return;
+    };
+    // The menu flips the check mark on click, so it already shows the
+    // requested state
+    let enabled = item.is_checked().unwrap_or(false);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<SidecarState>().inner().clone();
+        if let Err(e) = crate::apply_cooler_boost(&app, &state, enabled, ActionSource::Tray).await {
+            eprintln!("Tray: failed to set Cooler Boost: {}", e);
+            set_cooler_boost_checked(&app, !enabled);
+        }
+    });
+}
+
+fn check_items(submenu: Option<Submenu<Wry>>) -> Vec<CheckMenuItem<Wry>> {
+    let Some(submenu) = submenu else {
+        return Vec::new();
+    };
+    submenu
+        .items()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|item| match item {
+            MenuItemKind::Check(check) => Some(check),
+            _ => None,
+        })
+        .collect()
+}
+
+fn profile_items(app: &AppHandle) -> Vec<CheckMenuItem<Wry>> {
+    check_items(menu_items(app).and_then(|items| items.profiles))
+}
+
+fn switch_fan_mode(app: &AppHandle, mode: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let previous = profiles::load_last_applied()
+            .ok()
+            .flatten()
+            .map(|p| p.fan_mode);
+        // The click toggled only the clicked item
+        set_fan_mode_checked(&app, &mode);
+        let state = app.state::<SidecarState>().inner().clone();
+        if let Err(e) = crate::apply_fan_mode(&app, &state, mode, ActionSource::Tray).await {
+            eprintln!("Tray: failed to set fan mode: {}", e);
+            set_fan_mode_checked(&app, previous.as_deref().unwrap_or_default());
+        }
+    });
+}
+
+fn switch_profile(app: &AppHandle, name: String) {
+    let items = profile_items(app);
+    let clicked_id = format!("{}{}", PROFILE_ID_PREFIX, name);
+    // Remember the marks as they were before the click for rollback
+    let previous: Vec<bool> = items
+        .iter()
+        .map(|item| {
+            let checked = item.is_checked().unwrap_or(false);
+            if item.id().as_ref() == clicked_id {
+                !checked
+            } else {
+                checked
+            }
+        })
+        .collect();
+    for item in &items {
+        let _ = item.set_checked(item.id().as_ref() == clicked_id);
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<SidecarState>().inner().clone();
+        match profiles::switch_profile(&app, &state, &name, ActionSource::Tray, None).await {
+            Ok(profile) => profiles::notify_profile_changed(&app, &profile),
+            Err(e) => {
+                eprintln!("Tray: failed to load profile '{}': {}", name, e);
+                for (item, checked) in items.iter().zip(previous) {
+                    let _ = item.set_checked(checked);
+                }
+            }
+        }
+    });
+}
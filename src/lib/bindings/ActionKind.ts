@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ActionKind = "profile_applied" | "fan_mode" | "fan_speed" | "fan_curve" | "cooler_boost" | "failsafe" | "register_write";
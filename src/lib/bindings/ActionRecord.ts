@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ActionKind } from "./ActionKind";
+import type { ActionSource } from "./ActionSource";
+
+export type ActionRecord = { 
+/**
+ * Unix time in milliseconds
+ */
+timestamp_ms: number, source: ActionSource, kind: ActionKind, 
+/**
+ * What was done, e.g. "Fan mode set to silent".
+ */
+detail: string, 
+/**
+ * Why, when it wasn't a direct user request.
+ */
+reason: string | null, };
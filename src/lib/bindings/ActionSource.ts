@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Who triggered an action.
+ */
+export type ActionSource = "window" | "tray" | "hotkey" | "startup" | "failsafe" | "app_rule" | "power_source" | "resume";
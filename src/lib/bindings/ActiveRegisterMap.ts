@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RegisterMapSource } from "./RegisterMapSource";
+import type { RegisterMapSpec } from "./RegisterMapSpec";
+
+export type ActiveRegisterMap = { source: RegisterMapSource, map: RegisterMapSpec, 
+/**
+ * Why an override file that exists was not used.
+ */
+override_error: string | null, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AnomalyKind } from "./AnomalyKind";
+
+export type Anomaly = { kind: AnomalyKind, 
+/**
+ * Unix time in milliseconds
+ */
+timestamp_ms: number, message: string, };
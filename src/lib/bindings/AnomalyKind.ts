@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AnomalyKind = "cpu_temp_spike" | "gpu_temp_spike" | "idle_temp_creep" | "fan1_rpm_drop" | "fan2_rpm_drop" | "fan1_stalled" | "fan2_stalled" | "fan1_oscillating" | "fan2_oscillating";
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AppRule = { 
+/**
+ * Process name as in `ps`, e.g. `steam` or `blender`; case-insensitive.
+ */
+process: string, 
+/**
+ * Profile loaded while the process runs.
+ */
+profile: string, };
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BackupTrigger } from "./BackupTrigger";
+import type { Profile } from "./Profile";
+
+export type Backup = { 
+/**
+ * Unix time in milliseconds; also identifies the backup.
+ */
+timestamp_ms: number, trigger: BackupTrigger, settings: Profile, };
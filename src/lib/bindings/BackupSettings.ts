@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type BackupSettings = { 
+/**
+ * Days between scheduled backups; 0 turns them off.
+ */
+interval_days: number, 
+/**
+ * Back up before each profile load.
+ */
+before_profile: boolean, 
+/**
+ * Backups kept; the oldest go first.
+ */
+keep: number, };
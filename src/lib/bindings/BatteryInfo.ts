@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type BatteryInfo = { 
+/**
+ * Kernel name, e.g. `BAT0`.
+ */
+name: string, 
+/**
+ * As the driver reports it: `Charging`, `Discharging`, `Full` or
+ * `Not charging`.
+ */
+status: string, charge_percent: number | null, 
+/**
+ * Charge or discharge rate, whichever `status` says is happening.
+ */
+power_watts: number | null, cycle_count: number | null, design_capacity_wh: number | null, 
+/**
+ * What the battery holds when full today.
+ */
+full_capacity_wh: number | null, 
+/**
+ * Full capacity as a share of the design capacity.
+ */
+health_percent: number | null, };
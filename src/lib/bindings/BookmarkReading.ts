@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RegisterBookmark } from "./RegisterBookmark";
+
+export type BookmarkReading = { bookmark: RegisterBookmark, value: number, 
+/**
+ * Whether the watch expression holds; `None` without one.
+ */
+watch_holds: boolean | null, };
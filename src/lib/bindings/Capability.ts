@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Something the app can do, reported on its own so a failure in one
+ * leaves the others usable.
+ */
+export type Capability = "monitor" | "write" | "cooler_boost" | "fan_curves" | "battery_threshold";
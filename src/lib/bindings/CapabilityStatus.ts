@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Capability } from "./Capability";
+import type { CapabilityState } from "./CapabilityState";
+
+export type CapabilityStatus = { capability: Capability, state: CapabilityState, 
+/**
+ * Why it is degraded or unavailable; `None` when available.
+ */
+reason: string | null, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CheckId = "debugfs" | "ec_sys" | "persistence" | "secure_boot" | "msi_ec" | "polkit" | "model";
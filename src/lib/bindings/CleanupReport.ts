@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What `cleanup_system_files` did. Entries are paths, with the reason for
+ * kept and failed ones.
+ */
+export type CleanupReport = { removed: Array<string>, 
+/**
+ * Owned by the package, or edited by someone else.
+ */
+kept: Array<string>, failed: Array<string>, };
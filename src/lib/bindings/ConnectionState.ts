@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ConnectionState = { "state": "connected" } | { "state": "disconnected" } | { "state": "idle" } | { "state": "reconnecting", attempt: number, retry_in_ms: number, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContributionStep } from "./ContributionStep";
+import type { ProbeResult } from "./ProbeResult";
+import type { RegisterBookmark } from "./RegisterBookmark";
+import type { SupportLevel } from "./SupportLevel";
+
+export type ContributionReport = { model: string, backend: string, support_level: SupportLevel, probes: Array<ProbeResult>, steps: Array<ContributionStep>, bookmarks: Array<RegisterBookmark>, 
+/**
+ * Rust snippet for `quirks.rs`.
+ */
+draft_quirks: string, 
+/**
+ * Markdown summary to paste into a GitHub issue.
+ */
+report: string, };
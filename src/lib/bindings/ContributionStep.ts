@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RegisterChange } from "./RegisterChange";
+
+export type ContributionStep = { 
+/**
+ * What the user did, e.g. "Pressed Fn+F8 (Cooler Boost on)".
+ */
+label: string, changes: Array<RegisterChange>, };
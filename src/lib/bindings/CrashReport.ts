@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CrashSource } from "./CrashSource";
+
+export type CrashReport = { 
+/**
+ * Unix time in milliseconds.
+ */
+timestamp_ms: number, source: CrashSource, 
+/**
+ * Version of the app or sidecar that crashed.
+ */
+version: string, message: string, 
+/**
+ * `file:line:column` of the panic.
+ */
+location: string | null, thread: string | null, backtrace: string, 
+/**
+ * The last lines the sidecar logged, oldest first.
+ */
+log_tail: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One point of an EC fan curve: at `temp` °C and above, run at `speed` %.
+ */
+export type CurvePoint = { temp: number, speed: number, };
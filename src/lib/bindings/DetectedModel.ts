@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type DetectedModel = { 
+/**
+ * DMI product name.
+ */
+model: string, 
+/**
+ * Register map used for raw EC access; `generic` when the model isn't
+ * in the built-in table.
+ */
+register_map: string, 
+/**
+ * Backend in use; with `msi-ec` the driver's own config applies instead.
+ */
+backend: string, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type DiskHealth = { 
+/**
+ * Kernel name, e.g. `nvme0n1` or `sda`.
+ */
+device: string, model: string | null, 
+/**
+ * °C
+ */
+temperature: number | null, 
+/**
+ * Share of the rated endurance used; may pass 100.
+ */
+percentage_used: number | null, bytes_written: number | null, 
+/**
+ * The drive's overall SMART self-assessment.
+ */
+smart_passed: boolean | null, 
+/**
+ * Why smartctl didn't report, e.g. not installed or needs root.
+ */
+smart_error: string | null, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BookmarkReading } from "./BookmarkReading";
+
+export type EcDump = { data: Array<number>, 
+/**
+ * 16 bytes per line behind their offset, e.g. `60: 00 1f ...`.
+ */
+hex: string, 
+/**
+ * Bookmarked registers and their values in this dump.
+ */
+bookmarks: Array<BookmarkReading>, };
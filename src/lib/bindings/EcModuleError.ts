@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Why `ec_sys` could not be loaded.
+ */
+export type EcModuleError = { 
+/**
+ * Running kernel release, as `uname -r` reports it.
+ */
+kernel: string, 
+/**
+ * No `ec_sys` is installed for the running kernel.
+ */
+module_missing: boolean, 
+/**
+ * The running kernel's modules are gone, usually because an update
+ * replaced the kernel; rebooting into the new one fixes it.
+ */
+reboot_required: boolean, 
+/**
+ * Package shipping the module on this distribution, when it is split
+ * out of the kernel package.
+ */
+suggested_package: string | null, 
+/**
+ * What modprobe reported.
+ */
+detail: string, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A fan beyond the CPU and GPU fans, e.g. the third fan of GE and Titan
+ * models. It is monitored only; writes still cover the first two fans.
+ */
+export type ExtraFanSpec = { name: string, 
+/**
+ * `[low, high]` tachometer bytes.
+ */
+rpm: [number, number], 
+/**
+ * Register holding the fan's current duty in %, if known.
+ */
+duty: number | null, };
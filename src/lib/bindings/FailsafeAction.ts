@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What the watchdog does when it trips.
+ */
+export type FailsafeAction = "auto" | "cooler_boost";
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FailsafeAction } from "./FailsafeAction";
+
+/**
+ * Watchdog settings. It only acts while a fixed fan speed or Silent mode
+ * is active.
+ */
+export type FailsafeConfig = { 
+/**
+ * Trip when the CPU or GPU reaches this temperature, in °C.
+ */
+max_temp: number, 
+/**
+ * Trip when no heartbeat arrived for this long; 0 disables. Only
+ * armed once the first heartbeat is received.
+ */
+heartbeat_timeout_secs: number, action: FailsafeAction, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Which fan a per-fan command targets.
+ */
+export type Fan = "fan1" | "fan2";
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How the sidecar smooths fan speed changes. All zeros (the default)
+ * writes speeds at once and leaves curves to the EC.
+ */
+export type FanBehavior = { 
+/**
+ * °C a temperature has to fall before a curve steps down. Non-zero
+ * makes the sidecar follow curves itself instead of the EC.
+ */
+hysteresis: number, 
+/**
+ * Fastest speed change in % per second; 0 changes at once. Non-zero
+ * also makes the sidecar follow curves itself.
+ */
+ramp_rate: number, };
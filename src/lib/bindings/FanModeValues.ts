@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * EC values the fan mode register takes in each mode.
+ */
+export type FanModeValues = { auto: number, silent: number, basic: number, advanced: number, };
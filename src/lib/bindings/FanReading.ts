@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One fan in a status reading.
+ */
+export type FanReading = { 
+/**
+ * `CPU`, `GPU`, or the name the register map gives it.
+ */
+name: string, rpm: number, 
+/**
+ * Duty in % the EC is driving the fan at, when the backend can tell.
+ */
+target_percent: number | null, };
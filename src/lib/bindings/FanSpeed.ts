@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Fan } from "./Fan";
+
+/**
+ * A speed set for one fan by `set_fan_speed_for`.
+ */
+export type FanSpeed = { fan: Fan, percent: number, };
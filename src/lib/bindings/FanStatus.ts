@@ -0,0 +1,44 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FanReading } from "./FanReading";
+import type { SupportLevel } from "./SupportLevel";
+
+export type FanStatus = { cpu_temp: number, gpu_temp: number, 
+/**
+ * The first two entries of `fans`, for clients that predate it.
+ */
+fan1_rpm: number, fan2_rpm: number, 
+/**
+ * Every fan, the CPU fan first and the GPU fan second.
+ */
+fans: Array<FanReading>, cooler_boost: boolean, fan_mode: string, 
+/**
+ * Cooler Boost has run longer than the model's recommended limit.
+ */
+boost_cooldown_suggested: boolean, 
+/**
+ * Hardware backend that served this reading (`ec_sys` or `msi-ec`).
+ */
+backend: string, 
+/**
+ * DMI product name.
+ */
+model: string, 
+/**
+ * Writes on anything but `Verified` need a per-feature opt-in and a
+ * trial write.
+ */
+support_level: SupportLevel, 
+/**
+ * The sidecar runs with `--simulate` against an in-memory EC.
+ */
+simulated: boolean, 
+/**
+ * `--demo`: simulated, with animated readings for screenshots and
+ * trying the UI.
+ */
+demo: boolean, 
+/**
+ * Fan trouble seen right now, e.g. a stalled fan. Filled in by the
+ * app from its anomaly checks; the sidecar leaves it empty.
+ */
+warnings: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Fixes the app can run; each asks for the admin password.
+ */
+export type Fix = "mount_debugfs" | "load_ec_sys" | "install_persistence" | "install_polkit_policy";
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FanStatus } from "./FanStatus";
+
+/**
+ * `FanStatus` with every reading pre-formatted for display, in the user's
+ * units.
+ */
+export type FormattedFanStatus = { cpu_temp: string, gpu_temp: string, fan1_rpm: string, fan2_rpm: string, cooler_boost: boolean, fan_mode: string, 
+/**
+ * The readings as the sidecar reported them, in °C and RPM.
+ */
+raw: FanStatus, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Fields are `None` when the driver doesn't report them (common for
+ * clocks and utilization on Optimus laptops with the dGPU asleep).
+ */
+export type GpuStats = { model: string, temperature: number | null, 
+/**
+ * Percent
+ */
+utilization: number | null, 
+/**
+ * Bytes
+ */
+memory_used: number | null, 
+/**
+ * Bytes
+ */
+memory_total: number | null, graphics_clock_mhz: number | null, memory_clock_mhz: number | null, 
+/**
+ * `nvml` or `nvidia-smi`
+ */
+source: "nvml" | "nvidia-smi", };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type HistorySample = { 
+/**
+ * Unix time in milliseconds
+ */
+timestamp_ms: number, cpu_temp: number, gpu_temp: number, 
+/**
+ * `None` for samples taken with the lite query while the window was hidden.
+ */
+fan1_rpm: number | null, fan2_rpm: number | null, };
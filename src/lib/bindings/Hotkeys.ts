@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One binding per action; `None` leaves the action unbound.
+ */
+export type Hotkeys = { cooler_boost: string | null, };
@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SensorKind } from "./SensorKind";
+
+export type HwmonSensor = { 
+/**
+ * Driver name, e.g. `nvme`, `coretemp` or `acpitz`.
+ */
+chip: string, 
+/**
+ * hwmon device, e.g. `hwmon3`; tells two chips with one driver apart.
+ */
+device: string, 
+/**
+ * The driver's label, e.g. `Composite`, or the attribute name without
+ * one, e.g. `temp1`.
+ */
+label: string, kind: SensorKind, 
+/**
+ * In the kind's unit.
+ */
+value: number, };
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Profile } from "./Profile";
+
+export type ImportedProfile = { profile: Profile, 
+/**
+ * Model the profile was tuned on, when the file says, so the window
+ * can warn when it isn't this one.
+ */
+model: string | null, };
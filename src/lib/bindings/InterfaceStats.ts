@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type InterfaceStats = { name: string, 
+/**
+ * Bytes per second since the previous call.
+ */
+received_per_sec: number, transmitted_per_sec: number, 
+/**
+ * Bytes since the interface came up.
+ */
+total_received: number, total_transmitted: number, };
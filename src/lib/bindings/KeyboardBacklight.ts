@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Keyboard backlight level as exposed by the LED class device.
+ */
+export type KeyboardBacklight = { brightness: number, max_brightness: number, };
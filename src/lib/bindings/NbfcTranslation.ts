@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FanCurve } from "./FanCurve";
+import type { RegisterBookmark } from "./RegisterBookmark";
+import type { SoftwareCurve } from "./SoftwareCurve";
+
+export type NbfcTranslation = { model: string, 
+/**
+ * From the first two fans' thresholds; the second fan's curve is the
+ * first's on single-fan configs.
+ */
+curve: FanCurve | null, 
+/**
+ * From the first fan's thresholds.
+ */
+software_curve: SoftwareCurve | null, 
+/**
+ * NBFC's critical temperature, for `set_failsafe`.
+ */
+failsafe_max_temp: number | null, 
+/**
+ * Every register the config reads or writes, for
+ * `set_register_bookmarks`.
+ */
+bookmarks: Array<RegisterBookmark>, 
+/**
+ * What was left out or approximated.
+ */
+notes: Array<string>, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { InterfaceStats } from "./InterfaceStats";
+
+export type NetworkStats = { 
+/**
+ * Sum over all interfaces, loopback excluded.
+ */
+received_per_sec: number, transmitted_per_sec: number, interfaces: Array<InterfaceStats>, };
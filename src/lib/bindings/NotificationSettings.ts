@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SinkRoute } from "./SinkRoute";
+
+export type NotificationSettings = { enabled: boolean, 
+/**
+ * °C, for both CPU and GPU.
+ */
+warn_temp: number, critical_temp: number, 
+/**
+ * A fan at 0 RPM is reported once its chip is this hot.
+ */
+stalled_fan_temp: number, routes: Array<SinkRoute>, };
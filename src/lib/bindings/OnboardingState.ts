@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OnboardingStep } from "./OnboardingStep";
+
+export type OnboardingState = { completed: Array<OnboardingStep>, 
+/**
+ * Steps still to do, in the order the frontend should suggest them.
+ */
+pending: Array<OnboardingStep>, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What `install_persistence` or `remove_persistence` did to the files
+ * that load `ec_sys` at boot.
+ */
+export type PersistenceReport = { 
+/**
+ * Both files are in place with the expected contents.
+ */
+installed: boolean, written: Array<string>, removed: Array<string>, 
+/**
+ * Left alone because their contents aren't what the sidecar writes.
+ */
+kept: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Profile names per source; a source without one leaves the fans alone.
+ */
+export type PowerProfiles = { ac: string | null, battery: string | null, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CheckId } from "./CheckId";
+import type { CheckStatus } from "./CheckStatus";
+import type { Fix } from "./Fix";
+
+export type PreflightCheck = { id: CheckId, status: CheckStatus, detail: string, 
+/**
+ * What to do about it by hand, when it isn't fine.
+ */
+advice: string | null, fix: Fix | null, };
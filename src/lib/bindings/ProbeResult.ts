@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Result of a sanity check against the register layout we know.
+ */
+export type ProbeResult = { name: string, passed: boolean, detail: string, };
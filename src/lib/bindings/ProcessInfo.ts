@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ProcessInfo = { pid: number, name: string, 
+/**
+ * Percent of one core since the previous call, so up to 100 × cores.
+ */
+cpu_usage: number, 
+/**
+ * Resident memory in bytes.
+ */
+memory: number, };
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FanCurve } from "./FanCurve";
+import type { FanSpeed } from "./FanSpeed";
+
+export type Profile = { name: string, fan_mode: string, 
+/**
+ * Flat speed for all curve points, as set by `set_fan_speed`.
+ */
+fan_speed: number | null, curve: FanCurve | null, 
+/**
+ * Speeds for single fans, applied over `fan_speed` or `curve`.
+ */
+fan_speeds: Array<FanSpeed>, cooler_boost: boolean, 
+/**
+ * Keyboard backlight level; untouched when `None`.
+ */
+keyboard_backlight: number | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type RegisterChange = { offset: number, before: number, after: number, 
+/**
+ * The register's bookmark label.
+ */
+label: string | null, };
@@ -0,0 +1,37 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExtraFanSpec } from "./ExtraFanSpec";
+import type { FanModeValues } from "./FanModeValues";
+
+/**
+ * Register layout for raw EC access. Also the format of the user override
+ * file, so a map reported by `get_register_map` can be saved and edited.
+ */
+export type RegisterMapSpec = { name: string, cpu_temp: number, gpu_temp: number, cooler_boost: number, cooler_boost_bit: number, 
+/**
+ * `[low, high]` tachometer byte pairs of the CPU fan, in probe order.
+ */
+fan1_rpm: Array<[number, number]>, 
+/**
+ * `None` on single-fan models.
+ */
+fan2_rpm: [number, number] | null, 
+/**
+ * Fan mode register candidates, in probe order.
+ */
+fan_mode: Array<number>, modes: FanModeValues, 
+/**
+ * First of 7 speed points per fan.
+ */
+fan1_speed_start: number, fan2_speed_start: number, 
+/**
+ * First of 6 temperature thresholds per fan.
+ */
+fan1_temp_start: number, fan2_temp_start: number, 
+/**
+ * Tachometers hold `rpm_divisor / RPM`.
+ */
+rpm_divisor: number, 
+/**
+ * Fans beyond the CPU and GPU fans.
+ */
+extra_fans: Array<ExtraFanSpec>, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Resumed = { 
+/**
+ * Roughly how long the laptop was suspended.
+ */
+slept_secs: bigint, 
+/**
+ * Whether the last applied settings were written again.
+ */
+reapplied: boolean, 
+/**
+ * Why they weren't, when there was something to re-apply.
+ */
+error: string | null, };
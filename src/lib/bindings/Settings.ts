@@ -0,0 +1,127 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AppRule } from "./AppRule";
+import type { BackupSettings } from "./BackupSettings";
+import type { FailsafeConfig } from "./FailsafeConfig";
+import type { FanBehavior } from "./FanBehavior";
+import type { Hotkeys } from "./Hotkeys";
+import type { NotificationSettings } from "./NotificationSettings";
+import type { OnboardingStep } from "./OnboardingStep";
+import type { PermissionTier } from "./PermissionTier";
+import type { PowerProfiles } from "./PowerProfiles";
+import type { RegisterBookmark } from "./RegisterBookmark";
+import type { SoftwareCurve } from "./SoftwareCurve";
+import type { TemperatureUnit } from "./TemperatureUnit";
+import type { TrayMenuSettings } from "./TrayMenuSettings";
+
+export type Settings = { 
+/**
+ * How often the sidecar pushes the status while the window is
+ * visible, in milliseconds; 1000 when unset.
+ */
+poll_interval_ms: number | null, 
+/**
+ * How often it pushes the lite status for the tray while the window
+ * is hidden, in seconds; 10 when unset.
+ */
+hidden_poll_interval_secs: number | null, 
+/**
+ * Stop status pushes while the window is hidden on battery.
+ */
+pause_polling_on_battery: boolean, 
+/**
+ * Stop recording history on battery at or below this charge; never
+ * when unset.
+ */
+pause_history_below_battery_percent: number | null, 
+/**
+ * Stop a spawned sidecar once the window has been hidden this many
+ * minutes with nothing needing it; never when unset.
+ */
+stop_sidecar_when_hidden_mins: number | null, 
+/**
+ * Unit temperatures are shown in, by the window and by `format`.
+ * Thresholds and curves are always stored in °C.
+ */
+temperature_unit: TemperatureUnit, 
+/**
+ * Start the sidecar and re-apply the last settings when the app launches.
+ */
+apply_on_startup: boolean, 
+/**
+ * Seconds to wait before that, for distros that load `ec_sys` late.
+ */
+startup_delay_secs: number, 
+/**
+ * Write features the user enabled on an unverified model.
+ */
+experimental_opt_ins: Array<string>, 
+/**
+ * Models the user confirmed a trial write on; it is re-run without
+ * asking on every connect.
+ */
+trial_write_models: Array<string>, 
+/**
+ * Name of the profile last loaded, used as the starting point for cycling.
+ */
+active_profile: string | null, 
+/**
+ * Onboarding steps completed so far.
+ */
+onboarding: Array<OnboardingStep>, 
+/**
+ * Highest permission tier the UI and tray may use.
+ */
+max_tier: PermissionTier, 
+/**
+ * Tiers that need a confirmation before their commands run.
+ */
+confirm_tiers: Array<PermissionTier>, 
+/**
+ * Retries for EC writes that don't read back; the sidecar's default
+ * when unset.
+ */
+write_retries: number | null, 
+/**
+ * Failsafe watchdog thresholds; the sidecar's defaults when unset.
+ */
+failsafe: FailsafeConfig | null, 
+/**
+ * Hysteresis and ramp rate for fan changes; none when unset.
+ */
+fan_behavior: FanBehavior | null, 
+/**
+ * Entries shown in the tray menu.
+ */
+tray_menu: TrayMenuSettings, 
+/**
+ * Global keyboard shortcuts.
+ */
+hotkeys: Hotkeys, 
+/**
+ * Thermal and connection notifications.
+ */
+notifications: NotificationSettings, 
+/**
+ * Fan curve run by the app instead of the EC, while enabled.
+ */
+software_curve: SoftwareCurve | null, 
+/**
+ * Profiles loaded while given applications run, first match wins.
+ */
+app_rules: Array<AppRule>, 
+/**
+ * Profiles loaded when switching between AC and battery.
+ */
+power_profiles: PowerProfiles, 
+/**
+ * When configuration backups are taken and how many are kept.
+ */
+backups: BackupSettings, 
+/**
+ * Save a report when the app or sidecar panics.
+ */
+crash_reports: boolean, 
+/**
+ * Labeled EC registers, for mapping a new model.
+ */
+register_bookmarks: Array<RegisterBookmark>, };
@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Hotkeys } from "./Hotkeys";
+import type { NotificationSettings } from "./NotificationSettings";
+import type { TemperatureUnit } from "./TemperatureUnit";
+import type { TrayMenuSettings } from "./TrayMenuSettings";
+
+/**
+ * Fields to change with `update_settings`; those left out keep their value.
+ */
+export type SettingsPatch = { poll_interval_ms: number | null, hidden_poll_interval_secs: number | null, pause_polling_on_battery: boolean | null, 
+/**
+ * 0 keeps recording history at any charge.
+ */
+pause_history_below_battery_percent: number | null, 
+/**
+ * 0 turns stopping the idle sidecar off.
+ */
+stop_sidecar_when_hidden_mins: number | null, temperature_unit: TemperatureUnit | null, apply_on_startup: boolean | null, startup_delay_secs: number | null, 
+/**
+ * The autostart desktop entry, which lives outside the settings file;
+ * `get_autostart_enabled` reads it.
+ */
+autostart: boolean | null, hotkeys: Hotkeys | null, tray_menu: TrayMenuSettings | null, notifications: NotificationSettings | null, };
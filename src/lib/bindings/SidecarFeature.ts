@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Commands and fields a sidecar may lack, so the app can tell "not
+ * supported by this sidecar" from a failure.
+ */
+export type SidecarFeature = "per_fan_control" | "fan_list" | "unknown";
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DetectedModel } from "./DetectedModel";
+import type { SidecarFeature } from "./SidecarFeature";
+
+/**
+ * Reply to `hello`.
+ */
+export type SidecarHello = { protocol_version: number, 
+/**
+ * The sidecar's crate version.
+ */
+sidecar_version: string, model: DetectedModel, features: Array<SidecarFeature>, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SidecarHello } from "./SidecarHello";
+
+export type SidecarInfo = { connected: boolean, 
+/**
+ * The protocol this app speaks.
+ */
+protocol_version: number, 
+/**
+ * What the sidecar reported; `None` from one older than the handshake.
+ */
+hello: SidecarHello | null, 
+/**
+ * Why some features may be missing, when the versions differ.
+ */
+mismatch: string | null, };
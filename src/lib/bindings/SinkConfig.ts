@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SinkConfig = { "kind": "desktop" } | { "kind": "tray_badge" } | { "kind": "webhook", url: string, } | { "kind": "ntfy", server: string | null, topic: string, } | { "kind": "gotify", server: string, token: string, } | { "kind": "mqtt", host: string, port: number | null, topic: string, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AlertKind } from "./AlertKind";
+import type { SinkConfig } from "./SinkConfig";
+
+/**
+ * One sink and the alert kinds sent to it.
+ */
+export type SinkRoute = { sink: SinkConfig, alerts: Array<AlertKind>, };
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CurvePoint } from "./CurvePoint";
+
+export type SoftwareCurve = { 
+/**
+ * By rising temperature. The end duties hold below the first and
+ * above the last point.
+ */
+points: Array<CurvePoint>, 
+/**
+ * °C the temperature has to fall before the duty follows it down.
+ */
+hysteresis: number, 
+/**
+ * Fastest duty change, in % per second.
+ */
+ramp_rate: number, };
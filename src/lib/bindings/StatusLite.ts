@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Reduced status served by `get_status_lite`.
+ */
+export type StatusLite = { cpu_temp: number, gpu_temp: number, cooler_boost: boolean, boost_cooldown_suggested: boolean, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How much we trust the register layout on this machine.
+ */
+export type SupportLevel = "verified" | "experimental" | "unsupported";
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SystemStats = { memory_used: number, memory_total: number, swap_used: number, swap_total: number, cpu_global_frequency: number, };
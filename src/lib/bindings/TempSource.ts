@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TempSource = { 
+/**
+ * `CPU` or `GPU`
+ */
+sensor: string, 
+/**
+ * EC register the temperature is read from, e.g. `0x68`.
+ */
+ec_register: string, ec_temp: number, 
+/**
+ * °C from the kernel or driver; `None` when no sensor was found.
+ */
+reference_temp: number | null, 
+/**
+ * hwmon driver name, or `nvidia`.
+ */
+reference_source: string | null, 
+/**
+ * The readings differ by more than the threshold.
+ */
+diverged: boolean, };
@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ThermalTestRun } from "./ThermalTestRun";
+
+/**
+ * A saved test with its temperatures normalized to the room.
+ */
+export type ThermalTestEntry = { run: ThermalTestRun, 
+/**
+ * What the rises are measured from: the ambient temperature, or the
+ * idle CPU temperature without an ambient sensor.
+ */
+baseline_temp: number, 
+/**
+ * Peak temperature above the baseline, in °C.
+ */
+cpu_rise: number, gpu_rise: number, 
+/**
+ * `cpu_rise` against the previous completed run of the same profile;
+ * negative means it ran cooler.
+ */
+cpu_rise_change: number | null, };
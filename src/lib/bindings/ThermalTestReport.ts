@@ -0,0 +1,35 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ThermalTestReport = { 
+/**
+ * How long the load ran, which is shorter than asked when stopped.
+ */
+duration_secs: number, 
+/**
+ * `false` when stopped early.
+ */
+completed: boolean, 
+/**
+ * Why the load was stopped for safety, if it was.
+ */
+aborted: string | null, 
+/**
+ * Threads loading the CPU, one per logical CPU.
+ */
+workers: number, 
+/**
+ * Fan mode when the test started, to tell runs apart.
+ */
+fan_mode: string, samples: number, max_cpu_temp: number, max_gpu_temp: number, avg_cpu_temp: number, avg_gpu_temp: number, 
+/**
+ * Time the CPU or GPU spent at or above 85 °C.
+ */
+secs_above_85: number, avg_fan1_rpm: number, avg_fan2_rpm: number, 
+/**
+ * Before the load started.
+ */
+idle_cpu_temp: number, idle_gpu_temp: number, 
+/**
+ * From an hwmon sensor labeled ambient, on machines that have one.
+ */
+ambient_temp: number | null, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ThermalTestReport } from "./ThermalTestReport";
+
+/**
+ * A saved test.
+ */
+export type ThermalTestRun = { 
+/**
+ * Unix time in milliseconds
+ */
+timestamp_ms: number, 
+/**
+ * Active profile when the test started; `None` before any was loaded.
+ */
+profile: string | null, report: ThermalTestReport, };
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Which entries the tray menu shows.
+ */
+export type TrayMenuSettings = { 
+/**
+ * Read-only line with the latest temperatures.
+ */
+monitor: boolean, cooler_boost: boolean, 
+/**
+ * The Fan Mode submenu.
+ */
+fan_modes: boolean, 
+/**
+ * The Profiles submenu and Next Profile.
+ */
+profiles: boolean, show: boolean, quit: boolean, };
@@ -1,28 +1,95 @@
-export interface FanStatus {
-  cpu_temp: number;
-  gpu_temp: number;
-  fan1_rpm: number;
-  fan2_rpm: number;
-  cooler_boost: boolean;
-  fan_mode: string;
-}
+// Types shared with the Rust backend, generated into `bindings/` by ts-rs.
+// Run `npm run bindings` after changing a Rust type; never edit them by hand.
 
-export interface HardwareInfo {
-  cpu_model: string;
-  gpu_model: string;
-  memory_total: number;
-}
-
-export interface SystemStats {
-  memory_used: number;
-  memory_total: number;
-  swap_used: number;
-  swap_total: number;
-  cpu_global_frequency: number;
-}
-
-export interface CpuCoreDetail {
-  name: string;
-  frequency: number;
-  usage: number;
-}
+export type { ActionKind } from "./bindings/ActionKind";
+export type { ActionRecord } from "./bindings/ActionRecord";
+export type { ActionSource } from "./bindings/ActionSource";
+export type { ActiveRegisterMap } from "./bindings/ActiveRegisterMap";
+export type { AlertKind } from "./bindings/AlertKind";
+export type { Anomaly } from "./bindings/Anomaly";
+export type { AnomalyKind } from "./bindings/AnomalyKind";
+export type { AppRule } from "./bindings/AppRule";
+export type { Backup } from "./bindings/Backup";
+export type { BackupSettings } from "./bindings/BackupSettings";
+export type { BackupTrigger } from "./bindings/BackupTrigger";
+export type { BatteryInfo } from "./bindings/BatteryInfo";
+export type { BookmarkReading } from "./bindings/BookmarkReading";
+export type { Capability } from "./bindings/Capability";
+export type { CapabilityState } from "./bindings/CapabilityState";
+export type { CapabilityStatus } from "./bindings/CapabilityStatus";
+export type { CheckId } from "./bindings/CheckId";
+export type { CheckStatus } from "./bindings/CheckStatus";
+export type { CleanupReport } from "./bindings/CleanupReport";
+export type { ConnectionState } from "./bindings/ConnectionState";
+export type { ContributionReport } from "./bindings/ContributionReport";
+export type { ContributionStep } from "./bindings/ContributionStep";
+export type { CpuCoreDetail } from "./bindings/CpuCoreDetail";
+export type { CrashReport } from "./bindings/CrashReport";
+export type { CrashSource } from "./bindings/CrashSource";
+export type { CurvePoint } from "./bindings/CurvePoint";
+export type { CycleDirection } from "./bindings/CycleDirection";
+export type { DetectedModel } from "./bindings/DetectedModel";
+export type { DiskHealth } from "./bindings/DiskHealth";
+export type { EcDump } from "./bindings/EcDump";
+export type { EcModuleError } from "./bindings/EcModuleError";
+export type { EventTopic } from "./bindings/EventTopic";
+export type { ExportFormat } from "./bindings/ExportFormat";
+export type { ExtraFanSpec } from "./bindings/ExtraFanSpec";
+export type { FailsafeAction } from "./bindings/FailsafeAction";
+export type { FailsafeConfig } from "./bindings/FailsafeConfig";
+export type { Fan } from "./bindings/Fan";
+export type { FanBehavior } from "./bindings/FanBehavior";
+export type { FanCurve } from "./bindings/FanCurve";
+export type { FanModeValues } from "./bindings/FanModeValues";
+export type { FanReading } from "./bindings/FanReading";
+export type { FanSpeed } from "./bindings/FanSpeed";
+export type { FanStatus } from "./bindings/FanStatus";
+export type { Fix } from "./bindings/Fix";
+export type { FormattedFanStatus } from "./bindings/FormattedFanStatus";
+export type { GpuStats } from "./bindings/GpuStats";
+export type { HardwareInfo } from "./bindings/HardwareInfo";
+export type { HistorySample } from "./bindings/HistorySample";
+export type { Hotkeys } from "./bindings/Hotkeys";
+export type { HwmonSensor } from "./bindings/HwmonSensor";
+export type { ImportFormat } from "./bindings/ImportFormat";
+export type { ImportedProfile } from "./bindings/ImportedProfile";
+export type { InterfaceStats } from "./bindings/InterfaceStats";
+export type { KeyboardBacklight } from "./bindings/KeyboardBacklight";
+export type { NbfcTranslation } from "./bindings/NbfcTranslation";
+export type { NetworkStats } from "./bindings/NetworkStats";
+export type { NotificationSettings } from "./bindings/NotificationSettings";
+export type { OnboardingState } from "./bindings/OnboardingState";
+export type { OnboardingStep } from "./bindings/OnboardingStep";
+export type { PermissionSettings } from "./bindings/PermissionSettings";
+export type { PermissionTier } from "./bindings/PermissionTier";
+export type { PersistenceReport } from "./bindings/PersistenceReport";
+export type { PowerProfiles } from "./bindings/PowerProfiles";
+export type { PowerSource } from "./bindings/PowerSource";
+export type { PreflightCheck } from "./bindings/PreflightCheck";
+export type { ProbeResult } from "./bindings/ProbeResult";
+export type { ProcessInfo } from "./bindings/ProcessInfo";
+export type { Profile } from "./bindings/Profile";
+export type { RegisterBookmark } from "./bindings/RegisterBookmark";
+export type { RegisterChange } from "./bindings/RegisterChange";
+export type { RegisterMapSource } from "./bindings/RegisterMapSource";
+export type { RegisterMapSpec } from "./bindings/RegisterMapSpec";
+export type { Resumed } from "./bindings/Resumed";
+export type { Sensor } from "./bindings/Sensor";
+export type { SensorKind } from "./bindings/SensorKind";
+export type { Settings } from "./bindings/Settings";
+export type { SettingsPatch } from "./bindings/SettingsPatch";
+export type { SidecarFeature } from "./bindings/SidecarFeature";
+export type { SidecarHello } from "./bindings/SidecarHello";
+export type { SidecarInfo } from "./bindings/SidecarInfo";
+export type { SinkConfig } from "./bindings/SinkConfig";
+export type { SinkRoute } from "./bindings/SinkRoute";
+export type { SoftwareCurve } from "./bindings/SoftwareCurve";
+export type { StatusLite } from "./bindings/StatusLite";
+export type { SupportLevel } from "./bindings/SupportLevel";
+export type { SystemStats } from "./bindings/SystemStats";
+export type { TempSource } from "./bindings/TempSource";
+export type { TemperatureUnit } from "./bindings/TemperatureUnit";
+export type { ThermalTestEntry } from "./bindings/ThermalTestEntry";
+export type { ThermalTestReport } from "./bindings/ThermalTestReport";
+export type { ThermalTestRun } from "./bindings/ThermalTestRun";
+export type { TrayMenuSettings } from "./bindings/TrayMenuSettings";
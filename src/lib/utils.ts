@@ -1,4 +1,22 @@
+import type { TemperatureUnit } from "$lib/types";
+
 /** Convert bytes to a GB string, rounded to 1 decimal place. */
 export function formatGb(bytes: number): string {
   return (bytes / 1024 / 1024 / 1024).toFixed(1);
 }
+
+/** Convert bytes per second to a KB/s or MB/s string. */
+export function formatRate(bytesPerSec: number): string {
+  const kb = bytesPerSec / 1024;
+  return kb < 1024 ? `${kb.toFixed(0)} KB/s` : `${(kb / 1024).toFixed(1)} MB/s`;
+}
+
+/** A temperature in °C shown in the user's unit, rounded to a whole degree. */
+export function formatTemp(celsius: number, unit: TemperatureUnit): string {
+  return String(Math.round(unit === "fahrenheit" ? (celsius * 9) / 5 + 32 : celsius));
+}
+
+/** The symbol for a temperature unit. */
+export function tempSymbol(unit: TemperatureUnit): string {
+  return unit === "fahrenheit" ? "°F" : "°C";
+}